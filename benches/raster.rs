@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use weight_wb::printer::encode_raster_line;
+
+/// The number of bytes per raster line on the narrow QL models
+const LINE_WIDTH: usize = 90;
+
+/// A 62 mm continuous label has 696 printable dots and a right margin of 12 dots.
+const LABEL_WIDTH: usize = 696;
+const MARGIN_DOTS: u32 = 12;
+
+/// Encoding a label with this many lines should stay below ~20 ms on a Raspberry Pi Zero.
+const LINES_COUNT: usize = 1000;
+
+fn raster_label(c: &mut Criterion) {
+    // A diagonal gradient gives us a mix of printed and empty dots.
+    let image: Vec<u8> = (0..LINES_COUNT)
+        .flat_map(|y| (0..LABEL_WIDTH).map(move |x| ((x + y) % 256) as u8))
+        .collect();
+
+    let mut line = [0u8; LINE_WIDTH];
+
+    c.bench_function("raster 1000 lines (62 mm)", |b| {
+        b.iter(|| {
+            for row in black_box(&image).chunks_exact(LABEL_WIDTH) {
+                line.fill(0);
+                encode_raster_line(row, MARGIN_DOTS, false, &mut line);
+                black_box(&line);
+            }
+        })
+    });
+}
+
+criterion_group!(benches, raster_label);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use image::ImageReader;
+use weight_wb::voucher::{Alignment, Builder, Spacing, TextContext};
+
+/// A 62 mm continuous label at 300 dpi is 696 dots wide.
+const VOUCHER_WIDTH: u32 = 696;
+
+fn render_voucher(c: &mut Criterion) {
+    let logo = ImageReader::open("logo.png")
+        .expect("Failed to load logo")
+        .decode()
+        .expect("Failed to decode logo");
+
+    // Fonts and glyphs are loaded once and reused across iterations, just like the UI does.
+    let mut text_ctx = Some(TextContext::new());
+
+    c.bench_function("render realistic voucher (62 mm)", |b| {
+        b.iter(|| {
+            let (image, ctx) = Builder::with_text_context(VOUCHER_WIDTH, text_ctx.take().unwrap())
+                .start_image_component(&logo)
+                .spacing(Spacing::horz_vert(20.0, 20.0))
+                .finalize_image_component()
+                .start_text_component("Rinderhack")
+                .spacing(Spacing::horz_vert(16.0, 16.0))
+                .font_size(50.0)
+                .alignment(Alignment::Center)
+                .bold(true)
+                .finalize_text_component()
+                .start_text_component("Gewicht: 20 kg")
+                .spacing(Spacing::horz_vert(16.0, 12.0))
+                .font_size(25.0)
+                .finalize_text_component()
+                .start_text_component("Preis: 30,14 €")
+                .spacing(Spacing::horz_vert(16.0, 24.0))
+                .font_size(40.0)
+                .bold(true)
+                .finalize_text_component()
+                .start_text_component("Zutaten: Rind, Fleisch, Wasser, Zucker, Salz, Vitamine")
+                .spacing(Spacing::horz_vert(16.0, 12.0))
+                .font_size(25.0)
+                .finalize_text_component()
+                .start_text_component("... weitere Infos folgen!")
+                .spacing(Spacing::lrtb(8.0, 8.0, 48.0, 8.0))
+                .font_size(21.0)
+                .alignment(Alignment::Center)
+                .italic(true)
+                .finalize_text_component()
+                .build_with_text_context()
+                .expect("Voucher has no maximum height");
+
+            text_ctx = Some(ctx);
+            black_box(image)
+        })
+    });
+}
+
+criterion_group!(benches, render_voucher);
+criterion_main!(benches);
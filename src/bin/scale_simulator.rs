@@ -0,0 +1,215 @@
+//! Emulate the scales on a serial port, so the app can be run (and new protocols can be developed) without hardware.
+//!
+//! `scale-simulator [<port>] [--protocol <framing>]`
+//!
+//! Without a port, a pseudo terminal is created and its path is printed (use it as the serial port of the app).
+//! The framing is given like the `scale_protocol` setting. The weight is typed on stdin.
+
+use std::env;
+use std::ffi::CStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serialport::{DataBits, Parity, StopBits};
+
+use weight_wb::weight::{Protocol, Unit};
+
+/// What the simulated scales report
+struct State {
+    /// The weight in `unit`
+    weight: f64,
+    unit: Unit,
+
+    /// Leave the weight requests unanswered (like scales that have been switched off)
+    muted: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+
+    let protocol = match args.iter().position(|c| c == "--protocol") {
+        Some(idx) => args
+            .get(idx + 1)
+            .ok_or("--protocol needs a framing")?
+            .parse::<Protocol>()?,
+
+        None => Protocol::default(),
+    };
+
+    let port_path = args
+        .get(1)
+        .filter(|c| !c.starts_with("--"))
+        .map(String::as_str);
+
+    let state = Arc::new(Mutex::new(State {
+        weight: 0.0,
+        unit: Unit::Kilogram,
+        muted: false,
+    }));
+
+    let state2 = Arc::clone(&state);
+    thread::spawn(move || read_commands(&state2));
+
+    eprintln!("Framing: {}", protocol);
+    eprintln!("Type a weight (e.g. 1.25), a unit (kg, g, lb, oz) or \"mute\" and press enter.");
+
+    match port_path {
+        Some(port_path) => {
+            let mut port = serialport::new(port_path, 9600)
+                .data_bits(DataBits::Eight)
+                .stop_bits(StopBits::One)
+                .parity(Parity::None)
+                .timeout(Duration::from_secs(1))
+                .open()?;
+
+            eprintln!("Listening on {}", port_path);
+            serve(&mut port, &protocol, &state)
+        }
+
+        None => {
+            let (mut master, _slave, slave_path) = open_pty()?;
+
+            println!("{}", slave_path);
+            serve(&mut master, &protocol, &state)
+        }
+    }
+}
+
+/// Change the state by the lines typed on stdin.
+fn read_commands(state: &Mutex<State>) {
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            return;
+        };
+
+        let line = line.trim();
+        let mut state = state.lock().unwrap();
+
+        if line == "mute" {
+            state.muted = !state.muted;
+        } else if let Ok(weight) = line.replace(',', ".").parse::<f64>() {
+            state.weight = weight;
+        } else if let Ok(Some(unit)) = Unit::from_bytes(line.as_bytes()) {
+            state.unit = unit;
+        } else {
+            eprintln!("Unknown command \"{}\"", line);
+            continue;
+        }
+
+        eprintln!(
+            "Reporting {} {}{}",
+            state.weight,
+            state.unit,
+            if state.muted { " (muted)" } else { "" }
+        );
+    }
+}
+
+/// Answer the requests of the protocol until the port fails.
+/// Other bytes are ignored (they might be the start of a request).
+fn serve<P: Read + Write>(
+    port: &mut P,
+    protocol: &Protocol,
+    state: &Mutex<State>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut pending = Vec::new();
+    let mut byte = [0x00u8];
+
+    loop {
+        match port.read(&mut byte) {
+            Ok(0) => {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+
+            Ok(_) => pending.push(byte[0]),
+            Err(err) if matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => {
+                continue
+            }
+
+            Err(err) => return Err(err.into()),
+        }
+
+        let response = if pending.ends_with(&protocol.weight_request) {
+            let state = state.lock().unwrap();
+
+            (!state.muted).then(|| protocol.format_weight(state.weight, state.unit))
+        } else if !protocol.info_request.is_empty() && pending.ends_with(&protocol.info_request) {
+            // The content of the info response is ignored, so an ACK is as good as anything.
+            Some(vec![0x06; protocol.info_response_len])
+        } else {
+            // Only the end of the pending bytes can match a request.
+            if pending.len() > 64 {
+                pending.drain(..32);
+            }
+
+            continue;
+        };
+
+        pending.clear();
+
+        if let Some(response) = response {
+            port.write_all(&response)?;
+            port.flush()?;
+        }
+    }
+}
+
+/// Create a pseudo terminal and return its master, its slave and the path of the slave.
+/// The slave is kept open, so the master doesn't fail while the app has not opened it (yet).
+fn open_pty() -> io::Result<(File, File, String)> {
+    // SAFETY: The flags are valid and the result is checked.
+    let fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: The descriptor has just been opened and is owned by nobody else.
+    let master = unsafe { File::from_raw_fd(fd) };
+    let mut name = [0 as libc::c_char; 128];
+
+    // SAFETY: The descriptor is a pty master and the buffer is large enough for its name.
+    let failed = unsafe {
+        (libc::grantpt(fd) != 0)
+            || (libc::unlockpt(fd) != 0)
+            || (libc::ptsname_r(fd, name.as_mut_ptr(), name.len()) != 0)
+    };
+
+    if failed {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: `ptsname_r()` has succeeded, so the buffer holds a terminated string.
+    let slave_path = unsafe { CStr::from_ptr(name.as_ptr()) }
+        .to_string_lossy()
+        .into_owned();
+
+    let slave = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&slave_path)?;
+
+    // No echo and no line editing, the requests are binary.
+    let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+
+    // SAFETY: The descriptor is a terminal and `termios` is large enough for its attributes.
+    unsafe {
+        if libc::tcgetattr(slave.as_raw_fd(), termios.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut termios = termios.assume_init();
+        libc::cfmakeraw(&mut termios);
+
+        if libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok((master, slave, slave_path))
+}
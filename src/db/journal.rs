@@ -0,0 +1,417 @@
+use super::{Database, SaleEntry};
+
+use std::fmt::Display;
+use std::fs::{self, OpenOptions};
+use std::io::{Error as IOError, ErrorKind as IOErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::DateTime;
+use rusqlite::Error as SQLiteError;
+
+#[derive(Debug)]
+pub enum Error {
+    IO(IOError),
+    SQLite(SQLiteError),
+    InvalidLine(usize),
+    UnknownVersion { line: usize, version: String },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+
+        match self {
+            IO(err) => write!(f, "The journal could not be accessed: {}", err),
+            SQLite(err) => write!(f, "The sale could not be stored: {}", err),
+            InvalidLine(line) => write!(f, "The journal is malformed (line {}).", line),
+            UnknownVersion { line, version } => write!(
+                f,
+                "The journal has been written in an unknown format (line {}: version {}, expected {}).",
+                line, version, VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<IOError> for Error {
+    fn from(value: IOError) -> Self {
+        Error::IO(value)
+    }
+}
+
+impl From<SQLiteError> for Error {
+    fn from(value: SQLiteError) -> Self {
+        Error::SQLite(value)
+    }
+}
+
+/// The fields of a journal line
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Field {
+    /// RFC 2822
+    Date,
+    /// In kg, empty if there is none
+    Weight,
+    /// In ct
+    Price,
+    /// In ct
+    Total,
+    /// 0 or 1
+    DuplicateOverride,
+    /// Empty if the sale has not been counted
+    PieceCount,
+    Operator,
+    Station,
+    PaymentMethod,
+    /// RFC 2822
+    ExpirationOverride,
+    Name,
+}
+
+use Field::*;
+
+/// The version of the line format, which starts each line.
+/// If the fields change, this is bumped (lines of other versions are rejected, see `Error::UnknownVersion`).
+const VERSION: u32 = 1;
+
+/// The fields of a line (after the version)
+const FIELDS: &[Field] = &[
+    Date,
+    Weight,
+    Price,
+    Total,
+    DuplicateOverride,
+    PieceCount,
+    Operator,
+    Station,
+    PaymentMethod,
+    ExpirationOverride,
+    Name,
+];
+
+/// A plain text file that keeps sales which could not be written to the database.
+/// Each line holds one sale as tab-separated fields, starting with the version of the line format
+/// (see `VERSION`). Tabs and line breaks in the free text fields are replaced by spaces.
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Append a sale to the journal.
+    pub fn append(&self, sale: &SaleEntry) -> Result<(), Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        file.write_all(Self::format_line(sale).as_bytes())?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
+    /// Load all sales from the journal.
+    /// A missing journal file is equivalent to an empty one.
+    pub fn load(&self) -> Result<Vec<SaleEntry>, Error> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == IOErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(idx, line)| Self::parse_line(idx + 1, line))
+            .collect()
+    }
+
+    /// Store all sales from the journal in the database.
+    /// Sales that have been stored are removed from the journal, so a failure can be retried later.
+    /// Returns the number of stored sales.
+    pub fn replay(&self, db: &mut Database) -> Result<usize, Error> {
+        let sales = self.load()?;
+
+        for (idx, sale) in sales.iter().enumerate() {
+            if let Err(err) = db.add_sale(sale) {
+                self.rewrite(&sales[idx..])?;
+                return Err(err.into());
+            }
+        }
+
+        self.rewrite(&[])?;
+
+        Ok(sales.len())
+    }
+
+    fn rewrite(&self, sales: &[SaleEntry]) -> Result<(), Error> {
+        if sales.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(err) if err.kind() != IOErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            };
+        }
+
+        let content: String = sales.iter().map(Self::format_line).collect();
+        fs::write(&self.path, content)?;
+
+        Ok(())
+    }
+
+    fn format_line(sale: &SaleEntry) -> String {
+        let fields = FIELDS.iter().map(|field| match field {
+            Date => sale.date.to_rfc2822(),
+            Weight => sale.weight_kg.map_or_else(String::new, |w| w.to_string()),
+            Price => sale.price_ct.to_string(),
+            Total => sale.total_ct.to_string(),
+            DuplicateOverride => (sale.duplicate_override as u8).to_string(),
+            PieceCount => sale.piece_count.map_or_else(String::new, |c| c.to_string()),
+            Operator => Self::escape(sale.operator.as_deref().unwrap_or("")),
+            Station => Self::escape(sale.station.as_deref().unwrap_or("")),
+            PaymentMethod => sale
+                .payment_method
+                .map_or_else(String::new, |method| method.to_string()),
+            ExpirationOverride => sale
+                .expiration_override
+                .map_or_else(String::new, |date| date.to_rfc2822()),
+            Name => Self::escape(&sale.name),
+        });
+
+        let mut line = VERSION.to_string();
+
+        for field in fields {
+            line.push('\t');
+            line.push_str(&field);
+        }
+
+        line.push('\n');
+        line
+    }
+
+    /// Replace the characters that would break the line format.
+    fn escape(field: &str) -> String {
+        field.replace(['\t', '\r', '\n'], " ")
+    }
+
+    /// Parse the line with the given number (starting at 1).
+    fn parse_line(number: usize, line: &str) -> Result<SaleEntry, Error> {
+        let (version, fields) = line.split_once('\t').unwrap_or((line, ""));
+
+        if version != VERSION.to_string() {
+            return Err(Error::UnknownVersion {
+                line: number,
+                version: String::from(version),
+            });
+        }
+
+        Self::parse_fields(fields).ok_or(Error::InvalidLine(number))
+    }
+
+    fn parse_fields(fields: &str) -> Option<SaleEntry> {
+        let values = fields.split('\t').collect::<Vec<_>>();
+
+        if values.len() != FIELDS.len() {
+            return None;
+        }
+
+        let value = |field: Field| values[FIELDS.iter().position(|&f| f == field).unwrap()];
+
+        let date = DateTime::parse_from_rfc2822(value(Date)).ok()?.into();
+
+        let weight_kg = match value(Weight) {
+            "" => None,
+            weight => Some(weight.parse().ok()?),
+        };
+
+        let price_ct = value(Price).parse().ok()?;
+        let total_ct = value(Total).parse().ok()?;
+
+        let duplicate_override = match value(DuplicateOverride) {
+            "0" => false,
+            "1" => true,
+            _ => return None,
+        };
+
+        let piece_count = match value(PieceCount) {
+            "" => None,
+            count => Some(count.parse().ok()?),
+        };
+
+        let operator = Some(value(Operator))
+            .filter(|o| !o.is_empty())
+            .map(String::from);
+        let station = Some(value(Station))
+            .filter(|s| !s.is_empty())
+            .map(String::from);
+
+        let payment_method = match value(PaymentMethod) {
+            "" => None,
+            method => Some(method.parse().ok()?),
+        };
+
+        let expiration_override = match value(ExpirationOverride) {
+            "" => None,
+            date => Some(DateTime::parse_from_rfc2822(date).ok()?.into()),
+        };
+
+        let name = String::from(value(Name));
+
+        let mut sale = SaleEntry::new(date, name, weight_kg, price_ct, total_ct);
+        sale.duplicate_override = duplicate_override;
+        sale.piece_count = piece_count;
+        sale.operator = operator;
+        sale.station = station;
+        sale.payment_method = payment_method;
+        sale.expiration_override = expiration_override;
+
+        Some(sale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::db::test_support::{date, memory_db};
+    use crate::db::PaymentMethod;
+
+    use std::{env, process};
+
+    use chrono::Utc;
+
+    /// The fields of a sale that the journal keeps
+    type Summary = (
+        DateTime<Utc>,
+        String,
+        Option<f64>,
+        u64,
+        u64,
+        bool,
+        Option<u64>,
+        Option<String>,
+        Option<String>,
+        Option<PaymentMethod>,
+        Option<DateTime<Utc>>,
+    );
+
+    fn summary(sale: &SaleEntry) -> Summary {
+        (
+            sale.date,
+            sale.name.clone(),
+            sale.weight_kg,
+            sale.price_ct,
+            sale.total_ct,
+            sale.duplicate_override,
+            sale.piece_count,
+            sale.operator.clone(),
+            sale.station.clone(),
+            sale.payment_method,
+            sale.expiration_override,
+        )
+    }
+
+    /// A journal in the temp dir that does not exist yet
+    fn journal(name: &str) -> Journal {
+        let path = env::temp_dir().join(format!("weight-wb-{}-{}.journal", name, process::id()));
+        _ = fs::remove_file(&path);
+
+        Journal::new(path)
+    }
+
+    #[test]
+    fn sales_are_journaled_and_replayed() {
+        let journal = journal("replay");
+        assert!(journal.load().unwrap().is_empty());
+
+        let mut sale = SaleEntry::new(
+            date(1, 9),
+            String::from("Brot\tvom\nVortag"),
+            None,
+            250,
+            750,
+        );
+        sale.duplicate_override = true;
+        sale.piece_count = Some(3);
+        sale.operator = Some(String::from("anna"));
+        sale.station = Some(String::from("kasse-1"));
+        sale.payment_method = Some(PaymentMethod::Card);
+        sale.expiration_override = Some(date(4, 0));
+
+        let weighed = SaleEntry::new(date(1, 10), String::from("Käse"), Some(0.347), 1299, 451);
+
+        journal.append(&sale).unwrap();
+        journal.append(&weighed).unwrap();
+
+        // The version comes first.
+        let content = fs::read_to_string(&journal.path).unwrap();
+        assert!(content.lines().all(|line| line.starts_with("1\t")));
+
+        sale.name = String::from("Brot vom Vortag");
+        let loaded = journal.load().unwrap();
+        assert_eq!(
+            loaded.iter().map(summary).collect::<Vec<_>>(),
+            [summary(&sale), summary(&weighed)]
+        );
+
+        let mut db = memory_db();
+        assert_eq!(journal.replay(&mut db).unwrap(), 2);
+        assert!(!journal.path.exists());
+
+        let mut sales = Vec::new();
+        db.sales(&mut sales).unwrap();
+        assert_eq!(sales.len(), 2);
+    }
+
+    #[test]
+    fn malformed_lines_are_reported() {
+        let journal = journal("malformed");
+        let line = Journal::format_line(&SaleEntry::new(
+            date(1, 9),
+            String::from("Brot"),
+            None,
+            250,
+            250,
+        ));
+
+        for malformed in ["1\tBrot", "1", &line.replacen("\t0\t", "\tja\t", 1)] {
+            fs::write(&journal.path, format!("{line}{malformed}\n")).unwrap();
+            assert!(
+                matches!(journal.load(), Err(Error::InvalidLine(2))),
+                "{malformed}"
+            );
+        }
+
+        // Lines of other versions are not guessed at.
+        for (malformed, version) in [("2\tBrot", "2"), ("Brot\t1", "Brot")] {
+            fs::write(&journal.path, format!("{line}{malformed}\n")).unwrap();
+            let Err(Error::UnknownVersion { line, version: v }) = journal.load() else {
+                panic!("{malformed}");
+            };
+
+            assert_eq!((line, v.as_str()), (2, version));
+        }
+
+        fs::remove_file(&journal.path).unwrap();
+    }
+
+    #[test]
+    fn the_journal_is_next_to_the_db() {
+        let mut db = memory_db();
+        assert_eq!(db.journal_path(), Path::new("sales.journal"));
+
+        db.update_settings(|settings| {
+            settings.journal_path = String::from("/srv/wb/sales.journal")
+        })
+        .unwrap();
+        assert_eq!(db.journal_path(), Path::new("/srv/wb/sales.journal"));
+    }
+}
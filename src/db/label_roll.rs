@@ -0,0 +1,148 @@
+use crate::printer::LabelType;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{named_params, Connection, OptionalExtension, Result as SQLiteResult, Row};
+
+/// A roll of labels in the printer.
+/// Its usage is estimated from the printed lengths, which helps to plan purchases.
+#[derive(Clone)]
+pub struct LabelRollEntry {
+    pub label_type: LabelType,
+
+    /// When the roll has been inserted (or first been printed on, if the change was not recorded)
+    pub inserted: DateTime<Utc>,
+    pub length_mm: f64,
+    pub used_mm: f64,
+    pub print_count: u64,
+}
+
+impl LabelRollEntry {
+    pub fn new(label_type: LabelType, inserted: DateTime<Utc>, length_mm: f64) -> Self {
+        Self {
+            label_type,
+            inserted,
+            length_mm,
+            used_mm: 0.0,
+            print_count: 0,
+        }
+    }
+
+    /// The estimated length that is left on the roll (in mm)
+    pub fn remaining_mm(&self) -> f64 {
+        (self.length_mm - self.used_mm).max(0.0)
+    }
+
+    /// The estimated number of die-cut labels that are left on the roll
+    pub fn remaining_labels(&self) -> Option<u64> {
+        self.label_type
+            .die_cut_pitch_mm()
+            .map(|pitch_mm| (self.remaining_mm() / pitch_mm).floor() as u64)
+    }
+
+    fn load(row: &Row) -> SQLiteResult<Self> {
+        let label_type: String = row.get("label_type")?;
+        let inserted_rfc2822: String = row.get("inserted_2822")?;
+
+        Ok(Self {
+            label_type: label_type.parse().expect("Invalid label type"),
+            inserted: DateTime::parse_from_rfc2822(&inserted_rfc2822)
+                .expect("Invalid timestamp format (expected RFC 2822)")
+                .into(),
+            length_mm: row.get("length_mm")?,
+            used_mm: row.get("used_mm")?,
+            print_count: row.get("print_count")?,
+        })
+    }
+
+    /// Load all rolls, newest first.
+    pub(super) fn load_all(con: &Connection, rolls: &mut Vec<Self>) -> SQLiteResult<()> {
+        let mut stmt = con.prepare(
+            "SELECT
+                label_type,
+                inserted_2822,
+                length_mm,
+                used_mm,
+                print_count
+            FROM label_rolls
+            ORDER BY id DESC",
+        )?;
+
+        rolls.clear();
+
+        for roll in stmt.query_map((), Self::load)? {
+            rolls.push(roll?);
+        }
+
+        Ok(())
+    }
+
+    /// Load the roll of the label type that has been inserted last.
+    pub(super) fn load_current(
+        con: &Connection,
+        label_type: LabelType,
+    ) -> SQLiteResult<Option<Self>> {
+        con.query_row(
+            "SELECT
+                label_type,
+                inserted_2822,
+                length_mm,
+                used_mm,
+                print_count
+            FROM label_rolls
+            WHERE label_type = :label_type
+            ORDER BY id DESC
+            LIMIT 1",
+            named_params! {":label_type": label_type.to_string()},
+            Self::load,
+        )
+        .optional()
+    }
+
+    pub(super) fn store(&self, con: &Connection) -> SQLiteResult<()> {
+        con.execute(
+            "INSERT INTO label_rolls (
+                label_type,
+                inserted_2822,
+                length_mm,
+                used_mm,
+                print_count
+            ) VALUES (
+                :label_type,
+                :inserted_2822,
+                :length_mm,
+                :used_mm,
+                :print_count
+            )",
+            named_params! {
+                ":label_type": self.label_type.to_string(),
+                ":inserted_2822": self.inserted.to_rfc2822(),
+                ":length_mm": self.length_mm,
+                ":used_mm": self.used_mm,
+                ":print_count": self.print_count,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Add a print to the current roll of the label type.
+    pub(super) fn add_usage(
+        con: &Connection,
+        label_type: LabelType,
+        used_mm: f64,
+    ) -> SQLiteResult<()> {
+        con.execute(
+            "UPDATE label_rolls
+            SET used_mm = used_mm + :used_mm, print_count = print_count + 1
+            WHERE id = (
+                SELECT MAX(id) FROM label_rolls WHERE label_type = :label_type
+            )",
+            named_params! {
+                ":label_type": label_type.to_string(),
+                ":used_mm": used_mm,
+            },
+        )?;
+
+        Ok(())
+    }
+}
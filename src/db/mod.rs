@@ -1,9 +1,194 @@
-use std::path::Path;
-
-use chrono::{DateTime, Local, TimeDelta, Utc};
-use rusqlite::{named_params, Connection, Error as SQLiteError, Result as SQLiteResult, Row};
+use crate::format;
+use crate::printer::LabelType;
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+use rusqlite::{
+    functions::FunctionFlags, named_params, Connection, Error as SQLiteError, OptionalExtension,
+    Result as SQLiteResult, Row,
+};
+
+/// Sales that could not be stored are kept in a journal file until they can be replayed.
+mod journal;
+pub use journal::{Error as JournalError, Journal as SaleJournal};
+
+/// Every print attempt is recorded, so printer problems can be investigated later.
+mod print_job;
+pub use print_job::{PrintJobEntry, PrintOutcome, MAX_PRINT_JOBS};
+
+/// Price changes are recorded, so older vouchers can be explained.
+mod price_change;
+pub use price_change::PriceChangeEntry;
+
+/// The usage of label rolls is estimated, so they can be replaced and bought in time.
+mod label_roll;
+pub use label_roll::LabelRollEntry;
+
+/// The raw readings of the scales behind the sold weights are kept for a while.
+mod weighing;
+pub use weighing::WeighingEntry;
+
+/// The cash drawer is counted after every shift and compared with the cash sales.
+mod shift;
+pub use shift::{ShiftClosing, ShiftEntry};
+
+/// Sales can note how they have been paid.
+mod payment;
+pub use payment::{PaymentMethod, PaymentTotal};
+
+/// Products can be labeled in several languages.
+mod translation;
+pub use translation::ProductTranslation;
+
+/// Products are checked before they are stored.
+mod validation;
+pub use validation::{ProductError, ValidationError, ValidationWarning, MAX_COPIES};
+
+/// Fixtures for the tests of the DB and its users
+#[cfg(test)]
+pub(crate) mod test_support;
+
+/// Operator settings are stored as key-value pairs.
+mod settings;
+pub use settings::{
+    LogoUpscale, ProductColumn, ProductColumns, RedrawStrategy, RoundingMode, SettingsEntry,
+    UiCharset, UiTheme, UnderweightPolicy,
+};
+
+/// The migrations to bring a DB from version `idx + 1` to `idx + 2`.
+/// Fresh databases are created with the latest schema and don't need them.
+const MIGRATIONS: &[&str] = &[
+    // Version 2: Mark sales that have been confirmed despite the duplicate guard.
+    "ALTER TABLE sales ADD COLUMN duplicate_override INTEGER NOT NULL DEFAULT 0",
+    // Version 3: Product-specific adjustments of the voucher template.
+    "ALTER TABLE products ADD COLUMN voucher_overrides TEXT NOT NULL DEFAULT ''",
+    // Version 4: Organic certification of products.
+    "ALTER TABLE products ADD COLUMN organic_control_code TEXT;
+    ALTER TABLE products ADD COLUMN agriculture_origin TEXT;",
+    // Version 5: Origin and processing of meat products.
+    "ALTER TABLE products ADD COLUMN show_origin INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE products ADD COLUMN born_in TEXT;
+    ALTER TABLE products ADD COLUMN reared_in TEXT;
+    ALTER TABLE products ADD COLUMN slaughtered_in TEXT;
+    ALTER TABLE products ADD COLUMN cut_in TEXT;",
+    // Version 6: Sales of counted pieces.
+    "ALTER TABLE sales ADD COLUMN piece_count INTEGER",
+    // Version 7: Store the rounded total of each sale.
+    "ALTER TABLE sales ADD COLUMN total_ct INTEGER NOT NULL DEFAULT 0;
+    UPDATE sales SET total_ct = CAST(ROUND(price_ct * COALESCE(piece_count, weight_kg, 1)) AS INTEGER);",
+    // Version 8: Attribute sales to the operator and the station.
+    "ALTER TABLE sales ADD COLUMN operator TEXT;
+    ALTER TABLE sales ADD COLUMN station TEXT;",
+    // Version 9: Record price changes.
+    "CREATE TABLE IF NOT EXISTS price_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        date_2822 TEXT NOT NULL,
+        product_id INTEGER NOT NULL,
+        product_name TEXT NOT NULL,
+        old_price_ct INTEGER NOT NULL,
+        new_price_ct INTEGER NOT NULL,
+        operator TEXT
+    )",
+    // Version 10: Translations of product names and ingredients.
+    "CREATE TABLE IF NOT EXISTS product_translations (
+        product_id INTEGER NOT NULL,
+        language TEXT NOT NULL,
+        name TEXT NOT NULL,
+        ingredients TEXT NOT NULL DEFAULT '',
+        PRIMARY KEY (product_id, language)
+    )",
+    // Version 11: Note how sales have been paid.
+    "ALTER TABLE sales ADD COLUMN payment_method TEXT",
+    // Version 12: Number the receipts for customers.
+    "CREATE TABLE IF NOT EXISTS receipts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        date_2822 TEXT NOT NULL,
+        total_ct INTEGER NOT NULL
+    )",
+    // Version 13: Attribute vouchers to their sales to detect interrupted sales.
+    // DBs of version 1 do not record print jobs yet.
+    "CREATE TABLE IF NOT EXISTS print_jobs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        date_2822 TEXT NOT NULL,
+        product_name TEXT NOT NULL,
+        outcome TEXT NOT NULL,
+        error TEXT
+    );
+    ALTER TABLE print_jobs ADD COLUMN sale_date_2822 TEXT;",
+    // Version 14: Estimate the usage of label rolls.
+    "CREATE TABLE IF NOT EXISTS label_rolls (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        label_type TEXT NOT NULL,
+        inserted_2822 TEXT NOT NULL,
+        length_mm REAL NOT NULL,
+        used_mm REAL NOT NULL DEFAULT 0,
+        print_count INTEGER NOT NULL DEFAULT 0
+    )",
+    // Version 15: Print some products on more than one voucher by default.
+    "ALTER TABLE products ADD COLUMN default_copies INTEGER NOT NULL DEFAULT 1",
+    // Version 16: Trace sold weights back to the readings of the scales.
+    "CREATE TABLE IF NOT EXISTS weighings (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        sale_date_2822 TEXT NOT NULL,
+        product_name TEXT NOT NULL,
+        weight_kg REAL NOT NULL,
+        tare_kg REAL NOT NULL,
+        frame BLOB
+    )",
+    // Version 17: Reconcile the cash drawer per shift.
+    "CREATE TABLE IF NOT EXISTS shifts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        opened_2822 TEXT NOT NULL,
+        float_ct INTEGER NOT NULL,
+        operator TEXT,
+        closed_2822 TEXT,
+        expected_ct INTEGER,
+        counted_ct INTEGER,
+        difference_ct INTEGER
+    )",
+    // Version 18: Note best-before dates that have been set by the operator.
+    "ALTER TABLE sales ADD COLUMN expiration_override_2822 TEXT",
+    // Version 19: Settings that are changed at runtime (DBs of version 1 have none).
+    "CREATE TABLE IF NOT EXISTS settings (
+        key TEXT NOT NULL PRIMARY KEY,
+        value TEXT NOT NULL
+    )",
+];
+
+const DB_VERSION: u32 = 1 + (MIGRATIONS.len() as u32);
+
+/// The path of a DB that is not backed by a file
+const MEMORY_PATH: &str = ":memory:";
+
+/// Who works on which terminal (both `None` if unknown)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attribution {
+    pub operator: Option<String>,
+    pub station: Option<String>,
+}
 
-const DB_VERSION: u32 = 1;
+impl Attribution {
+    /// The user that is logged in on this terminal and its hostname.
+    /// This touches the environment and the file system, so it should be read once at startup.
+    pub fn current() -> Self {
+        let operator = env::var("USER")
+            .or_else(|_| env::var("LOGNAME"))
+            .ok()
+            .filter(|operator| !operator.is_empty());
+
+        let station = fs::read_to_string("/proc/sys/kernel/hostname")
+            .or_else(|_| fs::read_to_string("/etc/hostname"))
+            .or_else(|_| env::var("HOSTNAME"))
+            .ok()
+            .map(|station| String::from(station.trim()))
+            .filter(|station| !station.is_empty());
+
+        Self { operator, station }
+    }
+}
 
 fn non_empty_name(name: String) -> String {
     if name.is_empty() {
@@ -185,6 +370,18 @@ pub struct ProductEntry {
     pub additional_info: String,
     pub storage_temp: Option<f64>,
     pub expiration_days: Option<u64>,
+    pub voucher_overrides: String,
+    pub organic_control_code: Option<String>,
+    pub agriculture_origin: Option<String>,
+    pub show_origin: bool,
+    pub born_in: Option<String>,
+    pub reared_in: Option<String>,
+    pub slaughtered_in: Option<String>,
+    pub cut_in: Option<String>,
+
+    /// The number of vouchers that is preselected in the dialog (e.g. 2 for cheese wheels)
+    pub default_copies: u64,
+    pub translations: Vec<ProductTranslation>,
 }
 
 impl ProductEntry {
@@ -206,22 +403,66 @@ impl ProductEntry {
             additional_info,
             storage_temp,
             expiration_days,
+            voucher_overrides: String::new(),
+            organic_control_code: None,
+            agriculture_origin: None,
+            show_origin: false,
+            born_in: None,
+            reared_in: None,
+            slaughtered_in: None,
+            cut_in: None,
+            default_copies: 1,
+            translations: Vec::new(),
+        }
+    }
+
+    /// Calculate the total price for the given quantity.
+    /// Pieces win over the weight, without both, the fixed price is returned.
+    pub fn total_price_ct(
+        &self,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+        rounding_mode: RoundingMode,
+    ) -> u64 {
+        match (piece_count, weight_kg) {
+            (Some(piece_count), _) => piece_count * self.price_ct,
+            (None, Some(weight_kg)) if self.is_kg_price => {
+                rounding_mode.round(weight_kg * (self.price_ct as f64))
+            }
+            _ => self.price_ct,
         }
     }
 
+    /// The ID in the DB (`None` if the product has not been stored yet)
+    pub fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    /// An EAN-13 code for in-store use without the check digit (prefix 20, then the ID).
+    pub fn in_store_code(&self) -> Option<String> {
+        self.id
+            .filter(|&id| (0..10_000_000_000).contains(&id))
+            .map(|id| format!("20{:010}", id))
+    }
+
     pub fn storage_temp_formatted(&self) -> Option<String> {
-        self.storage_temp.map(|temp| format!("{:.1}°C", temp))
+        self.storage_temp
+            .map(|temp| format!("{}°C", format::fixed(temp, 1)))
     }
 
-    pub fn expiration_date(&self) -> Option<DateTime<Local>> {
-        self.expiration_days.map(|days| {
-            Local::now() + TimeDelta::try_days(days as _).expect("Expiration days out of bound")
-        })
+    /// The expiration date of a product that is sold at the given date.
+    pub fn expiration_date(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        self.expiration_days
+            .map(|days| now + TimeDelta::try_days(days as _).expect("Expiration days out of bound"))
+    }
+
+    pub fn expiration_date_formatted(&self, now: DateTime<Local>) -> Option<String> {
+        self.expiration_date(now).map(Self::format_expiration_date)
     }
 
-    pub fn expiration_date_formatted(&self) -> Option<String> {
-        self.expiration_date()
-            .map(|date| date.format("%d.%m.%Y %H:%M:%S").to_string())
+    /// Format an expiration date like on the vouchers.
+    pub fn format_expiration_date(date: DateTime<Local>) -> String {
+        date.format("%d.%m.%Y %H:%M:%S").to_string()
     }
 
     fn load(row: &Row) -> SQLiteResult<Self> {
@@ -234,6 +475,16 @@ impl ProductEntry {
             additional_info: row.get("additional_info")?,
             storage_temp: row.get("storage_temp")?,
             expiration_days: row.get("expiration_days")?,
+            voucher_overrides: row.get("voucher_overrides")?,
+            organic_control_code: row.get("organic_control_code")?,
+            agriculture_origin: row.get("agriculture_origin")?,
+            show_origin: row.get("show_origin")?,
+            born_in: row.get("born_in")?,
+            reared_in: row.get("reared_in")?,
+            slaughtered_in: row.get("slaughtered_in")?,
+            cut_in: row.get("cut_in")?,
+            default_copies: row.get("default_copies")?,
+            translations: Vec::new(),
         })
     }
 
@@ -247,7 +498,16 @@ impl ProductEntry {
                 ingredients,
                 additional_info,
                 storage_temp,
-                expiration_days
+                expiration_days,
+                voucher_overrides,
+                organic_control_code,
+                agriculture_origin,
+                show_origin,
+                born_in,
+                reared_in,
+                slaughtered_in,
+                cut_in,
+                default_copies
             FROM products",
         )?;
 
@@ -257,6 +517,7 @@ impl ProductEntry {
             products.push(product?);
         }
 
+        ProductTranslation::load_into(con, products)?;
         products.sort_by(|p0, p1| p0.name.cmp(&p1.name));
 
         Ok(())
@@ -268,7 +529,7 @@ impl ProductEntry {
             // However, it could have been modified from outside.
             // So we force-push our entry via `REPLACE`.
             con.execute(
-                "REPLACE INTO product (
+                "REPLACE INTO products (
                     id,
                     name,
                     price_ct,
@@ -276,7 +537,16 @@ impl ProductEntry {
                     ingredients,
                     additional_info,
                     storage_temp,
-                    expiration_days
+                    expiration_days,
+                    voucher_overrides,
+                    organic_control_code,
+                    agriculture_origin,
+                    show_origin,
+                    born_in,
+                    reared_in,
+                    slaughtered_in,
+                    cut_in,
+                    default_copies
                 ) VALUES (
                     :id,
                     :name,
@@ -285,7 +555,16 @@ impl ProductEntry {
                     :ingredients,
                     :additional_info,
                     :storage_temp,
-                    :expiration_days
+                    :expiration_days,
+                    :voucher_overrides,
+                    :organic_control_code,
+                    :agriculture_origin,
+                    :show_origin,
+                    :born_in,
+                    :reared_in,
+                    :slaughtered_in,
+                    :cut_in,
+                    :default_copies
                 )",
                 named_params! {
                     ":id": id,
@@ -296,19 +575,37 @@ impl ProductEntry {
                     ":additional_info": self.additional_info,
                     ":storage_temp": self.storage_temp,
                     ":expiration_days": self.expiration_days,
+                    ":voucher_overrides": self.voucher_overrides,
+                    ":organic_control_code": self.organic_control_code,
+                    ":agriculture_origin": self.agriculture_origin,
+                    ":show_origin": self.show_origin,
+                    ":born_in": self.born_in,
+                    ":reared_in": self.reared_in,
+                    ":slaughtered_in": self.slaughtered_in,
+                    ":cut_in": self.cut_in,
+                    ":default_copies": self.default_copies,
                 },
             )?;
         } else {
             // If there is no ID, we perform an insert and retrieve the auto-increment afterwards.
             con.execute(
-                "INSERT INTO product (
+                "INSERT INTO products (
                     name,
                     price_ct,
                     is_kg_price,
                     ingredients,
                     additional_info,
                     storage_temp,
-                    expiration_days
+                    expiration_days,
+                    voucher_overrides,
+                    organic_control_code,
+                    agriculture_origin,
+                    show_origin,
+                    born_in,
+                    reared_in,
+                    slaughtered_in,
+                    cut_in,
+                    default_copies
                 ) VALUES (
                     :name,
                     :price_ct,
@@ -316,7 +613,16 @@ impl ProductEntry {
                     :ingredients,
                     :additional_info,
                     :storage_temp,
-                    :expiration_days
+                    :expiration_days,
+                    :voucher_overrides,
+                    :organic_control_code,
+                    :agriculture_origin,
+                    :show_origin,
+                    :born_in,
+                    :reared_in,
+                    :slaughtered_in,
+                    :cut_in,
+                    :default_copies
                 )",
                 named_params! {
                     ":name": self.name,
@@ -326,12 +632,23 @@ impl ProductEntry {
                     ":additional_info": self.additional_info,
                     ":storage_temp": self.storage_temp,
                     ":expiration_days": self.expiration_days,
+                    ":voucher_overrides": self.voucher_overrides,
+                    ":organic_control_code": self.organic_control_code,
+                    ":agriculture_origin": self.agriculture_origin,
+                    ":show_origin": self.show_origin,
+                    ":born_in": self.born_in,
+                    ":reared_in": self.reared_in,
+                    ":slaughtered_in": self.slaughtered_in,
+                    ":cut_in": self.cut_in,
+                    ":default_copies": self.default_copies,
                 },
             )?;
 
             self.id = Some(con.last_insert_rowid());
         }
 
+        ProductTranslation::store_all(con, self.id.unwrap(), &self.translations)?;
+
         Ok(())
     }
 
@@ -345,30 +662,119 @@ impl ProductEntry {
             named_params! {":id": id},
         )?;
 
+        ProductTranslation::delete_all(con, id)?;
+
         Ok(())
     }
 }
 
+/// Restrict the sales in the history view
+#[derive(Clone, Default)]
+pub struct SaleFilter {
+    /// Only sales of the product with this name
+    pub product_name: Option<String>,
+
+    /// Only sales that have been confirmed despite the duplicate guard
+    pub duplicate_override: bool,
+
+    /// Only sales on or after this business day
+    pub from: Option<NaiveDate>,
+
+    /// Only sales on or before this business day
+    pub until: Option<NaiveDate>,
+}
+
+impl SaleFilter {
+    /// The condition of the filter with the parameters `:name`, `:duplicate_override`, `:from` and `:until`
+    const WHERE_CLAUSE: &'static str = "(:name IS NULL OR name = :name)
+        AND (:duplicate_override = 0 OR duplicate_override = 1)
+        AND (:from IS NULL OR unix_time_2822(date_2822) >= :from)
+        AND (:until IS NULL OR unix_time_2822(date_2822) < :until)";
+
+    /// The UNIX time of the (local) cutoff that starts the business day
+    fn start_of_day(day: NaiveDate, cutoff: NaiveTime) -> i64 {
+        let start = day.and_time(cutoff);
+
+        // Some time zones skip midnight when switching to DST.
+        Local
+            .from_local_datetime(&start)
+            .earliest()
+            .map_or_else(|| start.and_utc().timestamp(), |date| date.timestamp())
+    }
+
+    /// The values of `:from` (inclusive) and `:until` (exclusive) as UNIX times
+    fn bounds(&self, cutoff: NaiveTime) -> (Option<i64>, Option<i64>) {
+        (
+            self.from.map(|from| Self::start_of_day(from, cutoff)),
+            self.until
+                .and_then(|until| until.succ_opt())
+                .map(|until| Self::start_of_day(until, cutoff)),
+        )
+    }
+}
+
+/// The number and the total of the sales that match a filter
+#[derive(Clone, Default)]
+pub struct SalesSummary {
+    pub count: u64,
+    pub total_ct: u64,
+
+    /// The sales broken out by payment method (only the methods that occur)
+    pub payment_totals: Vec<PaymentTotal>,
+}
+
 #[derive(Clone)]
 pub struct SaleEntry {
     pub date: DateTime<Utc>,
     pub name: String,
     pub weight_kg: Option<f64>,
     pub price_ct: u64,
+    pub total_ct: u64,
+    pub duplicate_override: bool,
+    pub piece_count: Option<u64>,
+    pub operator: Option<String>,
+    pub station: Option<String>,
+    pub payment_method: Option<PaymentMethod>,
+
+    /// The best-before date that the operator has printed instead of the computed one
+    pub expiration_override: Option<DateTime<Utc>>,
 }
 
 impl SaleEntry {
-    pub fn new(date: DateTime<Utc>, name: String, weight_kg: Option<f64>, price_ct: u64) -> Self {
+    /// Create a sale that is not attributed to anyone yet (see `attributed_to()`).
+    pub fn new(
+        date: DateTime<Utc>,
+        name: String,
+        weight_kg: Option<f64>,
+        price_ct: u64,
+        total_ct: u64,
+    ) -> Self {
         Self {
             date,
             name: non_empty_name(name),
             weight_kg,
             price_ct,
+            total_ct,
+            duplicate_override: false,
+            piece_count: None,
+            operator: None,
+            station: None,
+            payment_method: None,
+            expiration_override: None,
         }
     }
 
+    /// Attribute the sale to the operator and the station.
+    pub fn attributed_to(mut self, attribution: &Attribution) -> Self {
+        self.operator = attribution.operator.clone();
+        self.station = attribution.station.clone();
+        self
+    }
+
     pub fn load(row: &Row) -> SQLiteResult<Self> {
         let date_rfc2822: String = row.get("date_2822")?;
+        let payment_method: Option<String> = row.get("payment_method")?;
+        let expiration_override_rfc2822: Option<String> = row.get("expiration_override_2822")?;
 
         Ok(Self {
             date: DateTime::parse_from_rfc2822(&date_rfc2822)
@@ -377,6 +783,17 @@ impl SaleEntry {
             name: non_empty_name(row.get("name")?),
             weight_kg: row.get("weight_kg")?,
             price_ct: row.get("price_ct")?,
+            total_ct: row.get("total_ct")?,
+            duplicate_override: row.get("duplicate_override")?,
+            piece_count: row.get("piece_count")?,
+            operator: row.get("operator")?,
+            station: row.get("station")?,
+            payment_method: payment_method.and_then(|method| method.parse().ok()),
+            expiration_override: expiration_override_rfc2822.map(|rfc2822| {
+                DateTime::parse_from_rfc2822(&rfc2822)
+                    .expect("Invalid timestamp format (expected RFC 2822)")
+                    .into()
+            }),
         })
     }
 
@@ -386,7 +803,14 @@ impl SaleEntry {
                 date_2822,
                 name,
                 weight_kg,
-                price_ct
+                price_ct,
+                total_ct,
+                duplicate_override,
+                piece_count,
+                operator,
+                station,
+                payment_method,
+                expiration_override_2822
             FROM sales",
         )?;
 
@@ -401,43 +825,367 @@ impl SaleEntry {
         Ok(())
     }
 
+    /// Load the most recently stored sale of a product at the given date (if any).
+    pub fn find(con: &Connection, date: DateTime<Utc>, name: &str) -> SQLiteResult<Option<Self>> {
+        con.query_row(
+            "SELECT
+                date_2822,
+                name,
+                weight_kg,
+                price_ct,
+                total_ct,
+                duplicate_override,
+                piece_count,
+                operator,
+                station,
+                payment_method,
+                expiration_override_2822
+            FROM sales
+            WHERE date_2822 = :date_2822 AND name = :name
+            ORDER BY id DESC
+            LIMIT 1",
+            named_params! {
+                ":date_2822": date.to_rfc2822(),
+                ":name": name,
+            },
+            Self::load,
+        )
+        .optional()
+    }
+
+    /// Load the sales between the given dates (inclusive), oldest first.
+    pub fn load_between(
+        con: &Connection,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        sales: &mut Vec<Self>,
+    ) -> SQLiteResult<()> {
+        let mut stmt = con.prepare(
+            "SELECT
+                date_2822,
+                name,
+                weight_kg,
+                price_ct,
+                total_ct,
+                duplicate_override,
+                piece_count,
+                operator,
+                station,
+                payment_method,
+                expiration_override_2822
+            FROM sales
+            WHERE unix_time_2822(date_2822) >= :from AND unix_time_2822(date_2822) <= :until
+            ORDER BY id",
+        )?;
+
+        sales.clear();
+
+        let params = named_params! {
+            ":from": from.timestamp(),
+            ":until": until.timestamp(),
+        };
+
+        for sale in stmt.query_map(params, Self::load)? {
+            sales.push(sale?);
+        }
+
+        Ok(())
+    }
+
+    /// Load the `limit` most recent sales that match the filter, newest first.
+    /// The business days of the filter start at `cutoff`.
+    pub fn load_filtered(
+        con: &Connection,
+        filter: &SaleFilter,
+        cutoff: NaiveTime,
+        limit: usize,
+        sales: &mut Vec<Self>,
+    ) -> SQLiteResult<()> {
+        let mut stmt = con.prepare(&format!(
+            "SELECT
+                date_2822,
+                name,
+                weight_kg,
+                price_ct,
+                total_ct,
+                duplicate_override,
+                piece_count,
+                operator,
+                station,
+                payment_method,
+                expiration_override_2822
+            FROM sales
+            WHERE {}
+            ORDER BY id DESC
+            LIMIT :limit",
+            SaleFilter::WHERE_CLAUSE
+        ))?;
+
+        sales.clear();
+
+        let (from, until) = filter.bounds(cutoff);
+
+        let params = named_params! {
+            ":name": filter.product_name,
+            ":duplicate_override": filter.duplicate_override,
+            ":from": from,
+            ":until": until,
+            ":limit": limit,
+        };
+
+        for sale in stmt.query_map(params, Self::load)? {
+            sales.push(sale?);
+        }
+
+        Ok(())
+    }
+
+    /// Count and sum up all sales that match the filter, in total and per payment method.
+    pub fn summarize(
+        con: &Connection,
+        filter: &SaleFilter,
+        cutoff: NaiveTime,
+    ) -> SQLiteResult<SalesSummary> {
+        let mut stmt = con.prepare(&format!(
+            "SELECT
+                payment_method,
+                COUNT(*) AS count,
+                COALESCE(SUM(total_ct), 0) AS total_ct
+            FROM sales
+            WHERE {}
+            GROUP BY payment_method",
+            SaleFilter::WHERE_CLAUSE
+        ))?;
+
+        let (from, until) = filter.bounds(cutoff);
+
+        let params = named_params! {
+            ":name": filter.product_name,
+            ":duplicate_override": filter.duplicate_override,
+            ":from": from,
+            ":until": until,
+        };
+
+        let mut summary = SalesSummary::default();
+
+        let totals = stmt.query_map(params, |row| {
+            let payment_method: Option<String> = row.get("payment_method")?;
+
+            Ok(PaymentTotal {
+                payment_method: payment_method.and_then(|method| method.parse().ok()),
+                count: row.get("count")?,
+                total_ct: row.get("total_ct")?,
+            })
+        })?;
+
+        for total in totals {
+            let total = total?;
+
+            summary.count += total.count;
+            summary.total_ct += total.total_ct;
+            summary.payment_totals.push(total);
+        }
+
+        // Keep the order of the dialog, sales without a method come last.
+        summary.payment_totals.sort_by_key(|total| {
+            total
+                .payment_method
+                .map_or(PaymentMethod::ALL.len(), |method| {
+                    PaymentMethod::ALL
+                        .iter()
+                        .position(|m| *m == method)
+                        .unwrap()
+                })
+        });
+
+        Ok(summary)
+    }
+
     pub fn store(&self, con: &Connection) -> SQLiteResult<()> {
         con.execute(
             "INSERT INTO sales (
                 date_2822,
                 name,
                 weight_kg,
-                price_ct
+                price_ct,
+                total_ct,
+                duplicate_override,
+                piece_count,
+                operator,
+                station,
+                payment_method,
+                expiration_override_2822
             ) VALUES (
                 :date_2822,
                 :name,
                 :weight_kg,
-                :price_ct
+                :price_ct,
+                :total_ct,
+                :duplicate_override,
+                :piece_count,
+                :operator,
+                :station,
+                :payment_method,
+                :expiration_override_2822
             )",
             named_params! {
                 ":date_2822": self.date.to_rfc2822(),
                 ":name": self.name,
                 ":weight_kg": self.weight_kg,
                 ":price_ct": self.price_ct,
+                ":total_ct": self.total_ct,
+                ":duplicate_override": self.duplicate_override,
+                ":piece_count": self.piece_count,
+                ":operator": self.operator,
+                ":station": self.station,
+                ":payment_method": self.payment_method.map(|method| method.to_string()),
+                ":expiration_override_2822": self
+                    .expiration_override
+                    .map(|date| date.to_rfc2822()),
             },
         )?;
 
         Ok(())
     }
+
+    /// Delete the most recently stored copy of this sale (e.g. to undo it).
+    /// Returns whether there was one.
+    pub fn delete(&self, con: &Connection) -> SQLiteResult<bool> {
+        let deleted = con.execute(
+            "DELETE FROM sales
+            WHERE id = (
+                SELECT MAX(id)
+                FROM sales
+                WHERE date_2822 = :date_2822 AND name = :name AND total_ct = :total_ct
+            )",
+            named_params! {
+                ":date_2822": self.date.to_rfc2822(),
+                ":name": self.name,
+                ":total_ct": self.total_ct,
+            },
+        )?;
+
+        Ok(deleted > 0)
+    }
 }
 
 pub struct Database {
     con: Connection,
     info: InfoEntry,
+    settings: SettingsEntry,
     products: Vec<ProductEntry>,
 }
 
 impl Database {
+    /// The file of the DB (`None` for in-memory DBs)
+    pub fn path(&self) -> Option<&Path> {
+        self.con
+            .path()
+            .filter(|path| !path.is_empty())
+            .map(Path::new)
+    }
+
+    /// The journal file of the sales that this DB could not take:
+    /// The `journal_path` setting or "sales.journal" in the directory of the DB.
+    pub fn journal_path(&self) -> PathBuf {
+        match self.settings.journal_path.as_str() {
+            "" => self
+                .path()
+                .and_then(Path::parent)
+                .unwrap_or(Path::new(""))
+                .join("sales.journal"),
+            path => PathBuf::from(path),
+        }
+    }
+
+    /// Open the DB at `path` or create a fresh one.
+    /// `:memory:` creates a DB that only lives as long as this instance (e.g. for tests).
     pub fn open_or_create<P: AsRef<Path>>(path: P) -> SQLiteResult<Self> {
         // Open the database.
-        let con = Connection::open(path.as_ref())?;
+        let path = path.as_ref();
+
+        let mut con = if path == Path::new(MEMORY_PATH) {
+            Connection::open_in_memory()?
+        } else {
+            Connection::open(path)?
+        };
+
+        // Dates are stored as RFC 2822, which cannot be compared as text.
+        con.create_scalar_function(
+            "unix_time_2822",
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let date_rfc2822: String = ctx.get(0)?;
+
+                DateTime::parse_from_rfc2822(&date_rfc2822)
+                    .map(|date| date.timestamp())
+                    .map_err(|err| SQLiteError::UserFunctionError(err.into()))
+            },
+        )?;
+
+        // Query the DB version.
+        // Without an `info` table or a row in it, this is a fresh DB.
+        let has_info = con.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'info')",
+            (),
+            |row| row.get(0),
+        )?;
+
+        let version = if has_info {
+            match con.query_row("SELECT * FROM info", (), |row| {
+                // If there is a row, but no version column, this is version 0.
+                Ok(row.get("version").unwrap_or(0))
+            }) {
+                Ok(version) => Some(version),
+                Err(SQLiteError::QueryReturnedNoRows) => None,
+                Err(err) => panic!("Failed to query version: {err:?}"),
+            }
+        } else {
+            None
+        };
+
+        match version {
+            // Validate the version if there is one.
+            // Older versions are migrated, but there is no way back from newer ones.
+            Some(version) => {
+                if (version == 0) || (version > DB_VERSION) {
+                    panic!(
+                        "Version mismatch: expected {DB_VERSION}, got {version}. Please migrate!"
+                    );
+                }
+
+                Self::migrate(&mut con, version)?;
+            }
+
+            // A fresh DB gets the latest schema and our own version right away.
+            // The `info` table must never be empty, so a dummy is inserted along with it.
+            None => {
+                let tx = con.transaction()?;
+                Self::create_tables(&tx)?;
+                InfoEntry::dummy().store_if_missing(&tx)?;
+                tx.commit()?;
+            }
+        }
+
+        let info = InfoEntry::load(&con)?;
+        let settings = SettingsEntry::load(&con)?;
+
+        // Build the DB and load the products for the first time.
+        let mut db = Self {
+            con,
+            info,
+            settings,
+            products: Vec::new(),
+        };
+
+        db.reload_products()?;
 
-        // Create the tables if they do not exist yet.
+        Ok(db)
+    }
+
+    /// Create the tables of the latest schema (for fresh DBs only, older ones are migrated).
+    fn create_tables(con: &Connection) -> SQLiteResult<()> {
         con.execute(
             "CREATE TABLE IF NOT EXISTS info (
                 _lock INTEGER NOT NULL PRIMARY KEY,
@@ -463,7 +1211,16 @@ impl Database {
                 ingredients TEXT NOT NULL,
                 additional_info TEXT NOT NULL,
                 storage_temp REAL,
-                expiration_days INTEGER
+                expiration_days INTEGER,
+                voucher_overrides TEXT NOT NULL DEFAULT '',
+                organic_control_code TEXT,
+                agriculture_origin TEXT,
+                show_origin INTEGER NOT NULL DEFAULT 0,
+                born_in TEXT,
+                reared_in TEXT,
+                slaughtered_in TEXT,
+                cut_in TEXT,
+                default_copies INTEGER NOT NULL DEFAULT 1
             )",
             (),
         )?;
@@ -474,48 +1231,161 @@ impl Database {
                 date_2822 TEXT NOT NULL,
                 name TEXT NOT NULL,
                 weight_kg REAL,
-                price_ct INTEGER NOT NULL
+                price_ct INTEGER NOT NULL,
+                total_ct INTEGER NOT NULL DEFAULT 0,
+                duplicate_override INTEGER NOT NULL DEFAULT 0,
+                piece_count INTEGER,
+                operator TEXT,
+                station TEXT,
+                payment_method TEXT,
+                expiration_override_2822 TEXT
             )",
             (),
         )?;
 
-        // Query the DB version.
-        match con.query_row("SELECT * FROM info", (), |row| {
-            // If there is a row, but no version column, this is version 0.
-            Ok(row.get("version").unwrap_or(0))
-        }) {
-            // Validate the version if there is one.
-            Ok(version) => {
-                if version != DB_VERSION {
-                    panic!(
-                        "Version mismatch: expected {DB_VERSION}, got {version}. Please migrate!"
-                    );
-                }
-            }
+        con.execute(
+            "CREATE TABLE IF NOT EXISTS print_jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                date_2822 TEXT NOT NULL,
+                product_name TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                error TEXT,
+                sale_date_2822 TEXT
+            )",
+            (),
+        )?;
 
-            Err(err) => {
-                // If there is no row yet, this is a fresh DB and we can set our own version.
-                if err != SQLiteError::QueryReturnedNoRows {
-                    panic!("Failed to query version: {err:?}")
-                }
-            }
-        };
+        con.execute(
+            "CREATE TABLE IF NOT EXISTS price_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                date_2822 TEXT NOT NULL,
+                product_id INTEGER NOT NULL,
+                product_name TEXT NOT NULL,
+                old_price_ct INTEGER NOT NULL,
+                new_price_ct INTEGER NOT NULL,
+                operator TEXT
+            )",
+            (),
+        )?;
 
-        // The `info` table must never be empty.
-        // Insert a dummy if necessary before loading it.
-        InfoEntry::dummy().store_if_missing(&con)?;
-        let info = InfoEntry::load(&con)?;
+        con.execute(
+            "CREATE TABLE IF NOT EXISTS product_translations (
+                product_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                name TEXT NOT NULL,
+                ingredients TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (product_id, language)
+            )",
+            (),
+        )?;
 
-        // Build the DB and load the products for the first time.
-        let mut db = Self {
-            con,
-            info,
-            products: Vec::new(),
-        };
+        con.execute(
+            "CREATE TABLE IF NOT EXISTS receipts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                date_2822 TEXT NOT NULL,
+                total_ct INTEGER NOT NULL
+            )",
+            (),
+        )?;
 
-        db.reload_products()?;
+        con.execute(
+            "CREATE TABLE IF NOT EXISTS label_rolls (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                label_type TEXT NOT NULL,
+                inserted_2822 TEXT NOT NULL,
+                length_mm REAL NOT NULL,
+                used_mm REAL NOT NULL DEFAULT 0,
+                print_count INTEGER NOT NULL DEFAULT 0
+            )",
+            (),
+        )?;
 
-        Ok(db)
+        con.execute(
+            "CREATE TABLE IF NOT EXISTS weighings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                sale_date_2822 TEXT NOT NULL,
+                product_name TEXT NOT NULL,
+                weight_kg REAL NOT NULL,
+                tare_kg REAL NOT NULL,
+                frame BLOB
+            )",
+            (),
+        )?;
+
+        con.execute(
+            "CREATE TABLE IF NOT EXISTS shifts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                opened_2822 TEXT NOT NULL,
+                float_ct INTEGER NOT NULL,
+                operator TEXT,
+                closed_2822 TEXT,
+                expected_ct INTEGER,
+                counted_ct INTEGER,
+                difference_ct INTEGER
+            )",
+            (),
+        )?;
+
+        con.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT NOT NULL PRIMARY KEY,
+                value TEXT NOT NULL
+            )",
+            (),
+        )?;
+
+        Ok(())
+    }
+
+    /// Run the pending migrations and bump the version.
+    /// This happens in a single transaction, so a failed migration leaves the DB at its old version.
+    fn migrate(con: &mut Connection, version: u32) -> SQLiteResult<()> {
+        let tx = con.transaction()?;
+
+        for migration in &MIGRATIONS[((version as usize) - 1)..] {
+            tx.execute_batch(migration)?;
+        }
+
+        tx.execute(
+            "UPDATE info SET version = :version",
+            named_params! {":version": DB_VERSION},
+        )?;
+
+        tx.commit()
+    }
+
+    /// Check if the DB is still accessible.
+    pub fn check(&self) -> SQLiteResult<()> {
+        self.con
+            .query_row("SELECT version FROM info", (), |_| Ok(()))
+    }
+
+    /// A counter that changes whenever another connection (e.g. the `sqlite3` shell) commits a change.
+    /// Changes of this connection don't count, they are applied to the loaded data anyway.
+    pub fn data_version(&self) -> SQLiteResult<u64> {
+        self.con
+            .query_row("PRAGMA data_version", (), |row| row.get(0))
+    }
+
+    /// The version of the DB layout
+    pub fn version(&self) -> SQLiteResult<u32> {
+        self.con
+            .query_row("SELECT version FROM info", (), |row| row.get("version"))
+    }
+
+    /// Load the statements that create the tables and indices.
+    pub fn schema(&self, statements: &mut Vec<String>) -> SQLiteResult<()> {
+        let mut stmt = self
+            .con
+            .prepare("SELECT sql FROM sqlite_master WHERE sql IS NOT NULL ORDER BY name")?;
+
+        statements.clear();
+
+        for statement in stmt.query_map((), |row| row.get("sql"))? {
+            statements.push(statement?);
+        }
+
+        Ok(())
     }
 
     pub fn info(&self) -> &InfoEntry {
@@ -534,6 +1404,22 @@ impl Database {
         Ok(())
     }
 
+    pub fn settings(&self) -> &SettingsEntry {
+        &self.settings
+    }
+
+    pub fn reload_settings(&mut self) -> SQLiteResult<()> {
+        self.settings = SettingsEntry::load(&self.con)?;
+        Ok(())
+    }
+
+    pub fn update_settings<F: FnMut(&mut SettingsEntry)>(&mut self, mut f: F) -> SQLiteResult<()> {
+        f(&mut self.settings);
+        self.settings.store(&self.con)?;
+
+        Ok(())
+    }
+
     pub fn products(&self) -> &[ProductEntry] {
         &self.products
     }
@@ -543,23 +1429,95 @@ impl Database {
         Ok(())
     }
 
-    pub fn add_product(&mut self, new_product: ProductEntry) -> SQLiteResult<()> {
+    pub fn add_product(&mut self, new_product: ProductEntry) -> Result<(), ProductError> {
+        new_product.validate().map_err(ProductError::Invalid)?;
+
         self.products.push(new_product);
         self.products.last_mut().unwrap().store(&self.con)?;
 
         Ok(())
     }
 
+    /// Change a product. Invalid changes are not applied.
     pub fn update_product<F: FnMut(&mut ProductEntry)>(
         &mut self,
         idx: usize,
         mut f: F,
-    ) -> SQLiteResult<()> {
-        let product = &mut self.products[idx];
+    ) -> Result<(), ProductError> {
+        let mut product = self.products[idx].clone();
 
-        f(product);
+        f(&mut product);
+        product.validate().map_err(ProductError::Invalid)?;
         product.store(&self.con)?;
 
+        self.products[idx] = product;
+
+        Ok(())
+    }
+
+    /// Store a copy of a product under a new name (e.g. another cut of the same meat).
+    /// Returns the ID of the copy.
+    pub fn duplicate_product(&mut self, idx: usize, name: String) -> Result<i64, ProductError> {
+        let mut copy = self.products[idx].clone();
+        copy.id = None;
+        copy.name = non_empty_name(name);
+
+        self.add_product(copy)?;
+
+        let id = self.products.last().and_then(ProductEntry::id);
+
+        // Keep the order of `reload_products()`.
+        self.products.sort_by(|p0, p1| p0.name.cmp(&p1.name));
+
+        Ok(id.expect("Stored products have an ID"))
+    }
+
+    /// Change the price of a product and record the change (by the operator) in the price history.
+    pub fn update_product_price(
+        &mut self,
+        idx: usize,
+        price_ct: u64,
+        date: DateTime<Utc>,
+        operator: Option<String>,
+    ) -> Result<(), ProductError> {
+        let old_price_ct = self.products[idx].price_ct;
+        self.update_product(idx, |product| product.price_ct = price_ct)?;
+
+        let product = &self.products[idx];
+
+        let change = PriceChangeEntry {
+            date,
+            product_id: product.id.expect("Stored products have an ID"),
+            product_name: product.name.clone(),
+            old_price_ct,
+            new_price_ct: price_ct,
+            operator,
+        };
+
+        change.store(&self.con)?;
+
+        Ok(())
+    }
+
+    /// The languages that at least one product has been translated to
+    pub fn translation_languages(&self) -> Vec<String> {
+        let languages: BTreeSet<&String> = self
+            .products
+            .iter()
+            .flat_map(|product| &product.translations)
+            .map(|translation| &translation.language)
+            .collect();
+
+        languages.into_iter().cloned().collect()
+    }
+
+    /// Load the `limit` most recent price changes, newest first.
+    pub fn price_history(
+        &self,
+        limit: usize,
+        changes: &mut Vec<PriceChangeEntry>,
+    ) -> SQLiteResult<()> {
+        PriceChangeEntry::load_recent(&self.con, limit, changes)?;
         Ok(())
     }
 
@@ -575,8 +1533,1020 @@ impl Database {
         Ok(())
     }
 
-    pub fn add_sale(&self, new_sale: &SaleEntry) -> SQLiteResult<()> {
+    /// Load the `limit` most recent sales that match the filter, newest first.
+    pub fn filtered_sales(
+        &self,
+        filter: &SaleFilter,
+        limit: usize,
+        sales: &mut Vec<SaleEntry>,
+    ) -> SQLiteResult<()> {
+        let cutoff = self.settings.business_day_cutoff;
+        SaleEntry::load_filtered(&self.con, filter, cutoff, limit, sales)
+    }
+
+    /// Count and sum up all sales that match the filter (not only the loaded ones).
+    pub fn sales_summary(&self, filter: &SaleFilter) -> SQLiteResult<SalesSummary> {
+        SaleEntry::summarize(&self.con, filter, self.settings.business_day_cutoff)
+    }
+
+    pub fn add_sale(&mut self, new_sale: &SaleEntry) -> SQLiteResult<()> {
         new_sale.store(&self.con)?;
         Ok(())
     }
+
+    /// The sale of a product at the given date (see `SaleEntry::find()`)
+    pub fn find_sale(&self, date: DateTime<Utc>, name: &str) -> SQLiteResult<Option<SaleEntry>> {
+        SaleEntry::find(&self.con, date, name)
+    }
+
+    /// Take back a sale that has just been stored. Returns whether it was found.
+    pub fn remove_sale(&mut self, sale: &SaleEntry) -> SQLiteResult<bool> {
+        sale.delete(&self.con)
+    }
+
+    /// Load the `limit` most recent print jobs, newest first.
+    pub fn print_jobs(&self, limit: usize, jobs: &mut Vec<PrintJobEntry>) -> SQLiteResult<()> {
+        PrintJobEntry::load_recent(&self.con, limit, jobs)?;
+        Ok(())
+    }
+
+    pub fn add_print_job(&self, new_job: &PrintJobEntry) -> SQLiteResult<()> {
+        new_job.store(&self.con)?;
+        Ok(())
+    }
+
+    /// The roll of the label type that has been inserted last (if any)
+    pub fn label_roll(&self, label_type: LabelType) -> SQLiteResult<Option<LabelRollEntry>> {
+        LabelRollEntry::load_current(&self.con, label_type)
+    }
+
+    /// Load all label rolls, newest first.
+    pub fn label_rolls(&self, rolls: &mut Vec<LabelRollEntry>) -> SQLiteResult<()> {
+        LabelRollEntry::load_all(&self.con, rolls)
+    }
+
+    /// Record that a new roll has been inserted.
+    pub fn add_label_roll(&self, new_roll: &LabelRollEntry) -> SQLiteResult<()> {
+        new_roll.store(&self.con)
+    }
+
+    /// Add a print to the current roll of the label type and return the updated roll.
+    /// If no roll has been recorded yet, a new one of the configured length is assumed.
+    pub fn consume_label(
+        &self,
+        label_type: LabelType,
+        date: DateTime<Utc>,
+        used_mm: f64,
+    ) -> SQLiteResult<LabelRollEntry> {
+        if LabelRollEntry::load_current(&self.con, label_type)?.is_none() {
+            let length_mm = 1000.0 * self.settings.label_roll_length_m;
+            LabelRollEntry::new(label_type, date, length_mm).store(&self.con)?;
+        }
+
+        LabelRollEntry::add_usage(&self.con, label_type, used_mm)?;
+
+        Ok(LabelRollEntry::load_current(&self.con, label_type)?
+            .expect("The roll has been stored above"))
+    }
+
+    /// Record a receipt for a customer and return its number.
+    /// Numbers are never reused, even if the receipt could not be printed.
+    pub fn add_receipt(&self, date: DateTime<Utc>, total_ct: u64) -> SQLiteResult<u64> {
+        self.con.execute(
+            "INSERT INTO receipts (date_2822, total_ct) VALUES (:date_2822, :total_ct)",
+            named_params! {
+                ":date_2822": date.to_rfc2822(),
+                ":total_ct": total_ct,
+            },
+        )?;
+
+        Ok(self.con.last_insert_rowid() as u64)
+    }
+
+    /// The shift that has been opened, but not yet closed (if any)
+    pub fn current_shift(&self) -> SQLiteResult<Option<ShiftEntry>> {
+        ShiftEntry::load_open(&self.con)
+    }
+
+    /// Load the `limit` most recent shifts, newest first.
+    pub fn shifts(&self, limit: usize, shifts: &mut Vec<ShiftEntry>) -> SQLiteResult<()> {
+        ShiftEntry::load_recent(&self.con, limit, shifts)
+    }
+
+    /// Record that a shift has been opened with a float.
+    pub fn open_shift(&self, new_shift: &ShiftEntry) -> SQLiteResult<()> {
+        new_shift.store(&self.con)
+    }
+
+    /// Close the current shift with the counted cash and return it (`None` if there is none).
+    /// The counted cash is compared with the float plus the cash sales since the shift has been opened.
+    pub fn close_shift(
+        &self,
+        date: DateTime<Utc>,
+        counted_ct: u64,
+    ) -> SQLiteResult<Option<ShiftEntry>> {
+        let Some(mut shift) = ShiftEntry::load_open(&self.con)? else {
+            return Ok(None);
+        };
+
+        let cash_sales_ct = ShiftEntry::cash_sales_ct(&self.con, shift.opened, date)?;
+
+        let closing = ShiftClosing {
+            closed: date,
+            expected_ct: shift.float_ct + cash_sales_ct,
+            counted_ct,
+        };
+
+        ShiftEntry::close(&self.con, &closing)?;
+        shift.closing = Some(closing);
+
+        Ok(Some(shift))
+    }
+
+    /// Load the sales of a shift (until now if it is still open), oldest first.
+    pub fn shift_sales(
+        &self,
+        shift: &ShiftEntry,
+        now: DateTime<Utc>,
+        sales: &mut Vec<SaleEntry>,
+    ) -> SQLiteResult<()> {
+        let until = shift.closing.map_or(now, |closing| closing.closed);
+        SaleEntry::load_between(&self.con, shift.opened, until, sales)
+    }
+
+    /// Load the `limit` most recent weighings, newest first.
+    pub fn weighings(&self, limit: usize, weighings: &mut Vec<WeighingEntry>) -> SQLiteResult<()> {
+        WeighingEntry::load_recent(&self.con, limit, weighings)
+    }
+
+    /// Record the reading behind a sold weight.
+    /// The log is kept short, so older weighings are deleted (unless the retention is 0).
+    pub fn add_weighing(&self, weighing: &WeighingEntry) -> SQLiteResult<()> {
+        let retention_days = self.settings.weighing_log_days;
+
+        if retention_days == 0 {
+            return Ok(());
+        }
+
+        weighing.store(&self.con)?;
+
+        let retention =
+            TimeDelta::try_days(retention_days as _).expect("Weighing log retention out of bound");
+        WeighingEntry::delete_before(&self.con, weighing.sale_date - retention)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{date, memory_db, ProductBuilder, SaleBuilder};
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    #[test]
+    fn fresh_memory_db() {
+        let db = memory_db();
+
+        assert_eq!(db.version().unwrap(), DB_VERSION);
+        assert!(db.products().is_empty());
+        assert!(db.check().is_ok());
+
+        // Every instance gets its own DB.
+        let mut other_db = memory_db();
+        ProductBuilder::new("Rinderhack").add_to(&mut other_db);
+
+        assert!(db.products().is_empty());
+        assert_eq!(other_db.products().len(), 1);
+    }
+
+    /// The schema of version 1 (e.g. the `db.sqlite` that is shipped with the repo) with a product and a sale
+    const SCHEMA_V1: &str = "
+        CREATE TABLE info (
+            _lock INTEGER NOT NULL PRIMARY KEY,
+            version INTEGER NOT NULL,
+            business TEXT NOT NULL,
+            owners TEXT NOT NULL,
+            street TEXT NOT NULL,
+            locality TEXT NOT NULL,
+            phone TEXT NOT NULL,
+            mail TEXT NOT NULL,
+            serial_port TEXT NOT NULL,
+            printer_model TEXT
+        );
+        CREATE TABLE products (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            price_ct INTEGER NOT NULL,
+            is_kg_price INTEGER NOT NULL,
+            ingredients TEXT NOT NULL,
+            additional_info TEXT NOT NULL,
+            storage_temp REAL,
+            expiration_days INTEGER
+        );
+        CREATE TABLE sales (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date_2822 TEXT NOT NULL,
+            name TEXT NOT NULL,
+            weight_kg REAL,
+            price_ct INTEGER NOT NULL
+        );
+        INSERT INTO info VALUES (0, 1, 'Hof', 'Jan', 'Weg 1', 'Dorf', '0', '@', '/dev/ttyUSB0', NULL);
+        INSERT INTO products (name, price_ct, is_kg_price, ingredients, additional_info)
+            VALUES ('Rinderhack', 1299, 1, 'Rind', '');
+        INSERT INTO sales (date_2822, name, weight_kg, price_ct)
+            VALUES ('Sun, 1 Sep 2024 10:00:00 +0000', 'Rinderhack', 0.5, 1299);
+    ";
+
+    /// The columns of all tables, sorted by table and column (migrations append their columns)
+    fn schema(db: &Database) -> Vec<(String, String, String, bool, Option<String>)> {
+        let mut stmt = db
+            .con
+            .prepare(
+                "SELECT m.name, p.name, p.type, p.\"notnull\", p.dflt_value
+                FROM sqlite_master AS m JOIN pragma_table_info(m.name) AS p
+                WHERE m.type = 'table'
+                ORDER BY m.name, p.name",
+            )
+            .unwrap();
+
+        stmt.query_map((), |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })
+        .unwrap()
+        .collect::<SQLiteResult<_>>()
+        .unwrap()
+    }
+
+    /// A DB of version 1 in the temp dir
+    fn db_v1(name: &str) -> PathBuf {
+        let path =
+            env::temp_dir().join(format!("weight-wb-{}-{}.sqlite", name, std::process::id()));
+        _ = fs::remove_file(&path);
+
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(SCHEMA_V1)
+            .unwrap();
+
+        path
+    }
+
+    #[test]
+    fn db_of_version_1_is_migrated() {
+        let path = db_v1("migrate");
+
+        let db = Database::open_or_create(&path).unwrap();
+        assert_eq!(db.version().unwrap(), DB_VERSION);
+        assert_eq!(schema(&db), schema(&memory_db()));
+
+        // The data survives and gets the defaults of the new columns.
+        assert_eq!(db.products().len(), 1);
+        assert_eq!(db.products()[0].name, "Rinderhack");
+        assert_eq!(db.products()[0].default_copies, 1);
+
+        let mut sales = Vec::new();
+        db.sales(&mut sales).unwrap();
+        assert_eq!(sales.len(), 1);
+        assert_eq!(sales[0].total_ct, 650);
+        assert_eq!(sales[0].operator, None);
+
+        // Opening it again does not migrate anything.
+        drop(db);
+        let db = Database::open_or_create(&path).unwrap();
+        assert_eq!(db.version().unwrap(), DB_VERSION);
+
+        drop(db);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_migrations_are_rolled_back() {
+        // The print jobs of version 13 already exist, so adding their column fails.
+        let path = db_v1("rollback");
+        let con = Connection::open(&path).unwrap();
+        con.execute_batch("CREATE TABLE print_jobs (id INTEGER PRIMARY KEY, sale_date_2822 TEXT)")
+            .unwrap();
+
+        assert!(Database::open_or_create(&path).is_err());
+
+        // The earlier migrations have been rolled back as well.
+        let version: u32 = con
+            .query_row("SELECT version FROM info", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, 1);
+        assert!(con
+            .query_row("SELECT duplicate_override FROM sales", (), |_| Ok(()))
+            .is_err());
+
+        // Once the cause is gone, the DB is migrated from version 1 again.
+        con.execute_batch("DROP TABLE print_jobs").unwrap();
+        let db = Database::open_or_create(&path).unwrap();
+        assert_eq!(db.version().unwrap(), DB_VERSION);
+        assert_eq!(schema(&db), schema(&memory_db()));
+
+        drop((con, db));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn products_are_sorted_by_name() {
+        let mut db = memory_db();
+
+        ProductBuilder::new("Wildschweinsülze").add_to(&mut db);
+        ProductBuilder::new("Bio-Bananen").add_to(&mut db);
+        ProductBuilder::new("Rinderhack").add_to(&mut db);
+
+        let names: Vec<_> = db.products().iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Bio-Bananen", "Rinderhack", "Wildschweinsülze"]);
+        assert!(db.products().iter().all(|p| p.id().is_some()));
+    }
+
+    #[test]
+    fn weighed_and_counted_totals() {
+        let product = ProductBuilder::new("Rinderhack").price_ct(1299).build();
+
+        // 0.347 kg * 12.99 € = 4.50753 €
+        assert_eq!(
+            product.total_price_ct(Some(0.347), None, RoundingMode::Cent),
+            451
+        );
+
+        assert_eq!(
+            product.total_price_ct(Some(0.347), None, RoundingMode::FiveCents),
+            450
+        );
+
+        assert_eq!(
+            product.total_price_ct(Some(0.347), None, RoundingMode::Down),
+            450
+        );
+
+        // Pieces win over the weight.
+        assert_eq!(
+            product.total_price_ct(Some(0.347), Some(3), RoundingMode::Cent),
+            3897
+        );
+
+        let fixed = ProductBuilder::new("Sülze").fixed_price().build();
+        assert_eq!(
+            fixed.total_price_ct(Some(0.347), None, RoundingMode::Cent),
+            1000
+        );
+    }
+
+    #[test]
+    fn weights_are_rounded_to_the_step() {
+        let settings = SettingsEntry {
+            weight_step_g: 5,
+            weight_decimals: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(settings.round_weight(0.347), 0.345);
+        assert_eq!(settings.round_weight(0.348), 0.35);
+        assert_eq!(settings.format_weight(0.35), "0,350 kg");
+    }
+
+    #[test]
+    fn sales_roundtrip() {
+        let mut db = memory_db();
+        let idx = ProductBuilder::new("Rinderhack").add_to(&mut db);
+        let product = db.products()[idx].clone();
+
+        let weighed = SaleBuilder::new(&product, date(1, 9))
+            .weight_kg(0.5)
+            .add_to(&mut db);
+
+        let counted = SaleBuilder::new(&product, date(1, 10))
+            .piece_count(3)
+            .add_to(&mut db);
+
+        let mut sales = Vec::new();
+        db.sales(&mut sales).unwrap();
+
+        assert_eq!(sales.len(), 2);
+
+        for (loaded, stored) in sales.iter().zip([&weighed, &counted]) {
+            assert_eq!(loaded.date, stored.date);
+            assert_eq!(loaded.name, stored.name);
+            assert_eq!(loaded.weight_kg, stored.weight_kg);
+            assert_eq!(loaded.piece_count, stored.piece_count);
+            assert_eq!(loaded.total_ct, stored.total_ct);
+            assert_eq!(loaded.operator, stored.operator);
+            assert_eq!(loaded.station, stored.station);
+        }
+
+        assert_eq!(sales[0].total_ct, 500);
+        assert_eq!(sales[1].total_ct, 3000);
+    }
+
+    #[test]
+    fn scale_verification_reminder() {
+        let mut db = memory_db();
+        let verified = NaiveDate::from_ymd_opt(2022, 6, 15).unwrap();
+
+        db.update_settings(|settings| settings.scale_verification_date = Some(verified))
+            .unwrap();
+
+        // The date survives the roundtrip through the key-value pairs.
+        let settings = SettingsEntry::load(&db.con).unwrap();
+        let due = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+
+        assert_eq!(settings.scale_verification_date, Some(verified));
+        assert_eq!(settings.scale_verification_due(), Some(due));
+
+        // The reminder starts 30 days before and stays until the scales have been verified again.
+        assert!(!settings.is_scale_verification_due(date(15, 0).date_naive()));
+        assert!(settings.is_scale_verification_due(date(16, 0).date_naive()));
+        assert!(settings.is_scale_verification_due(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()));
+
+        // Without a date, there is nothing to remind of.
+        db.update_settings(|settings| settings.scale_verification_date = None)
+            .unwrap();
+
+        let settings = SettingsEntry::load(&db.con).unwrap();
+        assert_eq!(settings.scale_verification_due(), None);
+    }
+
+    #[test]
+    fn removed_sales_are_gone() {
+        let mut db = memory_db();
+        let idx = ProductBuilder::new("Rinderhack").add_to(&mut db);
+        let product = db.products()[idx].clone();
+
+        // Two identical sales (e.g. a confirmed duplicate): Only the latest one is removed.
+        let sale = SaleBuilder::new(&product, date(1, 9))
+            .weight_kg(0.5)
+            .add_to(&mut db);
+        SaleBuilder::new(&product, date(1, 9))
+            .weight_kg(0.5)
+            .duplicate_override()
+            .add_to(&mut db);
+
+        assert!(db.remove_sale(&sale).unwrap());
+
+        let mut sales = Vec::new();
+        db.sales(&mut sales).unwrap();
+
+        assert_eq!(sales.len(), 1);
+        assert!(!sales[0].duplicate_override);
+
+        assert!(db.remove_sale(&sale).unwrap());
+        assert!(!db.remove_sale(&sale).unwrap());
+    }
+
+    #[test]
+    fn receipts_are_numbered() {
+        let db = memory_db();
+
+        assert_eq!(db.add_receipt(date(1, 9), 745).unwrap(), 1);
+        assert_eq!(db.add_receipt(date(1, 10), 300).unwrap(), 2);
+    }
+
+    #[test]
+    fn sales_are_filtered_by_date() {
+        let mut db = memory_db();
+        let idx = ProductBuilder::new("Rinderhack").add_to(&mut db);
+        let product = db.products()[idx].clone();
+
+        // Noon is on the same local day in all common time zones.
+        for day in 1..=4 {
+            SaleBuilder::new(&product, date(day, 12))
+                .weight_kg(0.5)
+                .add_to(&mut db);
+        }
+
+        let filter = SaleFilter {
+            from: NaiveDate::from_ymd_opt(2024, 5, 2),
+            until: NaiveDate::from_ymd_opt(2024, 5, 3),
+            ..Default::default()
+        };
+
+        let mut sales = Vec::new();
+        db.filtered_sales(&filter, 10, &mut sales).unwrap();
+
+        let dates: Vec<_> = sales.iter().map(|sale| sale.date).collect();
+        assert_eq!(dates, [date(3, 12), date(2, 12)]);
+
+        let summary = db.sales_summary(&filter).unwrap();
+        assert_eq!((summary.count, summary.total_ct), (2, 1000));
+
+        // Open ranges
+        let filter = SaleFilter {
+            from: NaiveDate::from_ymd_opt(2024, 5, 4),
+            ..Default::default()
+        };
+
+        assert_eq!(db.sales_summary(&filter).unwrap().count, 1);
+    }
+
+    #[test]
+    fn business_days_start_at_the_cutoff() {
+        let mut db = memory_db();
+        let idx = ProductBuilder::new("Rinderhack").add_to(&mut db);
+        let product = db.products()[idx].clone();
+
+        db.update_settings(|settings| {
+            settings.business_day_cutoff = NaiveTime::from_hms_opt(4, 0, 0).unwrap()
+        })
+        .unwrap();
+
+        // The evening market ends after midnight.
+        let local = |day, hour| Local.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap();
+
+        for (day, hour) in [(2, 23), (3, 2), (3, 5)] {
+            SaleBuilder::new(&product, local(day, hour).with_timezone(&Utc))
+                .weight_kg(0.5)
+                .add_to(&mut db);
+        }
+
+        let day = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+        let filter = SaleFilter {
+            from: Some(day),
+            until: Some(day),
+            ..Default::default()
+        };
+
+        assert_eq!(db.sales_summary(&filter).unwrap().count, 2);
+        assert_eq!(db.settings().business_day(local(3, 2)), day);
+        assert_eq!(
+            db.settings().business_day(local(3, 5)),
+            day.succ_opt().unwrap()
+        );
+    }
+
+    #[test]
+    fn expiration_overrides_are_stored_with_sales() {
+        let mut db = memory_db();
+        let idx = ProductBuilder::new("Rinderhack")
+            .expiration_days(Some(3))
+            .add_to(&mut db);
+        let product = db.products()[idx].clone();
+
+        // Packed a day earlier for a pre-order
+        let mut sale = SaleBuilder::new(&product, date(2, 9))
+            .weight_kg(0.5)
+            .build();
+        sale.expiration_override = Some(date(4, 9));
+        db.add_sale(&sale).unwrap();
+
+        SaleBuilder::new(&product, date(2, 10)).add_to(&mut db);
+
+        let mut sales = Vec::new();
+        db.sales(&mut sales).unwrap();
+
+        assert_eq!(sales[0].expiration_override, Some(date(4, 9)));
+        assert_eq!(sales[1].expiration_override, None);
+    }
+
+    #[test]
+    fn sales_are_summarized_by_payment_method() {
+        let mut db = memory_db();
+        let idx = ProductBuilder::new("Rinderhack").add_to(&mut db);
+        let product = db.products()[idx].clone();
+
+        for (hour, payment_method) in [
+            (9, Some(PaymentMethod::Card)),
+            (10, None),
+            (11, Some(PaymentMethod::Cash)),
+            (12, Some(PaymentMethod::Card)),
+        ] {
+            let builder = SaleBuilder::new(&product, date(1, hour)).weight_kg(0.5);
+
+            match payment_method {
+                Some(payment_method) => builder.payment_method(payment_method).add_to(&mut db),
+                None => builder.add_to(&mut db),
+            };
+        }
+
+        let mut sales = Vec::new();
+        db.sales(&mut sales).unwrap();
+
+        assert_eq!(sales[0].payment_method, Some(PaymentMethod::Card));
+        assert_eq!(sales[1].payment_method, None);
+
+        // Cash first, sales without a method last
+        let summary = db.sales_summary(&SaleFilter::default()).unwrap();
+        let total = |payment_method, count, total_ct| PaymentTotal {
+            payment_method,
+            count,
+            total_ct,
+        };
+
+        assert_eq!((summary.count, summary.total_ct), (4, 2000));
+        assert_eq!(
+            summary.payment_totals,
+            [
+                total(Some(PaymentMethod::Cash), 1, 500),
+                total(Some(PaymentMethod::Card), 2, 1000),
+                total(None, 1, 500),
+            ]
+        );
+    }
+
+    #[test]
+    fn filtered_sales_and_summary() {
+        let mut db = memory_db();
+        let hack_idx = ProductBuilder::new("Rinderhack").add_to(&mut db);
+        let ham_idx = ProductBuilder::new("Rinderschinken").add_to(&mut db);
+        let (hack, ham) = (
+            db.products()[hack_idx].clone(),
+            db.products()[ham_idx].clone(),
+        );
+
+        SaleBuilder::new(&hack, date(1, 9))
+            .weight_kg(0.5)
+            .add_to(&mut db);
+        SaleBuilder::new(&ham, date(1, 10))
+            .weight_kg(0.2)
+            .add_to(&mut db);
+
+        SaleBuilder::new(&hack, date(1, 11))
+            .weight_kg(0.5)
+            .duplicate_override()
+            .add_to(&mut db);
+
+        // Everything, newest first
+        let mut sales = Vec::new();
+        db.filtered_sales(&SaleFilter::default(), 10, &mut sales)
+            .unwrap();
+
+        let dates: Vec<_> = sales.iter().map(|sale| sale.date).collect();
+        assert_eq!(dates, [date(1, 11), date(1, 10), date(1, 9)]);
+
+        let summary = db.sales_summary(&SaleFilter::default()).unwrap();
+        assert_eq!((summary.count, summary.total_ct), (3, 1200));
+
+        // The limit only affects the loaded sales, not the summary.
+        db.filtered_sales(&SaleFilter::default(), 1, &mut sales)
+            .unwrap();
+
+        assert_eq!(sales.len(), 1);
+
+        // By product
+        let filter = SaleFilter {
+            product_name: Some(hack.name.clone()),
+            ..Default::default()
+        };
+
+        db.filtered_sales(&filter, 10, &mut sales).unwrap();
+        assert!(sales.iter().all(|sale| sale.name == hack.name));
+
+        let summary = db.sales_summary(&filter).unwrap();
+        assert_eq!((summary.count, summary.total_ct), (2, 1000));
+
+        // Duplicate overrides only
+        let filter = SaleFilter {
+            duplicate_override: true,
+            ..Default::default()
+        };
+
+        db.filtered_sales(&filter, 10, &mut sales).unwrap();
+        assert_eq!(sales.len(), 1);
+        assert!(sales[0].duplicate_override);
+
+        let summary = db.sales_summary(&filter).unwrap();
+        assert_eq!((summary.count, summary.total_ct), (1, 500));
+    }
+
+    #[test]
+    fn price_changes_are_recorded() {
+        let mut db = memory_db();
+        let idx = ProductBuilder::new("Rinderhack").add_to(&mut db);
+
+        db.update_product_price(idx, 1199, date(2, 8), None)
+            .unwrap();
+        db.update_product_price(idx, 1249, date(3, 8), Some(String::from("anna")))
+            .unwrap();
+        db.reload_products().unwrap();
+
+        assert_eq!(db.products()[idx].price_ct, 1249);
+
+        let mut changes = Vec::new();
+        db.price_history(10, &mut changes).unwrap();
+
+        let prices: Vec<_> = changes
+            .iter()
+            .map(|change| (change.date, change.old_price_ct, change.new_price_ct))
+            .collect();
+
+        assert_eq!(prices, [(date(3, 8), 1199, 1249), (date(2, 8), 1000, 1199)]);
+        assert!(changes
+            .iter()
+            .all(|change| change.product_name == "Rinderhack"));
+
+        let operators: Vec<_> = changes
+            .iter()
+            .map(|change| change.operator.as_deref())
+            .collect();
+        assert_eq!(operators, [Some("anna"), None]);
+    }
+
+    #[test]
+    fn duplicated_products_are_independent() {
+        let mut db = memory_db();
+
+        let idx = ProductBuilder::new("Rinderhack")
+            .ingredients("Rind, Salz")
+            .default_copies(2)
+            .add_to(&mut db);
+
+        let copy_id = db
+            .duplicate_product(idx, String::from("Rinderhack (Kopie)"))
+            .unwrap();
+
+        db.reload_products().unwrap();
+
+        let original = &db.products()[0];
+        let copy = &db.products()[1];
+
+        assert_eq!(copy.id(), Some(copy_id));
+        assert_ne!(copy.id(), original.id());
+        assert_eq!(copy.name, "Rinderhack (Kopie)");
+        assert_eq!(copy.ingredients, original.ingredients);
+        assert_eq!(copy.default_copies, 2);
+    }
+
+    #[test]
+    fn invalid_products_are_not_stored() {
+        let mut db = memory_db();
+
+        let invalid = ProductBuilder::new(" ")
+            .price_ct(0)
+            .storage_temp(Some(80.0))
+            .expiration_days(Some(100_000))
+            .default_copies(0)
+            .build();
+
+        let Err(ProductError::Invalid(errors)) = db.add_product(invalid) else {
+            panic!("Invalid product has been stored");
+        };
+
+        assert_eq!(
+            errors,
+            [
+                ValidationError::EmptyName,
+                ValidationError::ZeroPrice,
+                ValidationError::StorageTemp(80.0),
+                ValidationError::ExpirationDays(100_000),
+                ValidationError::Copies(0),
+            ]
+        );
+
+        // Invalid changes leave the product untouched.
+        let idx = ProductBuilder::new("Rinderhack").add_to(&mut db);
+
+        assert!(db.update_product_price(idx, 0, date(2, 8), None).is_err());
+        assert_eq!(db.products()[idx].price_ct, 1000);
+
+        db.reload_products().unwrap();
+        assert_eq!(db.products().len(), 1);
+        assert_eq!(db.products()[idx].price_ct, 1000);
+
+        let mut changes = Vec::new();
+        db.price_history(10, &mut changes).unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn long_ingredients_are_warned_about() {
+        let mut settings = SettingsEntry {
+            max_voucher_length_mm: 100,
+            ..Default::default()
+        };
+
+        let product = ProductBuilder::new("Sülze")
+            .ingredients(&"Schwein, ".repeat(100))
+            .build();
+
+        assert_eq!(
+            product.warnings(&settings),
+            [ValidationWarning::LongIngredients {
+                len: 900,
+                max_len: 750
+            }]
+        );
+
+        // Without a limit, every length is fine.
+        settings.max_voucher_length_mm = 0;
+        assert!(product.warnings(&settings).is_empty());
+    }
+
+    #[test]
+    fn translations_roundtrip() {
+        let mut db = memory_db();
+
+        let idx = ProductBuilder::new("Rinderhack")
+            .ingredients("Rind, Salz")
+            .translation("pl", "Mięso mielone wołowe", "Wołowina, sól")
+            .translation("en", "Minced beef", "")
+            .add_to(&mut db);
+
+        let product = &db.products()[idx];
+        assert_eq!(db.translation_languages(), ["en", "pl"]);
+
+        // Missing texts fall back to the original ones.
+        let english = product.localized("en");
+        assert_eq!(english.name, "Minced beef");
+        assert_eq!(english.ingredients, "Rind, Salz");
+
+        let polish = product.localized("pl");
+        assert_eq!(polish.name, "Mięso mielone wołowe");
+        assert_eq!(polish.ingredients, "Wołowina, sól");
+
+        assert_eq!(product.localized("").name, "Rinderhack");
+        assert_eq!(product.localized("fr").name, "Rinderhack");
+
+        // Copies are translated as well, deleted products take their translations with them.
+        db.duplicate_product(idx, String::from("Rinderhack (Kopie)"))
+            .unwrap();
+
+        db.delete_product(idx).unwrap();
+        db.reload_products().unwrap();
+
+        assert_eq!(db.products().len(), 1);
+        assert_eq!(db.products()[0].translations.len(), 2);
+
+        let count: u64 = db
+            .con
+            .query_row("SELECT COUNT(*) FROM product_translations", (), |row| {
+                row.get(0)
+            })
+            .unwrap();
+
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn label_rolls_are_used_up_per_type() {
+        let db = memory_db();
+        let continuous = LabelType::Continuous { width: 62 };
+        let die_cut = LabelType::DieCut {
+            width: 62,
+            length: 100,
+        };
+
+        // Without a recorded roll change, a new roll of the configured length is assumed.
+        let roll = db.consume_label(continuous, date(1, 8), 80.0).unwrap();
+        assert_eq!(
+            (roll.length_mm, roll.used_mm, roll.print_count),
+            (30480.0, 80.0, 1)
+        );
+
+        db.add_label_roll(&LabelRollEntry::new(die_cut, date(1, 9), 300.0 * 103.0))
+            .unwrap();
+
+        for _ in 0..10 {
+            db.consume_label(die_cut, date(1, 10), 103.0).unwrap();
+        }
+
+        let roll = db.consume_label(continuous, date(2, 8), 120.0).unwrap();
+        assert_eq!((roll.used_mm, roll.print_count), (200.0, 2));
+        assert_eq!(roll.inserted, date(1, 8));
+
+        let roll = db.label_roll(die_cut).unwrap().unwrap();
+        assert_eq!(roll.remaining_labels(), Some(290));
+
+        // A roll change starts from scratch, the old roll is kept for the statistics.
+        db.add_label_roll(&LabelRollEntry::new(continuous, date(3, 8), 30480.0))
+            .unwrap();
+        assert_eq!(db.label_roll(continuous).unwrap().unwrap().used_mm, 0.0);
+
+        let mut rolls = Vec::new();
+        db.label_rolls(&mut rolls).unwrap();
+        assert_eq!(rolls.len(), 3);
+        assert_eq!(rolls[2].print_count, 2);
+    }
+
+    #[test]
+    fn weighings_are_kept_for_the_retention() {
+        let mut db = memory_db();
+        db.update_settings(|settings| settings.weighing_log_days = 7)
+            .unwrap();
+
+        let weighing = |day, frame: &[u8]| WeighingEntry {
+            sale_date: date(day, 9),
+            product_name: String::from("Rinderhack"),
+            weight_kg: 0.5,
+            tare_kg: 0.1,
+            frame: Some(frame.to_vec()),
+        };
+
+        for (day, frame) in [
+            (1, b"\x02+0.600kg"),
+            (5, b"\x02+0.500kg"),
+            (10, b"\x02+0.400kg"),
+        ] {
+            db.add_weighing(&weighing(day, frame)).unwrap();
+        }
+
+        // The weighing of the first day has expired.
+        let mut weighings = Vec::new();
+        db.weighings(10, &mut weighings).unwrap();
+
+        let dates: Vec<_> = weighings
+            .iter()
+            .map(|weighing| weighing.sale_date)
+            .collect();
+        assert_eq!(dates, [date(10, 9), date(5, 9)]);
+        assert_eq!(weighings[1].frame.as_deref(), Some(&b"\x02+0.500kg"[..]));
+        assert_eq!(weighings[1].tare_kg, 0.1);
+
+        // Without a retention, nothing is logged.
+        db.update_settings(|settings| settings.weighing_log_days = 0)
+            .unwrap();
+        db.add_weighing(&weighing(11, b"")).unwrap();
+
+        db.weighings(10, &mut weighings).unwrap();
+        assert_eq!(weighings.len(), 2);
+    }
+
+    #[test]
+    fn shifts_are_reconciled_with_cash_sales() {
+        let mut db = memory_db();
+        let bread = ProductBuilder::new("Brot").price_ct(400).build();
+
+        assert_eq!(db.close_shift(date(1, 18), 0).unwrap(), None);
+
+        // Only the cash sales during the shift count (sales without a method are cash).
+        for (hour, payment_method) in [
+            (7, Some(PaymentMethod::Cash)),
+            (9, Some(PaymentMethod::Cash)),
+            (10, Some(PaymentMethod::Card)),
+            (11, None),
+            (19, Some(PaymentMethod::Cash)),
+        ] {
+            let mut sale = SaleBuilder::new(&bread, date(1, hour)).build();
+            sale.payment_method = payment_method;
+            db.add_sale(&sale).unwrap();
+        }
+
+        db.open_shift(&ShiftEntry::new(date(1, 8), 10000, None))
+            .unwrap();
+        assert_eq!(db.current_shift().unwrap().unwrap().float_ct, 10000);
+
+        let shift = db.close_shift(date(1, 18), 10750).unwrap().unwrap();
+        let closing = shift.closing.unwrap();
+
+        assert_eq!(closing.expected_ct, 10800);
+        assert_eq!(closing.difference_ct(), -50);
+        assert_eq!(db.current_shift().unwrap(), None);
+
+        let mut shifts = Vec::new();
+        db.shifts(10, &mut shifts).unwrap();
+        assert_eq!(shifts.len(), 1);
+        assert_eq!(shifts[0], shift);
+
+        let mut sales = Vec::new();
+        db.shift_sales(&shift, date(2, 8), &mut sales).unwrap();
+
+        let dates: Vec<_> = sales.iter().map(|sale| sale.date).collect();
+        assert_eq!(dates, [date(1, 9), date(1, 10), date(1, 11)]);
+    }
+
+    #[test]
+    fn interrupted_print_jobs_are_kept() {
+        let db = memory_db();
+        let job = PrintJobEntry::interrupted(
+            date(1, 8),
+            String::from("Brot"),
+            String::from("Abdeckung offen"),
+        )
+        .for_sale(Some(date(1, 8)));
+        db.add_print_job(&job).unwrap();
+
+        let mut jobs = Vec::new();
+        db.print_jobs(10, &mut jobs).unwrap();
+
+        assert_eq!(jobs.len(), 1);
+        assert!(jobs[0].outcome == PrintOutcome::Interrupted);
+        assert_eq!(jobs[0].error.as_deref(), Some("Abdeckung offen"));
+        assert_eq!(jobs[0].sale_date, Some(date(1, 8)));
+    }
+
+    #[test]
+    fn print_jobs_are_pruned() {
+        let db = memory_db();
+
+        for idx in 0..(MAX_PRINT_JOBS + 5) {
+            let job = PrintJobEntry::printed(date(1, 8), format!("Brot {idx}"));
+            db.add_print_job(&job).unwrap();
+        }
+
+        let mut jobs = Vec::new();
+        db.print_jobs(2 * MAX_PRINT_JOBS, &mut jobs).unwrap();
+
+        // The newest jobs survive.
+        assert_eq!(jobs.len(), MAX_PRINT_JOBS);
+        assert_eq!(jobs[0].product_name, format!("Brot {}", MAX_PRINT_JOBS + 4));
+        assert_eq!(jobs[MAX_PRINT_JOBS - 1].product_name, "Brot 5");
+    }
 }
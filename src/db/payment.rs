@@ -0,0 +1,72 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// How the customer has paid for a sale
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PaymentMethod {
+    Cash,
+    Card,
+    Invoice,
+}
+
+impl PaymentMethod {
+    /// All methods in the order of the dialog
+    pub const ALL: [PaymentMethod; 3] = [
+        PaymentMethod::Cash,
+        PaymentMethod::Card,
+        PaymentMethod::Invoice,
+    ];
+
+    /// The name in the UI
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaymentMethod::Cash => "Bar",
+            PaymentMethod::Card => "Karte",
+            PaymentMethod::Invoice => "Rechnung",
+        }
+    }
+
+    /// The method after this one (wrapping around)
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|method| method == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// The method before this one (wrapping around)
+    pub fn previous(&self) -> Self {
+        let idx = Self::ALL.iter().position(|method| method == self).unwrap();
+        Self::ALL[(idx + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+impl Display for PaymentMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaymentMethod::Cash => write!(f, "cash"),
+            PaymentMethod::Card => write!(f, "card"),
+            PaymentMethod::Invoice => write!(f, "invoice"),
+        }
+    }
+}
+
+impl FromStr for PaymentMethod {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cash" => Ok(PaymentMethod::Cash),
+            "card" => Ok(PaymentMethod::Card),
+            "invoice" => Ok(PaymentMethod::Invoice),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The number and the total of the sales with one payment method
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PaymentTotal {
+    /// `None` for sales without a payment method (e.g. older ones or express sales)
+    pub payment_method: Option<PaymentMethod>,
+    pub count: u64,
+    pub total_ct: u64,
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{named_params, Connection, Result as SQLiteResult, Row};
+
+/// A change of a product price, kept to answer "since when does this cost that much?"
+#[derive(Clone)]
+pub struct PriceChangeEntry {
+    pub date: DateTime<Utc>,
+    pub product_id: i64,
+    pub product_name: String,
+    pub old_price_ct: u64,
+    pub new_price_ct: u64,
+    pub operator: Option<String>,
+}
+
+impl PriceChangeEntry {
+    fn load(row: &Row) -> SQLiteResult<Self> {
+        let date_rfc2822: String = row.get("date_2822")?;
+
+        Ok(Self {
+            date: DateTime::parse_from_rfc2822(&date_rfc2822)
+                .expect("Invalid timestamp format (expected RFC 2822)")
+                .into(),
+            product_id: row.get("product_id")?,
+            product_name: row.get("product_name")?,
+            old_price_ct: row.get("old_price_ct")?,
+            new_price_ct: row.get("new_price_ct")?,
+            operator: row.get("operator")?,
+        })
+    }
+
+    /// Load the `limit` most recent price changes, newest first.
+    pub(super) fn load_recent(
+        con: &Connection,
+        limit: usize,
+        changes: &mut Vec<Self>,
+    ) -> SQLiteResult<()> {
+        let mut stmt = con.prepare(
+            "SELECT
+                date_2822,
+                product_id,
+                product_name,
+                old_price_ct,
+                new_price_ct,
+                operator
+            FROM price_history
+            ORDER BY id DESC
+            LIMIT :limit",
+        )?;
+
+        changes.clear();
+
+        for change in stmt.query_map(named_params! {":limit": limit}, Self::load)? {
+            changes.push(change?);
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn store(&self, con: &Connection) -> SQLiteResult<()> {
+        con.execute(
+            "INSERT INTO price_history (
+                date_2822,
+                product_id,
+                product_name,
+                old_price_ct,
+                new_price_ct,
+                operator
+            ) VALUES (
+                :date_2822,
+                :product_id,
+                :product_name,
+                :old_price_ct,
+                :new_price_ct,
+                :operator
+            )",
+            named_params! {
+                ":date_2822": self.date.to_rfc2822(),
+                ":product_id": self.product_id,
+                ":product_name": self.product_name,
+                ":old_price_ct": self.old_price_ct,
+                ":new_price_ct": self.new_price_ct,
+                ":operator": self.operator,
+            },
+        )?;
+
+        Ok(())
+    }
+}
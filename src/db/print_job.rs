@@ -0,0 +1,194 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{named_params, Connection, Result as SQLiteResult, Row};
+
+/// The number of print jobs that are kept (older ones are deleted when new ones are added)
+pub const MAX_PRINT_JOBS: usize = 1000;
+
+/// How a print attempt has ended
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum PrintOutcome {
+    /// The voucher has been printed
+    Printed,
+
+    /// The voucher has not been printed (see the error)
+    Failed,
+
+    /// The job has failed while the voucher was printing, so it might have been printed partially (see the error)
+    Interrupted,
+
+    /// The printed voucher has been thrown away because it belongs to no sale
+    Discarded,
+}
+
+impl Display for PrintOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrintOutcome::Printed => write!(f, "printed"),
+            PrintOutcome::Failed => write!(f, "failed"),
+            PrintOutcome::Interrupted => write!(f, "interrupted"),
+            PrintOutcome::Discarded => write!(f, "discarded"),
+        }
+    }
+}
+
+impl FromStr for PrintOutcome {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "printed" => Ok(PrintOutcome::Printed),
+            "failed" => Ok(PrintOutcome::Failed),
+            "interrupted" => Ok(PrintOutcome::Interrupted),
+            "discarded" => Ok(PrintOutcome::Discarded),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A single print attempt, kept to investigate printer problems afterwards
+#[derive(Clone)]
+pub struct PrintJobEntry {
+    pub date: DateTime<Utc>,
+    pub product_name: String,
+    pub outcome: PrintOutcome,
+    pub error: Option<String>,
+
+    /// The date of the sale that the voucher belongs to (if any).
+    /// Vouchers are printed before their sale is stored, so a crash in between leaves a mismatch.
+    pub sale_date: Option<DateTime<Utc>>,
+}
+
+impl PrintJobEntry {
+    pub fn printed(date: DateTime<Utc>, product_name: String) -> Self {
+        Self {
+            date,
+            product_name,
+            outcome: PrintOutcome::Printed,
+            error: None,
+            sale_date: None,
+        }
+    }
+
+    pub fn failed(date: DateTime<Utc>, product_name: String, error: String) -> Self {
+        Self {
+            date,
+            product_name,
+            outcome: PrintOutcome::Failed,
+            error: Some(error),
+            sale_date: None,
+        }
+    }
+
+    pub fn interrupted(date: DateTime<Utc>, product_name: String, error: String) -> Self {
+        Self {
+            date,
+            product_name,
+            outcome: PrintOutcome::Interrupted,
+            error: Some(error),
+            sale_date: None,
+        }
+    }
+
+    /// Note that the voucher of a sale has been thrown away (e.g. after an undo).
+    pub fn discarded(date: DateTime<Utc>, product_name: String, sale_date: DateTime<Utc>) -> Self {
+        Self {
+            date,
+            product_name,
+            outcome: PrintOutcome::Discarded,
+            error: None,
+            sale_date: Some(sale_date),
+        }
+    }
+
+    /// Attribute the print attempt to the sale at `sale_date`.
+    pub fn for_sale(mut self, sale_date: Option<DateTime<Utc>>) -> Self {
+        self.sale_date = sale_date;
+        self
+    }
+
+    fn load(row: &Row) -> SQLiteResult<Self> {
+        let date_rfc2822: String = row.get("date_2822")?;
+        let outcome: String = row.get("outcome")?;
+        let sale_date_rfc2822: Option<String> = row.get("sale_date_2822")?;
+
+        Ok(Self {
+            date: DateTime::parse_from_rfc2822(&date_rfc2822)
+                .expect("Invalid timestamp format (expected RFC 2822)")
+                .into(),
+            product_name: row.get("product_name")?,
+            outcome: outcome.parse().unwrap_or(PrintOutcome::Failed),
+            error: row.get("error")?,
+            sale_date: sale_date_rfc2822.map(|date_rfc2822| {
+                DateTime::parse_from_rfc2822(&date_rfc2822)
+                    .expect("Invalid timestamp format (expected RFC 2822)")
+                    .into()
+            }),
+        })
+    }
+
+    /// Load the `limit` most recent print jobs, newest first.
+    pub(super) fn load_recent(
+        con: &Connection,
+        limit: usize,
+        jobs: &mut Vec<Self>,
+    ) -> SQLiteResult<()> {
+        let mut stmt = con.prepare(
+            "SELECT
+                date_2822,
+                product_name,
+                outcome,
+                error,
+                sale_date_2822
+            FROM print_jobs
+            ORDER BY id DESC
+            LIMIT :limit",
+        )?;
+
+        jobs.clear();
+
+        for job in stmt.query_map(named_params! {":limit": limit}, Self::load)? {
+            jobs.push(job?);
+        }
+
+        Ok(())
+    }
+
+    /// Store the print job and drop the oldest ones beyond `MAX_PRINT_JOBS`.
+    pub(super) fn store(&self, con: &Connection) -> SQLiteResult<()> {
+        con.execute(
+            "INSERT INTO print_jobs (
+                date_2822,
+                product_name,
+                outcome,
+                error,
+                sale_date_2822
+            ) VALUES (
+                :date_2822,
+                :product_name,
+                :outcome,
+                :error,
+                :sale_date_2822
+            )",
+            named_params! {
+                ":date_2822": self.date.to_rfc2822(),
+                ":product_name": self.product_name,
+                ":outcome": self.outcome.to_string(),
+                ":error": self.error,
+                ":sale_date_2822": self.sale_date.map(|date| date.to_rfc2822()),
+            },
+        )?;
+
+        con.execute(
+            "DELETE FROM print_jobs
+            WHERE id NOT IN (
+                SELECT id FROM print_jobs ORDER BY id DESC LIMIT :limit
+            )",
+            named_params! {":limit": MAX_PRINT_JOBS},
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1108 @@
+use crate::format::{self, NumberFormat};
+use crate::gpio::ButtonBindings;
+use crate::pos::Permissions;
+use crate::printer::ModelPreference as PrinterPreference;
+use crate::template::LabelMargins;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, Months, NaiveDate, NaiveTime, Weekday};
+use rusqlite::{named_params, Connection, Result as SQLiteResult};
+
+/// Parse `value` into `target`. On failure, `target` is left untouched.
+fn parse_into<T: FromStr>(target: &mut T, value: &str) -> bool {
+    match value.parse() {
+        Ok(parsed) => {
+            *target = parsed;
+            true
+        }
+
+        Err(_) => false,
+    }
+}
+
+/// Like `parse_into()`, but an empty value clears `target`.
+fn parse_optional_into<T: FromStr>(target: &mut Option<T>, value: &str) -> bool {
+    if value.is_empty() {
+        *target = None;
+        return true;
+    }
+
+    match value.parse() {
+        Ok(parsed) => {
+            *target = Some(parsed);
+            true
+        }
+
+        Err(_) => false,
+    }
+}
+
+/// How computed prices (e.g. weight times kg price) are rounded to whole cents
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round to the nearest cent
+    Cent,
+
+    /// Round to the nearest multiple of 5 cents
+    FiveCents,
+
+    /// Always round up to the next cent
+    Up,
+
+    /// Always round down to the previous cent
+    Down,
+}
+
+impl RoundingMode {
+    pub fn round(&self, price_ct: f64) -> u64 {
+        use RoundingMode::*;
+
+        let rounded = match self {
+            Cent => price_ct.round(),
+            FiveCents => (price_ct / 5.0).round() * 5.0,
+            Up => price_ct.ceil(),
+            Down => price_ct.floor(),
+        };
+
+        rounded.max(0.0) as u64
+    }
+}
+
+impl Display for RoundingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use RoundingMode::*;
+
+        match self {
+            Cent => write!(f, "cent"),
+            FiveCents => write!(f, "5ct"),
+            Up => write!(f, "up"),
+            Down => write!(f, "down"),
+        }
+    }
+}
+
+impl FromStr for RoundingMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use RoundingMode::*;
+
+        match s {
+            "cent" => Ok(Cent),
+            "5ct" => Ok(FiveCents),
+            "up" => Ok(Up),
+            "down" => Ok(Down),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How the logo is scaled up on labels that are wider than the logo
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum LogoUpscale {
+    /// Keep the original size
+    Off,
+
+    /// Repeat pixels (for pixel logos)
+    Nearest,
+
+    /// Interpolate smoothly (for photos)
+    Smooth,
+}
+
+impl Display for LogoUpscale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use LogoUpscale::*;
+
+        match self {
+            Off => write!(f, "off"),
+            Nearest => write!(f, "nearest"),
+            Smooth => write!(f, "smooth"),
+        }
+    }
+}
+
+impl FromStr for LogoUpscale {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use LogoUpscale::*;
+
+        match s {
+            "off" => Ok(Off),
+            "nearest" => Ok(Nearest),
+            "smooth" => Ok(Smooth),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How a sale is handled if the scales report a negative weight
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum UnderweightPolicy {
+    /// Refuse the sale
+    Block,
+
+    /// Allow the sale after a warning in the confirmation dialog (e.g. for returns).
+    /// There are no refunds yet, so the sale is booked with a price of 0 ct.
+    Confirm,
+
+    /// Zero the scales, so the current weight becomes the new zero point
+    AutoTare,
+}
+
+impl Display for UnderweightPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use UnderweightPolicy::*;
+
+        match self {
+            Block => write!(f, "block"),
+            Confirm => write!(f, "confirm"),
+            AutoTare => write!(f, "auto_tare"),
+        }
+    }
+}
+
+impl FromStr for UnderweightPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use UnderweightPolicy::*;
+
+        match s {
+            "block" => Ok(Block),
+            "confirm" => Ok(Confirm),
+            "auto_tare" => Ok(AutoTare),
+            _ => Err(()),
+        }
+    }
+}
+
+/// When the UI is redrawn
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum RedrawStrategy {
+    /// After every event, including the periodic tick
+    Always,
+
+    /// Only if something might have changed (saves CPU time)
+    OnChange,
+}
+
+impl Display for RedrawStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use RedrawStrategy::*;
+
+        match self {
+            Always => write!(f, "always"),
+            OnChange => write!(f, "on_change"),
+        }
+    }
+}
+
+impl FromStr for RedrawStrategy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use RedrawStrategy::*;
+
+        match s {
+            "always" => Ok(Always),
+            "on_change" => Ok(OnChange),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The characters and colors the UI is drawn with
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum UiCharset {
+    /// Box-drawing characters, arrows and the bright colors
+    Unicode,
+
+    /// ASCII borders and symbols with the 8 basic colors (for serial consoles and limited SSH terminals)
+    Ascii,
+}
+
+impl Display for UiCharset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use UiCharset::*;
+
+        match self {
+            Unicode => write!(f, "unicode"),
+            Ascii => write!(f, "ascii"),
+        }
+    }
+}
+
+impl FromStr for UiCharset {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use UiCharset::*;
+
+        match s {
+            "unicode" => Ok(Unicode),
+            "ascii" => Ok(Ascii),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The colors the UI is drawn with
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum UiTheme {
+    /// Colors highlight focus, warnings and errors
+    Color,
+
+    /// Pure black on white, meaning is carried by bold, underlined and reversed text
+    /// (for e-ink displays and operators with color-vision deficiencies)
+    Monochrome,
+}
+
+impl Display for UiTheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use UiTheme::*;
+
+        match self {
+            Color => write!(f, "color"),
+            Monochrome => write!(f, "monochrome"),
+        }
+    }
+}
+
+impl FromStr for UiTheme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use UiTheme::*;
+
+        match s {
+            "color" => Ok(Color),
+            "monochrome" => Ok(Monochrome),
+            _ => Err(()),
+        }
+    }
+}
+
+/// An extra column of the product list (next to the name)
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum ProductColumn {
+    /// The price per kg or per piece
+    Price,
+
+    /// The in-store code of the barcode on shelf labels
+    Code,
+}
+
+impl Display for ProductColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ProductColumn::*;
+
+        match self {
+            Price => write!(f, "price"),
+            Code => write!(f, "code"),
+        }
+    }
+}
+
+impl FromStr for ProductColumn {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ProductColumn::*;
+
+        match s {
+            "price" => Ok(Price),
+            "code" => Ok(Code),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The extra columns of the product list from left to right, like "price;code"
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ProductColumns(Vec<ProductColumn>);
+
+impl ProductColumns {
+    pub fn as_slice(&self) -> &[ProductColumn] {
+        &self.0
+    }
+}
+
+impl Display for ProductColumns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let columns: Vec<String> = self.0.iter().map(ProductColumn::to_string).collect();
+        write!(f, "{}", columns.join(";"))
+    }
+}
+
+impl FromStr for ProductColumns {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(ProductColumns)
+    }
+}
+
+/// Settings that can be adjusted by the operator.
+/// They are stored as key-value pairs, so new settings don't require a migration.
+/// Missing keys fall back to their defaults.
+#[derive(Clone)]
+pub struct SettingsEntry {
+    /// Warn if an identical sale is confirmed again within this many seconds (0 disables the guard)
+    pub duplicate_guard_secs: u64,
+
+    /// The notice that the scales are not calibrated (e.g. "nicht geeicht")
+    pub calibration_notice: String,
+
+    /// Print the calibration notice on every voucher
+    pub force_calibration_notice: bool,
+
+    /// Raise an alarm if the scales fail for this many seconds (0 disables the alarm)
+    pub scale_alarm_secs: u64,
+
+    /// Beep repeatedly while the scale alarm is raised
+    pub scale_alarm_beep: bool,
+
+    /// The number of pieces that are placed on the scales to capture the average piece weight
+    pub counting_reference_pieces: u64,
+
+    /// Show the comparison price per 100 g next to the kg price
+    pub show_price_per_100g: bool,
+
+    /// The rounding of computed prices
+    pub rounding_mode: RoundingMode,
+
+    /// A short message that is appended to the trailer of every voucher (empty for none)
+    pub daily_message: String,
+
+    /// The maximum length of a voucher in mm (0 disables the limit)
+    pub max_voucher_length_mm: u32,
+
+    /// Shrink vouchers that exceed the maximum length instead of refusing to print them
+    pub shrink_long_vouchers: bool,
+
+    /// The upscaling of the logo on wide labels
+    pub logo_upscale: LogoUpscale,
+
+    /// The number of decimals of displayed and printed weights (in kg)
+    pub weight_decimals: usize,
+
+    /// The legal step of weights in g (e.g. 2 for scales with e = 2 g)
+    pub weight_step_g: u32,
+
+    /// The handling of negative weights
+    pub underweight_policy: UnderweightPolicy,
+
+    /// In batch and express mode, a weight counts as stable once it has not changed for this many ms
+    pub batch_stable_ms: u64,
+
+    /// The request / response framing of the scales, like "digits=15..21;unit=21..24"
+    /// (empty for the default framing, checked and applied on the next start)
+    pub scale_protocol: String,
+
+    /// The flow control of the serial port of the scales: "none", "xonxoff" or "rtscts"
+    /// (checked and applied on the next start)
+    pub scale_flow_control: String,
+
+    /// Toggle the driver of a half-duplex RS-485 converter via RTS (applied on the next start)
+    pub scale_rs485: bool,
+
+    /// The time between enabling the RS-485 driver and sending a request in µs
+    pub scale_rs485_delay_before_us: u64,
+
+    /// The time between sending a request and disabling the RS-485 driver in µs
+    pub scale_rs485_delay_after_us: u64,
+
+    /// Focus the product list when this many g are placed on the empty scales (0 disables it)
+    pub wake_on_weight_g: u64,
+
+    /// Capture every print job into this directory for bug reports (empty disables it)
+    pub printer_capture_dir: String,
+
+    /// The acceptable USB printers in the order they are tried, like "QL-700; QL-570; any".
+    /// If empty, the printer model in the info table is used.
+    pub printer_preference: PrinterPreference,
+
+    /// The interval of the periodic UI tick in ms
+    pub ui_tick_ms: u64,
+
+    /// When the UI is redrawn
+    pub ui_redraw: RedrawStrategy,
+
+    /// Draw the UI with ASCII characters and basic colors for remote maintenance ("unicode" or "ascii")
+    pub ui_charset: UiCharset,
+
+    /// The colors of the UI
+    pub ui_theme: UiTheme,
+
+    /// Extra columns of the product list, like "price;code" (empty for the names only)
+    pub product_columns: ProductColumns,
+
+    /// Columns are hidden from the right if the names would get narrower than this (in characters).
+    /// Longer names are cut off with an ellipsis.
+    pub product_name_min_width: u16,
+
+    /// Show a screensaver after this many s without keys or weight changes (0 disables it)
+    pub screensaver_secs: u64,
+
+    /// Let the screensaver show the prices of the products one after another
+    pub screensaver_prices: bool,
+
+    /// The PIN that protects product changes in the UI (empty = no PIN)
+    pub admin_pin: String,
+
+    /// Disable sales, product changes and printing (e.g. on demo devices)
+    pub read_only: bool,
+
+    /// The language of product names and ingredients on vouchers (empty for the original texts)
+    pub voucher_language: String,
+
+    /// Perform the selected action for a stable weight right away, without the confirmation dialog
+    pub express_mode: bool,
+
+    /// In express mode, the last sale can be undone for this many seconds
+    pub express_undo_secs: u64,
+
+    /// The date of the last calibration / verification of the scales ("YYYY-MM-DD", none if unknown)
+    pub scale_verification_date: Option<NaiveDate>,
+
+    /// The scales must be verified again after this many months
+    pub scale_verification_months: u32,
+
+    /// Warn this many days before the next verification is due
+    pub scale_verification_warn_days: u64,
+
+    /// The ID of the last verification (e.g. the number on the verification mark)
+    pub scale_verification_id: String,
+
+    /// Print the verification ID in the footer of every voucher
+    pub print_verification_id: bool,
+
+    /// Warn if less than this many MB are free on the disk of the DB
+    pub disk_warn_mb: u64,
+
+    /// Refuse sales if less than this many MB are free on the disk of the DB
+    pub disk_critical_mb: u64,
+
+    /// Warn if the DB grows beyond this many MB (0 disables the warning)
+    pub db_warn_mb: u64,
+
+    /// The journal of the sales that the DB could not take (empty: "sales.journal" next to the DB)
+    pub journal_path: String,
+
+    /// Serve "GET /health" on this address, like "127.0.0.1:9090" (empty disables it, applied on the next start)
+    pub health_endpoint: String,
+
+    /// Look for a newer release once a day and point it out in the status bar (applied on the next start)
+    pub update_check: bool,
+
+    /// The login names of trainees, separated by commas
+    pub trainee_operators: String,
+
+    /// The actions that staff may perform (e.g. "sell,print_only,void,edit_products")
+    pub staff_permissions: Permissions,
+
+    /// The actions that trainees may perform
+    pub trainee_permissions: Permissions,
+
+    /// Ask for the payment method (cash, card, invoice) in the dialog of sales
+    pub ask_payment_method: bool,
+
+    /// Preselect a receipt for the customer in the dialog of sales
+    pub print_customer_receipt: bool,
+
+    /// Allow to move the best-before date by up to this many days in the dialog (0 disables it),
+    /// e.g. for pre-ordered goods that have been packed earlier
+    pub expiration_override_days: u64,
+
+    /// The VAT rate that is included in the prices (listed on customer receipts)
+    pub vat_percent: f64,
+
+    /// Extra margins in mm inside the printable area, per label type (e.g. "62=1,1,0,0;62x100=0.5,0.5,1,1")
+    pub label_margins: LabelMargins,
+
+    /// The length of a new label roll in m, assumed until a roll change is recorded
+    pub label_roll_length_m: f64,
+
+    /// Warn if the estimated rest of the label roll is shorter than this (in m)
+    pub label_stock_warning_m: f64,
+
+    /// Keep the raw readings of the scales behind sold weights for this many days (0 disables the log)
+    pub weighing_log_days: u64,
+
+    /// The decimal separator in exported tables ("comma" or "dot" for spreadsheets that expect it)
+    pub export_number_format: NumberFormat,
+
+    /// The time at which the business day changes in reports (e.g. "04:00" for late-evening markets)
+    pub business_day_cutoff: NaiveTime,
+
+    /// The first day of the week in reports (e.g. "mon")
+    pub week_start: Weekday,
+
+    /// Allow the export of anonymized weekly totals per product (e.g. for a producer cooperative)
+    pub statistics_export: bool,
+
+    /// Run this shell command after each sale, e.g. to pop the cash drawer (empty = none).
+    /// The sale is passed in the environment (SALE_PRODUCT, SALE_TOTAL_CT, ...).
+    pub post_sale_command: String,
+
+    /// Pulse this GPIO pin (sysfs number) after each sale, e.g. for a lamp on a Raspberry Pi
+    pub post_sale_gpio_pin: Option<u32>,
+
+    /// The length of the pulse on the GPIO pin (in ms)
+    pub post_sale_gpio_pulse_ms: u64,
+
+    /// Physical buttons on GPIO pins (sysfs numbers), like "confirm=529;tare=539;reprint=534".
+    /// Only supported if built with the "gpio" feature.
+    pub gpio_buttons: ButtonBindings,
+
+    /// Pressed buttons pull their pin low (e.g. wired to ground against a pull-up)
+    pub gpio_buttons_active_low: bool,
+
+    /// Warn if more than this share of the last responses of the scales failed (in %)
+    pub scale_max_failure_percent: f64,
+
+    /// Warn if the scales take longer than this to respond on average (in ms)
+    pub scale_max_latency_ms: u64,
+}
+
+impl Default for SettingsEntry {
+    fn default() -> Self {
+        Self {
+            duplicate_guard_secs: 10,
+            calibration_notice: String::from("Nicht geeichte Waage - Gewicht ohne Gewähr"),
+            force_calibration_notice: false,
+            scale_alarm_secs: 30,
+            scale_alarm_beep: false,
+            counting_reference_pieces: 10,
+            show_price_per_100g: false,
+            rounding_mode: RoundingMode::Cent,
+            daily_message: String::new(),
+            max_voucher_length_mm: 300,
+            shrink_long_vouchers: false,
+            logo_upscale: LogoUpscale::Off,
+            weight_decimals: 3,
+            weight_step_g: 1,
+            underweight_policy: UnderweightPolicy::Block,
+            batch_stable_ms: 1000,
+            scale_protocol: String::new(),
+            scale_flow_control: String::from("none"),
+            scale_rs485: false,
+            scale_rs485_delay_before_us: 0,
+            scale_rs485_delay_after_us: 0,
+            wake_on_weight_g: 0,
+            printer_capture_dir: String::new(),
+            printer_preference: PrinterPreference::default(),
+            ui_tick_ms: 250,
+            ui_redraw: RedrawStrategy::Always,
+            ui_charset: UiCharset::Unicode,
+            ui_theme: UiTheme::Color,
+            product_columns: ProductColumns::default(),
+            product_name_min_width: 16,
+            screensaver_secs: 0,
+            screensaver_prices: true,
+            admin_pin: String::new(),
+            read_only: false,
+            voucher_language: String::new(),
+            express_mode: false,
+            express_undo_secs: 15,
+            scale_verification_date: None,
+            scale_verification_months: 24,
+            scale_verification_warn_days: 30,
+            scale_verification_id: String::new(),
+            print_verification_id: false,
+            disk_warn_mb: 500,
+            disk_critical_mb: 50,
+            db_warn_mb: 1024,
+            journal_path: String::new(),
+            health_endpoint: String::new(),
+            update_check: false,
+            trainee_operators: String::new(),
+            staff_permissions: Permissions::all(),
+            trainee_permissions: Permissions::SELL,
+            ask_payment_method: false,
+            print_customer_receipt: false,
+            expiration_override_days: 0,
+            vat_percent: 7.0,
+            label_margins: LabelMargins::default(),
+            label_roll_length_m: 30.48,
+            label_stock_warning_m: 3.0,
+            weighing_log_days: 90,
+            export_number_format: NumberFormat::Comma,
+            business_day_cutoff: NaiveTime::MIN,
+            week_start: Weekday::Mon,
+            statistics_export: false,
+            post_sale_command: String::new(),
+            post_sale_gpio_pin: None,
+            post_sale_gpio_pulse_ms: 200,
+            gpio_buttons: ButtonBindings::default(),
+            gpio_buttons_active_low: true,
+            scale_max_failure_percent: 5.0,
+            scale_max_latency_ms: 500,
+        }
+    }
+}
+
+impl SettingsEntry {
+    /// Apply a single stored setting.
+    /// Unknown keys and invalid values are reported and ignored.
+    fn apply(&mut self, key: &str, value: &str) {
+        let is_valid = match key {
+            "duplicate_guard_secs" => parse_into(&mut self.duplicate_guard_secs, value),
+            "calibration_notice" => parse_into(&mut self.calibration_notice, value),
+            "force_calibration_notice" => parse_into(&mut self.force_calibration_notice, value),
+            "scale_alarm_secs" => parse_into(&mut self.scale_alarm_secs, value),
+            "scale_alarm_beep" => parse_into(&mut self.scale_alarm_beep, value),
+            "counting_reference_pieces" => parse_into(&mut self.counting_reference_pieces, value),
+            "show_price_per_100g" => parse_into(&mut self.show_price_per_100g, value),
+            "rounding_mode" => parse_into(&mut self.rounding_mode, value),
+            "daily_message" => parse_into(&mut self.daily_message, value),
+            "max_voucher_length_mm" => parse_into(&mut self.max_voucher_length_mm, value),
+            "shrink_long_vouchers" => parse_into(&mut self.shrink_long_vouchers, value),
+            "logo_upscale" => parse_into(&mut self.logo_upscale, value),
+            "weight_decimals" => parse_into(&mut self.weight_decimals, value),
+            "weight_step_g" => parse_into(&mut self.weight_step_g, value),
+            "underweight_policy" => parse_into(&mut self.underweight_policy, value),
+            "batch_stable_ms" => parse_into(&mut self.batch_stable_ms, value),
+            "scale_protocol" => parse_into(&mut self.scale_protocol, value),
+            "scale_flow_control" => parse_into(&mut self.scale_flow_control, value),
+            "scale_rs485" => parse_into(&mut self.scale_rs485, value),
+            "scale_rs485_delay_before_us" => {
+                parse_into(&mut self.scale_rs485_delay_before_us, value)
+            }
+            "scale_rs485_delay_after_us" => parse_into(&mut self.scale_rs485_delay_after_us, value),
+            "wake_on_weight_g" => parse_into(&mut self.wake_on_weight_g, value),
+            "printer_capture_dir" => parse_into(&mut self.printer_capture_dir, value),
+            "printer_preference" => parse_into(&mut self.printer_preference, value),
+            "ui_tick_ms" => parse_into(&mut self.ui_tick_ms, value),
+            "ui_redraw" => parse_into(&mut self.ui_redraw, value),
+            "ui_charset" => parse_into(&mut self.ui_charset, value),
+            "ui_theme" => parse_into(&mut self.ui_theme, value),
+            "product_columns" => parse_into(&mut self.product_columns, value),
+            "product_name_min_width" => parse_into(&mut self.product_name_min_width, value),
+            "screensaver_secs" => parse_into(&mut self.screensaver_secs, value),
+            "screensaver_prices" => parse_into(&mut self.screensaver_prices, value),
+            "admin_pin" => parse_into(&mut self.admin_pin, value),
+            "read_only" => parse_into(&mut self.read_only, value),
+            "voucher_language" => parse_into(&mut self.voucher_language, value),
+            "express_mode" => parse_into(&mut self.express_mode, value),
+            "express_undo_secs" => parse_into(&mut self.express_undo_secs, value),
+            "scale_verification_date" => {
+                parse_optional_into(&mut self.scale_verification_date, value)
+            }
+            "scale_verification_months" => parse_into(&mut self.scale_verification_months, value),
+            "scale_verification_warn_days" => {
+                parse_into(&mut self.scale_verification_warn_days, value)
+            }
+            "scale_verification_id" => parse_into(&mut self.scale_verification_id, value),
+            "print_verification_id" => parse_into(&mut self.print_verification_id, value),
+            "disk_warn_mb" => parse_into(&mut self.disk_warn_mb, value),
+            "disk_critical_mb" => parse_into(&mut self.disk_critical_mb, value),
+            "db_warn_mb" => parse_into(&mut self.db_warn_mb, value),
+            "journal_path" => parse_into(&mut self.journal_path, value),
+            "health_endpoint" => parse_into(&mut self.health_endpoint, value),
+            "update_check" => parse_into(&mut self.update_check, value),
+            "trainee_operators" => parse_into(&mut self.trainee_operators, value),
+            "staff_permissions" => parse_into(&mut self.staff_permissions, value),
+            "trainee_permissions" => parse_into(&mut self.trainee_permissions, value),
+            "ask_payment_method" => parse_into(&mut self.ask_payment_method, value),
+            "print_customer_receipt" => parse_into(&mut self.print_customer_receipt, value),
+            "expiration_override_days" => parse_into(&mut self.expiration_override_days, value),
+            "vat_percent" => parse_into(&mut self.vat_percent, value),
+            "label_margins" => parse_into(&mut self.label_margins, value),
+            "label_roll_length_m" => parse_into(&mut self.label_roll_length_m, value),
+            "label_stock_warning_m" => parse_into(&mut self.label_stock_warning_m, value),
+            "weighing_log_days" => parse_into(&mut self.weighing_log_days, value),
+            "export_number_format" => parse_into(&mut self.export_number_format, value),
+            "business_day_cutoff" => parse_into(&mut self.business_day_cutoff, value),
+            "week_start" => parse_into(&mut self.week_start, value),
+            "statistics_export" => parse_into(&mut self.statistics_export, value),
+            "post_sale_command" => parse_into(&mut self.post_sale_command, value),
+            "post_sale_gpio_pin" => parse_optional_into(&mut self.post_sale_gpio_pin, value),
+            "post_sale_gpio_pulse_ms" => parse_into(&mut self.post_sale_gpio_pulse_ms, value),
+            "gpio_buttons" => parse_into(&mut self.gpio_buttons, value),
+            "gpio_buttons_active_low" => parse_into(&mut self.gpio_buttons_active_low, value),
+            "scale_max_failure_percent" => parse_into(&mut self.scale_max_failure_percent, value),
+            "scale_max_latency_ms" => parse_into(&mut self.scale_max_latency_ms, value),
+
+            _ => {
+                eprintln!("Unknown setting: {}", key);
+                return;
+            }
+        };
+
+        if !is_valid {
+            eprintln!("Invalid value for setting {}: {}", key, value);
+        }
+    }
+
+    /// List all settings as key-value pairs.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "duplicate_guard_secs",
+                self.duplicate_guard_secs.to_string(),
+            ),
+            ("calibration_notice", self.calibration_notice.clone()),
+            (
+                "force_calibration_notice",
+                self.force_calibration_notice.to_string(),
+            ),
+            ("scale_alarm_secs", self.scale_alarm_secs.to_string()),
+            ("scale_alarm_beep", self.scale_alarm_beep.to_string()),
+            (
+                "counting_reference_pieces",
+                self.counting_reference_pieces.to_string(),
+            ),
+            ("show_price_per_100g", self.show_price_per_100g.to_string()),
+            ("rounding_mode", self.rounding_mode.to_string()),
+            ("daily_message", self.daily_message.clone()),
+            (
+                "max_voucher_length_mm",
+                self.max_voucher_length_mm.to_string(),
+            ),
+            (
+                "shrink_long_vouchers",
+                self.shrink_long_vouchers.to_string(),
+            ),
+            ("logo_upscale", self.logo_upscale.to_string()),
+            ("weight_decimals", self.weight_decimals.to_string()),
+            ("weight_step_g", self.weight_step_g.to_string()),
+            ("underweight_policy", self.underweight_policy.to_string()),
+            ("batch_stable_ms", self.batch_stable_ms.to_string()),
+            ("scale_protocol", self.scale_protocol.clone()),
+            ("scale_flow_control", self.scale_flow_control.clone()),
+            ("scale_rs485", self.scale_rs485.to_string()),
+            (
+                "scale_rs485_delay_before_us",
+                self.scale_rs485_delay_before_us.to_string(),
+            ),
+            (
+                "scale_rs485_delay_after_us",
+                self.scale_rs485_delay_after_us.to_string(),
+            ),
+            ("wake_on_weight_g", self.wake_on_weight_g.to_string()),
+            ("printer_capture_dir", self.printer_capture_dir.clone()),
+            ("printer_preference", self.printer_preference.to_string()),
+            ("ui_tick_ms", self.ui_tick_ms.to_string()),
+            ("ui_redraw", self.ui_redraw.to_string()),
+            ("ui_charset", self.ui_charset.to_string()),
+            ("ui_theme", self.ui_theme.to_string()),
+            ("product_columns", self.product_columns.to_string()),
+            (
+                "product_name_min_width",
+                self.product_name_min_width.to_string(),
+            ),
+            ("screensaver_secs", self.screensaver_secs.to_string()),
+            ("screensaver_prices", self.screensaver_prices.to_string()),
+            ("admin_pin", self.admin_pin.clone()),
+            ("read_only", self.read_only.to_string()),
+            ("voucher_language", self.voucher_language.clone()),
+            ("express_mode", self.express_mode.to_string()),
+            ("express_undo_secs", self.express_undo_secs.to_string()),
+            (
+                "scale_verification_date",
+                self.scale_verification_date
+                    .map(|date| date.to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "scale_verification_months",
+                self.scale_verification_months.to_string(),
+            ),
+            (
+                "scale_verification_warn_days",
+                self.scale_verification_warn_days.to_string(),
+            ),
+            ("scale_verification_id", self.scale_verification_id.clone()),
+            (
+                "print_verification_id",
+                self.print_verification_id.to_string(),
+            ),
+            ("disk_warn_mb", self.disk_warn_mb.to_string()),
+            ("disk_critical_mb", self.disk_critical_mb.to_string()),
+            ("db_warn_mb", self.db_warn_mb.to_string()),
+            ("journal_path", self.journal_path.clone()),
+            ("health_endpoint", self.health_endpoint.clone()),
+            ("update_check", self.update_check.to_string()),
+            ("trainee_operators", self.trainee_operators.clone()),
+            ("staff_permissions", self.staff_permissions.to_string()),
+            ("trainee_permissions", self.trainee_permissions.to_string()),
+            ("ask_payment_method", self.ask_payment_method.to_string()),
+            (
+                "print_customer_receipt",
+                self.print_customer_receipt.to_string(),
+            ),
+            (
+                "expiration_override_days",
+                self.expiration_override_days.to_string(),
+            ),
+            ("vat_percent", self.vat_percent.to_string()),
+            ("label_margins", self.label_margins.to_string()),
+            ("label_roll_length_m", self.label_roll_length_m.to_string()),
+            (
+                "label_stock_warning_m",
+                self.label_stock_warning_m.to_string(),
+            ),
+            ("weighing_log_days", self.weighing_log_days.to_string()),
+            (
+                "export_number_format",
+                self.export_number_format.to_string(),
+            ),
+            (
+                "business_day_cutoff",
+                self.business_day_cutoff.format("%H:%M").to_string(),
+            ),
+            ("week_start", self.week_start.to_string().to_lowercase()),
+            ("statistics_export", self.statistics_export.to_string()),
+            ("post_sale_command", self.post_sale_command.clone()),
+            (
+                "post_sale_gpio_pin",
+                self.post_sale_gpio_pin
+                    .map(|pin| pin.to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "post_sale_gpio_pulse_ms",
+                self.post_sale_gpio_pulse_ms.to_string(),
+            ),
+            ("gpio_buttons", self.gpio_buttons.to_string()),
+            (
+                "gpio_buttons_active_low",
+                self.gpio_buttons_active_low.to_string(),
+            ),
+            (
+                "scale_max_failure_percent",
+                self.scale_max_failure_percent.to_string(),
+            ),
+            (
+                "scale_max_latency_ms",
+                self.scale_max_latency_ms.to_string(),
+            ),
+        ]
+    }
+
+    /// Round a weight from the scales to the legal step and the display precision.
+    /// All prices and sales are based on the rounded weight, so they match the voucher.
+    pub fn round_weight(&self, weight_kg: f64) -> f64 {
+        let step_kg = (self.weight_step_g.max(1) as f64) / 1000.0;
+        let factor = 10_f64.powi(self.weight_decimals as i32);
+
+        (((weight_kg / step_kg).round() * step_kg) * factor).round() / factor
+    }
+
+    /// The date when the scales must be verified again (`None` if the last verification is unknown)
+    pub fn scale_verification_due(&self) -> Option<NaiveDate> {
+        self.scale_verification_date?
+            .checked_add_months(Months::new(self.scale_verification_months))
+    }
+
+    /// Check if the operator should be reminded of the verification on the given day.
+    pub fn is_scale_verification_due(&self, today: NaiveDate) -> bool {
+        self.scale_verification_due().is_some_and(|due| {
+            (due - today).num_days() <= (self.scale_verification_warn_days as i64)
+        })
+    }
+
+    /// The business day of a date: Until the cutoff, dates belong to the day before.
+    pub fn business_day(&self, date: DateTime<Local>) -> NaiveDate {
+        (date.naive_local()
+            - self
+                .business_day_cutoff
+                .signed_duration_since(NaiveTime::MIN))
+        .date()
+    }
+
+    /// Format a weight with the display precision (e.g. "1,234 kg").
+    pub fn format_weight(&self, weight_kg: f64) -> String {
+        format!("{} kg", format::fixed(weight_kg, self.weight_decimals))
+    }
+
+    pub(super) fn load(con: &Connection) -> SQLiteResult<Self> {
+        let mut settings = Self::default();
+        let mut stmt = con.prepare("SELECT key, value FROM settings")?;
+        let mut rows = stmt.query(())?;
+
+        while let Some(row) = rows.next()? {
+            let key: String = row.get("key")?;
+            let value: String = row.get("value")?;
+
+            settings.apply(&key, &value);
+        }
+
+        Ok(settings)
+    }
+
+    pub(super) fn store(&self, con: &Connection) -> SQLiteResult<()> {
+        for (key, value) in self.entries() {
+            con.execute(
+                "REPLACE INTO settings (key, value) VALUES (:key, :value)",
+                named_params! {
+                    ":key": key,
+                    ":value": value,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::db::Database;
+
+    use std::{env, fs, process};
+
+    /// Store the settings changed by `f` in a new DB, then open it again and load them.
+    fn reopened<F: FnMut(&mut SettingsEntry)>(name: &str, f: F) -> SettingsEntry {
+        let path = env::temp_dir().join(format!("weight-wb-{}-{}.db", name, process::id()));
+        _ = fs::remove_file(&path);
+
+        let mut db = Database::open_or_create(&path).unwrap();
+        db.update_settings(f).unwrap();
+        drop(db);
+
+        let settings = Database::open_or_create(&path).unwrap().settings().clone();
+        fs::remove_file(&path).unwrap();
+
+        settings
+    }
+
+    #[test]
+    fn price_per_100g_is_loaded_back() {
+        let settings = reopened("price-per-100g", |settings| {
+            settings.show_price_per_100g = true
+        });
+        assert!(settings.show_price_per_100g);
+    }
+
+    #[test]
+    fn rounding_mode_is_loaded_back() {
+        let settings = reopened("rounding-mode", |settings| {
+            settings.rounding_mode = RoundingMode::FiveCents
+        });
+        assert!(settings.rounding_mode == RoundingMode::FiveCents);
+    }
+
+    #[test]
+    fn daily_message_is_loaded_back() {
+        let settings = reopened("daily-message", |settings| {
+            settings.daily_message = String::from("Nächste Woche Urlaub!")
+        });
+        assert_eq!(settings.daily_message, "Nächste Woche Urlaub!");
+    }
+
+    #[test]
+    fn every_setting_is_loaded_back() {
+        // A value that differs from the default for every key
+        let stored = [
+            ("duplicate_guard_secs", String::from("3")),
+            ("calibration_notice", String::from("geeicht")),
+            ("force_calibration_notice", String::from("true")),
+            ("scale_alarm_secs", String::from("5")),
+            ("scale_alarm_beep", String::from("true")),
+            ("counting_reference_pieces", String::from("20")),
+            ("show_price_per_100g", String::from("true")),
+            ("rounding_mode", String::from("5ct")),
+            ("daily_message", String::from("Frohe Ostern!")),
+            ("max_voucher_length_mm", String::from("150")),
+            ("shrink_long_vouchers", String::from("true")),
+            ("logo_upscale", String::from("smooth")),
+            ("weight_decimals", String::from("2")),
+            ("weight_step_g", String::from("2")),
+            ("underweight_policy", String::from("confirm")),
+            ("batch_stable_ms", String::from("500")),
+            (
+                "scale_protocol",
+                String::from("info=;sign=;digits=0..5;unit=;weight=13/5"),
+            ),
+            ("scale_flow_control", String::from("rtscts")),
+            ("scale_rs485", String::from("true")),
+            ("scale_rs485_delay_before_us", String::from("100")),
+            ("scale_rs485_delay_after_us", String::from("200")),
+            ("wake_on_weight_g", String::from("50")),
+            ("printer_capture_dir", String::from("/tmp/captures")),
+            ("printer_preference", String::from("Brother QL-700; any")),
+            ("ui_tick_ms", String::from("100")),
+            ("ui_redraw", String::from("on_change")),
+            ("ui_charset", String::from("ascii")),
+            ("ui_theme", String::from("monochrome")),
+            ("product_columns", String::from("price;code")),
+            ("product_name_min_width", String::from("20")),
+            ("screensaver_secs", String::from("300")),
+            ("screensaver_prices", String::from("false")),
+            ("admin_pin", String::from("1234")),
+            ("read_only", String::from("true")),
+            ("voucher_language", String::from("en")),
+            ("express_mode", String::from("true")),
+            ("express_undo_secs", String::from("30")),
+            ("scale_verification_date", String::from("2025-03-01")),
+            ("scale_verification_months", String::from("12")),
+            ("scale_verification_warn_days", String::from("14")),
+            ("scale_verification_id", String::from("E-123")),
+            ("print_verification_id", String::from("true")),
+            ("disk_warn_mb", String::from("1000")),
+            ("disk_critical_mb", String::from("100")),
+            ("db_warn_mb", String::from("2048")),
+            (
+                "journal_path",
+                String::from("/var/lib/weight-wb/sales.journal"),
+            ),
+            ("health_endpoint", String::from("127.0.0.1:9090")),
+            ("update_check", String::from("true")),
+            ("trainee_operators", String::from("azubi")),
+            ("staff_permissions", String::from("sell,void")),
+            ("trainee_permissions", String::from("sell,print_only")),
+            ("ask_payment_method", String::from("true")),
+            ("print_customer_receipt", String::from("true")),
+            ("expiration_override_days", String::from("3")),
+            ("vat_percent", String::from("19")),
+            ("label_margins", String::from("62=1,1,0,0")),
+            ("label_roll_length_m", String::from("15.24")),
+            ("label_stock_warning_m", String::from("1.5")),
+            ("weighing_log_days", String::from("30")),
+            ("export_number_format", String::from("dot")),
+            ("business_day_cutoff", String::from("04:00")),
+            ("week_start", String::from("sun")),
+            ("statistics_export", String::from("true")),
+            ("post_sale_command", String::from("true")),
+            ("post_sale_gpio_pin", String::from("17")),
+            ("post_sale_gpio_pulse_ms", String::from("100")),
+            ("gpio_buttons", String::from("confirm=529;tare=539")),
+            ("gpio_buttons_active_low", String::from("false")),
+            ("scale_max_failure_percent", String::from("10")),
+            ("scale_max_latency_ms", String::from("1000")),
+        ];
+
+        let mut settings = SettingsEntry::default();
+
+        for (key, value) in &stored {
+            settings.apply(key, value);
+        }
+
+        let defaults = SettingsEntry::default().entries();
+        let entries = settings.entries();
+        assert_eq!(entries.len(), stored.len());
+
+        for ((key, value), (_, default_value)) in entries.iter().zip(&defaults) {
+            let (_, stored_value) = stored
+                .iter()
+                .find(|(stored_key, _)| stored_key == key)
+                .unwrap_or_else(|| panic!("{} is missing in the test", key));
+
+            assert_eq!(value, stored_value, "{} has not been loaded", key);
+            assert_ne!(value, default_value, "{} is the default", key);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use super::PaymentMethod;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{named_params, Connection, OptionalExtension, Result as SQLiteResult, Row};
+
+/// The counting of the cash drawer at the end of a shift
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ShiftClosing {
+    pub closed: DateTime<Utc>,
+
+    /// The float plus the cash sales of the shift
+    pub expected_ct: u64,
+    pub counted_ct: u64,
+}
+
+impl ShiftClosing {
+    /// The counted minus the expected cash (negative if cash is missing)
+    pub fn difference_ct(&self) -> i64 {
+        (self.counted_ct as i64) - (self.expected_ct as i64)
+    }
+}
+
+/// A shift at the cash drawer: It is opened with a starting float and closed by counting the cash.
+/// The difference to the cash sales in between is kept for the books.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShiftEntry {
+    pub opened: DateTime<Utc>,
+
+    /// The cash in the drawer when the shift has been opened
+    pub float_ct: u64,
+    pub operator: Option<String>,
+
+    /// `None` while the shift is open
+    pub closing: Option<ShiftClosing>,
+}
+
+impl ShiftEntry {
+    pub fn new(opened: DateTime<Utc>, float_ct: u64, operator: Option<String>) -> Self {
+        Self {
+            opened,
+            float_ct,
+            operator,
+            closing: None,
+        }
+    }
+
+    fn load(row: &Row) -> SQLiteResult<Self> {
+        let opened_rfc2822: String = row.get("opened_2822")?;
+        let closed_rfc2822: Option<String> = row.get("closed_2822")?;
+
+        let closing = match closed_rfc2822 {
+            Some(closed_rfc2822) => Some(ShiftClosing {
+                closed: DateTime::parse_from_rfc2822(&closed_rfc2822)
+                    .expect("Invalid timestamp format (expected RFC 2822)")
+                    .into(),
+                expected_ct: row.get("expected_ct")?,
+                counted_ct: row.get("counted_ct")?,
+            }),
+
+            None => None,
+        };
+
+        Ok(Self {
+            opened: DateTime::parse_from_rfc2822(&opened_rfc2822)
+                .expect("Invalid timestamp format (expected RFC 2822)")
+                .into(),
+            float_ct: row.get("float_ct")?,
+            operator: row.get("operator")?,
+            closing,
+        })
+    }
+
+    /// Load the `limit` most recent shifts, newest first.
+    pub(super) fn load_recent(
+        con: &Connection,
+        limit: usize,
+        shifts: &mut Vec<Self>,
+    ) -> SQLiteResult<()> {
+        let mut stmt = con.prepare(
+            "SELECT
+                opened_2822,
+                float_ct,
+                operator,
+                closed_2822,
+                expected_ct,
+                counted_ct
+            FROM shifts
+            ORDER BY id DESC
+            LIMIT :limit",
+        )?;
+
+        shifts.clear();
+
+        for shift in stmt.query_map(named_params! {":limit": limit}, Self::load)? {
+            shifts.push(shift?);
+        }
+
+        Ok(())
+    }
+
+    /// Load the shift that is still open (if any).
+    pub(super) fn load_open(con: &Connection) -> SQLiteResult<Option<Self>> {
+        con.query_row(
+            "SELECT
+                opened_2822,
+                float_ct,
+                operator,
+                closed_2822,
+                expected_ct,
+                counted_ct
+            FROM shifts
+            WHERE closed_2822 IS NULL
+            ORDER BY id DESC
+            LIMIT 1",
+            (),
+            Self::load,
+        )
+        .optional()
+    }
+
+    pub(super) fn store(&self, con: &Connection) -> SQLiteResult<()> {
+        con.execute(
+            "INSERT INTO shifts (
+                opened_2822,
+                float_ct,
+                operator,
+                closed_2822,
+                expected_ct,
+                counted_ct,
+                difference_ct
+            ) VALUES (
+                :opened_2822,
+                :float_ct,
+                :operator,
+                :closed_2822,
+                :expected_ct,
+                :counted_ct,
+                :difference_ct
+            )",
+            named_params! {
+                ":opened_2822": self.opened.to_rfc2822(),
+                ":float_ct": self.float_ct,
+                ":operator": self.operator,
+                ":closed_2822": self.closing.map(|closing| closing.closed.to_rfc2822()),
+                ":expected_ct": self.closing.map(|closing| closing.expected_ct),
+                ":counted_ct": self.closing.map(|closing| closing.counted_ct),
+                ":difference_ct": self.closing.map(|closing| closing.difference_ct()),
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// The total of the cash sales between the given dates.
+    /// Sales without a payment method count as cash (they are only recorded if it is not asked for).
+    pub(super) fn cash_sales_ct(
+        con: &Connection,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> SQLiteResult<u64> {
+        con.query_row(
+            "SELECT COALESCE(SUM(total_ct), 0)
+            FROM sales
+            WHERE (payment_method IS NULL OR payment_method = :cash)
+                AND unix_time_2822(date_2822) >= :from
+                AND unix_time_2822(date_2822) <= :until",
+            named_params! {
+                ":cash": PaymentMethod::Cash.to_string(),
+                ":from": from.timestamp(),
+                ":until": until.timestamp(),
+            },
+            |row| row.get(0),
+        )
+    }
+
+    /// Close the open shift with the counted cash.
+    pub(super) fn close(con: &Connection, closing: &ShiftClosing) -> SQLiteResult<()> {
+        con.execute(
+            "UPDATE shifts
+            SET
+                closed_2822 = :closed_2822,
+                expected_ct = :expected_ct,
+                counted_ct = :counted_ct,
+                difference_ct = :difference_ct
+            WHERE id = (SELECT MAX(id) FROM shifts WHERE closed_2822 IS NULL)",
+            named_params! {
+                ":closed_2822": closing.closed.to_rfc2822(),
+                ":expected_ct": closing.expected_ct,
+                ":counted_ct": closing.counted_ct,
+                ":difference_ct": closing.difference_ct(),
+            },
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Database, PaymentMethod, ProductEntry, ProductTranslation, SaleEntry};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+/// A fresh DB that only lives as long as the connection
+pub fn memory_db() -> Database {
+    Database::open_or_create(":memory:").expect("Failed to create in-memory DB")
+}
+
+/// A fixed date, so stored and loaded dates can be compared
+pub fn date(day: u32, hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+}
+
+/// Build products with sensible defaults (a kg price of 10 €).
+pub struct ProductBuilder {
+    product: ProductEntry,
+}
+
+impl ProductBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            product: ProductEntry::new(
+                String::from(name),
+                1000,
+                true,
+                String::from("Rind"),
+                String::new(),
+                Some(7.0),
+                Some(3),
+            ),
+        }
+    }
+
+    pub fn price_ct(mut self, price_ct: u64) -> Self {
+        self.product.price_ct = price_ct;
+        self
+    }
+
+    /// A fixed price per piece instead of a kg price
+    pub fn fixed_price(mut self) -> Self {
+        self.product.is_kg_price = false;
+        self
+    }
+
+    pub fn ingredients(mut self, ingredients: &str) -> Self {
+        self.product.ingredients = String::from(ingredients);
+        self
+    }
+
+    pub fn storage_temp(mut self, storage_temp: Option<f64>) -> Self {
+        self.product.storage_temp = storage_temp;
+        self
+    }
+
+    pub fn expiration_days(mut self, expiration_days: Option<u64>) -> Self {
+        self.product.expiration_days = expiration_days;
+        self
+    }
+
+    pub fn default_copies(mut self, default_copies: u64) -> Self {
+        self.product.default_copies = default_copies;
+        self
+    }
+
+    pub fn translation(mut self, language: &str, name: &str, ingredients: &str) -> Self {
+        self.product.translations.push(ProductTranslation {
+            language: String::from(language),
+            name: String::from(name),
+            ingredients: String::from(ingredients),
+        });
+
+        self
+    }
+
+    pub fn build(self) -> ProductEntry {
+        self.product
+    }
+
+    /// Store the product and return its index in the DB.
+    pub fn add_to(self, db: &mut Database) -> usize {
+        let name = self.product.name.clone();
+
+        db.add_product(self.product).expect("Failed to add product");
+        db.reload_products().expect("Failed to reload products");
+
+        db.products()
+            .iter()
+            .position(|product| product.name == name)
+            .expect("Product has just been added")
+    }
+}
+
+/// Build sales of a product like the UI does (weighed, unless pieces are given).
+pub struct SaleBuilder {
+    sale: SaleEntry,
+}
+
+impl SaleBuilder {
+    pub fn new(product: &ProductEntry, date: DateTime<Utc>) -> Self {
+        Self {
+            sale: SaleEntry {
+                date,
+                name: product.name.clone(),
+                weight_kg: None,
+                price_ct: product.price_ct,
+                total_ct: product.price_ct,
+                duplicate_override: false,
+                piece_count: None,
+                operator: Some(String::from("verkauf")),
+                station: Some(String::from("stand-1")),
+                payment_method: None,
+                expiration_override: None,
+            },
+        }
+    }
+
+    /// Weigh the sale and calculate the total from the kg price.
+    pub fn weight_kg(mut self, weight_kg: f64) -> Self {
+        self.sale.weight_kg = Some(weight_kg);
+        self.sale.total_ct = (weight_kg * (self.sale.price_ct as f64)).round() as u64;
+        self
+    }
+
+    /// Count pieces and calculate the total from the price per piece.
+    pub fn piece_count(mut self, piece_count: u64) -> Self {
+        self.sale.piece_count = Some(piece_count);
+        self.sale.total_ct = piece_count * self.sale.price_ct;
+        self
+    }
+
+    pub fn payment_method(mut self, payment_method: PaymentMethod) -> Self {
+        self.sale.payment_method = Some(payment_method);
+        self
+    }
+
+    pub fn duplicate_override(mut self) -> Self {
+        self.sale.duplicate_override = true;
+        self
+    }
+
+    pub fn build(self) -> SaleEntry {
+        self.sale
+    }
+
+    pub fn add_to(self, db: &mut Database) -> SaleEntry {
+        db.add_sale(&self.sale).expect("Failed to add sale");
+        self.sale
+    }
+}
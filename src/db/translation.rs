@@ -0,0 +1,125 @@
+use super::ProductEntry;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use rusqlite::{named_params, Connection, Result as SQLiteResult};
+
+/// The name and the ingredients of a product in another language
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProductTranslation {
+    /// A short code like "en" or "pl"
+    pub language: String,
+    pub name: String,
+
+    /// Empty if the ingredients need no translation
+    pub ingredients: String,
+}
+
+impl ProductTranslation {
+    /// Attach the stored translations to the products with the same ID.
+    pub(super) fn load_into(con: &Connection, products: &mut [ProductEntry]) -> SQLiteResult<()> {
+        let mut stmt = con.prepare(
+            "SELECT
+                product_id,
+                language,
+                name,
+                ingredients
+            FROM product_translations
+            ORDER BY language",
+        )?;
+
+        let idx_by_id: HashMap<i64, usize> = products
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, product)| product.id.map(|id| (id, idx)))
+            .collect();
+
+        let mut rows = stmt.query(())?;
+
+        while let Some(row) = rows.next()? {
+            let product_id: i64 = row.get("product_id")?;
+
+            // Translations of deleted products are ignored.
+            if let Some(&idx) = idx_by_id.get(&product_id) {
+                products[idx].translations.push(Self {
+                    language: row.get("language")?,
+                    name: row.get("name")?,
+                    ingredients: row.get("ingredients")?,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace the stored translations of a product.
+    pub(super) fn store_all(
+        con: &Connection,
+        product_id: i64,
+        translations: &[Self],
+    ) -> SQLiteResult<()> {
+        Self::delete_all(con, product_id)?;
+
+        for translation in translations {
+            con.execute(
+                "INSERT INTO product_translations (
+                    product_id,
+                    language,
+                    name,
+                    ingredients
+                ) VALUES (
+                    :product_id,
+                    :language,
+                    :name,
+                    :ingredients
+                )",
+                named_params! {
+                    ":product_id": product_id,
+                    ":language": translation.language,
+                    ":name": translation.name,
+                    ":ingredients": translation.ingredients,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn delete_all(con: &Connection, product_id: i64) -> SQLiteResult<()> {
+        con.execute(
+            "DELETE FROM product_translations WHERE product_id = :product_id",
+            named_params! {":product_id": product_id},
+        )?;
+
+        Ok(())
+    }
+}
+
+impl ProductEntry {
+    pub fn translation(&self, language: &str) -> Option<&ProductTranslation> {
+        self.translations
+            .iter()
+            .find(|translation| translation.language == language)
+    }
+
+    /// The product with the name and the ingredients in the given language.
+    /// Missing translations fall back to the original texts.
+    pub fn localized(&self, language: &str) -> Cow<'_, ProductEntry> {
+        let Some(translation) = self.translation(language) else {
+            return Cow::Borrowed(self);
+        };
+
+        let mut product = self.clone();
+
+        if !translation.name.is_empty() {
+            product.name = translation.name.clone();
+        }
+
+        if !translation.ingredients.is_empty() {
+            product.ingredients = translation.ingredients.clone();
+        }
+
+        Cow::Owned(product)
+    }
+}
@@ -0,0 +1,166 @@
+use super::{ProductEntry, SettingsEntry};
+
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+use rusqlite::Error as SQLiteError;
+
+/// The storage temperatures that make sense for food (from the freezer to the shelf)
+const STORAGE_TEMP_RANGE: RangeInclusive<f64> = -30.0..=30.0;
+
+/// Longer expiration dates are most likely typos (and would overflow the date calculation at some point).
+const MAX_EXPIRATION_DAYS: u64 = 3 * 365;
+
+/// More vouchers per item are most likely typos (and would empty the label roll).
+pub const MAX_COPIES: u64 = 10;
+
+/// A rough estimate of the ingredient characters per mm of voucher length
+/// (about 45 characters per line of 3 mm in the body font).
+const INGREDIENT_CHARS_PER_MM: usize = 15;
+
+/// A reason why a product must not be stored
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    EmptyName,
+    ZeroPrice,
+    StorageTemp(f64),
+    ExpirationDays(u64),
+    Copies(u64),
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ValidationError::*;
+
+        match self {
+            EmptyName => write!(f, "The name is empty."),
+            ZeroPrice => write!(f, "The price is 0."),
+            StorageTemp(temp) => write!(
+                f,
+                "The storage temperature of {:.1}°C is outside of {:.1}°C to {:.1}°C.",
+                temp,
+                STORAGE_TEMP_RANGE.start(),
+                STORAGE_TEMP_RANGE.end()
+            ),
+            ExpirationDays(days) => write!(
+                f,
+                "The expiration of {} days exceeds the limit of {} days.",
+                days, MAX_EXPIRATION_DAYS
+            ),
+            Copies(copies) => write!(
+                f,
+                "The default of {} copies is outside of 1 to {}.",
+                copies, MAX_COPIES
+            ),
+        }
+    }
+}
+
+/// A product that can be stored, but might cause trouble
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationWarning {
+    /// The ingredients might not fit on a voucher of the maximum length.
+    LongIngredients { len: usize, max_len: usize },
+}
+
+impl Display for ValidationWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ValidationWarning::*;
+
+        match self {
+            LongIngredients { len, max_len } => write!(
+                f,
+                "The ingredients have {} characters, more than about {} might not fit on the voucher.",
+                len, max_len
+            ),
+        }
+    }
+}
+
+/// Why a product could not be stored
+#[derive(Debug)]
+pub enum ProductError {
+    Invalid(Vec<ValidationError>),
+    DB(SQLiteError),
+}
+
+impl Display for ProductError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ProductError::*;
+
+        match self {
+            Invalid(errors) => {
+                write!(f, "The product is invalid:")?;
+
+                for err in errors {
+                    write!(f, " {}", err)?;
+                }
+
+                Ok(())
+            }
+
+            DB(err) => write!(f, "The product could not be stored: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ProductError {}
+
+impl From<SQLiteError> for ProductError {
+    fn from(value: SQLiteError) -> Self {
+        ProductError::DB(value)
+    }
+}
+
+impl ProductEntry {
+    /// Check the product before it is stored.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::EmptyName);
+        }
+
+        if self.price_ct == 0 {
+            errors.push(ValidationError::ZeroPrice);
+        }
+
+        if let Some(temp) = self.storage_temp {
+            if !STORAGE_TEMP_RANGE.contains(&temp) {
+                errors.push(ValidationError::StorageTemp(temp));
+            }
+        }
+
+        if let Some(days) = self.expiration_days {
+            if days > MAX_EXPIRATION_DAYS {
+                errors.push(ValidationError::ExpirationDays(days));
+            }
+        }
+
+        if !(1..=MAX_COPIES).contains(&self.default_copies) {
+            errors.push(ValidationError::Copies(self.default_copies));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check for problems that don't prevent storing the product.
+    /// The ingredients are measured against the maximum voucher length (if any).
+    pub fn warnings(&self, settings: &SettingsEntry) -> Vec<ValidationWarning> {
+        let mut warnings = Vec::new();
+
+        // Header, price and trailer need about half of the voucher.
+        let max_len = (settings.max_voucher_length_mm as usize) / 2 * INGREDIENT_CHARS_PER_MM;
+        let len = self.ingredients.chars().count();
+
+        if (max_len > 0) && (len > max_len) {
+            warnings.push(ValidationWarning::LongIngredients { len, max_len });
+        }
+
+        warnings
+    }
+}
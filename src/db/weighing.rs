@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{named_params, Connection, Result as SQLiteResult, Row};
+
+/// The weight of a sale together with the raw response of the scales.
+/// If the weight on a label is disputed, it can be traced back to the exact reading.
+#[derive(Clone)]
+pub struct WeighingEntry {
+    /// The date of the sale (which identifies it together with the product name)
+    pub sale_date: DateTime<Utc>,
+    pub product_name: String,
+
+    /// The weight that has been sold (after the tare and the legal rounding)
+    pub weight_kg: f64,
+    pub tare_kg: f64,
+
+    /// The raw weight response of the scales (`None` for emulated scales)
+    pub frame: Option<Vec<u8>>,
+}
+
+impl WeighingEntry {
+    fn load(row: &Row) -> SQLiteResult<Self> {
+        let sale_date_rfc2822: String = row.get("sale_date_2822")?;
+
+        Ok(Self {
+            sale_date: DateTime::parse_from_rfc2822(&sale_date_rfc2822)
+                .expect("Invalid timestamp format (expected RFC 2822)")
+                .into(),
+            product_name: row.get("product_name")?,
+            weight_kg: row.get("weight_kg")?,
+            tare_kg: row.get("tare_kg")?,
+            frame: row.get("frame")?,
+        })
+    }
+
+    /// Load the `limit` most recent weighings, newest first.
+    pub(super) fn load_recent(
+        con: &Connection,
+        limit: usize,
+        weighings: &mut Vec<Self>,
+    ) -> SQLiteResult<()> {
+        let mut stmt = con.prepare(
+            "SELECT
+                sale_date_2822,
+                product_name,
+                weight_kg,
+                tare_kg,
+                frame
+            FROM weighings
+            ORDER BY id DESC
+            LIMIT :limit",
+        )?;
+
+        weighings.clear();
+
+        for weighing in stmt.query_map(named_params! {":limit": limit}, Self::load)? {
+            weighings.push(weighing?);
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn store(&self, con: &Connection) -> SQLiteResult<()> {
+        con.execute(
+            "INSERT INTO weighings (
+                sale_date_2822,
+                product_name,
+                weight_kg,
+                tare_kg,
+                frame
+            ) VALUES (
+                :sale_date_2822,
+                :product_name,
+                :weight_kg,
+                :tare_kg,
+                :frame
+            )",
+            named_params! {
+                ":sale_date_2822": self.sale_date.to_rfc2822(),
+                ":product_name": self.product_name,
+                ":weight_kg": self.weight_kg,
+                ":tare_kg": self.tare_kg,
+                ":frame": self.frame,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Delete the weighings of sales before the given date. Returns how many have been deleted.
+    pub(super) fn delete_before(con: &Connection, date: DateTime<Utc>) -> SQLiteResult<usize> {
+        con.execute(
+            "DELETE FROM weighings WHERE unix_time_2822(sale_date_2822) < :before",
+            named_params! {":before": date.timestamp()},
+        )
+    }
+}
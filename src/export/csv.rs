@@ -0,0 +1,68 @@
+use super::{Exporter, Table, Value};
+use crate::format::NumberFormat;
+
+use std::io::{Error as IOError, Write};
+
+/// Separated values with a header line.
+/// Fields that contain the separator, quotes or line breaks are quoted (RFC 4180).
+pub struct Csv {
+    separator: char,
+    number_format: NumberFormat,
+}
+
+impl Csv {
+    pub fn comma_separated(number_format: NumberFormat) -> Self {
+        Self {
+            separator: ',',
+            number_format,
+        }
+    }
+
+    pub fn tab_separated(number_format: NumberFormat) -> Self {
+        Self {
+            separator: '\t',
+            number_format,
+        }
+    }
+
+    fn field(&self, text: &str) -> String {
+        if text.contains([self.separator, '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            String::from(text)
+        }
+    }
+
+    fn value(&self, value: &Value) -> String {
+        match value {
+            Value::Empty => String::new(),
+            Value::Text(text) => self.field(text),
+            Value::Integer(integer) => integer.to_string(),
+            Value::Decimal(decimal) => self.field(&self.number_format.shortest(*decimal)),
+            Value::Bool(flag) => flag.to_string(),
+        }
+    }
+
+    fn line<I: Iterator<Item = String>>(&self, fields: I) -> String {
+        let mut line = fields.collect::<Vec<_>>().join(&self.separator.to_string());
+        line.push('\n');
+
+        line
+    }
+}
+
+impl Exporter for Csv {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> Result<(), IOError> {
+        let header = self.line(table.columns.iter().map(|column| self.field(column)));
+        out.write_all(header.as_bytes())?;
+
+        for row in &table.rows {
+            out.write_all(
+                self.line(row.iter().map(|value| self.value(value)))
+                    .as_bytes(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use super::{Exporter, Table, Value};
+
+use std::fmt::Write as _;
+use std::io::{Error as IOError, Write};
+
+/// An array with an object per row, keyed by the column names.
+/// Numbers are written with a decimal dot, regardless of the number format.
+pub struct Json;
+
+impl Json {
+    fn string(text: &str) -> String {
+        let mut escaped = String::from("\"");
+
+        for c in text.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if c.is_control() => _ = write!(escaped, "\\u{:04x}", c as u32),
+                c => escaped.push(c),
+            }
+        }
+
+        escaped.push('"');
+        escaped
+    }
+
+    fn value(value: &Value) -> String {
+        match value {
+            Value::Text(text) => Self::string(text),
+            Value::Integer(integer) => integer.to_string(),
+
+            // JSON has no NaN or infinity.
+            Value::Decimal(decimal) if decimal.is_finite() => decimal.to_string(),
+            Value::Bool(flag) => flag.to_string(),
+            Value::Empty | Value::Decimal(_) => String::from("null"),
+        }
+    }
+}
+
+impl Exporter for Json {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> Result<(), IOError> {
+        out.write_all(b"[\n")?;
+
+        for (idx, row) in table.rows.iter().enumerate() {
+            let fields: Vec<String> = table
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(column, value)| format!("{}: {}", Self::string(column), Self::value(value)))
+                .collect();
+
+            let comma = if idx + 1 < table.rows.len() { "," } else { "" };
+            writeln!(out, "  {{{}}}{}", fields.join(", "), comma)?;
+        }
+
+        out.write_all(b"]\n")
+    }
+}
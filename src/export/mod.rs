@@ -0,0 +1,461 @@
+use crate::{
+    db::{LabelRollEntry, PrintJobEntry, ProductEntry, SaleEntry, ShiftEntry, WeighingEntry},
+    format::NumberFormat,
+};
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufWriter, Error as IOError, Write};
+use std::path::{Path, PathBuf};
+
+/// Comma- or tab-separated values
+mod csv;
+pub use csv::Csv;
+
+/// An array of objects per table
+mod json;
+pub use json::Json;
+
+/// Spreadsheets for Excel and LibreOffice
+mod xlsx;
+pub use xlsx::Xlsx;
+
+/// Anonymized weekly totals per product
+mod statistics;
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownFormat(PathBuf),
+    IO(IOError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+
+        match self {
+            UnknownFormat(path) => write!(
+                f,
+                "The format of {} is unknown (use .csv, .tsv, .json or .xlsx).",
+                path.display()
+            ),
+            IO(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<IOError> for Error {
+    fn from(value: IOError) -> Self {
+        Error::IO(value)
+    }
+}
+
+/// A single cell of a table
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Empty,
+    Text(String),
+    Integer(i64),
+    Decimal(f64),
+    Bool(bool),
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Empty, Into::into)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Integer(value as i64)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Decimal(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+/// The data of an export, independent of the file format
+pub struct Table {
+    /// The name of the sheet (in formats that have one)
+    pub name: &'static str,
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    /// The sales in the given order
+    pub fn sales(sales: &[SaleEntry]) -> Self {
+        let rows = sales
+            .iter()
+            .map(|sale| {
+                vec![
+                    Value::Text(sale.date.to_rfc3339()),
+                    sale.name.clone().into(),
+                    sale.weight_kg.into(),
+                    sale.piece_count.into(),
+                    sale.price_ct.into(),
+                    sale.total_ct.into(),
+                    sale.duplicate_override.into(),
+                    sale.operator.clone().into(),
+                    sale.station.clone().into(),
+                    sale.payment_method.map(|method| method.to_string()).into(),
+                    sale.expiration_override
+                        .map(|date| date.to_rfc3339())
+                        .into(),
+                ]
+            })
+            .collect();
+
+        Self {
+            name: "sales",
+            columns: vec![
+                "date",
+                "name",
+                "weight_kg",
+                "piece_count",
+                "price_ct",
+                "total_ct",
+                "duplicate_override",
+                "operator",
+                "station",
+                "payment_method",
+                "expiration_override",
+            ],
+            rows,
+        }
+    }
+
+    /// The products with their prices and label data (without translations)
+    pub fn products(products: &[ProductEntry]) -> Self {
+        let rows = products
+            .iter()
+            .map(|product| {
+                vec![
+                    product.id().map(|id| id as u64).into(),
+                    product.name.clone().into(),
+                    product.price_ct.into(),
+                    product.is_kg_price.into(),
+                    product.ingredients.clone().into(),
+                    product.additional_info.clone().into(),
+                    product.storage_temp.into(),
+                    product.expiration_days.into(),
+                    product.organic_control_code.clone().into(),
+                    product.default_copies.into(),
+                    product.in_store_code().into(),
+                ]
+            })
+            .collect();
+
+        Self {
+            name: "products",
+            columns: vec![
+                "id",
+                "name",
+                "price_ct",
+                "is_kg_price",
+                "ingredients",
+                "additional_info",
+                "storage_temp",
+                "expiration_days",
+                "organic_control_code",
+                "default_copies",
+                "in_store_code",
+            ],
+            rows,
+        }
+    }
+
+    /// The print attempts in the given order
+    pub fn print_jobs<'a, I: IntoIterator<Item = &'a PrintJobEntry>>(jobs: I) -> Self {
+        let rows = jobs
+            .into_iter()
+            .map(|job| {
+                vec![
+                    Value::Text(job.date.to_rfc3339()),
+                    Value::Text(job.outcome.to_string()),
+                    job.product_name.clone().into(),
+                    job.error.clone().into(),
+                    job.sale_date.map(|date| date.to_rfc3339()).into(),
+                ]
+            })
+            .collect();
+
+        Self {
+            name: "print_jobs",
+            columns: vec!["date", "outcome", "product_name", "error", "sale_date"],
+            rows,
+        }
+    }
+
+    /// The label rolls in the given order with their estimated usage
+    pub fn label_rolls(rolls: &[LabelRollEntry]) -> Self {
+        let rows = rolls
+            .iter()
+            .map(|roll| {
+                vec![
+                    Value::Text(roll.inserted.to_rfc3339()),
+                    Value::Text(roll.label_type.to_string()),
+                    roll.length_mm.into(),
+                    roll.used_mm.into(),
+                    roll.print_count.into(),
+                ]
+            })
+            .collect();
+
+        Self {
+            name: "label_rolls",
+            columns: vec![
+                "inserted",
+                "label_type",
+                "length_mm",
+                "used_mm",
+                "print_count",
+            ],
+            rows,
+        }
+    }
+
+    /// The weighings in the given order with their raw frames as hex
+    pub fn weighings(weighings: &[WeighingEntry]) -> Self {
+        let rows = weighings
+            .iter()
+            .map(|weighing| {
+                let frame = weighing.frame.as_ref().map(|frame| {
+                    frame
+                        .iter()
+                        .map(|byte| format!("{:02x}", byte))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                });
+
+                vec![
+                    Value::Text(weighing.sale_date.to_rfc3339()),
+                    weighing.product_name.clone().into(),
+                    weighing.weight_kg.into(),
+                    weighing.tare_kg.into(),
+                    frame.into(),
+                ]
+            })
+            .collect();
+
+        Self {
+            name: "weighings",
+            columns: vec!["sale_date", "product_name", "weight_kg", "tare_kg", "frame"],
+            rows,
+        }
+    }
+
+    /// The shifts in the given order with their cash reconciliation (empty while open)
+    pub fn shifts(shifts: &[ShiftEntry]) -> Self {
+        let rows = shifts
+            .iter()
+            .map(|shift| {
+                vec![
+                    Value::Text(shift.opened.to_rfc3339()),
+                    shift.operator.clone().into(),
+                    shift.float_ct.into(),
+                    shift
+                        .closing
+                        .map(|closing| closing.closed.to_rfc3339())
+                        .into(),
+                    shift.closing.map(|closing| closing.expected_ct).into(),
+                    shift.closing.map(|closing| closing.counted_ct).into(),
+                    shift.closing.map(|closing| closing.difference_ct()).into(),
+                ]
+            })
+            .collect();
+
+        Self {
+            name: "shifts",
+            columns: vec![
+                "opened",
+                "operator",
+                "float_ct",
+                "closed",
+                "expected_ct",
+                "counted_ct",
+                "difference_ct",
+            ],
+            rows,
+        }
+    }
+}
+
+/// A file format that tables can be exported to.
+/// A new format only needs a module with an implementation of this trait
+/// and a variant in `ExportFormat`, the call sites stay the same.
+pub trait Exporter {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> Result<(), IOError>;
+}
+
+/// The formats that are chosen by the file extension
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Json,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "tsv" => Some(ExportFormat::Tsv),
+            "json" => Some(ExportFormat::Json),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    /// The exporter of the format.
+    /// Only the text formats use the number format, the others have native numbers.
+    pub fn exporter(&self, number_format: NumberFormat) -> Box<dyn Exporter> {
+        match self {
+            ExportFormat::Csv => Box::new(Csv::comma_separated(number_format)),
+            ExportFormat::Tsv => Box::new(Csv::tab_separated(number_format)),
+            ExportFormat::Json => Box::new(Json),
+            ExportFormat::Xlsx => Box::new(Xlsx),
+        }
+    }
+}
+
+/// Write a table into a file whose format is chosen by its extension.
+pub fn export_table(table: &Table, path: &Path, number_format: NumberFormat) -> Result<(), Error> {
+    let format =
+        ExportFormat::from_path(path).ok_or_else(|| Error::UnknownFormat(path.to_path_buf()))?;
+
+    let mut out = BufWriter::new(File::create(path)?);
+    format.exporter(number_format).write(table, &mut out)?;
+    out.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        Table {
+            name: "test",
+            columns: vec!["name", "weight_kg", "count", "ok"],
+            rows: vec![
+                vec![
+                    Value::Text(String::from("Wurst, \"fein\"")),
+                    Value::Decimal(0.5),
+                    Value::Integer(3),
+                    Value::Bool(true),
+                ],
+                vec![
+                    Value::Text(String::from("Käse")),
+                    Value::Empty,
+                    Value::Integer(-1),
+                    Value::Bool(false),
+                ],
+            ],
+        }
+    }
+
+    fn export(format: ExportFormat, number_format: NumberFormat) -> Vec<u8> {
+        let mut out = Vec::new();
+        format
+            .exporter(number_format)
+            .write(&table(), &mut out)
+            .unwrap();
+
+        out
+    }
+
+    #[test]
+    fn text_formats_escape_their_separators() {
+        let csv = export(ExportFormat::Csv, NumberFormat::Dot);
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "name,weight_kg,count,ok\n\"Wurst, \"\"fein\"\"\",0.5,3,true\nKäse,,-1,false\n"
+        );
+
+        // The decimal comma needs no quotes in TSV.
+        let tsv = export(ExportFormat::Tsv, NumberFormat::Comma);
+        assert_eq!(
+            String::from_utf8(tsv).unwrap(),
+            "name\tweight_kg\tcount\tok\n\"Wurst, \"\"fein\"\"\"\t0,5\t3\ttrue\nKäse\t\t-1\tfalse\n"
+        );
+
+        let json = export(ExportFormat::Json, NumberFormat::Comma);
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "[\n  {\"name\": \"Wurst, \\\"fein\\\"\", \"weight_kg\": 0.5, \"count\": 3, \"ok\": true},\n  \
+             {\"name\": \"Käse\", \"weight_kg\": null, \"count\": -1, \"ok\": false}\n]\n"
+        );
+    }
+
+    fn unzip(archive: &[u8], name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let mut file = archive.by_name(name).unwrap();
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut file, &mut text).unwrap();
+
+        text
+    }
+
+    #[test]
+    fn spreadsheets_are_zipped_xml() {
+        let xlsx = export(ExportFormat::Xlsx, NumberFormat::Comma);
+        assert!(xlsx.starts_with(b"PK"));
+
+        let content_types = unzip(&xlsx, "[Content_Types].xml");
+        assert!(content_types.contains(r#"PartName="/xl/worksheets/sheet1.xml""#));
+
+        let workbook = unzip(&xlsx, "xl/workbook.xml");
+        assert!(workbook.contains(r#"<sheet name="test" sheetId="1""#));
+
+        // Text goes to the shared strings, numbers and flags stay in the sheet.
+        let strings = unzip(&xlsx, "xl/sharedStrings.xml");
+        assert!(strings.contains(r#"<t>Wurst, "fein"</t>"#));
+
+        let mut escaped = Vec::new();
+        let table = Table {
+            name: "test",
+            columns: vec!["name"],
+            rows: vec![vec![Value::Text(String::from("Wurst & <Käse>"))]],
+        };
+        ExportFormat::Xlsx
+            .exporter(NumberFormat::Comma)
+            .write(&table, &mut escaped)
+            .unwrap();
+        let strings = unzip(&escaped, "xl/sharedStrings.xml");
+        assert!(strings.contains("<t>Wurst &amp; &lt;Käse&gt;</t>"));
+
+        let sheet = unzip(&xlsx, "xl/worksheets/sheet1.xml");
+        assert!(sheet.contains(r#"<c r="A2" t="s">"#));
+        assert!(sheet.contains(r#"<c r="B2"><v>0.5</v></c>"#));
+        assert!(sheet.contains(r#"<c r="C3"><v>-1</v></c>"#));
+        assert!(sheet.contains(r#"<c r="D3" t="b"><v>0</v></c>"#));
+        assert!(!sheet.contains(r#"r="B3""#));
+    }
+}
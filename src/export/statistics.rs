@@ -0,0 +1,137 @@
+use super::{Table, Value};
+use crate::db::{SaleEntry, SettingsEntry};
+
+use std::collections::BTreeMap;
+
+use chrono::{Local, NaiveDate};
+
+/// The sums of a product in a week
+#[derive(Default)]
+struct WeekTotals {
+    count: u64,
+    weight_kg: f64,
+    piece_count: u64,
+    total_ct: u64,
+}
+
+impl Table {
+    /// Anonymized statistics for sharing (e.g. with a producer cooperative):
+    /// The sales are summed up per product and week, without times, operators, stations or payment methods.
+    /// Weeks are business weeks (see `business_day_cutoff` and `week_start`).
+    pub fn statistics(sales: &[SaleEntry], settings: &SettingsEntry) -> Self {
+        let mut weeks: BTreeMap<(NaiveDate, &str), WeekTotals> = BTreeMap::new();
+
+        for sale in sales {
+            let day = settings.business_day(sale.date.with_timezone(&Local));
+            let week = day.week(settings.week_start).first_day();
+            let totals = weeks.entry((week, &sale.name)).or_default();
+
+            totals.count += 1;
+            totals.weight_kg += sale.weight_kg.unwrap_or(0.0);
+            totals.piece_count += sale.piece_count.unwrap_or(0);
+            totals.total_ct += sale.total_ct;
+        }
+
+        let rows = weeks
+            .into_iter()
+            .map(|((week, name), totals)| {
+                vec![
+                    Value::Text(week.format("%Y-%m-%d").to_string()),
+                    Value::Text(String::from(name)),
+                    totals.count.into(),
+                    // Sums of floats would show their rounding errors.
+                    Value::Decimal((totals.weight_kg * 1000.0).round() / 1000.0),
+                    totals.piece_count.into(),
+                    totals.total_ct.into(),
+                ]
+            })
+            .collect();
+
+        Self {
+            name: "statistics",
+            columns: vec![
+                "week",
+                "name",
+                "sales",
+                "weight_kg",
+                "piece_count",
+                "total_ct",
+            ],
+            rows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{date, ProductBuilder, SaleBuilder};
+    use crate::db::ProductEntry;
+
+    use chrono::Weekday;
+
+    fn sale(product: &ProductEntry, day: u32, weight_kg: f64) -> SaleEntry {
+        // 12:00 UTC is the same day in every European time zone.
+        SaleBuilder::new(product, date(day, 12))
+            .weight_kg(weight_kg)
+            .build()
+    }
+
+    #[test]
+    fn sales_are_summed_up_per_product_and_week() {
+        let beef = ProductBuilder::new("Rinderbraten").build();
+        let sausage = ProductBuilder::new("Bratwurst").build();
+
+        // 2024-05-06 is a monday, 2024-05-12 a sunday.
+        let sales = [
+            sale(&beef, 6, 0.5),
+            sale(&beef, 12, 0.25),
+            sale(&beef, 13, 1.0),
+            sale(&sausage, 7, 0.1),
+        ];
+
+        let settings = SettingsEntry::default();
+        let table = Table::statistics(&sales, &settings);
+
+        assert_eq!(
+            table.rows,
+            vec![
+                vec![
+                    Value::Text(String::from("2024-05-06")),
+                    Value::Text(String::from("Bratwurst")),
+                    Value::Integer(1),
+                    Value::Decimal(0.1),
+                    Value::Integer(0),
+                    Value::Integer(100),
+                ],
+                vec![
+                    Value::Text(String::from("2024-05-06")),
+                    Value::Text(String::from("Rinderbraten")),
+                    Value::Integer(2),
+                    Value::Decimal(0.75),
+                    Value::Integer(0),
+                    Value::Integer(750),
+                ],
+                vec![
+                    Value::Text(String::from("2024-05-13")),
+                    Value::Text(String::from("Rinderbraten")),
+                    Value::Integer(1),
+                    Value::Decimal(1.0),
+                    Value::Integer(0),
+                    Value::Integer(1000),
+                ],
+            ]
+        );
+
+        // Weeks that start on sunday move the sunday sale into the next week.
+        let settings = SettingsEntry {
+            week_start: Weekday::Sun,
+            ..SettingsEntry::default()
+        };
+        let table = Table::statistics(&sales, &settings);
+
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.rows[2][0], Value::Text(String::from("2024-05-12")));
+        assert_eq!(table.rows[2][2], Value::Integer(2));
+    }
+}
@@ -0,0 +1,56 @@
+use super::{Exporter, Table, Value};
+
+use std::io::{Error as IOError, Write};
+
+use rust_xlsxwriter::{Workbook, Worksheet, XlsxError};
+
+/// An Office Open XML workbook with a single sheet
+pub struct Xlsx;
+
+impl Xlsx {
+    /// Write a cell (empty and non-finite values leave it out).
+    fn cell(
+        worksheet: &mut Worksheet,
+        row: u32,
+        column: u16,
+        value: &Value,
+    ) -> Result<(), XlsxError> {
+        match value {
+            Value::Empty => (),
+            Value::Text(text) => _ = worksheet.write_string(row, column, text)?,
+            Value::Integer(integer) => _ = worksheet.write_number(row, column, *integer as f64)?,
+            Value::Decimal(decimal) if decimal.is_finite() => {
+                _ = worksheet.write_number(row, column, *decimal)?
+            }
+            Value::Decimal(_) => (),
+            Value::Bool(flag) => _ = worksheet.write_boolean(row, column, *flag)?,
+        }
+
+        Ok(())
+    }
+
+    fn workbook(table: &Table) -> Result<Vec<u8>, XlsxError> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(table.name)?;
+
+        for (column, name) in table.columns.iter().enumerate() {
+            worksheet.write_string(0, column as u16, *name)?;
+        }
+
+        for (row, values) in table.rows.iter().enumerate() {
+            for (column, value) in values.iter().enumerate() {
+                Self::cell(worksheet, (row + 1) as u32, column as u16, value)?;
+            }
+        }
+
+        workbook.save_to_buffer()
+    }
+}
+
+impl Exporter for Xlsx {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> Result<(), IOError> {
+        let workbook = Self::workbook(table).map_err(IOError::other)?;
+        out.write_all(&workbook)
+    }
+}
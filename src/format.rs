@@ -0,0 +1,151 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// How decimal numbers are written
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// Decimal comma and dots between thousands, like "1.234,56" (German)
+    #[default]
+    Comma,
+
+    /// Decimal dot without separators, like "1234.56" (for spreadsheets and scripts)
+    Dot,
+}
+
+impl NumberFormat {
+    /// Format a number with a fixed count of decimals.
+    pub fn fixed(&self, value: f64, decimals: usize) -> String {
+        self.localize(&format!("{:.*}", decimals, value))
+    }
+
+    /// Format a number with as few decimals as possible (e.g. "7" or "5,5").
+    pub fn shortest(&self, value: f64) -> String {
+        self.localize(&value.to_string())
+    }
+
+    /// Format cents as euros without the currency sign (e.g. "1.234,50").
+    /// This is exact even for totals that `f64` cannot represent.
+    pub fn euro(&self, ct: u64) -> String {
+        self.localize(&format!("{}.{:02}", ct / 100, ct % 100))
+    }
+
+    /// Replace the separators of a number that has been formatted by Rust (like "-1234.5").
+    fn localize(&self, number: &str) -> String {
+        if *self == NumberFormat::Dot {
+            return String::from(number);
+        }
+
+        let (sign, number) = match number.strip_prefix('-') {
+            Some(number) => ("-", number),
+            None => ("", number),
+        };
+
+        let (int_part, fraction) = match number.split_once('.') {
+            Some((int_part, fraction)) => (int_part, Some(fraction)),
+            None => (number, None),
+        };
+
+        // Group the integer part from the right.
+        let mut localized = String::from(sign);
+
+        for (idx, digit) in int_part.chars().enumerate() {
+            if (idx > 0) && ((int_part.len() - idx) % 3 == 0) {
+                localized.push('.');
+            }
+
+            localized.push(digit);
+        }
+
+        if let Some(fraction) = fraction {
+            localized.push(',');
+            localized.push_str(fraction);
+        }
+
+        localized
+    }
+}
+
+impl Display for NumberFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumberFormat::Comma => write!(f, "comma"),
+            NumberFormat::Dot => write!(f, "dot"),
+        }
+    }
+}
+
+impl FromStr for NumberFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "comma" => Ok(NumberFormat::Comma),
+            "dot" => Ok(NumberFormat::Dot),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Format a number for the UI and the vouchers (e.g. "1.234,5").
+pub fn fixed(value: f64, decimals: usize) -> String {
+    NumberFormat::Comma.fixed(value, decimals)
+}
+
+/// Format cents for the UI and the vouchers (e.g. "12,99 €").
+pub fn euro(ct: u64) -> String {
+    format!("{} €", NumberFormat::Comma.euro(ct))
+}
+
+/// Format a difference of cents with its sign (e.g. "-2,50 €" or "+0,10 €").
+pub fn euro_difference(ct: i64) -> String {
+    let sign = match ct.signum() {
+        -1 => "-",
+        1 => "+",
+        _ => "",
+    };
+
+    format!("{}{}", sign, euro(ct.unsigned_abs()))
+}
+
+/// Cut a text to at most `width` characters, marking the cut with an ellipsis.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return String::from(text);
+    }
+
+    match width.checked_sub(1) {
+        Some(kept) => text.chars().take(kept).chain(['…']).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_localized() {
+        assert_eq!(fixed(1234567.891, 2), "1.234.567,89");
+        assert_eq!(fixed(-999.5, 1), "-999,5");
+        assert_eq!(fixed(0.25, 3), "0,250");
+        assert_eq!(NumberFormat::Comma.shortest(5.5), "5,5");
+        assert_eq!(NumberFormat::Comma.shortest(19.0), "19");
+        assert_eq!(euro(123405), "1.234,05 €");
+        assert_eq!(euro(7), "0,07 €");
+        assert_eq!(euro_difference(-250), "-2,50 €");
+        assert_eq!(euro_difference(10), "+0,10 €");
+        assert_eq!(euro_difference(0), "0,00 €");
+
+        assert_eq!(NumberFormat::Dot.fixed(1234.5, 2), "1234.50");
+        assert_eq!(NumberFormat::Dot.euro(123405), "1234.05");
+    }
+
+    #[test]
+    fn texts_are_truncated() {
+        assert_eq!(truncate("Rinderhack", 20), "Rinderhack");
+        assert_eq!(truncate("Rinderhack", 10), "Rinderhack");
+        assert_eq!(truncate("Rinderhüftsteak", 8), "Rinderh…");
+        assert_eq!(truncate("Rinderhack", 1), "…");
+        assert_eq!(truncate("Rinderhack", 0), "");
+    }
+}
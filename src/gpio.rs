@@ -0,0 +1,232 @@
+use std::fmt::Display;
+use std::fs;
+use std::io::Error as IOError;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[cfg(feature = "gpio")]
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    os::fd::AsRawFd,
+    time::{Duration, Instant},
+};
+
+/// The sysfs interface of the GPIO pins (e.g. on a Raspberry Pi)
+const GPIO_DIR: &str = "/sys/class/gpio";
+
+/// Presses of the same button within this time are contact bounce.
+#[cfg(feature = "gpio")]
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A pin of the sysfs GPIO interface.
+/// Newer kernels number the pins of the Raspberry Pi with an offset (e.g. BCM 17 is 529).
+pub struct Pin {
+    dir: PathBuf,
+}
+
+impl Pin {
+    /// Export the pin unless it already is (which might need membership in the "gpio" group).
+    pub fn export(number: u32) -> Result<Self, IOError> {
+        let gpio_dir = Path::new(GPIO_DIR);
+        let dir = gpio_dir.join(format!("gpio{}", number));
+
+        if !dir.exists() {
+            fs::write(gpio_dir.join("export"), number.to_string())?;
+        }
+
+        Ok(Self { dir })
+    }
+
+    /// Drive the pin high or low.
+    pub fn write(&self, high: bool) -> Result<(), IOError> {
+        fs::write(self.dir.join("direction"), "out")?;
+        fs::write(self.dir.join("value"), if high { "1" } else { "0" })
+    }
+
+    /// Switch the pin to input and report both edges to `poll()` on the returned value file.
+    #[cfg(feature = "gpio")]
+    fn watch(&self) -> Result<File, IOError> {
+        fs::write(self.dir.join("direction"), "in")?;
+        fs::write(self.dir.join("edge"), "both")?;
+
+        File::open(self.dir.join("value"))
+    }
+}
+
+/// What a physical button does (the same as the corresponding key)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ButtonAction {
+    /// Like `Enter`: Open the dialog, confirm it or close a message
+    Confirm,
+
+    /// Take the weight on the scales as tare (e.g. of a bowl)
+    Tare,
+
+    /// Print the last voucher again
+    Reprint,
+}
+
+impl Display for ButtonAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ButtonAction::Confirm => write!(f, "confirm"),
+            ButtonAction::Tare => write!(f, "tare"),
+            ButtonAction::Reprint => write!(f, "reprint"),
+        }
+    }
+}
+
+impl FromStr for ButtonAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "confirm" => Ok(ButtonAction::Confirm),
+            "tare" => Ok(ButtonAction::Tare),
+            "reprint" => Ok(ButtonAction::Reprint),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The GPIO pins of the buttons, like "confirm=529;tare=539;reprint=534"
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ButtonBindings(Vec<(ButtonAction, u32)>);
+
+impl ButtonBindings {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Display for ButtonBindings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|(action, pin)| format!("{}={}", action, pin))
+            .collect();
+
+        write!(f, "{}", entries.join(";"))
+    }
+}
+
+impl FromStr for ButtonBindings {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (action, pin) = entry.split_once('=').ok_or(())?;
+                let action: ButtonAction = action.trim().parse()?;
+
+                Ok((action, pin.trim().parse().map_err(|_| ())?))
+            })
+            .collect::<Result<_, _>>()
+            .map(ButtonBindings)
+    }
+}
+
+/// Block and call `on_press` for every press of a bound button until it returns `false`.
+/// Pressed buttons pull their pin low if `active_low` is set (e.g. against a pull-up).
+#[cfg(feature = "gpio")]
+pub fn watch_buttons<F: FnMut(ButtonAction) -> bool>(
+    bindings: &ButtonBindings,
+    active_low: bool,
+    mut on_press: F,
+) -> Result<(), IOError> {
+    let mut values = bindings
+        .0
+        .iter()
+        .map(|&(action, pin)| Ok((action, Pin::export(pin)?.watch()?)))
+        .collect::<Result<Vec<_>, IOError>>()?;
+
+    let mut pollfds: Vec<libc::pollfd> = values
+        .iter()
+        .map(|(_, value)| libc::pollfd {
+            fd: value.as_raw_fd(),
+            events: libc::POLLPRI | libc::POLLERR,
+            revents: 0,
+        })
+        .collect();
+
+    let mut last_presses: Vec<Option<Instant>> = vec![None; values.len()];
+
+    // The initial values are reported as changes, so they are read once before waiting.
+    for (_, value) in &mut values {
+        read_value(value)?;
+    }
+
+    loop {
+        if unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as _, -1) } < 0 {
+            let err = IOError::last_os_error();
+
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+
+            return Err(err);
+        }
+
+        for ((pollfd, (action, value)), last_press) in
+            pollfds.iter().zip(&mut values).zip(&mut last_presses)
+        {
+            if (pollfd.revents & libc::POLLPRI) == 0 {
+                continue;
+            }
+
+            // Only the edge into the pressed state counts, and only once per bounce.
+            if read_value(value)? == active_low {
+                continue;
+            }
+
+            if last_press.is_some_and(|last_press| last_press.elapsed() < DEBOUNCE) {
+                continue;
+            }
+
+            *last_press = Some(Instant::now());
+
+            if !on_press(*action) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Read the level of a watched pin (which needs a seek before every read).
+#[cfg(feature = "gpio")]
+fn read_value(value: &mut File) -> Result<bool, IOError> {
+    let mut level = String::new();
+
+    value.seek(SeekFrom::Start(0))?;
+    value.read_to_string(&mut level)?;
+
+    Ok(level.trim() == "1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_bindings_round_trip() {
+        let bindings: ButtonBindings = "confirm=529; tare = 539;reprint=534".parse().unwrap();
+
+        assert_eq!(
+            bindings.0,
+            [
+                (ButtonAction::Confirm, 529),
+                (ButtonAction::Tare, 539),
+                (ButtonAction::Reprint, 534)
+            ]
+        );
+        assert_eq!(bindings.to_string(), "confirm=529;tare=539;reprint=534");
+
+        assert!("".parse::<ButtonBindings>().unwrap().is_empty());
+        assert!("print=1".parse::<ButtonBindings>().is_err());
+        assert!("tare=-1".parse::<ButtonBindings>().is_err());
+    }
+}
@@ -13,5 +13,26 @@ pub mod voucher;
 /// Access the product database.
 pub mod db;
 
+/// Format numbers for the UI, the vouchers and exports.
+pub mod format;
+
+/// Write tables in several formats for spreadsheets and scripts.
+pub mod export;
+
+/// Lay out the vouchers for products.
+pub mod template;
+
+/// Tie the hardware and the storage together to perform sales.
+pub mod pos;
+
 /// Render the UI.
 pub mod ui;
+
+/// Collect diagnostic data into a bundle for bug reports.
+pub mod support;
+
+/// Drive and watch GPIO pins (e.g. on a Raspberry Pi).
+pub mod gpio;
+
+/// Look for newer releases (without installing them).
+pub mod update;
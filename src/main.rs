@@ -1,12 +1,211 @@
 use std::env;
+use std::path::{Path, PathBuf};
 
+use chrono::Local;
+
+use weight_wb::db::{
+    Database, LabelRollEntry, PrintJobEntry, SaleEntry, ShiftEntry, WeighingEntry, MAX_PRINT_JOBS,
+};
+use weight_wb::export::{export_table, Table};
+use weight_wb::support::write_support_bundle;
+use weight_wb::template::export_product_labels;
 use weight_wb::ui::App;
+use weight_wb::update::{check_for_update, Version};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Pars the CLI args.
     let args: Vec<String> = env::args().collect();
     let emulated_scales = args.iter().any(|c| c == "--emulated-scales");
     let dump_voucher = args.iter().any(|c| c == "--dump-voucher");
+    let print_jobs = args.iter().any(|c| c == "--print-jobs");
+    let read_only = args.iter().any(|c| c == "--read-only");
+
+    if print_jobs {
+        return show_print_jobs();
+    }
+
+    if args.iter().any(|c| c == "--check-update") {
+        return check_update();
+    }
+
+    // `support-bundle [<file>] [--without-sales]`
+    if args.get(1).is_some_and(|c| c == "support-bundle") {
+        let path = args
+            .get(2)
+            .filter(|c| !c.starts_with("--"))
+            .map(PathBuf::from);
+
+        let include_sales = !args.iter().any(|c| c == "--without-sales");
+
+        return support_bundle(path, include_sales);
+    }
+
+    // `export <sales|products|print-jobs|label-rolls|weighings|statistics> <file>` (the format is chosen by the extension)
+    if args.get(1).is_some_and(|c| c == "export") {
+        let (Some(data), Some(path)) = (args.get(2), args.get(3)) else {
+            return Err(
+                "Usage: export <sales|products|print-jobs|label-rolls|weighings|statistics> <file>"
+                    .into(),
+            );
+        };
+
+        return export(data, Path::new(path));
+    }
+
+    // `--export-labels <dir> [<product name> ...]`
+    if let Some(idx) = args.iter().position(|c| c == "--export-labels") {
+        let dir = args
+            .get(idx + 1)
+            .ok_or("--export-labels needs a target directory")?;
+
+        return export_labels(Path::new(dir), &args[(idx + 2)..]);
+    }
+
+    App::run(emulated_scales, dump_voucher, read_only)
+}
+
+/// Render the labels of the given products (or all of them) into PNG files.
+fn export_labels(dir: &Path, product_names: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open_or_create("db.sqlite")?;
+
+    // TODO: Allow to configure the width.
+    for path in export_product_labels(&db, product_names, 720, dir)? {
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Export the sales, the products, the print history, the label rolls, the weighing log
+/// or the anonymized statistics as CSV, TSV, JSON or XLSX.
+fn export(data: &str, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open_or_create("db.sqlite")?;
+
+    let table = match data {
+        "sales" => {
+            let mut sales: Vec<SaleEntry> = Vec::new();
+            db.sales(&mut sales)?;
+            Table::sales(&sales)
+        }
+
+        "products" => Table::products(db.products()),
+
+        // Only with the consent of the business, the statistics are meant to leave the shop.
+        "statistics" => {
+            if !db.settings().statistics_export {
+                return Err(
+                    "The statistics export is disabled (see the setting statistics_export).".into(),
+                );
+            }
+
+            let mut sales: Vec<SaleEntry> = Vec::new();
+            db.sales(&mut sales)?;
+            Table::statistics(&sales, db.settings())
+        }
+
+        "print-jobs" => {
+            let mut jobs: Vec<PrintJobEntry> = Vec::new();
+            db.print_jobs(MAX_PRINT_JOBS, &mut jobs)?;
+            Table::print_jobs(jobs.iter().rev())
+        }
+
+        "label-rolls" => {
+            let mut rolls: Vec<LabelRollEntry> = Vec::new();
+            db.label_rolls(&mut rolls)?;
+            rolls.reverse();
+            Table::label_rolls(&rolls)
+        }
+
+        "weighings" => {
+            let mut weighings: Vec<WeighingEntry> = Vec::new();
+            db.weighings(10_000, &mut weighings)?;
+            weighings.reverse();
+            Table::weighings(&weighings)
+        }
+
+        "shifts" => {
+            let mut shifts: Vec<ShiftEntry> = Vec::new();
+            db.shifts(10_000, &mut shifts)?;
+            shifts.reverse();
+            Table::shifts(&shifts)
+        }
+
+        _ => {
+            return Err(format!(
+                "Unknown export: {} (use sales, products, print-jobs, label-rolls, weighings, shifts or statistics)",
+                data
+            )
+            .into())
+        }
+    };
+
+    export_table(&table, path, db.settings().export_number_format)?;
+    println!(
+        "{} rows have been written to {}.",
+        table.rows.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Collect diagnostic data for a bug report into a ZIP file.
+fn support_bundle(
+    path: Option<PathBuf>,
+    include_sales: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open_or_create("db.sqlite")?;
+
+    let path = path.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "support-bundle_{}.zip",
+            Local::now().format("%Y-%m-%d_%H-%M-%S")
+        ))
+    });
+
+    for name in write_support_bundle(&db, &path, include_sales)? {
+        println!("{}", name);
+    }
+
+    println!("The bundle has been written to {}.", path.display());
+
+    Ok(())
+}
+
+/// Look for a newer release and report it on stdout.
+/// Scripts can tell the outcomes apart by the exit code: 0 (up to date), 2 (update available) or 1 (failed).
+fn check_update() -> Result<(), Box<dyn std::error::Error>> {
+    let current = Version::current();
+
+    match check_for_update()? {
+        Some(latest) => {
+            println!("Version {} is available (installed: {}).", latest, current);
+            std::process::exit(2);
+        }
+
+        None => println!("Version {} is up to date.", current),
+    }
+
+    Ok(())
+}
+
+/// List the print history on stdout instead of starting the UI.
+fn show_print_jobs() -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open_or_create("db.sqlite")?;
+
+    let mut jobs: Vec<PrintJobEntry> = Vec::new();
+    db.print_jobs(MAX_PRINT_JOBS, &mut jobs)?;
+
+    // Print the oldest job first, like a log file.
+    for job in jobs.iter().rev() {
+        println!(
+            "{}\t{}\t{}\t{}",
+            job.date.to_rfc3339(),
+            job.outcome,
+            job.product_name,
+            job.error.as_deref().unwrap_or("")
+        );
+    }
 
-    App::run(emulated_scales, dump_voucher)
+    Ok(())
 }
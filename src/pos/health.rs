@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+/// The state of a single component of the point of sale
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComponentState {
+    /// The component works as expected.
+    Ok,
+
+    /// The component works, but needs attention soon (e.g. the label roll is ending).
+    Degraded(String),
+
+    /// The component does not work.
+    Failed(String),
+}
+
+impl ComponentState {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, ComponentState::Ok)
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self, ComponentState::Failed(_))
+    }
+}
+
+impl Display for ComponentState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ComponentState::*;
+
+        match self {
+            Ok => write!(f, "ok"),
+            Degraded(reason) => write!(f, "degraded ({})", reason),
+            Failed(reason) => write!(f, "failed ({})", reason),
+        }
+    }
+}
+
+/// A snapshot of the states of all components
+#[derive(Clone, PartialEq)]
+pub struct Health {
+    pub db: ComponentState,
+    pub scales: ComponentState,
+    pub printer: ComponentState,
+    pub storage: ComponentState,
+
+    /// The number of sales that wait in the journal to be stored in the DB
+    pub queue_depth: usize,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            db: ComponentState::Ok,
+            scales: ComponentState::Ok,
+            printer: ComponentState::Ok,
+            storage: ComponentState::Ok,
+            queue_depth: 0,
+        }
+    }
+}
+
+impl Health {
+    /// Check if sales can be performed (even if some components need attention).
+    pub fn is_operational(&self) -> bool {
+        !self.db.is_failed()
+            && !self.scales.is_failed()
+            && !self.printer.is_failed()
+            && !self.storage.is_failed()
+    }
+}
+
+impl Display for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "db: {}, scales: {}, printer: {}, storage: {}, queue depth: {}",
+            self.db, self.scales, self.printer, self.storage, self.queue_depth
+        )
+    }
+}
@@ -0,0 +1,129 @@
+use super::Health;
+
+use std::io::{BufRead, BufReader, Error as IOError, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A minimal HTTP server that answers `GET /health` for monitoring.
+/// It runs on its own thread and serves the last published health,
+/// so requests never touch the DB or the hardware.
+pub struct HealthEndpoint {
+    addr: SocketAddr,
+    health: Arc<Mutex<Option<Health>>>,
+}
+
+impl HealthEndpoint {
+    /// Listen on the given address (like "127.0.0.1:9090").
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, IOError> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let health = Arc::new(Mutex::new(None));
+        let published = Arc::clone(&health);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let health = published.lock().unwrap().clone();
+                _ = Self::respond(stream, health.as_ref());
+            }
+        });
+
+        Ok(Self { addr, health })
+    }
+
+    /// The address the endpoint actually listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Answer the following requests with this health.
+    pub fn publish(&self, health: &Health) {
+        *self.health.lock().unwrap() = Some(health.clone());
+    }
+
+    /// Answer with 200 if sales can be performed, 503 if not (or before the first health check).
+    fn respond(mut stream: TcpStream, health: Option<&Health>) -> Result<(), IOError> {
+        // A silent client must not block the others for long.
+        stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+
+        // Read the headers, so closing the connection does not reset it.
+        let mut header = String::new();
+
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let mut request = request_line.split_whitespace();
+
+        let (status, body) = match (request.next(), request.next(), health) {
+            (Some("GET"), Some("/health"), Some(health)) if health.is_operational() => {
+                ("200 OK", health.to_string())
+            }
+            (Some("GET"), Some("/health"), Some(health)) => {
+                ("503 Service Unavailable", health.to_string())
+            }
+            (Some("GET"), Some("/health"), None) => {
+                ("503 Service Unavailable", String::from("starting"))
+            }
+            _ => ("404 Not Found", String::from("not found")),
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}\n",
+            status,
+            body.len() + 1,
+            body
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::pos::ComponentState;
+
+    use std::io::Read;
+
+    fn get(endpoint: &HealthEndpoint, path: &str) -> String {
+        let mut stream = TcpStream::connect(endpoint.addr()).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        response
+    }
+
+    #[test]
+    fn the_published_health_is_served() {
+        let endpoint = HealthEndpoint::bind("127.0.0.1:0").unwrap();
+        assert!(get(&endpoint, "/health").starts_with("HTTP/1.1 503 "));
+
+        let mut health = Health::default();
+        endpoint.publish(&health);
+
+        let response = get(&endpoint, "/health");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response
+            .ends_with("\r\n\r\ndb: ok, scales: ok, printer: ok, storage: ok, queue depth: 0\n"));
+
+        // Degraded components still allow sales.
+        health.printer = ComponentState::Degraded(String::from("label roll ending"));
+        endpoint.publish(&health);
+        assert!(get(&endpoint, "/health").starts_with("HTTP/1.1 200 OK\r\n"));
+
+        health.scales = ComponentState::Failed(String::from("timeout"));
+        endpoint.publish(&health);
+        assert!(get(&endpoint, "/health").contains("scales: failed (timeout)"));
+        assert!(get(&endpoint, "/health").starts_with("HTTP/1.1 503 "));
+
+        assert!(get(&endpoint, "/").starts_with("HTTP/1.1 404 "));
+    }
+}
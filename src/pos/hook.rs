@@ -0,0 +1,106 @@
+use crate::{
+    db::{SaleEntry, SettingsEntry},
+    gpio::Pin,
+};
+
+use std::io::Error as IOError;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Signal the peripherals after a sale (e.g. pop the cash drawer or flash a lamp).
+/// Both the command and the pin run in the background, so the sale never waits for them.
+/// Failures are only logged.
+pub(super) fn fire(settings: &SettingsEntry, sale: &SaleEntry) {
+    if !settings.post_sale_command.is_empty() {
+        let command = settings.post_sale_command.clone();
+        let sale = sale.clone();
+
+        thread::spawn(move || {
+            if let Err(err) = run_command(&command, &sale) {
+                eprintln!("The post-sale command has failed: {err}");
+            }
+        });
+    }
+
+    if let Some(pin) = settings.post_sale_gpio_pin {
+        let pulse = Duration::from_millis(settings.post_sale_gpio_pulse_ms);
+
+        thread::spawn(move || {
+            if let Err(err) = pulse_gpio(pin, pulse) {
+                eprintln!("The post-sale pulse on GPIO {pin} has failed: {err}");
+            }
+        });
+    }
+}
+
+/// Run the command in a shell. The sale is passed in `SALE_*` environment variables.
+/// The output is discarded, it would garble the terminal UI.
+fn run_command(command: &str, sale: &SaleEntry) -> Result<(), IOError> {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("SALE_DATE", sale.date.to_rfc3339())
+        .env("SALE_PRODUCT", &sale.name)
+        .env("SALE_TOTAL_CT", sale.total_ct.to_string())
+        .env(
+            "SALE_WEIGHT_KG",
+            optional(sale.weight_kg.map(|weight_kg| weight_kg.to_string())),
+        )
+        .env(
+            "SALE_PIECE_COUNT",
+            optional(sale.piece_count.map(|count| count.to_string())),
+        )
+        .env(
+            "SALE_PAYMENT_METHOD",
+            optional(sale.payment_method.map(|method| method.to_string())),
+        )
+        .env(
+            "SALE_EXPIRATION_OVERRIDE",
+            optional(sale.expiration_override.map(|date| date.to_rfc3339())),
+        )
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        return Err(IOError::other(format!("exited with {}", status)));
+    }
+
+    Ok(())
+}
+
+/// Set the pin high for the duration of the pulse.
+fn pulse_gpio(number: u32, pulse: Duration) -> Result<(), IOError> {
+    let pin = Pin::export(number)?;
+
+    pin.write(true)?;
+    thread::sleep(pulse);
+    pin.write(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{date, ProductBuilder, SaleBuilder};
+
+    #[test]
+    fn commands_see_the_sale_and_report_failures() {
+        let product = ProductBuilder::new("Bratwurst").price_ct(250).build();
+        let sale = SaleBuilder::new(&product, date(1, 8))
+            .weight_kg(0.5)
+            .build();
+
+        let check = format!(
+            "test \"$SALE_PRODUCT\" = Bratwurst && test \"$SALE_TOTAL_CT\" = {} \
+             && test \"$SALE_WEIGHT_KG\" = 0.5 && test -z \"$SALE_PIECE_COUNT\"",
+            sale.total_ct
+        );
+
+        run_command(&check, &sale).unwrap();
+        assert!(run_command("exit 3", &sale).is_err());
+    }
+}
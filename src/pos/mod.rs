@@ -0,0 +1,616 @@
+use crate::{
+    db::{
+        Attribution, Database, JournalError, ProductError, SaleEntry, SaleJournal,
+        UnderweightPolicy, WeighingEntry,
+    },
+    printer::{
+        AttachError, EmulatedPage, Label, LabelType, Model as PrinterModel, ModelPreference,
+        PrintError, Printer, StatusError, StatusErrorFlags,
+    },
+    weight::{QualityIssue, Scales, WeightResult},
+};
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use image::GrayImage;
+use rusqlite::Error as SQLiteError;
+
+/// Structured states of all components
+mod health;
+pub use health::{ComponentState, Health};
+
+/// Serve the health over HTTP for monitoring
+mod health_endpoint;
+pub use health_endpoint::HealthEndpoint;
+
+/// Tell the service manager that we are still alive
+mod watchdog;
+pub use watchdog::Watchdog;
+
+/// Keep an eye on the disk, so sales don't fail halfway
+mod storage;
+pub use storage::{StorageLevel, StorageUsage};
+
+/// Restrict what trainees may do
+mod role;
+pub use role::{Permissions, Role};
+
+/// Check everything at once before the first sale
+mod preflight;
+pub use preflight::{Preflight, PreflightCheck};
+
+/// Find sales whose voucher or booking has been interrupted
+mod recovery;
+pub use recovery::InterruptedSale;
+
+/// Trigger peripherals like a cash drawer after a sale
+mod hook;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidPrinterModel(String),
+    SaleJournaled(SQLiteError),
+    SaleLost(SQLiteError, JournalError),
+    DiskFull { free_mb: u64 },
+    NotPermitted { role: Role, permission: Permissions },
+    NegativeWeight,
+    NoPrinter,
+    Status(StatusError),
+    Print(PrintError),
+    DB(SQLiteError),
+    Product(ProductError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+
+        match self {
+            InvalidPrinterModel(err) => write!(f, "The printer model is invalid: {}", err),
+            SaleJournaled(err) => write!(
+                f,
+                "The sale could not be stored in the DB and has been journaled: {}",
+                err
+            ),
+            SaleLost(db_err, journal_err) => write!(
+                f,
+                "The sale could neither be stored in the DB ({}) nor in the journal ({})",
+                db_err, journal_err
+            ),
+            DiskFull { free_mb } => write!(
+                f,
+                "The sale has been refused because the disk is almost full ({} MB free)",
+                free_mb
+            ),
+            NotPermitted { role, permission } => {
+                write!(f, "The {} role lacks the permission {}", role, permission)
+            }
+            NegativeWeight => write!(
+                f,
+                "The sale has been refused because its weight is negative and has not been confirmed"
+            ),
+            NoPrinter => write!(f, "No printer is attached"),
+            Status(err) => write!(f, "Status error: {}", err),
+            Print(err) => write!(f, "Print error: {}", err),
+            DB(err) => write!(f, "DB error: {}", err),
+            Product(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<SQLiteError> for Error {
+    fn from(value: SQLiteError) -> Self {
+        Error::DB(value)
+    }
+}
+
+impl From<StatusError> for Error {
+    fn from(value: StatusError) -> Self {
+        Error::Status(value)
+    }
+}
+
+impl From<PrintError> for Error {
+    fn from(value: PrintError) -> Self {
+        Error::Print(value)
+    }
+}
+
+impl From<ProductError> for Error {
+    fn from(value: ProductError) -> Self {
+        Error::Product(value)
+    }
+}
+
+/// How a sale with a given weight may proceed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WeightCheck {
+    /// The weight is fine.
+    Accepted,
+
+    /// The weight is negative, the operator has to confirm it explicitly.
+    NeedsConfirmation,
+
+    /// The weight was negative and has become the new zero point, so the item must be weighed again.
+    Tared,
+
+    /// The weight is negative and must not be sold.
+    Rejected,
+}
+
+/// The hardware and the storage of the point of sale, independent of the UI
+pub struct Engine {
+    db: Database,
+    journal: SaleJournal,
+    unsaved_sales: usize,
+    scales: Scales,
+    printer: Result<Printer, AttachError>,
+    printer_emulation: Option<(PrinterModel, LabelType)>,
+    printer_rank: usize,
+    printer_error_flags: Option<StatusErrorFlags>,
+    printer_reconnects: u64,
+    printer_reconnect_failures: u64,
+    storage: Option<StorageUsage>,
+    tare_kg: f64,
+    health: Health,
+    attribution: Attribution,
+}
+
+impl Engine {
+    /// `attribution` tells who works on this terminal (see `Attribution::current()`).
+    pub fn new(
+        db: Database,
+        journal: SaleJournal,
+        scales: Scales,
+        attribution: Attribution,
+    ) -> Self {
+        let mut engine = Self {
+            db,
+            journal,
+            unsaved_sales: 0,
+            scales,
+            printer: Err(AttachError::NoPrinter),
+            printer_emulation: None,
+            printer_rank: 0,
+            printer_error_flags: None,
+            printer_reconnects: 0,
+            printer_reconnect_failures: 0,
+            storage: None,
+            tare_kg: 0.0,
+            health: Health::default(),
+            attribution,
+        };
+
+        engine.refresh_health();
+        engine
+    }
+
+    pub fn db(&self) -> &Database {
+        &self.db
+    }
+
+    /// Reload everything from the DB (e.g. after another connection has changed it).
+    pub fn reload_db(&mut self) -> Result<(), Error> {
+        self.db.reload_info()?;
+        self.db.reload_settings()?;
+        self.db.reload_products()?;
+
+        Ok(())
+    }
+
+    /// Change the message that is printed on every voucher of the day (empty to remove it).
+    pub fn set_daily_message(&mut self, message: String) -> Result<(), Error> {
+        Ok(self
+            .db
+            .update_settings(|settings| settings.daily_message = message.clone())?)
+    }
+
+    pub fn scales(&self) -> &Scales {
+        &self.scales
+    }
+
+    /// The attached printer for queries (printing goes through `print_pages()`)
+    pub(crate) fn printer(&self) -> Result<&Printer, &AttachError> {
+        self.printer.as_ref()
+    }
+
+    /// Print some pages as a single job.
+    /// `permission` covers what is printed, e.g. `SELL` for the voucher of a sale or `PRINT_ONLY` for a shelf label.
+    pub fn print_pages(&self, pages: &[&GrayImage], permission: Permissions) -> Result<(), Error> {
+        self.authorize(permission)?;
+
+        let printer = self.printer.as_ref().map_err(|_| Error::NoPrinter)?;
+        Ok(printer.print_pages(pages)?)
+    }
+
+    /// The number of pages that the last job has printed completely (also if it has failed afterwards)
+    pub fn completed_pages(&self) -> usize {
+        self.printer.as_ref().map_or(0, Printer::completed_pages)
+    }
+
+    /// The label in the printer (`None` if there is none)
+    pub fn printer_label(&self) -> Result<Option<Label>, Error> {
+        let printer = self.printer.as_ref().map_err(|_| Error::NoPrinter)?;
+        Ok(printer.current_label()?)
+    }
+
+    /// The pages that an emulated printer has received (`None` for real printers)
+    pub fn emulated_pages(&self) -> Option<Vec<EmulatedPage>> {
+        self.printer.as_ref().ok()?.emulated_pages()
+    }
+
+    /// Use an emulated printer with the given label instead of a USB one from the next reconnect on.
+    pub fn emulate_printer(&mut self, model: PrinterModel, label_type: LabelType) {
+        self.printer_emulation = Some((model, label_type));
+    }
+
+    /// Drop the current printer and try to attach it again.
+    /// The acceptable printers are tried in the order of the settings.
+    /// Without a preference, the printer model can be restricted in the DB.
+    pub fn reconnect_printer(&mut self) -> Result<(), Error> {
+        // Ensure that the old printer is dropped first!
+        self.printer = Err(AttachError::NoPrinter);
+        self.printer_rank = 0;
+        self.printer_error_flags = None;
+
+        // Now try to reattach it.
+        let mut preference = self.db.settings().printer_preference.clone();
+
+        if preference.is_empty() {
+            let model_filter = self
+                .db
+                .info()
+                .printer_model
+                .as_deref()
+                .map(PrinterModel::try_from)
+                .transpose()
+                .map_err(Error::InvalidPrinterModel)?;
+
+            preference = ModelPreference::from(model_filter);
+        }
+
+        self.printer = match self.printer_emulation {
+            Some((model, label_type)) => Ok(Printer::emulated(model, label_type)),
+            None => Printer::attach_preferred(&preference).map(|(printer, rank)| {
+                self.printer_rank = rank;
+                printer
+            }),
+        };
+
+        self.printer_reconnects += 1;
+
+        // Print jobs are captured for bug reports if the operator asks for it.
+        let capture_dir = &self.db.settings().printer_capture_dir;
+
+        if let Ok(printer) = &mut self.printer {
+            printer.print_config().capture_dir =
+                (!capture_dir.is_empty()).then(|| PathBuf::from(capture_dir));
+        }
+
+        if self.printer.is_err() {
+            self.printer_reconnect_failures += 1;
+        }
+
+        Ok(())
+    }
+
+    /// The position of the attached printer in the preference (0 is the first choice)
+    pub fn printer_rank(&self) -> usize {
+        self.printer_rank
+    }
+
+    /// The number of attempts to attach the printer and how many of them failed
+    pub fn printer_reconnects(&self) -> (u64, u64) {
+        (self.printer_reconnects, self.printer_reconnect_failures)
+    }
+
+    /// The error flags of the last printer status poll (`None` if unknown)
+    pub fn printer_error_flags(&self) -> Option<StatusErrorFlags> {
+        self.printer_error_flags
+    }
+
+    /// Ask the printer for its current error flags.
+    /// This blocks for a moment, so it should only be done periodically.
+    pub fn query_printer_error_flags(&self) -> Option<StatusErrorFlags> {
+        self.printer
+            .as_ref()
+            .ok()
+            .and_then(|printer| printer.current_error_flags().ok())
+    }
+
+    pub fn set_printer_error_flags(&mut self, flags: Option<StatusErrorFlags>) {
+        self.printer_error_flags = flags;
+    }
+
+    /// Measure the DB and the free space on its disk.
+    /// This touches the file system, so it should only be done periodically.
+    pub fn check_storage(&mut self) {
+        self.storage = match StorageUsage::measure(&self.db) {
+            Ok(storage) => storage,
+
+            Err(err) => {
+                eprintln!("Failed to measure the storage: {err}");
+                None
+            }
+        };
+
+        self.refresh_health();
+    }
+
+    /// The result of the last storage check (`None` if unknown or in memory)
+    pub fn storage(&self) -> Option<StorageUsage> {
+        self.storage
+    }
+
+    pub fn storage_level(&self) -> StorageLevel {
+        self.storage.map_or(StorageLevel::Ok, |storage| {
+            storage.level(self.db.settings())
+        })
+    }
+
+    /// The weight that is subtracted from all weights of the scales
+    pub fn tare_kg(&self) -> f64 {
+        self.tare_kg
+    }
+
+    pub fn reset_tare(&mut self) {
+        self.tare_kg = 0.0;
+    }
+
+    /// Subtract this (gross) weight from now on, e.g. of a bowl.
+    pub fn set_tare(&mut self, tare_kg: f64) {
+        self.tare_kg = tare_kg;
+    }
+
+    /// Subtract the tare from a weight of the scales.
+    pub fn net_weight(&self, weight: WeightResult) -> WeightResult {
+        weight.map(|weight_kg| weight_kg - self.tare_kg)
+    }
+
+    /// Check a (net) weight before it is sold, following the under-weight policy.
+    pub fn check_weight(&mut self, weight_kg: f64) -> WeightCheck {
+        use UnderweightPolicy::*;
+
+        if weight_kg >= 0.0 {
+            return WeightCheck::Accepted;
+        }
+
+        match self.db.settings().underweight_policy {
+            Block => WeightCheck::Rejected,
+            Confirm => WeightCheck::NeedsConfirmation,
+
+            AutoTare => {
+                self.tare_kg += weight_kg;
+                WeightCheck::Tared
+            }
+        }
+    }
+
+    /// The number of sales that wait in the journal
+    pub fn unsaved_sales(&self) -> usize {
+        self.unsaved_sales
+    }
+
+    /// Store the sales that could not be written to the DB earlier.
+    /// Returns the number of stored sales.
+    pub fn replay_journal(&mut self) -> Result<usize, JournalError> {
+        let result = self.journal.replay(&mut self.db);
+
+        // Whatever is left in the journal is still unsaved.
+        self.unsaved_sales = self.journal.load().map_or(0, |sales| sales.len());
+        self.refresh_health();
+
+        result
+    }
+
+    /// Store a sale in the DB and fire the post-sale hook.
+    /// The label might already be printed, so we must not lose the sale if the DB fails.
+    /// Instead, it goes to the journal and is replayed on the next start.
+    /// On a critically full disk, the sale is refused, so check `storage_level()` before printing.
+    /// The raw response of the scales behind the weight (if any) goes to the weighing log.
+    /// Negative weights are refused, see `store_confirmed_sale()`.
+    pub fn store_sale(&mut self, sale: &SaleEntry, frame: Option<Vec<u8>>) -> Result<(), Error> {
+        self.store(sale, frame, false)
+    }
+
+    /// Store a sale like `store_sale()`, but the operator has confirmed a negative weight (see `WeightCheck::NeedsConfirmation`).
+    /// This is only possible if the under-weight policy asks for confirmation.
+    pub fn store_confirmed_sale(
+        &mut self,
+        sale: &SaleEntry,
+        frame: Option<Vec<u8>>,
+    ) -> Result<(), Error> {
+        self.store(sale, frame, true)
+    }
+
+    fn store(
+        &mut self,
+        sale: &SaleEntry,
+        frame: Option<Vec<u8>>,
+        underweight_confirmed: bool,
+    ) -> Result<(), Error> {
+        self.authorize(Permissions::SELL)?;
+
+        let may_be_negative = underweight_confirmed
+            && (self.db.settings().underweight_policy == UnderweightPolicy::Confirm);
+
+        if !may_be_negative && sale.weight_kg.is_some_and(|weight_kg| weight_kg < 0.0) {
+            return Err(Error::NegativeWeight);
+        }
+
+        // Neither the DB nor the journal could take the sale reliably.
+        if let Some(storage) = self
+            .storage
+            .filter(|_| self.storage_level() == StorageLevel::Critical)
+        {
+            return Err(Error::DiskFull {
+                free_mb: storage.free_mb(),
+            });
+        }
+
+        let Err(db_err) = self.db.add_sale(sale) else {
+            self.log_weighing(sale, frame);
+            hook::fire(self.db.settings(), sale);
+            return Ok(());
+        };
+
+        if let Err(journal_err) = self.journal.append(sale) {
+            return Err(Error::SaleLost(db_err, journal_err));
+        }
+
+        self.unsaved_sales += 1;
+        self.refresh_health();
+
+        // The journaled sale is complete for the customer, so the drawer opens anyway.
+        hook::fire(self.db.settings(), sale);
+
+        Err(Error::SaleJournaled(db_err))
+    }
+
+    /// Record the reading behind the weight of a sale.
+    /// The log only helps to settle disputes, so failures must not interrupt the sale.
+    fn log_weighing(&self, sale: &SaleEntry, frame: Option<Vec<u8>>) {
+        let Some(weight_kg) = sale.weight_kg else {
+            return;
+        };
+
+        let weighing = WeighingEntry {
+            sale_date: sale.date,
+            product_name: sale.name.clone(),
+            weight_kg,
+            tare_kg: self.tare_kg,
+            frame,
+        };
+
+        if let Err(err) = self.db.add_weighing(&weighing) {
+            eprintln!("Failed to record the weighing: {err}");
+        }
+    }
+
+    /// Take back a sale that has just been stored. Returns whether it was found.
+    pub fn void_sale(&mut self, sale: &SaleEntry) -> Result<bool, Error> {
+        self.authorize(Permissions::VOID)?;
+
+        Ok(self.db.remove_sale(sale)?)
+    }
+
+    /// Change the price of a product (see `Database::update_product_price()`).
+    pub fn update_product_price(
+        &mut self,
+        idx: usize,
+        price_ct: u64,
+        date: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        self.authorize(Permissions::EDIT_PRODUCTS)?;
+
+        let operator = self.attribution.operator.clone();
+        Ok(self
+            .db
+            .update_product_price(idx, price_ct, date, operator)?)
+    }
+
+    /// Store a copy of a product (see `Database::duplicate_product()`).
+    pub fn duplicate_product(&mut self, idx: usize, name: String) -> Result<i64, Error> {
+        self.authorize(Permissions::EDIT_PRODUCTS)?;
+
+        Ok(self.db.duplicate_product(idx, name)?)
+    }
+
+    /// The operator and the station that sales, shifts and price changes are attributed to
+    pub fn attribution(&self) -> &Attribution {
+        &self.attribution
+    }
+
+    /// The role of the operator that is logged in on this terminal
+    pub fn role(&self) -> Role {
+        Role::of(self.attribution.operator.as_deref(), self.db.settings())
+    }
+
+    /// Check if the operator may perform an action.
+    /// The UI asks before it offers an action, but the engine checks again before it changes anything.
+    pub fn authorize(&self, permission: Permissions) -> Result<(), Error> {
+        let role = self.role();
+
+        if role.permissions(self.db.settings()).contains(permission) {
+            Ok(())
+        } else {
+            Err(Error::NotPermitted { role, permission })
+        }
+    }
+
+    /// Check if the responses of the scales have become unreliable.
+    pub fn scale_quality_issue(&self) -> Option<QualityIssue> {
+        let settings = self.db.settings();
+
+        self.scales.diagnostics().quality.issue(
+            settings.scale_max_failure_percent,
+            Duration::from_millis(settings.scale_max_latency_ms),
+        )
+    }
+
+    /// The states of all components as of the last `refresh_health()`
+    pub fn health(&self) -> &Health {
+        &self.health
+    }
+
+    /// Check the states of all components again (this queries the DB, so it should only be done periodically).
+    /// Returns whether a state has changed.
+    pub fn refresh_health(&mut self) -> bool {
+        let db = match self.db.check() {
+            Err(err) => ComponentState::Failed(err.to_string()),
+            Ok(()) if self.unsaved_sales > 0 => {
+                ComponentState::Degraded(format!("{} unsaved sales", self.unsaved_sales))
+            }
+            Ok(()) => ComponentState::Ok,
+        };
+
+        let scales = match (self.scales.weight(), self.scale_quality_issue()) {
+            (Err(err), _) => ComponentState::Failed(err.to_string()),
+            (Ok(_), Some(issue)) => ComponentState::Degraded(issue.to_string()),
+            (Ok(_), None) => ComponentState::Ok,
+        };
+
+        // A sleeping printer is woken up by the next print, so it is no problem.
+        let printer = match (&self.printer, self.printer_error_flags) {
+            (Err(err), _) => ComponentState::Failed(err.to_string()),
+            (Ok(_), Some(StatusErrorFlags::TURNED_OFF)) => ComponentState::Ok,
+            (Ok(_), Some(flags)) if flags.contains(StatusErrorFlags::NO_MEDIA) => {
+                ComponentState::Failed(String::from("no media"))
+            }
+            (Ok(_), Some(flags)) if !flags.is_empty() => {
+                ComponentState::Degraded(format!("{:?}", flags))
+            }
+            (Ok(_), _) => ComponentState::Ok,
+        };
+
+        let storage = match (self.storage, self.storage_level()) {
+            (Some(storage), StorageLevel::Critical) => {
+                ComponentState::Failed(format!("{} MB free", storage.free_mb()))
+            }
+            (Some(storage), StorageLevel::Low) => ComponentState::Degraded(format!(
+                "{} MB free, DB has {} MB",
+                storage.free_mb(),
+                storage.db_mb()
+            )),
+            _ => ComponentState::Ok,
+        };
+
+        let health = Health {
+            db,
+            scales,
+            printer,
+            storage,
+            queue_depth: self.unsaved_sales,
+        };
+
+        let changed = health != self.health;
+        self.health = health;
+
+        changed
+    }
+}
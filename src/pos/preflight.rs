@@ -0,0 +1,203 @@
+use super::{ComponentState, Engine};
+use crate::{
+    voucher::TextContext,
+    weight::{Error as WeightError, Scales},
+};
+
+use std::fmt::Display;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::ImageReader;
+
+/// How long the scales get to answer for the first time
+const SCALES_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The things that are checked before the first sale
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PreflightCheck {
+    Database,
+    Scales,
+    Printer,
+    Label,
+    Logo,
+    Fonts,
+}
+
+impl PreflightCheck {
+    /// The name in the UI
+    pub fn name(&self) -> &'static str {
+        use PreflightCheck::*;
+
+        match self {
+            Database => "Datenbank",
+            Scales => "Waage",
+            Printer => "Drucker",
+            Label => "Etikett",
+            Logo => "Logo",
+            Fonts => "Schriften",
+        }
+    }
+}
+
+impl Display for PreflightCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use PreflightCheck::*;
+
+        match self {
+            Database => write!(f, "db"),
+            Scales => write!(f, "scales"),
+            Printer => write!(f, "printer"),
+            Label => write!(f, "label"),
+            Logo => write!(f, "logo"),
+            Fonts => write!(f, "fonts"),
+        }
+    }
+}
+
+/// The outcome of all checks at startup, so failures show up at once instead of during the first sale
+#[derive(Clone)]
+pub struct Preflight {
+    pub results: Vec<(PreflightCheck, ComponentState)>,
+}
+
+impl Preflight {
+    /// Check if everything is ready (nothing failed or needs attention).
+    pub fn is_ok(&self) -> bool {
+        self.results.iter().all(|(_, state)| state.is_ok())
+    }
+
+    /// The state of a single check
+    pub fn state(&self, check: PreflightCheck) -> Option<&ComponentState> {
+        self.results
+            .iter()
+            .find(|(c, _)| *c == check)
+            .map(|(_, state)| state)
+    }
+}
+
+impl Display for Preflight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let results: Vec<String> = self
+            .results
+            .iter()
+            .map(|(check, state)| format!("{}: {}", check, state))
+            .collect();
+
+        write!(f, "{}", results.join(", "))
+    }
+}
+
+/// Wait until the scales have answered once (or given up).
+fn check_scales(scales: &Scales) -> ComponentState {
+    let deadline = Instant::now() + SCALES_TIMEOUT;
+
+    loop {
+        match scales.weight() {
+            Ok(_) => return ComponentState::Ok,
+
+            Err(WeightError::NotOpenedYet) if Instant::now() < deadline => {
+                thread::sleep(Duration::from_millis(50))
+            }
+
+            Err(err) => return ComponentState::Failed(err.to_string()),
+        }
+    }
+}
+
+fn check_logo(path: &Path) -> ComponentState {
+    match ImageReader::open(path).map_err(|err| err.to_string()) {
+        Ok(reader) => match reader.decode() {
+            Ok(_) => ComponentState::Ok,
+            Err(err) => ComponentState::Failed(err.to_string()),
+        },
+
+        Err(err) => ComponentState::Failed(err),
+    }
+}
+
+impl Engine {
+    /// Check the DB, the devices and the files that are needed for vouchers.
+    /// This waits for the scales and the printer, so it should only be done at startup.
+    pub fn preflight(&self, logo_path: &Path, text_ctx: &TextContext) -> Preflight {
+        let db = match self.db.check() {
+            Ok(()) => ComponentState::Ok,
+            Err(err) => ComponentState::Failed(err.to_string()),
+        };
+
+        let (printer, label) = match &self.printer {
+            Ok(printer) => {
+                let label = match printer.current_label() {
+                    Ok(Some(_)) => ComponentState::Ok,
+                    Ok(None) => ComponentState::Failed(String::from("no label loaded")),
+                    Err(err) => ComponentState::Degraded(format!("unknown ({})", err)),
+                };
+
+                (ComponentState::Ok, label)
+            }
+
+            Err(err) => (
+                ComponentState::Failed(err.to_string()),
+                ComponentState::Failed(String::from("no printer")),
+            ),
+        };
+
+        let fonts = match text_ctx.font_count() {
+            0 => ComponentState::Failed(String::from("no fonts installed")),
+            _ => ComponentState::Ok,
+        };
+
+        Preflight {
+            results: vec![
+                (PreflightCheck::Database, db),
+                (PreflightCheck::Scales, check_scales(&self.scales)),
+                (PreflightCheck::Printer, printer),
+                (PreflightCheck::Label, label),
+                (PreflightCheck::Logo, check_logo(logo_path)),
+                (PreflightCheck::Fonts, fonts),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Attribution, Database, SaleJournal};
+
+    #[test]
+    fn preflight_reports_every_failure() {
+        let db = Database::open_or_create(":memory:").unwrap();
+        let engine = Engine::new(
+            db,
+            SaleJournal::new("/nonexistent"),
+            Scales::fixed(0.5),
+            Attribution::default(),
+        );
+
+        let preflight = engine.preflight(
+            Path::new("/nonexistent/logo.png"),
+            &TextContext::with_fonts(Vec::new()),
+        );
+
+        assert!(!preflight.is_ok());
+        assert_eq!(
+            preflight.state(PreflightCheck::Database),
+            Some(&ComponentState::Ok)
+        );
+        assert_eq!(
+            preflight.state(PreflightCheck::Scales),
+            Some(&ComponentState::Ok)
+        );
+
+        for check in [
+            PreflightCheck::Printer,
+            PreflightCheck::Label,
+            PreflightCheck::Logo,
+            PreflightCheck::Fonts,
+        ] {
+            assert!(preflight.state(check).unwrap().is_failed(), "{}", check);
+        }
+    }
+}
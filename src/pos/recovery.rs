@@ -0,0 +1,168 @@
+use super::Engine;
+use crate::db::{PrintJobEntry, PrintOutcome, SaleEntry};
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use rusqlite::Result as SQLiteResult;
+
+/// The number of recent print jobs that are searched for interrupted sales
+const RECOVERY_JOBS_COUNT: usize = 200;
+
+/// A sale whose voucher and booking don't match, usually because the app has crashed in between
+#[derive(Clone)]
+pub enum InterruptedSale {
+    /// The voucher has been printed, but the sale has not been stored.
+    Unbooked {
+        sale_date: DateTime<Utc>,
+        product_name: String,
+    },
+
+    /// The sale has been stored, but its voucher has never been printed.
+    Unprinted(SaleEntry),
+}
+
+impl InterruptedSale {
+    pub fn product_name(&self) -> &str {
+        match self {
+            InterruptedSale::Unbooked { product_name, .. } => product_name,
+            InterruptedSale::Unprinted(sale) => &sale.name,
+        }
+    }
+}
+
+/// What has happened to the vouchers of a single sale
+#[derive(Default)]
+struct VoucherState {
+    printed: bool,
+    discarded: bool,
+}
+
+impl Engine {
+    /// Compare the recent vouchers of sales with the stored sales (oldest first).
+    /// The journal should be replayed before, its sales count as stored.
+    pub fn interrupted_sales(&self) -> SQLiteResult<Vec<InterruptedSale>> {
+        let mut jobs: Vec<PrintJobEntry> = Vec::new();
+        self.db.print_jobs(RECOVERY_JOBS_COUNT, &mut jobs)?;
+
+        // A sale may have several print attempts (e.g. after a paper jam).
+        let mut states: BTreeMap<(DateTime<Utc>, String), VoucherState> = BTreeMap::new();
+
+        for job in jobs {
+            let Some(sale_date) = job.sale_date else {
+                continue;
+            };
+
+            let state = states.entry((sale_date, job.product_name)).or_default();
+
+            match job.outcome {
+                PrintOutcome::Printed => state.printed = true,
+                PrintOutcome::Discarded => state.discarded = true,
+
+                // A partially printed voucher is no voucher for the customer.
+                PrintOutcome::Failed | PrintOutcome::Interrupted => (),
+            }
+        }
+
+        let mut interrupted = Vec::new();
+
+        for ((sale_date, product_name), state) in states {
+            if state.discarded {
+                continue;
+            }
+
+            // Failed vouchers without a sale have been aborted on purpose.
+            match (state.printed, self.db.find_sale(sale_date, &product_name)?) {
+                (true, None) => interrupted.push(InterruptedSale::Unbooked {
+                    sale_date,
+                    product_name,
+                }),
+                (false, Some(sale)) => interrupted.push(InterruptedSale::Unprinted(sale)),
+                _ => (),
+            }
+        }
+
+        Ok(interrupted)
+    }
+
+    /// Note that the voucher of a sale has been thrown away, so it is not reported again.
+    pub fn discard_voucher(
+        &self,
+        now: DateTime<Utc>,
+        sale_date: DateTime<Utc>,
+        product_name: String,
+    ) -> SQLiteResult<()> {
+        self.db
+            .add_print_job(&PrintJobEntry::discarded(now, product_name, sale_date))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{date, memory_db, ProductBuilder, SaleBuilder};
+    use crate::db::{Attribution, SaleJournal};
+    use crate::weight::Scales;
+
+    #[test]
+    fn vouchers_and_sales_are_matched() {
+        let mut db = memory_db();
+        let idx = ProductBuilder::new("Rinderbraten").add_to(&mut db);
+        let product = db.products()[idx].clone();
+        let name = || product.name.clone();
+
+        let printed =
+            |day| PrintJobEntry::printed(date(day, 9), name()).for_sale(Some(date(day, 9)));
+        let failed = |day| {
+            PrintJobEntry::failed(date(day, 9), name(), String::from("Papierstau"))
+                .for_sale(Some(date(day, 9)))
+        };
+
+        // Complete: printed after a failed attempt and booked
+        db.add_print_job(&failed(1)).unwrap();
+        db.add_print_job(&printed(1)).unwrap();
+        SaleBuilder::new(&product, date(1, 9)).add_to(&mut db);
+
+        // Printed, but never booked
+        db.add_print_job(&printed(2)).unwrap();
+
+        // Booked, but the voucher waited for the printer
+        db.add_print_job(&failed(3)).unwrap();
+        let unprinted = SaleBuilder::new(&product, date(3, 9))
+            .weight_kg(0.5)
+            .add_to(&mut db);
+
+        // Aborted because of the printer
+        db.add_print_job(&failed(4)).unwrap();
+
+        // Printed without a sale (e.g. shelf labels)
+        db.add_print_job(&PrintJobEntry::printed(date(5, 9), name()))
+            .unwrap();
+
+        let mut engine = Engine::new(
+            db,
+            SaleJournal::new("/nonexistent"),
+            Scales::fixed(0.5),
+            Attribution::default(),
+        );
+        let interrupted = engine.interrupted_sales().unwrap();
+
+        assert_eq!(interrupted.len(), 2);
+        assert!(matches!(
+            &interrupted[0],
+            InterruptedSale::Unbooked { sale_date, .. } if *sale_date == date(2, 9)
+        ));
+        assert!(matches!(
+            &interrupted[1],
+            InterruptedSale::Unprinted(sale) if sale.weight_kg == unprinted.weight_kg
+        ));
+
+        // Resolved sales are not reported again.
+        engine
+            .discard_voucher(date(6, 9), date(2, 9), name())
+            .unwrap();
+        engine.void_sale(&unprinted).unwrap();
+
+        assert!(engine.interrupted_sales().unwrap().is_empty());
+    }
+}
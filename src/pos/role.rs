@@ -0,0 +1,144 @@
+use crate::db::SettingsEntry;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+bitflags! {
+    /// The actions that can be restricted per role
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct Permissions: u8 {
+        /// Book sales (with or without a voucher)
+        const SELL = 0b0000_0001;
+
+        /// Print vouchers and shelf labels without a sale
+        const PRINT_ONLY = 0b0000_0010;
+
+        /// Take back sales that have already been booked
+        const VOID = 0b0000_0100;
+
+        /// Change prices and copy products
+        const EDIT_PRODUCTS = 0b0000_1000;
+    }
+}
+
+impl Permissions {
+    /// The names in the settings
+    const NAMES: [(&'static str, Permissions); 4] = [
+        ("sell", Permissions::SELL),
+        ("print_only", Permissions::PRINT_ONLY),
+        ("void", Permissions::VOID),
+        ("edit_products", Permissions::EDIT_PRODUCTS),
+    ];
+}
+
+impl Display for Permissions {
+    /// A comma-separated list like "sell,void"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|(_, permission)| self.contains(*permission))
+            .map(|(name, _)| *name)
+            .collect();
+
+        write!(f, "{}", names.join(","))
+    }
+}
+
+impl FromStr for Permissions {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .try_fold(Permissions::empty(), |permissions, name| {
+                let (_, permission) = Self::NAMES.iter().find(|(n, _)| *n == name).ok_or(())?;
+                Ok(permissions | *permission)
+            })
+    }
+}
+
+/// The role of an operator decides which actions are permitted
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+    Staff,
+
+    /// Operators that are listed in the `trainee_operators` setting
+    Trainee,
+}
+
+impl Role {
+    /// Look up the role of an operator. Unknown operators are staff.
+    pub fn of(operator: Option<&str>, settings: &SettingsEntry) -> Self {
+        let is_trainee = operator.is_some_and(|operator| {
+            settings
+                .trainee_operators
+                .split(',')
+                .any(|trainee| trainee.trim() == operator)
+        });
+
+        if is_trainee {
+            Role::Trainee
+        } else {
+            Role::Staff
+        }
+    }
+
+    /// The configured permissions of this role
+    pub fn permissions(&self, settings: &SettingsEntry) -> Permissions {
+        match self {
+            Role::Staff => settings.staff_permissions,
+            Role::Trainee => settings.trainee_permissions,
+        }
+    }
+
+    /// The name in the UI
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Staff => "Mitarbeiter",
+            Role::Trainee => "Auszubildende",
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Staff => write!(f, "staff"),
+            Role::Trainee => write!(f, "trainee"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permissions_round_trip() {
+        let permissions = Permissions::SELL | Permissions::VOID;
+
+        assert_eq!(permissions.to_string(), "sell,void");
+        assert_eq!("sell, void".parse(), Ok(permissions));
+        assert_eq!("".parse(), Ok(Permissions::empty()));
+        assert_eq!("sell,refund".parse::<Permissions>(), Err(()));
+    }
+
+    #[test]
+    fn trainees_are_looked_up_by_name() {
+        let settings = SettingsEntry {
+            trainee_operators: String::from("lena, tim"),
+            ..Default::default()
+        };
+
+        assert_eq!(Role::of(Some("tim"), &settings), Role::Trainee);
+        assert_eq!(Role::of(Some("timo"), &settings), Role::Staff);
+        assert_eq!(Role::of(None, &settings), Role::Staff);
+
+        let permissions = Role::Trainee.permissions(&settings);
+
+        assert!(permissions.contains(Permissions::SELL));
+        assert!(!permissions.contains(Permissions::PRINT_ONLY));
+        assert!(Role::Staff.permissions(&settings).is_all());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::db::{Database, SettingsEntry};
+
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// How urgently the disk needs attention
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StorageLevel {
+    Ok,
+
+    /// The disk is getting full or the DB is getting large.
+    Low,
+
+    /// The disk is so full that sales might fail halfway, so they are refused.
+    Critical,
+}
+
+/// The space taken by the DB and the space that is left on its file system
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StorageUsage {
+    pub db_bytes: u64,
+    pub free_bytes: u64,
+}
+
+impl StorageUsage {
+    /// Measure the DB file and its file system.
+    /// In-memory DBs take no disk space, so there is nothing to measure (`None`).
+    pub fn measure(db: &Database) -> io::Result<Option<Self>> {
+        let Some(path) = db.path() else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            db_bytes: fs::metadata(path)?.len(),
+            free_bytes: free_bytes(path)?,
+        }))
+    }
+
+    pub fn db_mb(&self) -> u64 {
+        self.db_bytes / BYTES_PER_MB
+    }
+
+    pub fn free_mb(&self) -> u64 {
+        self.free_bytes / BYTES_PER_MB
+    }
+
+    /// Compare the usage against the thresholds of the settings.
+    pub fn level(&self, settings: &SettingsEntry) -> StorageLevel {
+        if self.free_mb() < settings.disk_critical_mb {
+            StorageLevel::Critical
+        } else if (self.free_mb() < settings.disk_warn_mb)
+            || ((settings.db_warn_mb > 0) && (self.db_mb() >= settings.db_warn_mb))
+        {
+            StorageLevel::Low
+        } else {
+            StorageLevel::Ok
+        }
+    }
+}
+
+/// The space that unprivileged processes can still use on the file system of `path`
+fn free_bytes(path: &Path) -> io::Result<u64> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: The path is a valid C string and `stat` is large enough for the result.
+    if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: `statvfs()` has succeeded, so `stat` has been filled.
+    let stat = unsafe { stat.assume_init() };
+
+    // The field types differ between platforms (e.g. 32 bit on the Raspberry Pi).
+    #[allow(clippy::unnecessary_cast)]
+    Ok((stat.f_bavail as u64) * (stat.f_frsize as u64))
+}
@@ -0,0 +1,44 @@
+use std::env;
+use std::io::Error as IOError;
+use std::os::unix::net::UnixDatagram;
+
+/// Notify the service manager (systemd) about our state.
+/// If we are not started as a notify service, all notifications are dropped.
+pub struct Watchdog {
+    socket_path: Option<String>,
+}
+
+impl Watchdog {
+    pub fn from_env() -> Self {
+        Self {
+            socket_path: env::var("NOTIFY_SOCKET").ok(),
+        }
+    }
+
+    /// Tell the service manager that startup has finished.
+    pub fn ready(&self) -> Result<(), IOError> {
+        self.notify("READY=1")
+    }
+
+    /// Keep the service manager from restarting us.
+    /// This must only be called while we are healthy.
+    pub fn ping(&self) -> Result<(), IOError> {
+        self.notify("WATCHDOG=1")
+    }
+
+    fn notify(&self, state: &str) -> Result<(), IOError> {
+        let Some(socket_path) = &self.socket_path else {
+            return Ok(());
+        };
+
+        // Abstract socket paths start with '@' and are not supported by `std`.
+        if socket_path.starts_with('@') {
+            return Ok(());
+        }
+
+        let socket = UnixDatagram::unbound()?;
+        socket.send_to(state.as_bytes(), socket_path)?;
+
+        Ok(())
+    }
+}
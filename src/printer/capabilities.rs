@@ -0,0 +1,58 @@
+use super::Model;
+
+/// The features a printer supports.
+/// The QL protocol offers no way to query them, so they are derived from the model on attach.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Red and black printing on special media
+    pub two_color: bool,
+
+    /// Doubled resolution in feed direction (600 dpi)
+    pub high_res: bool,
+
+    /// A cutter that cuts the label automatically
+    pub cutter: bool,
+
+    /// The maximum length of a continuous label in mm
+    pub max_length_mm: u32,
+
+    /// The resolution of the print head in dots per inch
+    pub dpi: u32,
+}
+
+impl Capabilities {
+    pub(super) fn of(model: Model) -> Self {
+        use Model::*;
+
+        let (high_res, cutter, max_length_mm) = match model {
+            BrotherQL500 => (false, false, 1000),
+            BrotherQL550 => (false, true, 1000),
+            BrotherQL560 => (false, true, 1000),
+            BrotherQL570 => (true, true, 1000),
+            BrotherQL580N => (true, true, 1000),
+            BrotherQL600 => (true, true, 1000),
+            BrotherQL650TD => (false, true, 1000),
+            BrotherQL700 => (true, true, 1000),
+            BrotherQL1050 => (true, true, 3000),
+            BrotherQL1060N => (true, true, 3000),
+            BrotherPTP700 => (true, true, 1000),
+            BrotherPTP750W => (true, true, 1000),
+        };
+
+        Self {
+            // None of the supported models can print in two colors.
+            two_color: false,
+            high_res,
+            cutter,
+            max_length_mm,
+            dpi: model.family().dpi(),
+        }
+    }
+
+    /// The maximum length of a continuous label in dots
+    pub fn max_length_dots(&self, high_res: bool) -> u32 {
+        // High resolution doubles the dpi of the print head in feed direction.
+        let dpi = if high_res { 2 * self.dpi } else { self.dpi };
+        ((self.max_length_mm as f32) * (dpi as f32) / 25.4).floor() as u32
+    }
+}
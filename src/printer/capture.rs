@@ -0,0 +1,102 @@
+use super::{PrintError, Printer};
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use image::{GrayImage, ImageFormat};
+
+/// The files of a print job that is being captured
+pub(super) struct Capture {
+    dir: PathBuf,
+    bytes: File,
+}
+
+impl Printer {
+    /// Start capturing the print of `pages` if the print config asks for it.
+    /// Each print gets a timestamped directory with the images, the bytes sent to the printer
+    /// and some info about the printer and the outcome.
+    /// Failures are only logged, a capture must never prevent a print.
+    pub(super) fn start_capture(&self, pages: &[&GrayImage]) {
+        let Some(capture_dir) = &self.print_config.capture_dir else {
+            return;
+        };
+
+        match self.create_capture(capture_dir, pages) {
+            Ok(capture) => *self.capture.borrow_mut() = Some(capture),
+            Err(err) => eprintln!("Failed to start the printer capture: {err}"),
+        }
+    }
+
+    fn create_capture(
+        &self,
+        capture_dir: &Path,
+        pages: &[&GrayImage],
+    ) -> Result<Capture, Box<dyn Error>> {
+        let dir = capture_dir.join(Local::now().format("%Y-%m-%d_%H-%M-%S%.3f").to_string());
+        fs::create_dir_all(&dir)?;
+
+        // The first page keeps the name of single page prints.
+        for (idx, image) in pages.iter().enumerate() {
+            let file_name = match idx {
+                0 => String::from("voucher.png"),
+                idx => format!("voucher-{}.png", idx + 1),
+            };
+
+            image.save_with_format(dir.join(file_name), ImageFormat::Png)?;
+        }
+
+        let info = format!(
+            "model: {}\nserial number: {}\npages: {}\nimage: {}x{}\nhigh res: {}\nauto cut: {}\ninvert: {}\n",
+            self.model,
+            self.serial_number,
+            pages.len(),
+            pages[0].width(),
+            pages[0].height(),
+            self.print_config.high_res,
+            self.print_config.auto_cut,
+            self.print_config.invert,
+        );
+
+        fs::write(dir.join("info.txt"), info)?;
+
+        let bytes = File::create(dir.join("printer.bin"))?;
+
+        Ok(Capture { dir, bytes })
+    }
+
+    /// Mirror bytes that are sent to the printer into the capture (if any).
+    pub(super) fn capture_bytes(&self, data: &[u8]) {
+        let mut capture = self.capture.borrow_mut();
+
+        if let Some(Capture { bytes, .. }) = capture.as_mut() {
+            if let Err(err) = bytes.write_all(data) {
+                eprintln!("Failed to write the printer capture: {err}");
+                *capture = None;
+            }
+        }
+    }
+
+    /// Finish the capture (if any) with the outcome of the print.
+    pub(super) fn finish_capture(&self, result: &Result<(), PrintError>) {
+        let Some(capture) = self.capture.borrow_mut().take() else {
+            return;
+        };
+
+        let outcome = match result {
+            Ok(()) => String::from("result: printed\n"),
+            Err(err) => format!("result: {}\n", err),
+        };
+
+        let appended = fs::OpenOptions::new()
+            .append(true)
+            .open(capture.dir.join("info.txt"))
+            .and_then(|mut info| info.write_all(outcome.as_bytes()));
+
+        if let Err(err) = appended {
+            eprintln!("Failed to finish the printer capture: {err}");
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use super::Family;
+
+bitflags! {
+    /// What the printer validates before printing and how it prints
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct PrintInfoFlags: u8 {
+        const VALIDATE_KIND = 0b0000_0010;
+        const VALIDATE_WIDTH = 0b0000_0100;
+        const VALIDATE_LENGTH = 0b0000_1000;
+        const PREFER_QUALITY = 0b0100_0000;
+        const RECOVER = 0b1000_0000;
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct ModeFlags: u8 {
+        const AUTO_CUT = 0b0100_0000;
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct ExpandedModeFlags: u8 {
+        const HIGHRES = 0b0100_0000;
+        const CUT_AT_END = 0b0001_0000;
+        const NO_CHAIN_PRINTING = 0b0000_1000;
+    }
+}
+
+/// A command of the raster protocol (ESC/i).
+/// It only knows its bytes, so it can be sent over any connection (or captured).
+pub trait Command {
+    /// Append the bytes of the command.
+    fn serialize(&self, out: &mut Vec<u8>);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.serialize(&mut out);
+
+        out
+    }
+}
+
+/// Zeros that flush a half-received command (e.g. of a cancelled print)
+pub struct Invalidate;
+
+impl Command for Invalidate {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x00; 350]);
+    }
+}
+
+/// Reset the printer to its defaults (which also wakes it up)
+pub struct Init;
+
+impl Command for Init {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x1b, 0x40]);
+    }
+}
+
+/// Ask for a status response
+pub struct StatusRequest;
+
+impl Command for StatusRequest {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x1b, 0x69, 0x53]);
+    }
+}
+
+/// Switch to raster mode (not all printers need this ... ?)
+pub struct SetRasterMode;
+
+impl Command for SetRasterMode {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x1b, 0x69, 0x61, 0x01]);
+    }
+}
+
+/// Describe the media and the number of raster lines of the page
+pub struct SetPrintInfo {
+    pub flags: PrintInfoFlags,
+
+    /// The label as reported in the status (see `LabelType::as_bytes()`)
+    pub label_bytes: (u8, u8, u8),
+    pub lines_count: u32,
+
+    /// Is this the first page of the job?
+    pub starting_page: bool,
+}
+
+impl Command for SetPrintInfo {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        let (label_ty, label_width, label_length) = self.label_bytes;
+
+        out.extend_from_slice(&[
+            0x1b,
+            0x69,
+            0x7a,
+            self.flags.bits(),
+            label_ty,
+            label_width,
+            label_length,
+        ]);
+        out.extend_from_slice(&self.lines_count.to_le_bytes());
+
+        // The starting page (0) or one of the following pages (1) and a reserved byte
+        out.extend_from_slice(&[(!self.starting_page) as u8, 0x00]);
+    }
+}
+
+pub struct SetMode {
+    pub flags: ModeFlags,
+}
+
+impl Command for SetMode {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x1b, 0x69, 0x4d, self.flags.bits()]);
+    }
+}
+
+/// Cut after this many pages (if auto-cut is enabled)
+pub struct SetCutEvery(pub u8);
+
+impl Command for SetCutEvery {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x1b, 0x69, 0x41, self.0]);
+    }
+}
+
+pub struct SetExpandedMode {
+    pub flags: ExpandedModeFlags,
+}
+
+impl Command for SetExpandedMode {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x1b, 0x69, 0x4b, self.flags.bits()]);
+    }
+}
+
+/// The feed before and after the page in dots
+pub struct SetMargin(pub u16);
+
+impl Command for SetMargin {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x1b, 0x69, 0x64]);
+        out.extend_from_slice(&self.0.to_le_bytes());
+    }
+}
+
+/// Enable or disable the TIFF compression of raster lines
+pub struct SetCompression(pub bool);
+
+impl Command for SetCompression {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x4d, if self.0 { 0x02 } else { 0x00 }]);
+    }
+}
+
+/// The packed bits of one line of the print head (see `encode_raster_line()`)
+pub struct RasterLine<'a> {
+    pub family: Family,
+    pub bits: &'a [u8],
+}
+
+impl Command for RasterLine<'_> {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.family.raster_line_header(self.bits.len() as u8));
+        out.extend_from_slice(self.bits);
+    }
+}
+
+/// Print the page without feeding the media (more pages follow)
+pub struct PrintWithoutFeed;
+
+impl Command for PrintWithoutFeed {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.push(0x0c);
+    }
+}
+
+/// Print the page and feed the media (after the last page)
+pub struct PrintWithFeed;
+
+impl Command for PrintWithFeed {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.push(0x1a);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_serialized() {
+        let print_info = SetPrintInfo {
+            flags: PrintInfoFlags::VALIDATE_WIDTH | PrintInfoFlags::RECOVER,
+            label_bytes: (0x0a, 62, 0),
+            lines_count: 0x0102_0304,
+            starting_page: true,
+        };
+
+        assert_eq!(
+            print_info.to_bytes(),
+            [0x1b, 0x69, 0x7a, 0x84, 0x0a, 62, 0, 0x04, 0x03, 0x02, 0x01, 0x00, 0x00]
+        );
+
+        // The following pages of a job are marked as such.
+        let print_info = SetPrintInfo {
+            starting_page: false,
+            ..print_info
+        };
+        assert_eq!(print_info.to_bytes()[11], 0x01);
+
+        let mode = SetMode {
+            flags: ModeFlags::AUTO_CUT,
+        };
+        assert_eq!(mode.to_bytes(), [0x1b, 0x69, 0x4d, 0x40]);
+        assert_eq!(SetMargin(0x0123).to_bytes(), [0x1b, 0x69, 0x64, 0x23, 0x01]);
+        assert_eq!(SetCompression(false).to_bytes(), [0x4d, 0x00]);
+        assert_eq!(PrintWithoutFeed.to_bytes(), [0x0c]);
+        assert_eq!(PrintWithFeed.to_bytes(), [0x1a]);
+
+        // The families differ in the header of the raster lines.
+        let bits = [0xff, 0x00, 0x81];
+
+        let ql_line = RasterLine {
+            family: Family::QL,
+            bits: &bits,
+        };
+        assert_eq!(ql_line.to_bytes(), [0x67, 0x00, 3, 0xff, 0x00, 0x81]);
+
+        let pt_line = RasterLine {
+            family: Family::PT,
+            bits: &bits,
+        };
+        assert_eq!(pt_line.to_bytes(), [0x47, 3, 0x00, 0xff, 0x00, 0x81]);
+    }
+}
@@ -0,0 +1,315 @@
+use super::{Capabilities, Connection, LabelType, Model, PrintConfig, Printer, StatusErrorFlags};
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+use rusb::Error as USBError;
+
+/// A page that has been printed by an emulated printer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmulatedPage {
+    /// The packed bits of each raster line (without the command header)
+    pub lines: Vec<Vec<u8>>,
+}
+
+/// The state of a printer without hardware.
+/// It answers the commands of a print job like a real printer with the given label loaded.
+pub(super) struct Emulation {
+    label_type: LabelType,
+    responses: VecDeque<[u8; 32]>,
+    expected_lines: usize,
+    lines: Vec<Vec<u8>>,
+    pages: Vec<EmulatedPage>,
+
+    /// In the printing phase (until the last page of the job has been fed out)
+    printing: bool,
+
+    /// Report `TURNED_OFF` until the next initialization
+    asleep: bool,
+
+    /// Fail with these flags instead of printing the page with this index (counting all pages so far)
+    failing_page: Option<(usize, StatusErrorFlags)>,
+}
+
+impl Emulation {
+    fn new(label_type: LabelType) -> Self {
+        Self {
+            label_type,
+            responses: VecDeque::new(),
+            expected_lines: 0,
+            lines: Vec::new(),
+            pages: Vec::new(),
+            printing: false,
+            asleep: false,
+            failing_page: None,
+        }
+    }
+
+    /// Build a status response without error flags.
+    fn status(&self, status_type: u8, phase_type: u8) -> [u8; 32] {
+        let (ty, width, length) = self.label_type.as_bytes();
+        let mut data = [0u8; 32];
+
+        data[0] = 0x80; // Print head mark
+        data[1] = 0x20; // Size
+        data[10] = width;
+        data[11] = ty;
+        data[17] = length;
+
+        if self.asleep {
+            data[8] = StatusErrorFlags::TURNED_OFF.bits() as u8;
+        }
+
+        data[18] = status_type;
+        data[19] = phase_type;
+
+        data
+    }
+
+    pub(super) fn write(&mut self, data: &[u8]) {
+        match data {
+            // Status request
+            [0x1b, 0x69, 0x53] => self.responses.push_back(self.status(0x00, 0x00)),
+
+            // Initialization
+            [0x1b, 0x40] => self.asleep = false,
+
+            // Print info with the number of raster lines
+            [0x1b, 0x69, 0x7a, _, _, _, _, l0, l1, l2, l3, ..] => {
+                self.expected_lines = u32::from_le_bytes([*l0, *l1, *l2, *l3]) as usize;
+                self.lines.clear();
+            }
+
+            // Raster line ("g" for QL, "G" for PT): Start printing after the last one of the first page.
+            [0x67 | 0x47, _, _, line @ ..] => {
+                self.lines.push(line.to_vec());
+
+                if (self.lines.len() == self.expected_lines) && !self.printing {
+                    self.printing = true;
+                    self.responses.push_back(self.status(0x06, 0x01));
+                }
+            }
+
+            // The page fails (e.g. because the cover has been opened) and the job is aborted.
+            [0x0c | 0x1a]
+                if self
+                    .failing_page
+                    .is_some_and(|(page, _)| page == self.pages.len()) =>
+            {
+                let (_, flags) = self
+                    .failing_page
+                    .take()
+                    .expect("Page has been checked above");
+                let mut status = self.status(0x02, 0x01);
+                status[8..10].copy_from_slice(&flags.bits().to_le_bytes());
+
+                self.lines.clear();
+                self.printing = false;
+
+                self.responses.push_back(status);
+            }
+
+            // Print without feeding (more pages follow)
+            [0x0c] => {
+                let lines = std::mem::take(&mut self.lines);
+                self.pages.push(EmulatedPage { lines });
+
+                self.responses.push_back(self.status(0x01, 0x01));
+            }
+
+            // Print with feeding
+            [0x1a] => {
+                let lines = std::mem::take(&mut self.lines);
+                self.pages.push(EmulatedPage { lines });
+                self.printing = false;
+
+                self.responses.push_back(self.status(0x01, 0x01));
+                self.responses.push_back(self.status(0x06, 0x00));
+            }
+
+            // Initialization, modes, margins, ...
+            _ => (),
+        }
+    }
+
+    /// Hand out the next response. Without one, a real printer would time out.
+    pub(super) fn read(&mut self, data: &mut [u8]) -> Result<usize, USBError> {
+        let response = self.responses.pop_front().ok_or(USBError::Timeout)?;
+        let len = response.len().min(data.len());
+
+        data[..len].copy_from_slice(&response[..len]);
+
+        Ok(len)
+    }
+}
+
+impl Printer {
+    /// A printer without hardware that has the given label loaded (e.g. for tests and demos).
+    /// The printed pages can be inspected via `emulated_pages()`.
+    pub fn emulated(model: Model, label_type: LabelType) -> Self {
+        let capabilities = Capabilities::of(model);
+
+        Printer {
+            connection: Connection::Emulated(RefCell::new(Emulation::new(label_type))),
+            model,
+            capabilities,
+            in_addr: 0x81,
+            out_addr: 0x02,
+            serial_number: String::from("EMULATED"),
+            print_config: PrintConfig {
+                auto_cut: capabilities.cutter,
+                ..Default::default()
+            },
+            last_status: Cell::new(None),
+            completed_pages: Cell::new(0),
+            capture: RefCell::new(None),
+        }
+    }
+
+    /// Let the emulated printer go to sleep like a real one after a while without jobs.
+    pub fn emulate_sleep(&self) {
+        if let Connection::Emulated(emulation) = &self.connection {
+            emulation.borrow_mut().asleep = true;
+        }
+    }
+
+    /// Let the emulated printer fail with the given flags instead of printing the page with the given index
+    /// (counting all pages printed so far), e.g. with `COVER_OPEN` in the middle of a job.
+    pub fn emulate_error(&self, page: usize, flags: StatusErrorFlags) {
+        if let Connection::Emulated(emulation) = &self.connection {
+            emulation.borrow_mut().failing_page = Some((page, flags));
+        }
+    }
+
+    /// The pages printed so far (`None` for real printers)
+    pub fn emulated_pages(&self) -> Option<Vec<EmulatedPage>> {
+        match &self.connection {
+            Connection::Usb(_) => None,
+            Connection::Emulated(emulation) => Some(emulation.borrow().pages.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printer::{PhaseType, StatusType};
+
+    use image::GrayImage;
+
+    #[test]
+    fn sleeping_printers_are_woken_up_before_printing() {
+        let printer = Printer::emulated(Model::BrotherQL700, LabelType::Continuous { width: 62 });
+        printer.emulate_sleep();
+
+        assert!(printer
+            .current_error_flags()
+            .unwrap()
+            .contains(StatusErrorFlags::TURNED_OFF));
+
+        let label = printer.current_label().unwrap().unwrap();
+        printer
+            .print(&GrayImage::new(label.printable_dots_width, 100))
+            .unwrap();
+
+        assert_eq!(
+            printer.current_error_flags().unwrap(),
+            StatusErrorFlags::empty()
+        );
+        assert_eq!(printer.emulated_pages().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn pages_are_printed_in_one_job() {
+        let printer = Printer::emulated(Model::BrotherQL700, LabelType::Continuous { width: 62 });
+        let label = printer.current_label().unwrap().unwrap();
+
+        let pages: Vec<_> = (1..=3)
+            .map(|height| GrayImage::new(label.printable_dots_width, 100 * height))
+            .collect();
+
+        printer
+            .print_pages(&pages.iter().collect::<Vec<_>>())
+            .unwrap();
+
+        let printed = printer.emulated_pages().unwrap();
+        assert_eq!(printed.len(), 3);
+        assert_eq!(printed[2].lines.len(), 300);
+
+        // The printer is ready for the next job.
+        assert!(printer.status().unwrap().is_ready());
+        printer.print(&pages[0]).unwrap();
+        assert_eq!(printer.emulated_pages().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn interrupted_jobs_report_the_completed_pages() {
+        let printer = Printer::emulated(Model::BrotherQL700, LabelType::Continuous { width: 62 });
+        let label = printer.current_label().unwrap().unwrap();
+        let page = GrayImage::new(label.printable_dots_width, 100);
+
+        // The cover is opened while the second of three pages is printing.
+        printer.emulate_error(1, StatusErrorFlags::COVER_OPEN);
+
+        let err = printer.print_pages(&[&page, &page, &page]).unwrap_err();
+        assert!(err.is_recoverable());
+        assert_eq!(printer.completed_pages(), 1);
+        assert_eq!(printer.emulated_pages().unwrap().len(), 1);
+
+        // The rest of the job can be printed afterwards.
+        printer.print_pages(&[&page, &page]).unwrap();
+        assert_eq!(printer.completed_pages(), 2);
+        assert_eq!(printer.emulated_pages().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn status_reports_the_inserted_media() {
+        let printer = Printer::emulated(Model::BrotherQL700, LabelType::Continuous { width: 62 });
+        let status = printer.status().unwrap();
+
+        assert!(status.is_ready());
+        assert_eq!(status.status_type(), StatusType::StatusReply);
+        assert_eq!(status.phase(), PhaseType::Waiting);
+        assert_eq!(status.notification(), None);
+        assert!(matches!(
+            status.label().map(|label| label.ty),
+            Some(LabelType::Continuous { width: 62 })
+        ));
+
+        // The last response is kept for diagnostics.
+        assert!(printer
+            .last_status()
+            .is_some_and(|status| status.is_ready()));
+
+        // Sleeping printers are not woken up by the status.
+        printer.emulate_sleep();
+        assert!(!printer.status().unwrap().is_ready());
+    }
+
+    #[test]
+    fn only_existing_labels_are_suggested() {
+        // There are no continuous rolls as wide as 17 mm die-cut labels.
+        let printer = Printer::emulated(
+            Model::BrotherQL700,
+            LabelType::DieCut {
+                width: 17,
+                length: 54,
+            },
+        );
+        let label = printer.current_label().unwrap().unwrap();
+        let voucher = GrayImage::new(label.printable_dots_width, 300);
+
+        assert_eq!(
+            printer.nearest_label(&voucher),
+            Some(LabelType::Continuous { width: 12 })
+        );
+
+        // A voucher for a continuous label of the same width suggests that label.
+        let voucher = GrayImage::new(306, 300);
+
+        assert_eq!(
+            printer.nearest_label(&voucher),
+            Some(LabelType::Continuous { width: 29 })
+        );
+    }
+}
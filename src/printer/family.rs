@@ -0,0 +1,41 @@
+use std::fmt::Display;
+
+/// The device families speak slightly different dialects of the raster protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Family {
+    /// Label printers for die-cut and continuous paper labels
+    QL,
+
+    /// Tape printers for laminated TZe tapes
+    PT,
+}
+
+impl Display for Family {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Family::QL => write!(f, "QL"),
+            Family::PT => write!(f, "PT"),
+        }
+    }
+}
+
+impl Family {
+    /// The resolution of the print head in dots per inch
+    pub fn dpi(&self) -> u32 {
+        match self {
+            Family::QL => 300,
+            Family::PT => 180,
+        }
+    }
+
+    /// The command that precedes the packed bits of each raster line
+    pub(super) fn raster_line_header(&self, line_width: u8) -> [u8; 3] {
+        match self {
+            // "g": The second byte is always zero because the lines are short enough.
+            Family::QL => [0x67, 0x00, line_width],
+
+            // "G": The line length is a little endian u16.
+            Family::PT => [0x47, line_width, 0x00],
+        }
+    }
+}
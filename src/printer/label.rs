@@ -1,14 +1,83 @@
-use super::{Model, Printer, StatusError};
+use super::{Family, Model, Printer, StatusError};
 
+use std::fmt::Display;
+use std::str::FromStr;
 use std::time::Duration;
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+use image::GrayImage;
+
+/// The gap between two die-cut labels on the roll (in mm)
+const DIE_CUT_GAP_MM: f64 = 3.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LabelType {
     Continuous { width: u8 },
     DieCut { width: u8, length: u8 },
+    Tape { width: u8 },
 }
 
 impl LabelType {
+    /// All label types we know the layout parameters for
+    pub const ALL: &'static [LabelType] = &[
+        LabelType::Continuous { width: 12 },
+        LabelType::Continuous { width: 29 },
+        LabelType::Continuous { width: 38 },
+        LabelType::Continuous { width: 50 },
+        LabelType::Continuous { width: 54 },
+        LabelType::Continuous { width: 62 },
+        LabelType::Continuous { width: 102 },
+        LabelType::DieCut {
+            width: 17,
+            length: 54,
+        },
+        LabelType::DieCut {
+            width: 17,
+            length: 87,
+        },
+        LabelType::DieCut {
+            width: 23,
+            length: 23,
+        },
+        LabelType::DieCut {
+            width: 29,
+            length: 90,
+        },
+        LabelType::DieCut {
+            width: 38,
+            length: 90,
+        },
+        LabelType::DieCut {
+            width: 39,
+            length: 48,
+        },
+        LabelType::DieCut {
+            width: 52,
+            length: 29,
+        },
+        LabelType::DieCut {
+            width: 62,
+            length: 29,
+        },
+        LabelType::DieCut {
+            width: 62,
+            length: 100,
+        },
+        LabelType::DieCut {
+            width: 102,
+            length: 51,
+        },
+        LabelType::DieCut {
+            width: 102,
+            length: 152,
+        },
+        LabelType::Tape { width: 4 },
+        LabelType::Tape { width: 6 },
+        LabelType::Tape { width: 9 },
+        LabelType::Tape { width: 12 },
+        LabelType::Tape { width: 18 },
+        LabelType::Tape { width: 24 },
+    ];
+
     pub(super) fn from_bytes(ty: u8, width: u8, length: u8) -> Option<Self> {
         use LabelType::*;
 
@@ -26,6 +95,9 @@ impl LabelType {
 
             0x0b => Some(DieCut { width, length }),
 
+            // Laminated, non-laminated and heat-shrink tapes share the same geometry.
+            0x01 | 0x03 | 0x11 => Some(Tape { width }),
+
             other => {
                 if other != 0x00 {
                     eprintln!("Unknown label type: {:#04x}", other);
@@ -42,6 +114,49 @@ impl LabelType {
         match *self {
             Continuous { width } => (0x0a, width, 0x00),
             DieCut { width, length } => (0x0b, width, length),
+            Tape { width } => (0x01, width, 0x00),
+        }
+    }
+
+    /// The length of the roll that a die-cut label takes, including the gap to the next one
+    pub fn die_cut_pitch_mm(&self) -> Option<f64> {
+        match *self {
+            LabelType::DieCut { length, .. } => Some((length as f64) + DIE_CUT_GAP_MM),
+            LabelType::Continuous { .. } | LabelType::Tape { .. } => None,
+        }
+    }
+}
+
+/// The short form used in settings and the DB, like "62", "62x100" or "tape12"
+impl Display for LabelType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LabelType::Continuous { width } => write!(f, "{}", width),
+            LabelType::DieCut { width, length } => write!(f, "{}x{}", width, length),
+            LabelType::Tape { width } => write!(f, "tape{}", width),
+        }
+    }
+}
+
+impl FromStr for LabelType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(width) = s.strip_prefix("tape") {
+            return Ok(LabelType::Tape {
+                width: width.parse().map_err(|_| ())?,
+            });
+        }
+
+        match s.split_once('x') {
+            Some((width, length)) => Ok(LabelType::DieCut {
+                width: width.parse().map_err(|_| ())?,
+                length: length.parse().map_err(|_| ())?,
+            }),
+
+            None => Ok(LabelType::Continuous {
+                width: s.parse().map_err(|_| ())?,
+            }),
         }
     }
 }
@@ -71,6 +186,13 @@ impl TryFrom<(Model, LabelType)> for Label {
         // "Wide" printers have more pins and therefore require different margins.
         let is_wide = [Model::BrotherQL1050, Model::BrotherQL1060N].contains(&model);
 
+        // Paper labels only fit into QL printers and tapes only into PT printers.
+        let family = model.family();
+
+        if matches!(ty, Tape { .. }) != (family == Family::PT) {
+            return Err(format!("{:?} does not fit into a {} printer", ty, family));
+        }
+
         match ty {
             Continuous { width } => {
                 printable_dots_length = None;
@@ -123,6 +245,24 @@ impl TryFrom<(Model, LabelType)> for Label {
                     }
                 }
             }
+
+            Tape { width } => {
+                printable_dots_length = None;
+                margin_dots_length = 14;
+
+                // Printable dots and margin pins for the 128 pin print head of the PT-P700 series.
+                // The 3.5 mm tape is reported as 4 mm.
+                (printable_dots_width, margin_dots_right) = match width {
+                    4 => (24, 52),
+                    6 => (32, 48),
+                    9 => (50, 39),
+                    12 => (70, 29),
+                    18 => (112, 8),
+                    24 => (128, 0),
+
+                    _ => return Err(format!("Unknown tape type (width: {} mm)", width)),
+                };
+            }
         }
 
         Ok(Label {
@@ -135,8 +275,46 @@ impl TryFrom<(Model, LabelType)> for Label {
     }
 }
 
+impl Label {
+    /// Find the label type supported by `model` whose printable area fits an image best.
+    /// Die-cut labels are only considered if their length matches the image height exactly.
+    /// Among the candidates, the one with the smallest width difference is chosen.
+    pub(super) fn nearest_for_image(
+        model: Model,
+        image_width: u32,
+        image_height: u32,
+        high_res: bool,
+    ) -> Option<LabelType> {
+        let max_dots = 8 * (model.line_width() as u32);
+
+        LabelType::ALL
+            .iter()
+            .filter_map(|&ty| Label::try_from((model, ty)).ok())
+            .filter(|label| (label.margin_dots_right + label.printable_dots_width) <= max_dots)
+            .filter(|label| {
+                let length = label
+                    .printable_dots_length
+                    .map(|l| if high_res { 2 * l } else { l });
+
+                length.unwrap_or(image_height) == image_height
+            })
+            .min_by_key(|label| label.printable_dots_width.abs_diff(image_width))
+            .map(|label| label.ty)
+    }
+}
+
 impl Printer {
     pub fn current_label(&self) -> Result<Option<Label>, StatusError> {
-        Ok(self.request_status(Duration::from_millis(500))?.label)
+        Ok(self.request_awake_status(Duration::from_millis(500))?.label)
+    }
+
+    /// The label type of this printer that fits an image best (see `Label::nearest_for_image()`)
+    pub fn nearest_label(&self, image: &GrayImage) -> Option<LabelType> {
+        Label::nearest_for_image(
+            self.model,
+            image.width(),
+            image.height(),
+            self.print_config.high_res,
+        )
     }
 }
@@ -1,30 +1,69 @@
+use std::cell::{Cell, RefCell};
+
 use rusb::{DeviceHandle, GlobalContext};
 
+/// Printer models belong to device families that differ in geometry and commands.
+mod family;
+pub use family::Family;
+
 /// There are different printer models with variable parameters.
 mod model;
-pub use model::Model;
+pub use model::{Model, Preference as ModelPreference};
 
 /// Brother printer labels are standardized. To properly print them, we need layout parameters (margins etc.).
 mod label;
 pub use label::{Label, LabelType};
 
+/// Not all models support the same features (high resolution, cutter, ...).
+mod capabilities;
+pub use capabilities::Capabilities;
+
 /// Search the list of available USB devices, find a Brother thermal printer, attach it and perform IO.
 mod usb;
 pub use usb::Error as AttachError;
 
 /// The status response is the basic feedback method from the printer to the host.
 mod status;
-pub use status::{Error as StatusError, ErrorFlags as StatusErrorFlags, PhaseType, StatusType};
+pub use status::{
+    Error as StatusError, ErrorFlags as StatusErrorFlags, Notification as StatusNotification,
+    PhaseType, Status as PrinterStatus, StatusType,
+};
+
+/// The print head expects the picture as raster lines of packed bits.
+mod raster;
+pub use raster::encode_raster_line;
+
+/// The commands of the raster protocol are typed and serialized independently of the connection.
+mod commands;
 
 /// Printing requires separate commands and the conversion of the input picture into raster lines.
 mod print;
 pub use print::{Error as PrintError, PrintConfig, PrintPriority};
 
+/// Print jobs can be captured into files, so they can be attached to bug reports.
+mod capture;
+use capture::Capture;
+
+/// A printer without hardware answers like a real one, so sales can be tested end to end.
+mod emulated;
+pub use emulated::EmulatedPage;
+use emulated::Emulation;
+
+/// The way commands reach the printer
+enum Connection {
+    Usb(DeviceHandle<GlobalContext>),
+    Emulated(RefCell<Emulation>),
+}
+
 pub struct Printer {
-    handle: DeviceHandle<GlobalContext>,
+    connection: Connection,
     model: Model,
+    capabilities: Capabilities,
     in_addr: u8,
     out_addr: u8,
     serial_number: String,
     print_config: PrintConfig,
+    last_status: Cell<Option<[u8; 32]>>,
+    completed_pages: Cell<usize>,
+    capture: RefCell<Option<Capture>>,
 }
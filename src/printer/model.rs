@@ -1,6 +1,9 @@
+use super::Family;
+
 use std::fmt::Display;
+use std::str::FromStr;
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Model {
     BrotherQL500,
     BrotherQL550,
@@ -12,6 +15,8 @@ pub enum Model {
     BrotherQL700,
     BrotherQL1050,
     BrotherQL1060N,
+    BrotherPTP700,
+    BrotherPTP750W,
 }
 
 impl Display for Model {
@@ -29,9 +34,11 @@ impl Display for Model {
             BrotherQL700 => "700",
             BrotherQL1050 => "1050",
             BrotherQL1060N => "1060N",
+            BrotherPTP700 => "P700",
+            BrotherPTP750W => "P750W",
         };
 
-        write!(f, "Brother QL-{}", model_nr)
+        write!(f, "Brother {}-{}", self.family(), model_nr)
     }
 }
 
@@ -52,6 +59,8 @@ impl TryFrom<u16> for Model {
             0x2042 => BrotherQL700,
             0x2020 => BrotherQL1050,
             0x202a => BrotherQL1060N,
+            0x2061 => BrotherPTP700,
+            0x2062 => BrotherPTP750W,
 
             _ => return Err(format!("Unknown product ID: {:#06x}", value)),
         })
@@ -65,7 +74,10 @@ impl TryFrom<&str> for Model {
         use Model::*;
 
         value = value.strip_prefix("Brother").unwrap_or(value);
-        value = value.strip_prefix("QL").unwrap_or(value);
+        value = value
+            .strip_prefix("QL")
+            .or_else(|| value.strip_prefix("PT"))
+            .unwrap_or(value);
 
         Ok(match value {
             "500" => BrotherQL500,
@@ -78,6 +90,8 @@ impl TryFrom<&str> for Model {
             "700" => BrotherQL700,
             "1050" => BrotherQL1050,
             "1060N" => BrotherQL1060N,
+            "P700" => BrotherPTP700,
+            "P750W" => BrotherPTP750W,
 
             _ => return Err(format!("Unknown product name: {}", value)),
         })
@@ -85,6 +99,15 @@ impl TryFrom<&str> for Model {
 }
 
 impl Model {
+    pub fn family(&self) -> Family {
+        use Model::*;
+
+        match self {
+            BrotherPTP700 | BrotherPTP750W => Family::PT,
+            _ => Family::QL,
+        }
+    }
+
     pub(super) fn line_width(&self) -> u8 {
         use Model::*;
 
@@ -99,6 +122,87 @@ impl Model {
             BrotherQL700 => 90,
             BrotherQL1050 => 162,
             BrotherQL1060N => 162,
+            BrotherPTP700 => 16,
+            BrotherPTP750W => 16,
         }
     }
 }
+
+/// The acceptable printers in the order they are tried when attaching, like "QL-700; QL-570; any".
+/// `None` stands for "any", i.e. the first printer that is found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preference(Vec<Option<Model>>);
+
+impl Preference {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn models(&self) -> impl Iterator<Item = Option<Model>> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+impl From<Option<Model>> for Preference {
+    fn from(model: Option<Model>) -> Self {
+        Self(vec![model])
+    }
+}
+
+impl Display for Preference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|model| model.map_or(String::from("any"), |model| model.to_string()))
+            .collect();
+
+        write!(f, "{}", entries.join("; "))
+    }
+}
+
+impl FromStr for Preference {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                if entry == "any" {
+                    return Ok(None);
+                }
+
+                // Accept the display form as well ("Brother QL-700").
+                let name: String = entry.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+
+                Model::try_from(name.as_str()).map(Some).map_err(|_| ())
+            })
+            .collect::<Result<_, _>>()
+            .map(Preference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preferences_round_trip() {
+        let preference: Preference = "QL-700; Brother QL-1060N ;any".parse().unwrap();
+
+        assert!(preference.models().eq([
+            Some(Model::BrotherQL700),
+            Some(Model::BrotherQL1060N),
+            None
+        ]));
+        assert_eq!(
+            preference.to_string(),
+            "Brother QL-700; Brother QL-1060N; any"
+        );
+        assert_eq!(preference.to_string().parse(), Ok(preference));
+
+        assert!("".parse::<Preference>().unwrap().is_empty());
+        assert!("QL-820NWB".parse::<Preference>().is_err());
+    }
+}
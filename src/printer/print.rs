@@ -1,7 +1,16 @@
-use super::{PhaseType, Printer, StatusError, StatusErrorFlags, StatusType};
+use super::commands::{
+    Command, ExpandedModeFlags, ModeFlags, PrintInfoFlags, PrintWithFeed, PrintWithoutFeed,
+    RasterLine, SetCompression, SetCutEvery, SetExpandedMode, SetMargin, SetMode, SetPrintInfo,
+    SetRasterMode,
+};
+use super::status::Status;
+use super::{
+    encode_raster_line, Family, Label, LabelType, PhaseType, Printer, StatusError,
+    StatusErrorFlags, StatusType,
+};
 
 use std::fmt::Display;
-use std::mem;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use image::GrayImage;
@@ -21,11 +30,20 @@ pub enum Error {
         got: PhaseType,
     },
     NoMedia,
+    HighResUnsupported,
+    AutoCutUnsupported,
+    ImageTooLong {
+        image_height: u32,
+        max_height: u32,
+    },
+    MediaRanOut,
+    PrintingFailed(StatusErrorFlags),
     WrongImageDimensions {
         image_width: u32,
         image_height: u32,
         label_width: u32,
         label_length: Option<u32>,
+        suggested_label: Option<LabelType>,
     },
 }
 
@@ -54,11 +72,38 @@ impl Display for Error {
                 )
             }
             NoMedia => write!(f, "The printer is not loaded. Please insert media."),
+            HighResUnsupported => {
+                write!(f, "The printer does not support high resolution printing.")
+            }
+            AutoCutUnsupported => write!(
+                f,
+                "The printer has no cutter. Please disable auto-cut and cut manually."
+            ),
+            ImageTooLong {
+                image_height,
+                max_height,
+            } => write!(
+                f,
+                "The image is too long for the printer (maximum: {} pixels, got {} pixels).",
+                max_height, image_height
+            ),
+            MediaRanOut => write!(
+                f,
+                "The media has run out while printing. Please insert new media and print again."
+            ),
+            PrintingFailed(flags) => {
+                write!(
+                    f,
+                    "The printer has reported an error while printing: {:?}",
+                    flags
+                )
+            }
             WrongImageDimensions {
                 image_width,
                 image_height,
                 label_width,
                 label_length,
+                ..
             } => write!(
                 f,
                 "The image has the wrong dimensions (expected: {}x{} pixels, got {}x{} pixels).",
@@ -73,6 +118,22 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+impl Error {
+    /// Check if the operator can clear the cause at the printer (e.g. by closing the cover).
+    /// A print that has failed this way can be repeated as soon as the error flags are gone.
+    pub fn is_recoverable(&self) -> bool {
+        let recoverable = StatusErrorFlags::COVER_OPEN | StatusErrorFlags::CANCEL_KEY;
+
+        match self {
+            Error::StatusErrorFlags(flags) | Error::PrintingFailed(flags) => {
+                !flags.is_empty() && recoverable.contains(*flags)
+            }
+
+            _ => false,
+        }
+    }
+}
+
 impl From<USBError> for Error {
     fn from(value: USBError) -> Self {
         Error::USBError(value)
@@ -88,25 +149,6 @@ impl From<StatusError> for Error {
     }
 }
 
-bitflags! {
-    struct PrintInfoFlags: u8 {
-        const VALIDATE_KIND = 0b0000_0010;
-        const VALIDATE_WIDTH = 0b0000_0100;
-        const VALIDATE_LENGTH = 0b0000_1000;
-        const PREFER_QUALITY = 0b0100_0000;
-        const RECOVER = 0b1000_0000;
-    }
-
-    struct PrintModeFlags: u8 {
-        const AUTO_CUT = 0b0100_0000;
-    }
-
-    struct ExpandedPrintModeFlags: u8 {
-        const HIGHRES = 0b0100_0000;
-        const CUT_AT_END = 0b0001_0000;
-    }
-}
-
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum PrintPriority {
     Quality,
@@ -118,6 +160,9 @@ pub struct PrintConfig {
     pub auto_cut: bool,
     pub high_res: bool,
     pub invert: bool,
+
+    /// Capture every print job into a subdirectory (for bug reports)
+    pub capture_dir: Option<PathBuf>,
 }
 
 impl Default for PrintConfig {
@@ -127,49 +172,78 @@ impl Default for PrintConfig {
             auto_cut: true,
             high_res: false,
             invert: false,
+            capture_dir: None,
         }
     }
 }
 
-struct BitWriter<'a> {
-    output: &'a mut [u8],
-    bit_idx: usize,
-}
-
-impl<'a> BitWriter<'a> {
-    pub fn new(output: &'a mut [u8]) -> Self {
-        Self { output, bit_idx: 7 }
+impl Printer {
+    pub fn print_config(&mut self) -> &mut PrintConfig {
+        &mut self.print_config
     }
 
-    pub fn write_bit(&mut self, bit: bool) {
-        self.output[0] |= (bit as u8) << self.bit_idx;
+    /// Print the image onto the current label.
+    /// If a capture dir is configured, the print job is captured.
+    pub fn print(&self, image: &GrayImage) -> Result<(), Error> {
+        self.print_pages(&[image])
+    }
 
-        if self.bit_idx == 0 {
-            // Borrow checker shenanigans ...
-            let output = mem::take(&mut self.output);
-            self.output = &mut output[1..];
+    /// Print the images onto consecutive labels in a single job (e.g. copies of a voucher).
+    /// The printer is checked once and streams the pages back to back, which is much faster
+    /// than a job per page. If the job fails, `completed_pages()` tells how many pages have been printed.
+    pub fn print_pages(&self, pages: &[&GrayImage]) -> Result<(), Error> {
+        self.completed_pages.set(0);
 
-            self.bit_idx = 7;
-        } else {
-            self.bit_idx -= 1;
+        if pages.is_empty() {
+            return Ok(());
         }
+
+        self.start_capture(pages);
+        let result = self.print_images(pages);
+        self.finish_capture(&result);
+
+        result
     }
-}
 
-impl Printer {
-    pub fn print_config(&mut self) -> &mut PrintConfig {
-        &mut self.print_config
+    /// The number of pages that the last job has printed completely (also if it has failed afterwards).
+    /// The page that was printing when the job failed might have been printed partially.
+    pub fn completed_pages(&self) -> usize {
+        self.completed_pages.get()
     }
 
-    pub fn print(&self, image: &GrayImage) -> Result<(), Error> {
+    /// Estimate the length of media in mm that printing the image has taken from the roll.
+    /// Die-cut labels always take a whole label, the others the image and the feed margins.
+    pub fn consumed_mm(&self, label: &Label, image: &GrayImage) -> f64 {
+        if let Some(pitch_mm) = label.ty.die_cut_pitch_mm() {
+            return pitch_mm;
+        }
+
+        // High resolution doubles the lines, but not the feed.
+        let lines = if self.print_config.high_res {
+            image.height() / 2
+        } else {
+            image.height()
+        };
+
+        let dots = lines + 2 * (label.margin_dots_length as u32);
+        (dots as f64) * 25.4 / (self.capabilities.dpi as f64)
+    }
+
+    fn print_images(&self, pages: &[&GrayImage]) -> Result<(), Error> {
         use PhaseType::*;
         use StatusType::*;
 
+        // Fail fast if the printer cannot handle the print config or the images.
+        for image in pages {
+            self.validate_capabilities(image)?;
+        }
+
         // The "normal" timeout for the first status request and the print commands
         let timeout = Duration::from_millis(500);
 
         // Perform a status request to check the error flags and obtain the current label.
-        let status = self.request_status(timeout)?;
+        // A sleeping printer is woken up instead of failing the print.
+        let status = self.request_awake_status(timeout)?;
 
         if !status.error_flags.is_empty() {
             return Err(Error::StatusErrorFlags(status.error_flags));
@@ -188,183 +262,244 @@ impl Printer {
                 .map(|l| if self.print_config.high_res { 2 * l } else { l });
 
         // Ensure that the image dimensions exactly match the label.
+        // If they don't, we suggest the label that would fit the image instead.
         // TODO: Should we support resizing?
         // TODO: Validate minimum / maximum for continuous labels.
-        if (label_width != image.width()) || label_length.is_some_and(|l| l != image.height()) {
-            return Err(Error::WrongImageDimensions {
-                image_width: image.width(),
-                image_height: image.height(),
-                label_width,
-                label_length,
-            });
+        for image in pages {
+            if (label_width != image.width()) || label_length.is_some_and(|l| l != image.height()) {
+                return Err(Error::WrongImageDimensions {
+                    image_width: image.width(),
+                    image_height: image.height(),
+                    label_width,
+                    label_length,
+                    suggested_label: Label::nearest_for_image(
+                        self.model,
+                        image.width(),
+                        image.height(),
+                        self.print_config.high_res,
+                    ),
+                });
+            }
         }
 
-        // Turn the printer into raster mode (not all of them need this ... ?).
-        self.write(&[0x1B, 0x69, 0x61, 0x01], timeout)?;
+        // Turn the printer into raster mode.
+        self.send(&SetRasterMode, timeout)?;
 
         // Assemble the print info flags.
-        let mut print_info_flags = PrintInfoFlags::VALIDATE_KIND
-            | PrintInfoFlags::VALIDATE_WIDTH
+        let family = self.model.family();
+
+        let mut print_info_flags = PrintInfoFlags::VALIDATE_WIDTH
             | PrintInfoFlags::VALIDATE_LENGTH
             | PrintInfoFlags::RECOVER;
 
+        // The different tape kinds print the same, so we only validate the kind of QL labels.
+        if family == Family::QL {
+            print_info_flags |= PrintInfoFlags::VALIDATE_KIND;
+        }
+
         if self.print_config.priority == PrintPriority::Quality {
             print_info_flags |= PrintInfoFlags::PREFER_QUALITY;
         }
 
-        // Provide the print info.
-        let (label_ty, label_width, label_length) = label.ty.as_bytes();
-        let lines_count_bytes = image.height().to_le_bytes();
-
-        self.write(
-            &[
-                0x1b,
-                0x69,
-                0x7a,
-                print_info_flags.bits(),
-                label_ty,
-                label_width,
-                label_length,
-                lines_count_bytes[0],
-                lines_count_bytes[1],
-                lines_count_bytes[2],
-                lines_count_bytes[3],
-                0x00, // Starting page (we only support to print one at a time).
-                0x00, // Reserved
-            ],
-            timeout,
-        )?;
+        // The printer stays in the printing phase until the last page has been printed.
+        // From there on, we use a longer timeout as we have to wait for the printer to finish.
+        let ext_timeout = Duration::from_secs(5);
+
+        for (idx, image) in pages.iter().enumerate() {
+            let is_last_page = idx + 1 == pages.len();
+
+            // Provide the print info.
+            let print_info = SetPrintInfo {
+                flags: print_info_flags,
+                label_bytes: label.ty.as_bytes(),
+                lines_count: image.height(),
+                starting_page: idx == 0,
+            };
+
+            self.send(&print_info, timeout)?;
+            self.send_page(image, &label, timeout)?;
+
+            // Wait for the print phase change (which the printer only reports for the first page).
+            // However, in my setup, this is not even necessary because the printer immediately
+            // returns zero-length packets while it is printing.
+            if idx == 0 {
+                let status = self.await_status(PhaseChange, ext_timeout)?;
+
+                if status.phase_type != Printing {
+                    return Err(Error::UnexpectedPhaseType {
+                        expected: Printing,
+                        got: status.phase_type,
+                    });
+                }
+            }
+
+            // Commit the page. Only the last one is fed out.
+            if is_last_page {
+                self.send(&PrintWithFeed, timeout)?;
+            } else {
+                self.send(&PrintWithoutFeed, timeout)?;
+            }
+
+            // Wait for the completion of the page.
+            // If the media runs out while printing, this is where we learn about it.
+            self.await_status(PrintingCompleted, ext_timeout)?;
+            self.completed_pages.set(idx + 1);
+        }
+
+        // Wait for the waiting phase change.
+        let status = self.await_status(PhaseChange, ext_timeout)?;
+
+        if status.phase_type != Waiting {
+            return Err(Error::UnexpectedPhaseType {
+                expected: Waiting,
+                got: status.phase_type,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Send the modes and the raster lines of a page (after its print info).
+    fn send_page(&self, image: &GrayImage, label: &Label, timeout: Duration) -> Result<(), Error> {
+        let family = self.model.family();
 
         // Specify the modes to use. Currently, there is only auto-cut.
-        let mut mode_flags = PrintModeFlags::empty();
+        let mut mode_flags = ModeFlags::empty();
 
         if self.print_config.auto_cut {
-            mode_flags |= PrintModeFlags::AUTO_CUT;
+            mode_flags |= ModeFlags::AUTO_CUT;
         }
 
-        self.write(&[0x1b, 0x69, 0x4d, mode_flags.bits()], timeout)?;
+        self.send(&SetMode { flags: mode_flags }, timeout)?;
 
         // Specify the auto-cut rate if auto-cut is enabled.
-        // We hardcode 1 (aka "Cut after every page") because we only print one page at all.
+        // We hardcode 1 (aka "Cut after every page"), so the pages of a job are separated.
         if self.print_config.auto_cut {
-            self.write(&[0x1b, 0x69, 0x41, 0x01], timeout)?;
+            self.send(&SetCutEvery(1), timeout)?;
         }
 
         // Specify the expanded (extended?) modes.
-        let mut expanded_mode_flags = ExpandedPrintModeFlags::CUT_AT_END;
+        // PT printers keep the end of the last tape inside unless chain printing is disabled.
+        let mut expanded_mode_flags = match family {
+            Family::QL => ExpandedModeFlags::CUT_AT_END,
+            Family::PT => ExpandedModeFlags::NO_CHAIN_PRINTING,
+        };
 
         if self.print_config.high_res {
-            expanded_mode_flags |= ExpandedPrintModeFlags::HIGHRES;
+            expanded_mode_flags |= ExpandedModeFlags::HIGHRES;
         }
 
-        self.write(&[0x1b, 0x69, 0x4b, expanded_mode_flags.bits()], timeout)?;
-
-        // Specify the feed margin.
-        let feed_margin_bytes = label.margin_dots_length.to_le_bytes();
-        self.write(
-            &[0x1b, 0x69, 0x64, feed_margin_bytes[0], feed_margin_bytes[1]],
+        self.send(
+            &SetExpandedMode {
+                flags: expanded_mode_flags,
+            },
             timeout,
         )?;
 
+        // Specify the feed margin.
+        self.send(&SetMargin(label.margin_dots_length), timeout)?;
+
         // Disable compression for now.
         // TODO: Maybe support it in the future?
-        self.write(&[0x4d, 0x00], timeout)?;
+        self.send(&SetCompression(false), timeout)?;
 
         // Walk the raster lines.
-        let mut line_command =
-            vec![0x00; 3 + (self.model.line_width() as usize)].into_boxed_slice();
-
-        line_command[0] = 0x67;
-        line_command[1] = 0x00;
-        line_command[2] = self.model.line_width();
+        // The buffers are reused for all lines, so there are no allocations per line.
+        let mut bits = vec![0x00; self.model.line_width() as usize];
+        let mut line_command = Vec::with_capacity(3 + bits.len());
+
+        // The raw image buffer holds the rows back to back, so we can encode them without copies.
+        for row in image.as_raw().chunks_exact(image.width() as usize) {
+            // Zero the line and encode the row into it.
+            bits.fill(0);
+            encode_raster_line(
+                row,
+                label.margin_dots_right,
+                self.print_config.invert,
+                &mut bits,
+            );
 
-        for row in image.rows() {
-            // Zero the line.
-            let line = &mut line_command[3..];
-            line.fill(0);
-
-            // Write the margin.
-            let mut bit_writer = BitWriter::new(line);
-
-            for _ in 0..label.margin_dots_right {
-                bit_writer.write_bit(false);
-            }
-
-            // Sample the row from back to front.
-            for pix in row
-                .rev()
-                .map(|p| (p.0[0] < 0x80) != self.print_config.invert)
-            {
-                bit_writer.write_bit(pix);
+            // Send the line to the printer.
+            line_command.clear();
+            RasterLine {
+                family,
+                bits: &bits,
             }
+            .serialize(&mut line_command);
 
-            // Send the line to the printer.
             self.write(&line_command, timeout)?;
         }
 
-        // Wait for the print phase change.
-        // From here on, we use a longer timeout as we have to wait for the printer to finish.
-        // However, in my setup, this is not even necessary because the printer immediately
-        // returns zero-length packets while it is printing.
-        let ext_timeout = Duration::from_secs(5);
-        let status = self.read_status_response(ext_timeout)?;
+        Ok(())
+    }
 
-        if !status.error_flags.is_empty() {
-            return Err(Error::StatusErrorFlags(status.error_flags));
-        }
+    /// Check the print config and the image against the capabilities of the printer.
+    fn validate_capabilities(&self, image: &GrayImage) -> Result<(), Error> {
+        let capabilities = self.capabilities;
 
-        if status.status_type != PhaseChange {
-            return Err(Error::UnexpectedStatusType {
-                expected: PhaseChange,
-                got: status.status_type,
-            });
+        if self.print_config.high_res && !capabilities.high_res {
+            return Err(Error::HighResUnsupported);
         }
 
-        if status.phase_type != Printing {
-            return Err(Error::UnexpectedPhaseType {
-                expected: Printing,
-                got: status.phase_type,
-            });
+        if self.print_config.auto_cut && !capabilities.cutter {
+            return Err(Error::AutoCutUnsupported);
         }
 
-        // Commit the print with feeding.
-        self.write(&[0x1a], timeout)?;
-
-        // Wait for the completion.
-        let status = self.read_status_response(ext_timeout)?;
+        let max_height = capabilities.max_length_dots(self.print_config.high_res);
 
-        if !status.error_flags.is_empty() {
-            return Err(Error::StatusErrorFlags(status.error_flags));
-        }
-
-        if status.status_type != PrintingCompleted {
-            return Err(Error::UnexpectedStatusType {
-                expected: PrintingCompleted,
-                got: status.status_type,
+        if image.height() > max_height {
+            return Err(Error::ImageTooLong {
+                image_height: image.height(),
+                max_height,
             });
         }
 
-        // Wait for the waiting phase change.
-        let status = self.read_status_response(ext_timeout)?;
+        Ok(())
+    }
 
-        if !status.error_flags.is_empty() {
-            return Err(Error::StatusErrorFlags(status.error_flags));
-        }
+    /// Read status responses until one of the `expected` type arrives.
+    /// Notifications (e.g. cooling) may arrive at any time and are skipped.
+    /// Error responses are interpreted, so a print that fails halfway is reported properly.
+    fn await_status(&self, expected: StatusType, timeout: Duration) -> Result<Status, Error> {
+        loop {
+            let status = self.read_status_response(timeout)?;
+
+            match status.status_type {
+                StatusType::Notification => continue,
+
+                // Some printers report the printing phase again for the following pages of a job.
+                StatusType::PhaseChange
+                    if (expected == StatusType::PrintingCompleted)
+                        && (status.phase_type == PhaseType::Printing) =>
+                {
+                    continue
+                }
+
+                StatusType::ErrorOccurred => {
+                    let media_flags = StatusErrorFlags::NO_MEDIA | StatusErrorFlags::END_OF_MEDIA;
+
+                    return Err(if status.error_flags.intersects(media_flags) {
+                        Error::MediaRanOut
+                    } else {
+                        Error::PrintingFailed(status.error_flags)
+                    });
+                }
+
+                _ => (),
+            }
 
-        if status.status_type != PhaseChange {
-            return Err(Error::UnexpectedStatusType {
-                expected: PhaseChange,
-                got: status.status_type,
-            });
-        }
+            if !status.error_flags.is_empty() {
+                return Err(Error::StatusErrorFlags(status.error_flags));
+            }
 
-        if status.phase_type != Waiting {
-            return Err(Error::UnexpectedPhaseType {
-                expected: Waiting,
-                got: status.phase_type,
-            });
-        }
+            if status.status_type != expected {
+                return Err(Error::UnexpectedStatusType {
+                    expected,
+                    got: status.status_type,
+                });
+            }
 
-        Ok(())
+            return Ok(status);
+        }
     }
 }
@@ -0,0 +1,67 @@
+/// Encode a row of grayscale pixels into a raster line for the print head.
+/// The print head starts at the right edge of the label, so after `margin_dots` empty dots,
+/// the row is sampled from back to front. Dark pixels (< 0x80) are printed unless `invert` is set.
+/// `line` must be zeroed and large enough to hold the margin and all pixels of the row.
+pub fn encode_raster_line(row: &[u8], margin_dots: u32, invert: bool, line: &mut [u8]) {
+    // The margin decides at which byte we start and how far the packed bits are shifted.
+    let first_byte_idx = (margin_dots / 8) as usize;
+    let shift = margin_dots % 8;
+
+    // Walk the row from back to front in chunks of 8 pixels.
+    // Each chunk is packed into one byte (MSB first) that covers at most two bytes of the line.
+    for (byte_idx, chunk) in (first_byte_idx..).zip(row.rchunks(8)) {
+        let mut bits = 0u8;
+
+        for &pix in chunk.iter().rev() {
+            bits = (bits << 1) | ((pix < 0x80) != invert) as u8;
+        }
+
+        // The last chunk might be shorter, so we align its bits with the MSB.
+        bits <<= 8 - chunk.len();
+
+        line[byte_idx] |= bits >> shift;
+
+        // Only touch the next byte if there are bits that spill over.
+        if shift != 0 {
+            let spill = bits << (8 - shift);
+
+            if spill != 0 {
+                line[byte_idx + 1] |= spill;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_bitwise_reference() {
+        // A row with an odd length and an irregular pattern
+        let row: Vec<u8> = (0..37u32).map(|x| ((x * 97) % 256) as u8).collect();
+
+        for margin_dots in 0..20 {
+            for invert in [false, true] {
+                // Reference: write the margin and the reversed row bit by bit.
+                let mut expected = [0u8; 8];
+                let bits = (0..margin_dots)
+                    .map(|_| false)
+                    .chain(row.iter().rev().map(|&p| (p < 0x80) != invert));
+
+                for (idx, bit) in bits.enumerate() {
+                    expected[idx / 8] |= (bit as u8) << (7 - (idx % 8));
+                }
+
+                let mut line = [0u8; 8];
+                encode_raster_line(&row, margin_dots, invert, &mut line);
+
+                assert_eq!(
+                    line, expected,
+                    "margin: {}, invert: {}",
+                    margin_dots, invert
+                );
+            }
+        }
+    }
+}
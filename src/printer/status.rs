@@ -1,4 +1,4 @@
-use super::{Label, LabelType, Printer};
+use super::{commands::StatusRequest, Label, LabelType, Model, Printer};
 
 use std::fmt::Display;
 use std::time::Duration;
@@ -104,8 +104,9 @@ impl From<u8> for PhaseType {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
-pub(super) enum Notification {
+/// Something the printer reports without being asked for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Notification {
     CoolingStart,
     CoolingFinish,
     Unknown(u8),
@@ -124,36 +125,21 @@ impl Notification {
     }
 }
 
-#[allow(dead_code)]
-pub(super) struct Status {
-    pub error_flags: ErrorFlags,
-    pub label: Option<Label>,
-    pub status_type: StatusType,
-    pub phase_type: PhaseType,
-    pub notification: Option<Notification>,
+/// A status response of the printer.
+/// It is sent on request, but also on its own while printing (e.g. when the phase changes).
+#[derive(Copy, Clone)]
+pub struct Status {
+    pub(super) error_flags: ErrorFlags,
+    pub(super) label: Option<Label>,
+    pub(super) status_type: StatusType,
+    pub(super) phase_type: PhaseType,
+    pub(super) notification: Option<Notification>,
 }
 
-impl Printer {
-    pub(super) fn request_status(&self, timeout: Duration) -> Result<Status, Error> {
-        self.write(&[0x1b, 0x69, 0x53], timeout)?;
-        self.read_status_response(timeout)
-    }
-
-    pub(super) fn read_status_response(&self, timeout: Duration) -> Result<Status, Error> {
-        // Read the status data. It has always 32 bytes.
-        // Skip zero-length packets.
-        let mut data = [0u8; 32];
-
-        loop {
-            let read_bytes = self.read(&mut data, timeout)?;
-
-            match read_bytes {
-                0 => continue,
-                32 => break,
-                _ => return Err(Error::WrongResponseSizeUSB(read_bytes)),
-            }
-        }
-
+impl Status {
+    /// Decode the 32 bytes of a response.
+    /// The media is mapped to a label of the model (see `label()`).
+    fn parse(model: Model, data: &[u8; 32]) -> Result<Self, Error> {
         // Check head mark and length.
         if data[0] != 0x80 {
             return Err(Error::WrongPrintHeadMark(data[0]));
@@ -165,7 +151,7 @@ impl Printer {
 
         // Extract the label type and map it to a label.
         let label = if let Some(ty) = LabelType::from_bytes(data[11], data[10], data[17]) {
-            Some(Label::try_from((self.model, ty)).map_err(Error::InvalidLabel)?)
+            Some(Label::try_from((model, ty)).map_err(Error::InvalidLabel)?)
         } else {
             None
         };
@@ -179,4 +165,99 @@ impl Printer {
             notification: Notification::from_byte(data[22]),
         })
     }
+
+    /// The errors of the printer (empty if there are none)
+    pub fn error_flags(&self) -> ErrorFlags {
+        self.error_flags
+    }
+
+    /// The inserted media (`None` if there is none or a sleeping printer doesn't tell)
+    pub fn label(&self) -> Option<Label> {
+        self.label
+    }
+
+    /// Why the response has been sent (e.g. as a reply or because a print has completed)
+    pub fn status_type(&self) -> StatusType {
+        self.status_type
+    }
+
+    /// Whether the printer is waiting or printing
+    pub fn phase(&self) -> PhaseType {
+        self.phase_type
+    }
+
+    /// The notification that has been sent along (if any), e.g. about cooling the print head
+    pub fn notification(&self) -> Option<Notification> {
+        self.notification
+    }
+
+    /// Can the next print start right away? There are no errors, media is inserted and the printer is waiting.
+    pub fn is_ready(&self) -> bool {
+        self.error_flags.is_empty()
+            && self.label.is_some()
+            && (self.phase_type == PhaseType::Waiting)
+    }
+}
+
+impl Printer {
+    /// The raw bytes of the last status response (if any)
+    pub fn last_status_bytes(&self) -> Option<[u8; 32]> {
+        self.last_status.get()
+    }
+
+    /// The last status response, decoded (`None` if there is none or it is broken)
+    pub fn last_status(&self) -> Option<Status> {
+        self.last_status
+            .get()
+            .and_then(|data| Status::parse(self.model, &data).ok())
+    }
+
+    /// Request the current status.
+    /// A sleeping printer is not woken up, so it reports `TURNED_OFF` (and often no media).
+    pub fn status(&self) -> Result<Status, Error> {
+        self.request_status(Duration::from_millis(500))
+    }
+
+    pub fn current_error_flags(&self) -> Result<ErrorFlags, Error> {
+        Ok(self.status()?.error_flags)
+    }
+
+    /// Request the status and wake the printer up first if it has gone to sleep.
+    /// Sleeping printers report `TURNED_OFF` (and often no label) until they are initialized again.
+    pub(super) fn request_awake_status(&self, timeout: Duration) -> Result<Status, Error> {
+        let status = self.request_status(timeout)?;
+
+        if !status.error_flags.contains(ErrorFlags::TURNED_OFF) {
+            return Ok(status);
+        }
+
+        self.initialize(timeout)?;
+        self.request_status(timeout)
+    }
+
+    pub(super) fn request_status(&self, timeout: Duration) -> Result<Status, Error> {
+        self.send(&StatusRequest, timeout)?;
+        self.read_status_response(timeout)
+    }
+
+    pub(super) fn read_status_response(&self, timeout: Duration) -> Result<Status, Error> {
+        // Read the status data. It has always 32 bytes.
+        // Skip zero-length packets.
+        let mut data = [0u8; 32];
+
+        loop {
+            let read_bytes = self.read(&mut data, timeout)?;
+
+            match read_bytes {
+                0 => continue,
+                32 => break,
+                _ => return Err(Error::WrongResponseSizeUSB(read_bytes)),
+            }
+        }
+
+        // Keep the raw response for diagnostics, even if it is broken.
+        self.last_status.set(Some(data));
+
+        Status::parse(self.model, &data)
+    }
 }
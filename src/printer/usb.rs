@@ -1,5 +1,10 @@
-use super::{model::Model, Printer};
+use super::{
+    commands::{Command, Init, Invalidate},
+    model::{Model, Preference},
+    Capabilities, Connection, PrintConfig, Printer,
+};
 
+use std::cell::{Cell, RefCell};
 use std::fmt::Display;
 use std::time::Duration;
 
@@ -17,6 +22,11 @@ const MASS_STORAGE_PRODUCT_IDS: &[u16] = &[0x2049];
 #[derive(Debug, Copy, Clone)]
 pub enum Error {
     USBError(USBError),
+    PermissionDenied {
+        bus: u8,
+        address: u8,
+        product_id: u16,
+    },
     NoPrinter,
     NoInterface,
     NoInterfaceDescriptor,
@@ -30,6 +40,18 @@ impl Display for Error {
 
         match self {
             USBError(inner) => write!(f, "An USB error has occurred: {}", inner),
+            PermissionDenied {
+                bus,
+                address,
+                product_id,
+            } => write!(
+                f,
+                "Access to the printer (USB bus {:03}, device {:03}) has been denied. \
+                Add a udev rule like `SUBSYSTEM==\"usb\", ATTRS{{idVendor}}==\"{:04x}\", \
+                ATTRS{{idProduct}}==\"{:04x}\", MODE=\"0660\", GROUP=\"lp\"` \
+                and add the user to the group `lp`.",
+                bus, address, VENDOR_ID, product_id
+            ),
             NoPrinter => write!(f, "No printer has been found."),
             NoInterface => write!(f, "The USB device for the printer offers no interace."),
             NoInterfaceDescriptor => {
@@ -136,54 +158,130 @@ impl Printer {
             select_device(|m1, _| model_filter.map_or(true, |m2| m1 == m2))?
                 .ok_or(Error::NoPrinter)?;
 
+        // Missing permissions are the most common reason why attaching fails.
+        // They get a dedicated error, so the operator knows how to fix them.
+        let map_access_err = |err| match err {
+            USBError::Access => Error::PermissionDenied {
+                bus: device.bus_number(),
+                address: device.address(),
+                product_id: device_desc.product_id(),
+            },
+
+            err => Error::USBError(err),
+        };
+
         // Try to open the USB device, giving us a handle.
         // Ensure that a potential kernel driver is automatically detached and later reattached.
-        let mut handle = device.open()?;
-        handle.set_auto_detach_kernel_driver(true)?;
+        let mut handle = device.open().map_err(map_access_err)?;
+        handle
+            .set_auto_detach_kernel_driver(true)
+            .map_err(map_access_err)?;
 
         // Select the correct interface for the printer.
         let (interface_number, in_addr, out_addr) = select_interface(&device)?;
 
         // Claim the interface.
-        handle.claim_interface(interface_number)?;
+        handle
+            .claim_interface(interface_number)
+            .map_err(map_access_err)?;
 
         // Read some meta info from the device descriptor.
         let serial_number = handle.read_serial_number_string_ascii(&device_desc)?;
 
         // Populate the printer struct.
+        // Auto-cut is only enabled by default if there is a cutter at all.
+        let capabilities = Capabilities::of(model);
+
         let printer = Printer {
-            handle,
+            connection: Connection::Usb(handle),
             model,
+            capabilities,
             in_addr,
             out_addr,
             serial_number,
-            print_config: Default::default(),
+            print_config: PrintConfig {
+                auto_cut: capabilities.cutter,
+                ..Default::default()
+            },
+            last_status: Cell::new(None),
+            completed_pages: Cell::new(0),
+            capture: RefCell::new(None),
         };
 
-        // Clear outstanding jobs by sending a bunch of "invalid" commands.
-        // Then initialize the printer.
-        let invalidate_timeout = Duration::from_millis(500);
-
-        printer.write(&[0x00; 350], invalidate_timeout)?;
-        printer.write(&[0x1b, 0x40], invalidate_timeout)?;
+        printer.initialize(Duration::from_millis(500))?;
 
         Ok(printer)
     }
 
+    /// Try the acceptable printers in the given order and attach the first one that is present.
+    /// Returns the printer and the position of its entry in the preference.
+    /// If none can be attached, the first error other than a missing printer is reported.
+    pub fn attach_preferred(preference: &Preference) -> Result<(Self, usize), Error> {
+        let mut result = Err(Error::NoPrinter);
+
+        for (rank, model_filter) in preference.models().enumerate() {
+            match Self::attach(model_filter) {
+                Ok(printer) => return Ok((printer, rank)),
+                Err(Error::NoPrinter) => (),
+                Err(err) => {
+                    if matches!(result, Err(Error::NoPrinter)) {
+                        result = Err(err);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Clear outstanding jobs by sending a bunch of "invalid" commands.
+    /// Then initialize the printer (which also wakes it up from sleep).
+    pub(super) fn initialize(&self, timeout: Duration) -> Result<(), USBError> {
+        self.send(&Invalidate, timeout)?;
+        self.send(&Init, timeout)
+    }
+
     pub fn model(&self) -> Model {
         self.model
     }
 
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
     pub fn serial_number(&self) -> &str {
         &self.serial_number
     }
 
+    /// The addresses of the bulk input and output endpoints
+    pub fn endpoints(&self) -> (u8, u8) {
+        (self.in_addr, self.out_addr)
+    }
+
     pub(super) fn read(&self, data: &mut [u8], timeout: Duration) -> Result<usize, USBError> {
-        self.handle.read_bulk(self.in_addr, data, timeout)
+        match &self.connection {
+            Connection::Usb(handle) => handle.read_bulk(self.in_addr, data, timeout),
+            Connection::Emulated(emulation) => emulation.borrow_mut().read(data),
+        }
+    }
+
+    /// Serialize the command and write it to the printer.
+    pub(super) fn send<C: Command>(&self, command: &C, timeout: Duration) -> Result<(), USBError> {
+        self.write(&command.to_bytes(), timeout)
     }
 
     pub(super) fn write(&self, data: &[u8], timeout: Duration) -> Result<(), USBError> {
-        let written_bytes = self.handle.write_bulk(self.out_addr, data, timeout)?;
+        self.capture_bytes(data);
+
+        let handle = match &self.connection {
+            Connection::Usb(handle) => handle,
+            Connection::Emulated(emulation) => {
+                emulation.borrow_mut().write(data);
+                return Ok(());
+            }
+        };
+
+        let written_bytes = handle.write_bulk(self.out_addr, data, timeout)?;
 
         // Can this happen at all ... ? Never seen it ...
         if written_bytes != data.len() {
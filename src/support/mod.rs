@@ -0,0 +1,326 @@
+use crate::{
+    db::{Database, PrintJobEntry, SaleEntry},
+    export::{Csv, Exporter, Table},
+};
+
+use std::fmt::Display;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{BufWriter, Error as IOError, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use rusb::{Context as USBContext, UsbContext};
+use rusqlite::Error as SQLiteError;
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// The number of captured print jobs that are added to the bundle
+const CAPTURES_COUNT: usize = 10;
+
+/// The number of print jobs that are added to the bundle
+const PRINT_JOBS_COUNT: usize = 1000;
+
+#[derive(Debug)]
+pub enum Error {
+    DB(SQLiteError),
+    IO(IOError),
+    Zip(ZipError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+
+        match self {
+            DB(err) => write!(f, "The database could not be read: {}", err),
+            IO(err) => write!(f, "{}", err),
+            Zip(err) => write!(f, "The bundle could not be written: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<SQLiteError> for Error {
+    fn from(value: SQLiteError) -> Self {
+        Error::DB(value)
+    }
+}
+
+impl From<IOError> for Error {
+    fn from(value: IOError) -> Self {
+        Error::IO(value)
+    }
+}
+
+impl From<ZipError> for Error {
+    fn from(value: ZipError) -> Self {
+        Error::Zip(value)
+    }
+}
+
+/// Collect everything that helps to investigate a problem into a ZIP file at `path`:
+/// The configuration, the DB schema, the print history, the last captured print jobs
+/// and the attached hardware.
+/// Sales (from the DB and the journal) are only added if `include_sales` is set.
+/// Returns the names of the files in the bundle.
+pub fn write_support_bundle(
+    db: &Database,
+    path: &Path,
+    include_sales: bool,
+) -> Result<Vec<String>, Error> {
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut names = Vec::new();
+
+    let mut add_file = |name: String, data: &[u8]| -> Result<(), Error> {
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(data)?;
+        names.push(name);
+
+        Ok(())
+    };
+
+    add_file(
+        String::from("info.txt"),
+        describe_installation(db)?.as_bytes(),
+    )?;
+
+    add_file(
+        String::from("settings.txt"),
+        describe_settings(db).as_bytes(),
+    )?;
+
+    let mut schema = Vec::new();
+    db.schema(&mut schema)?;
+    add_file(
+        String::from("schema.sql"),
+        (schema.join(";\n\n") + ";\n").as_bytes(),
+    )?;
+
+    add_file(String::from("print_jobs.tsv"), &describe_print_jobs(db)?)?;
+
+    add_file(String::from("hardware.txt"), describe_hardware().as_bytes())?;
+
+    // The last captured print jobs contain the vouchers and the raw printer bytes.
+    let capture_dir = &db.settings().printer_capture_dir;
+
+    if !capture_dir.is_empty() {
+        for capture in recent_captures(Path::new(capture_dir))? {
+            let capture_name = capture.file_name().unwrap_or_default().to_string_lossy();
+
+            for file in fs::read_dir(&capture)? {
+                let file = file?.path();
+
+                if file.is_file() {
+                    let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+
+                    add_file(
+                        format!("captures/{}/{}", capture_name, file_name),
+                        &fs::read(&file)?,
+                    )?;
+                }
+            }
+        }
+    }
+
+    // The voucher of "Bon dumpen" (if there is one)
+    match fs::read("voucher.png") {
+        Ok(voucher) => add_file(String::from("voucher.png"), &voucher)?,
+        Err(err) if err.kind() == ErrorKind::NotFound => (),
+        Err(err) => return Err(err.into()),
+    }
+
+    if include_sales {
+        add_file(String::from("sales.tsv"), &describe_sales(db)?)?;
+
+        match fs::read(db.journal_path()) {
+            Ok(journal) => add_file(String::from("sales.journal"), &journal)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => (),
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    zip.finish()?.flush()?;
+
+    Ok(names)
+}
+
+/// The version of the program and the DB as well as the business info
+fn describe_installation(db: &Database) -> Result<String, Error> {
+    let info = db.info();
+    let mut text = String::new();
+
+    _ = writeln!(text, "weight-wb: {}", env!("CARGO_PKG_VERSION"));
+    _ = writeln!(text, "created: {}", Local::now().to_rfc3339());
+    _ = writeln!(text, "DB version: {}", db.version()?);
+    _ = writeln!(text, "products: {}", db.products().len());
+    _ = writeln!(text, "business: {}", info.business);
+    _ = writeln!(text, "serial port: {}", info.serial_port);
+
+    _ = writeln!(
+        text,
+        "printer model: {}",
+        info.printer_model.as_deref().unwrap_or("(any)")
+    );
+
+    Ok(text)
+}
+
+/// The settings without secrets
+fn describe_settings(db: &Database) -> String {
+    db.settings()
+        .entries()
+        .into_iter()
+        .map(|(key, value)| match key {
+            "admin_pin" if !value.is_empty() => format!("{} = (set)\n", key),
+            _ => format!("{} = {}\n", key, value),
+        })
+        .collect()
+}
+
+/// The print history, oldest first like a log file (see `--print-jobs`)
+fn describe_print_jobs(db: &Database) -> Result<Vec<u8>, Error> {
+    let mut jobs: Vec<PrintJobEntry> = Vec::new();
+    db.print_jobs(PRINT_JOBS_COUNT, &mut jobs)?;
+
+    let mut tsv = Vec::new();
+    Csv::tab_separated(db.settings().export_number_format)
+        .write(&Table::print_jobs(jobs.iter().rev()), &mut tsv)?;
+
+    Ok(tsv)
+}
+
+fn describe_sales(db: &Database) -> Result<Vec<u8>, Error> {
+    let mut sales: Vec<SaleEntry> = Vec::new();
+    db.sales(&mut sales)?;
+
+    let mut tsv = Vec::new();
+    Csv::tab_separated(db.settings().export_number_format)
+        .write(&Table::sales(&sales), &mut tsv)?;
+
+    Ok(tsv)
+}
+
+/// List the serial ports and USB devices.
+/// Failures are part of the description, they might be the problem.
+fn describe_hardware() -> String {
+    let mut text = String::from("Serial ports:\n");
+
+    match serialport::available_ports() {
+        Ok(ports) if ports.is_empty() => text.push_str("  (none)\n"),
+
+        Ok(ports) => {
+            for port in ports {
+                _ = writeln!(text, "  {} ({:?})", port.port_name, port.port_type);
+            }
+        }
+
+        Err(err) => _ = writeln!(text, "  error: {}", err),
+    }
+
+    text.push_str("\nUSB devices:\n");
+
+    let devices = USBContext::new().and_then(|context| context.devices());
+
+    match devices {
+        Ok(devices) => {
+            for device in devices.iter() {
+                let ids = device.device_descriptor().map_or_else(
+                    |err| format!("error: {}", err),
+                    |desc| format!("{:04x}:{:04x}", desc.vendor_id(), desc.product_id()),
+                );
+
+                _ = writeln!(
+                    text,
+                    "  bus {:03} address {:03}: {}",
+                    device.bus_number(),
+                    device.address(),
+                    ids
+                );
+            }
+        }
+
+        Err(err) => _ = writeln!(text, "  error: {}", err),
+    }
+
+    text
+}
+
+/// The directories of the most recent captured print jobs, oldest first.
+/// Their names start with the timestamp, so they can be sorted by name.
+fn recent_captures(capture_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let entries = match fs::read_dir(capture_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut captures = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    captures.retain(|capture| capture.is_dir());
+    captures.sort();
+
+    let skipped = captures.len().saturating_sub(CAPTURES_COUNT);
+
+    Ok(captures.split_off(skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{memory_db, ProductBuilder};
+
+    use std::io::Read;
+    use std::{env, process};
+
+    use zip::ZipArchive;
+
+    #[test]
+    fn bundle_can_be_unzipped() {
+        let path = env::temp_dir().join(format!("weight-wb-support-{}.zip", process::id()));
+
+        let mut db = memory_db();
+        ProductBuilder::new("Rinderhack").add_to(&mut db);
+        db.update_settings(|settings| settings.admin_pin = String::from("4711"))
+            .unwrap();
+
+        let mut names = write_support_bundle(&db, &path, false).unwrap();
+        names.sort();
+
+        assert!(names.contains(&String::from("info.txt")));
+        assert!(names.contains(&String::from("schema.sql")));
+        assert!(!names.contains(&String::from("sales.tsv")));
+
+        // Every file ends up in the archive and can be read back.
+        let mut archive = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut archived: Vec<String> = archive.file_names().map(String::from).collect();
+        archived.sort();
+
+        assert_eq!(archived, names);
+
+        let mut info = String::new();
+        archive
+            .by_name("info.txt")
+            .unwrap()
+            .read_to_string(&mut info)
+            .unwrap();
+
+        assert!(info.contains("products: 1\n"));
+
+        // Secrets are left out.
+        let mut settings = String::new();
+        archive
+            .by_name("settings.txt")
+            .unwrap()
+            .read_to_string(&mut settings)
+            .unwrap();
+
+        assert!(settings.contains("admin_pin = (set)\n"));
+        assert!(!settings.contains("4711"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,41 @@
+use crate::voucher::{Alignment, Builder as VoucherBuilder, Spacing};
+
+/// The organic certification of a product.
+/// EU law requires the code of the control body to be printed in the same visual field as the
+/// organic logo, with the place of farming of the raw materials directly below it.
+pub struct Certification<'a> {
+    /// The code of the control body (e.g. "DE-ÖKO-006")
+    pub control_code: &'a str,
+
+    /// The place of farming (e.g. "EU-Landwirtschaft" or "Deutschland-Landwirtschaft")
+    pub origin: Option<&'a str>,
+}
+
+impl<'a> Certification<'a> {
+    /// Add the certification block to the voucher.
+    /// Both lines are centered and kept close together, so they are read as one unit.
+    pub fn add_to(&self, builder: VoucherBuilder, font_size: f32) -> VoucherBuilder {
+        let bottom_spacing = if self.origin.is_some() { 0.0 } else { 12.0 };
+
+        let builder = builder
+            // Control body
+            .start_text_component(self.control_code)
+            .spacing(Spacing::lrtb(16.0, 16.0, 12.0, bottom_spacing))
+            .font_size(font_size)
+            .alignment(Alignment::Center)
+            .bold(true)
+            .finalize_text_component();
+
+        let Some(origin) = self.origin else {
+            return builder;
+        };
+
+        builder
+            // Place of farming
+            .start_text_component(origin)
+            .spacing(Spacing::lrtb(16.0, 16.0, 0.0, 12.0))
+            .font_size(font_size)
+            .alignment(Alignment::Center)
+            .finalize_text_component()
+    }
+}
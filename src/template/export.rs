@@ -0,0 +1,102 @@
+use super::{Error as TemplateError, ProductVoucher};
+use crate::{db::Database, voucher::TextContext};
+
+use std::fmt::Display;
+use std::fs;
+use std::io::Error as IOError;
+use std::path::{Path, PathBuf};
+
+use image::{ImageError, ImageFormat};
+
+/// The weight that is printed on exported labels of products with a kg price.
+/// With 1 kg, the total equals the kg price.
+const PLACEHOLDER_WEIGHT_KG: f64 = 1.0;
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownProduct(String),
+    Template(String, TemplateError),
+    Save(PathBuf, ImageError),
+    IO(IOError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+
+        match self {
+            UnknownProduct(name) => write!(f, "There is no product named \"{}\".", name),
+            Template(name, err) => write!(f, "The label of \"{}\" failed: {}", name, err),
+            Save(path, err) => write!(f, "{} could not be saved: {}", path.display(), err),
+            IO(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<IOError> for Error {
+    fn from(value: IOError) -> Self {
+        Error::IO(value)
+    }
+}
+
+/// Render the vouchers of the given products (all if `product_names` is empty)
+/// into PNG files in `dir`, e.g. for shelf labels or a catalogue.
+/// Products with a kg price get a placeholder weight, fixed-price products none.
+/// Returns the paths of the written files.
+pub fn export_product_labels(
+    db: &Database,
+    product_names: &[String],
+    width: u32,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, Error> {
+    // Resolve the products first, so a typo does not leave half an export.
+    let products = if product_names.is_empty() {
+        db.products().iter().collect::<Vec<_>>()
+    } else {
+        product_names
+            .iter()
+            .map(|name| {
+                db.products()
+                    .iter()
+                    .find(|product| &product.name == name)
+                    .ok_or_else(|| Error::UnknownProduct(name.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    fs::create_dir_all(dir)?;
+
+    let mut text_ctx = TextContext::new();
+    let mut paths = Vec::with_capacity(products.len());
+
+    for (idx, product) in products.into_iter().enumerate() {
+        let weight_kg = product.is_kg_price.then_some(PLACEHOLDER_WEIGHT_KG);
+
+        let (voucher, next_text_ctx, _) =
+            ProductVoucher::new(product, weight_kg, db.info(), db.settings())
+                .build(width, text_ctx)
+                .map_err(|err| Error::Template(product.name.clone(), err))?;
+
+        text_ctx = next_text_ctx;
+
+        // Product names are not unique and might contain anything, so we prefix them.
+        let path = dir.join(format!("{:03}_{}.png", idx + 1, file_name(&product.name)));
+
+        voucher
+            .save_with_format(&path, ImageFormat::Png)
+            .map_err(|err| Error::Save(path.clone(), err))?;
+
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+/// Keep the characters of a product name that are safe in file names.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
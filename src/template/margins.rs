@@ -0,0 +1,182 @@
+use crate::printer::LabelType;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use image::GrayImage;
+
+/// Extra margins in mm inside the printable area of a label.
+/// Some roll brands have a slightly different liner, so texts at the edge get clipped.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Margins {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl Margins {
+    fn to_dots(mm: f32, dpi: u32) -> u32 {
+        (mm * (dpi as f32) / 25.4).round() as u32
+    }
+
+    /// The horizontal margins in dots
+    pub fn horz_dots(&self, dpi: u32) -> u32 {
+        Self::to_dots(self.left, dpi) + Self::to_dots(self.right, dpi)
+    }
+
+    /// The vertical margins in dots
+    pub fn vert_dots(&self, dpi: u32) -> u32 {
+        Self::to_dots(self.top, dpi) + Self::to_dots(self.bottom, dpi)
+    }
+
+    /// Surround an image with white margins.
+    /// Build it `horz_dots()` narrower (and `vert_dots()` shorter) than the label, so it fits again.
+    pub fn pad(&self, image: &GrayImage, dpi: u32) -> GrayImage {
+        let (left, top) = (Self::to_dots(self.left, dpi), Self::to_dots(self.top, dpi));
+
+        let mut padded = GrayImage::from_pixel(
+            image.width() + self.horz_dots(dpi),
+            image.height() + self.vert_dots(dpi),
+            image::Luma([0xff]),
+        );
+
+        image::imageops::replace(&mut padded, image, left as i64, top as i64);
+
+        padded
+    }
+}
+
+impl Display for Margins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{},{}",
+            self.left, self.right, self.top, self.bottom
+        )
+    }
+}
+
+impl FromStr for Margins {
+    type Err = ();
+
+    /// "<left>,<right>,<top>,<bottom>" in mm
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<f32>().map_err(|_| ()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match values[..] {
+            [left, right, top, bottom] if values.iter().all(|v| *v >= 0.0) => Ok(Self {
+                left,
+                right,
+                top,
+                bottom,
+            }),
+
+            _ => Err(()),
+        }
+    }
+}
+
+/// The extra margins per label type, like "62=1,1,0,0;62x100=0.5,0.5,1,1;tape12=0,0,2,2"
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LabelMargins(Vec<(LabelType, Margins)>);
+
+impl LabelMargins {
+    /// The margins of a label type (none if it is not configured)
+    pub fn of(&self, ty: LabelType) -> Margins {
+        self.0
+            .iter()
+            .find(|(configured, _)| *configured == ty)
+            .map(|(_, margins)| *margins)
+            .unwrap_or_default()
+    }
+}
+
+impl Display for LabelMargins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|(ty, margins)| format!("{}={}", ty, margins))
+            .collect();
+
+        write!(f, "{}", entries.join(";"))
+    }
+}
+
+impl FromStr for LabelMargins {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (ty, margins) = entry.split_once('=').ok_or(())?;
+                let ty: LabelType = ty.trim().parse()?;
+
+                Ok((ty, margins.parse()?))
+            })
+            .collect::<Result<_, _>>()
+            .map(LabelMargins)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_margins_round_trip() {
+        let margins: LabelMargins = "62=1,1,0,0; 62x100=0.5,0.5,1,1;tape12=0,0,2,2"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            margins.of(LabelType::DieCut {
+                width: 62,
+                length: 100
+            }),
+            Margins {
+                left: 0.5,
+                right: 0.5,
+                top: 1.0,
+                bottom: 1.0
+            }
+        );
+        assert_eq!(
+            margins.of(LabelType::Continuous { width: 102 }),
+            Margins::default()
+        );
+        assert_eq!(
+            margins.to_string(),
+            "62=1,1,0,0;62x100=0.5,0.5,1,1;tape12=0,0,2,2"
+        );
+
+        assert!("62=1,1,0".parse::<LabelMargins>().is_err());
+        assert!("62=-1,0,0,0".parse::<LabelMargins>().is_err());
+    }
+
+    #[test]
+    fn padding_keeps_the_label_size() {
+        let margins = Margins {
+            left: 1.0,
+            right: 2.0,
+            top: 0.0,
+            bottom: 1.0,
+        };
+
+        // 300 dpi: 1 mm is 12 dots.
+        let image = GrayImage::from_pixel(100 - margins.horz_dots(300), 50, image::Luma([0]));
+        let padded = margins.pad(&image, 300);
+
+        assert_eq!(margins.horz_dots(300), 12 + 24);
+        assert_eq!((padded.width(), padded.height()), (100, 62));
+        assert_eq!(padded.get_pixel(11, 0).0, [0xff]);
+        assert_eq!(padded.get_pixel(12, 0).0, [0]);
+        assert_eq!(padded.get_pixel(12, 50).0, [0xff]);
+    }
+}
@@ -0,0 +1,427 @@
+use crate::{
+    db::{InfoEntry, LogoUpscale, ProductEntry, SettingsEntry},
+    format,
+    voucher::{
+        parse_script_markup, Alignment, Builder as VoucherBuilder, Error as VoucherError, Metadata,
+        TabStop, TextContext, UpscaleFilter,
+    },
+};
+
+use std::fmt::Display;
+
+use chrono::{DateTime, Local};
+use image::{GrayImage, ImageError, ImageReader};
+
+/// The organic certification block
+mod certification;
+pub use certification::Certification;
+
+/// The origin of meat products
+mod origin;
+pub use origin::Origin;
+
+/// Render the labels of many products into files
+mod export;
+pub use export::{export_product_labels, Error as ExportError};
+
+/// Per-product adjustments of the template
+mod overrides;
+pub use overrides::{Overrides, RenderProfile};
+
+/// Font sizes and margins for the different label sizes
+mod preset;
+pub use preset::Preset;
+
+/// The price tag for the display counter
+mod shelf_label;
+pub use shelf_label::ShelfLabel;
+
+/// Extra margins per label type
+mod margins;
+pub use margins::{LabelMargins, Margins};
+
+/// The numbered receipt for the customer
+mod receipt;
+pub use receipt::CustomerReceipt;
+
+/// The summary of some sales as an interim cash-up slip
+mod summary;
+pub use summary::SummarySlip;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidOverride(String),
+    ExtraLogo(ImageError),
+    Voucher(VoucherError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+
+        match self {
+            InvalidOverride(line) => write!(f, "Invalid voucher override: \"{}\"", line),
+            ExtraLogo(err) => write!(f, "The extra logo could not be loaded: {}", err),
+            Voucher(err) => write!(f, "The voucher could not be built: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ImageError> for Error {
+    fn from(value: ImageError) -> Self {
+        Error::ExtraLogo(value)
+    }
+}
+
+impl From<VoucherError> for Error {
+    fn from(value: VoucherError) -> Self {
+        Error::Voucher(value)
+    }
+}
+
+/// The characters that most vouchers are made of
+const COMMON_GLYPHS: &str =
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÜ abcdefghijklmnopqrstuvwxyzäöüß 0123456789 €.,:;-/()%°";
+
+/// Build a throwaway voucher that shows the common glyphs in all text styles.
+/// Loading the fonts and rasterizing the glyphs takes seconds on slow devices,
+/// so this is better done at startup than during the first sale of the day.
+pub fn warm_up(
+    info: &InfoEntry,
+    settings: &SettingsEntry,
+    width: u32,
+    text_ctx: TextContext,
+) -> Result<TextContext, Error> {
+    let product = ProductEntry::new(
+        String::from(COMMON_GLYPHS),
+        1234,
+        true,
+        String::from(COMMON_GLYPHS),
+        String::from(COMMON_GLYPHS),
+        Some(7.0),
+        Some(7),
+    );
+
+    let (_, text_ctx, _) =
+        ProductVoucher::new(&product, Some(1.234), info, settings).build(width, text_ctx)?;
+
+    Ok(text_ctx)
+}
+
+/// The label that is printed for a sold product
+pub struct ProductVoucher<'a> {
+    pub product: &'a ProductEntry,
+    pub weight_kg: Option<f64>,
+    pub piece_count: Option<u64>,
+    pub info: &'a InfoEntry,
+    pub settings: &'a SettingsEntry,
+    pub dpi: Option<u32>,
+    pub date: DateTime<Local>,
+    pub expiration_override: Option<DateTime<Local>>,
+    pub language: &'a str,
+    pub preset: Preset,
+}
+
+impl<'a> ProductVoucher<'a> {
+    pub fn new(
+        product: &'a ProductEntry,
+        weight_kg: Option<f64>,
+        info: &'a InfoEntry,
+        settings: &'a SettingsEntry,
+    ) -> Self {
+        Self {
+            product,
+            weight_kg,
+            piece_count: None,
+            info,
+            settings,
+            dpi: None,
+            date: Local::now(),
+            expiration_override: None,
+            language: &settings.voucher_language,
+            preset: Preset::default(),
+        }
+    }
+
+    /// Print the name and the ingredients in another language than the configured one.
+    /// An empty language selects the original texts.
+    pub fn language(mut self, language: &'a str) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Sell the product by pieces that have been counted on the scales.
+    pub fn piece_count(mut self, piece_count: Option<u64>) -> Self {
+        self.piece_count = piece_count;
+        self
+    }
+
+    /// Calculate dates (like the expiration date) relative to the given sale date instead of the clock.
+    pub fn date(mut self, date: DateTime<Local>) -> Self {
+        self.date = date;
+        self
+    }
+
+    /// Print this best-before date instead of the one calculated from the sale date.
+    pub fn expiration_override(mut self, expiration_override: Option<DateTime<Local>>) -> Self {
+        self.expiration_override = expiration_override;
+        self
+    }
+
+    /// Lay the voucher out for another label size than 62 mm continuous.
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Enforce the maximum voucher length for a printer with the given resolution.
+    pub fn dpi(mut self, dpi: Option<u32>) -> Self {
+        self.dpi = dpi;
+        self
+    }
+
+    /// The maximum length of the voucher in pixels (if any)
+    /// Die-cut labels cannot grow, so their length is the limit.
+    fn max_height(&self) -> Option<u32> {
+        if let Some(length) = self.preset.length {
+            return Some(length);
+        }
+
+        let max_length_mm = self.settings.max_voucher_length_mm;
+        let dpi = self.dpi?;
+
+        (max_length_mm > 0).then(|| ((max_length_mm as f32) * (dpi as f32) / 25.4).floor() as u32)
+    }
+
+    /// Build the voucher with the given width.
+    /// The text context is handed back, so fonts and glyphs can be reused for the next voucher.
+    /// The metadata tells e.g. about truncated lines.
+    pub fn build(
+        &self,
+        width: u32,
+        text_ctx: TextContext,
+    ) -> Result<(GrayImage, TextContext, Metadata), Error> {
+        let product = self.product.localized(self.language);
+        let product = product.as_ref();
+        let overrides = Overrides::parse(&product.voucher_overrides)?;
+
+        let preset = &self.preset;
+
+        let name_font_size = preset.font_size(overrides.name_font_size.unwrap_or(50.0));
+        let price_font_size = preset.font_size(overrides.price_font_size.unwrap_or(40.0));
+        let body_font_size = preset.font_size(overrides.body_font_size.unwrap_or(25.0));
+        let trailer_font_size = preset.font_size(21.0);
+
+        // Calculate the price.
+        let quantity_str = if product.is_kg_price {
+            let weight_kg = self.weight_kg.expect("Product with kg price needs weight");
+            let weight_str = self.settings.format_weight(weight_kg);
+
+            format!("Gewicht:\t{}", weight_str)
+        } else if let Some(piece_count) = self.piece_count {
+            format!("Menge:\t{} Stück", piece_count)
+        } else {
+            String::from("Gewicht:\t-")
+        };
+
+        // The quantity and the price start at the same position, whatever their labels.
+        let value_tab_stops = vec![TabStop::Left(preset.font_size(150.0))];
+
+        let price_ct = product.total_price_ct(
+            self.weight_kg,
+            self.piece_count,
+            self.settings.rounding_mode,
+        );
+        let price_str = format::euro(price_ct);
+
+        // Load the logo.
+        let logo = ImageReader::open("logo.png")
+            .expect("Failed to load logo")
+            .decode()
+            .expect("Failed to decode logo");
+
+        // The extra logo is configured per product, so a broken one must not panic.
+        let extra_logo = overrides
+            .extra_logo
+            .as_ref()
+            .map(|path| ImageReader::open(path)?.decode())
+            .transpose()?;
+
+        // Format the product parameters.
+        let storage_temp = product.storage_temp_formatted();
+        let mhd = match self.expiration_override {
+            Some(date) => Some(ProductEntry::format_expiration_date(date)),
+            None => product.expiration_date_formatted(self.date),
+        };
+
+        let storage = match (storage_temp, mhd) {
+            (None, None) => None,
+            (Some(temp), None) => Some(format!("Lagerungstemperatur: {}", temp)),
+            (None, Some(mhd)) => Some(format!("Ungeöffnet mindestens haltbar bis: {}", mhd)),
+            (Some(temp), Some(mhd)) => Some(format!(
+                "Ungeöffnet bei {} mindestens haltbar bis: {}",
+                temp, mhd
+            )),
+        };
+
+        // Ingredients and additionals may contain super- and subscripts (e.g. allergen markers).
+        let (ingredients, ingredients_scripts) =
+            parse_script_markup(&format!("Zutaten: {}", product.ingredients));
+        let (additional_info, additional_info_scripts) =
+            parse_script_markup(&product.additional_info);
+
+        // Build the trailer.
+        let info = self.info;
+
+        let trailer = format!(
+            "{} · {} · {}, {}, · {} · {}",
+            info.business, info.owners, info.street, info.locality, info.phone, info.mail
+        );
+
+        // Finally, construct the voucher.
+        // Die-cut labels are filled exactly, so long vouchers are always shrunk.
+        let mut builder = VoucherBuilder::with_text_context(width, text_ctx)
+            .trim_empty_space(true)
+            .max_height(self.max_height())
+            .min_height(preset.length)
+            .shrink_to_fit(self.settings.shrink_long_vouchers || preset.length.is_some())
+            .heavy(overrides.render_profile == RenderProfile::Heavy)
+            // Logo
+            .start_image_component(&logo)
+            .spacing(preset.spacing_horz_vert(20.0, 20.0))
+            .upscale(match self.settings.logo_upscale {
+                LogoUpscale::Off => None,
+                LogoUpscale::Nearest => Some(UpscaleFilter::Nearest),
+                LogoUpscale::Smooth => Some(UpscaleFilter::CatmullRom),
+            })
+            .finalize_image_component()
+            // Product
+            .start_text_component(&product.name)
+            .spacing(preset.spacing_horz_vert(16.0, 16.0))
+            .font_size(name_font_size)
+            .alignment(Alignment::Center)
+            .bold(true)
+            .finalize_text_component();
+
+        // Extra logo
+        if let Some(extra_logo) = &extra_logo {
+            builder = builder
+                .start_image_component(extra_logo)
+                .spacing(preset.spacing_horz_vert(16.0, 12.0))
+                .finalize_image_component();
+        }
+
+        // Organic certification
+        if let Some(control_code) = &product.organic_control_code {
+            let certification = Certification {
+                control_code,
+                origin: product.agriculture_origin.as_deref(),
+            };
+
+            builder = certification.add_to(builder, body_font_size);
+        }
+
+        builder = builder
+            // Quantity
+            .start_text_component(&quantity_str)
+            .spacing(preset.spacing_horz_vert(16.0, 12.0))
+            .font_size(body_font_size)
+            .tab_stops(value_tab_stops.clone())
+            .finalize_text_component()
+            // Price
+            .start_text_component(&format!("Preis:\t{}", price_str))
+            .spacing(preset.spacing_horz_vert(16.0, 24.0))
+            .font_size(price_font_size)
+            .bold(true)
+            .tab_stops(value_tab_stops)
+            .finalize_text_component();
+
+        // Comparison price
+        if product.is_kg_price && self.settings.show_price_per_100g {
+            let euro_per_kg = (product.price_ct as f64) / 100.0;
+
+            let comparison_str = format!(
+                "Kilopreis: {} / kg · Grundpreis: {} € / 100 g",
+                format::euro(product.price_ct),
+                format::fixed(euro_per_kg / 10.0, 2)
+            );
+
+            builder = builder
+                .start_text_component(&comparison_str)
+                .spacing(preset.spacing(16.0, 16.0, 0.0, 24.0))
+                .font_size(body_font_size)
+                .finalize_text_component();
+        }
+
+        builder = builder
+            // Ingredients
+            .start_text_component(&ingredients)
+            .spacing(preset.spacing_horz_vert(16.0, 12.0))
+            .font_size(body_font_size)
+            .scripts(ingredients_scripts)
+            .finalize_text_component();
+
+        // Origin
+        if let Some(origin) = Origin::of(product) {
+            builder = origin.add_to(builder, body_font_size);
+        }
+
+        builder = builder
+            // Additionals
+            .start_text_component(&additional_info)
+            .spacing(preset.spacing_horz_vert(16.0, 12.0))
+            .font_size(body_font_size)
+            .scripts(additional_info_scripts)
+            .visible(!additional_info.is_empty())
+            .finalize_text_component()
+            // Storage
+            .start_text_component(storage.as_deref().unwrap_or_default())
+            .spacing(preset.spacing_horz_vert(16.0, 12.0))
+            .font_size(body_font_size)
+            .visible(storage.is_some())
+            .finalize_text_component()
+            // Calibration notice
+            .start_text_component(&self.settings.calibration_notice)
+            .spacing(preset.spacing(16.0, 16.0, 24.0, 0.0))
+            .font_size(body_font_size)
+            .alignment(Alignment::Center)
+            .bold(true)
+            .visible(self.settings.force_calibration_notice)
+            .finalize_text_component()
+            // Trailer
+            .start_text_component(&trailer)
+            .spacing(preset.spacing(8.0, 8.0, 48.0, 8.0))
+            .font_size(trailer_font_size)
+            .alignment(Alignment::Center)
+            .italic(true)
+            .finalize_text_component()
+            // Verification ID
+            .start_text_component(&format!(
+                "Eichnummer der Waage: {}",
+                self.settings.scale_verification_id
+            ))
+            .spacing(preset.spacing(8.0, 8.0, 0.0, 8.0))
+            .font_size(trailer_font_size)
+            .alignment(Alignment::Center)
+            .italic(true)
+            .visible(
+                self.settings.print_verification_id
+                    && !self.settings.scale_verification_id.is_empty(),
+            )
+            .finalize_text_component()
+            // Daily message
+            .start_text_component(&self.settings.daily_message)
+            .spacing(preset.spacing(8.0, 8.0, 0.0, 8.0))
+            .font_size(trailer_font_size)
+            .alignment(Alignment::Center)
+            .bold(true)
+            .visible(!self.settings.daily_message.is_empty())
+            .finalize_text_component();
+
+        let voucher = builder.build_with_metadata()?;
+
+        Ok(voucher)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    db::ProductEntry,
+    voucher::{Builder as VoucherBuilder, Spacing},
+};
+
+/// The origin and processing of a (meat) product.
+/// Each stage is optional, e.g. pork is not labelled with the country of birth.
+pub struct Origin<'a> {
+    pub born_in: Option<&'a str>,
+    pub reared_in: Option<&'a str>,
+    pub slaughtered_in: Option<&'a str>,
+    pub cut_in: Option<&'a str>,
+}
+
+impl<'a> Origin<'a> {
+    /// Collect the origin of the product if it should be printed.
+    pub fn of(product: &'a ProductEntry) -> Option<Self> {
+        if !product.show_origin {
+            return None;
+        }
+
+        let origin = Self {
+            born_in: product.born_in.as_deref(),
+            reared_in: product.reared_in.as_deref(),
+            slaughtered_in: product.slaughtered_in.as_deref(),
+            cut_in: product.cut_in.as_deref(),
+        };
+
+        (!origin.stages().is_empty()).then_some(origin)
+    }
+
+    /// The known stages, each as a printable line.
+    fn stages(&self) -> Vec<String> {
+        [
+            ("Geboren in", self.born_in),
+            ("Aufgezogen in", self.reared_in),
+            ("Geschlachtet in", self.slaughtered_in),
+            ("Zerlegt in", self.cut_in),
+        ]
+        .into_iter()
+        .filter_map(|(stage, place)| place.map(|place| format!("{}: {}", stage, place)))
+        .collect()
+    }
+
+    /// Add the origin section to the voucher.
+    pub fn add_to(&self, builder: VoucherBuilder, font_size: f32) -> VoucherBuilder {
+        builder
+            .start_text_component(&self.stages().join(" · "))
+            .spacing(Spacing::horz_vert(16.0, 12.0))
+            .font_size(font_size)
+            .finalize_text_component()
+    }
+}
@@ -0,0 +1,95 @@
+use super::Error;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// How heavily the labels of a product are rendered
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum RenderProfile {
+    #[default]
+    Normal,
+
+    /// For dark or frozen packaging: Bold texts, darker edges and thicker barcode modules
+    Heavy,
+}
+
+impl Display for RenderProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderProfile::Normal => write!(f, "normal"),
+            RenderProfile::Heavy => write!(f, "heavy"),
+        }
+    }
+}
+
+impl FromStr for RenderProfile {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(RenderProfile::Normal),
+            "heavy" => Ok(RenderProfile::Heavy),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Product-specific adjustments of the label template.
+/// They are stored as `key = value` fragments in the product entry, one per line.
+/// Empty lines and lines starting with `#` are ignored.
+#[derive(Clone, Default)]
+pub struct Overrides {
+    /// The font size of the product name (e.g. smaller for long names)
+    pub name_font_size: Option<f32>,
+
+    /// The font size of the price
+    pub price_font_size: Option<f32>,
+
+    /// The font size of the informational texts (weight, ingredients, ...)
+    pub body_font_size: Option<f32>,
+
+    /// An additional image (e.g. a certification logo) that is printed below the product name
+    pub extra_logo: Option<String>,
+
+    /// The rendering of all labels of the product (e.g. heavy for frozen goods)
+    pub render_profile: RenderProfile,
+}
+
+impl Overrides {
+    pub fn parse(fragments: &str) -> Result<Self, Error> {
+        let mut overrides = Self::default();
+
+        for line in fragments.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || Error::InvalidOverride(String::from(line));
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim();
+
+            match key.trim() {
+                "name_font_size" => {
+                    overrides.name_font_size = Some(parse_font_size(value).ok_or_else(invalid)?)
+                }
+                "price_font_size" => {
+                    overrides.price_font_size = Some(parse_font_size(value).ok_or_else(invalid)?)
+                }
+                "body_font_size" => {
+                    overrides.body_font_size = Some(parse_font_size(value).ok_or_else(invalid)?)
+                }
+                "extra_logo" => overrides.extra_logo = Some(String::from(value)),
+                "render_profile" => {
+                    overrides.render_profile = value.parse().map_err(|_| invalid())?
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(overrides)
+    }
+}
+
+fn parse_font_size(value: &str) -> Option<f32> {
+    value.parse().ok().filter(|&size: &f32| size > 0.0)
+}
@@ -0,0 +1,92 @@
+use crate::{
+    printer::{Label, LabelType},
+    voucher::Spacing,
+};
+
+/// The layout of a voucher for a physical label size.
+/// Font sizes and spacings are given for the 62 mm continuous label and scaled by the preset.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Preset {
+    /// A short name for the status bar
+    pub name: &'static str,
+
+    /// The factor for font sizes and vertical spacings
+    pub font_scale: f32,
+
+    /// The factor for horizontal spacings (the margins of the texts)
+    pub margin_scale: f32,
+
+    /// The exact length of the voucher in pixels (die-cut labels only)
+    pub length: Option<u32>,
+}
+
+impl Preset {
+    /// 62 mm continuous labels (and all other continuous labels without a preset)
+    pub const CONTINUOUS_62: Preset = Preset {
+        name: "62 mm Endlos",
+        font_scale: 1.0,
+        margin_scale: 1.0,
+        length: None,
+    };
+
+    /// 102 mm continuous labels: Larger texts and wider margins, so the lines don't get too long.
+    pub const CONTINUOUS_102: Preset = Preset {
+        name: "102 mm Endlos",
+        font_scale: 1.5,
+        margin_scale: 2.0,
+        length: None,
+    };
+
+    /// 62×100 mm die-cut labels: Slightly smaller texts, so most vouchers fit without shrinking.
+    pub const DIE_CUT_62X100: Preset = Preset {
+        name: "62x100 mm Einzeletikett",
+        font_scale: 0.9,
+        margin_scale: 1.0,
+        length: None,
+    };
+
+    /// Select the preset for the label that is loaded into the printer.
+    /// Other die-cut labels and tapes are not supported (`None`).
+    pub fn for_label(label: &Label) -> Option<Self> {
+        match label.ty {
+            LabelType::Continuous { width: 102 } => Some(Self::CONTINUOUS_102),
+            LabelType::Continuous { .. } => Some(Self::CONTINUOUS_62),
+
+            LabelType::DieCut {
+                width: 62,
+                length: 100,
+            } => Some(Self {
+                length: label.printable_dots_length,
+                ..Self::DIE_CUT_62X100
+            }),
+
+            LabelType::DieCut { .. } | LabelType::Tape { .. } => None,
+        }
+    }
+
+    /// Scale a font size of the 62 mm layout.
+    pub fn font_size(&self, font_size: f32) -> f32 {
+        font_size * self.font_scale
+    }
+
+    /// Scale a spacing of the 62 mm layout.
+    pub fn spacing(&self, left: f32, right: f32, top: f32, bottom: f32) -> Spacing {
+        Spacing::lrtb(
+            left * self.margin_scale,
+            right * self.margin_scale,
+            top * self.font_scale,
+            bottom * self.font_scale,
+        )
+    }
+
+    /// Like `spacing()`, but with the same spacing left and right as well as top and bottom
+    pub fn spacing_horz_vert(&self, horz: f32, vert: f32) -> Spacing {
+        self.spacing(horz, horz, vert, vert)
+    }
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self::CONTINUOUS_62
+    }
+}
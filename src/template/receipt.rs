@@ -0,0 +1,187 @@
+use super::Error;
+use crate::{
+    db::{InfoEntry, PaymentMethod, ProductEntry, SettingsEntry},
+    format::{self, NumberFormat},
+    voucher::{Alignment, Builder as VoucherBuilder, Metadata, Spacing, TabStop, TextContext},
+};
+
+use chrono::{DateTime, Local};
+use image::GrayImage;
+
+/// The horizontal margin of all texts in pixels
+const MARGIN: f32 = 16.0;
+
+/// The receipt that is handed to the customer in addition to the voucher on the package.
+/// It is numbered and lists the VAT that is included in the total.
+pub struct CustomerReceipt<'a> {
+    pub product: &'a ProductEntry,
+    pub weight_kg: Option<f64>,
+    pub piece_count: Option<u64>,
+    pub info: &'a InfoEntry,
+    pub settings: &'a SettingsEntry,
+    pub number: u64,
+    pub payment_method: Option<PaymentMethod>,
+    pub date: DateTime<Local>,
+}
+
+impl<'a> CustomerReceipt<'a> {
+    pub fn new(
+        product: &'a ProductEntry,
+        weight_kg: Option<f64>,
+        info: &'a InfoEntry,
+        settings: &'a SettingsEntry,
+        number: u64,
+    ) -> Self {
+        Self {
+            product,
+            weight_kg,
+            piece_count: None,
+            info,
+            settings,
+            number,
+            payment_method: None,
+            date: Local::now(),
+        }
+    }
+
+    /// Sell the product by pieces that have been counted on the scales.
+    pub fn piece_count(mut self, piece_count: Option<u64>) -> Self {
+        self.piece_count = piece_count;
+        self
+    }
+
+    /// Note how the customer has paid.
+    pub fn payment_method(mut self, payment_method: Option<PaymentMethod>) -> Self {
+        self.payment_method = payment_method;
+        self
+    }
+
+    /// Print the given sale date instead of the clock.
+    pub fn date(mut self, date: DateTime<Local>) -> Self {
+        self.date = date;
+        self
+    }
+
+    /// The VAT that is included in a gross total (rounded to whole cents)
+    fn included_vat_ct(&self, total_ct: u64) -> u64 {
+        let vat_percent = self.settings.vat_percent;
+
+        ((total_ct as f64) * vat_percent / (100.0 + vat_percent)).round() as u64
+    }
+
+    /// Build the receipt with the given width.
+    /// The text context is handed back, so fonts and glyphs can be reused for the next voucher.
+    pub fn build(
+        &self,
+        width: u32,
+        text_ctx: TextContext,
+    ) -> Result<(GrayImage, TextContext, Metadata), Error> {
+        let product = self.product;
+        let info = self.info;
+        let body_font_size = 25.0;
+
+        // Amounts are aligned to the right edge of the text.
+        let amount_stops = vec![TabStop::Right((width as f32) - (2.0 * MARGIN))];
+
+        // Describe the item like "0,500 kg × 14,90 € / kg".
+        let total_ct = product.total_price_ct(
+            self.weight_kg,
+            self.piece_count,
+            self.settings.rounding_mode,
+        );
+
+        let quantity_str = if product.is_kg_price {
+            let weight_kg = self.weight_kg.expect("Product with kg price needs weight");
+
+            format!(
+                "{} × {} / kg",
+                self.settings.format_weight(weight_kg),
+                format::euro(product.price_ct)
+            )
+        } else {
+            format!(
+                "{} Stück × {}",
+                self.piece_count.unwrap_or(1),
+                format::euro(product.price_ct)
+            )
+        };
+
+        let address = format!("{} · {}\nTel. {}", info.street, info.locality, info.phone);
+        let header = format!(
+            "Beleg Nr. {}\t{}",
+            self.number,
+            self.date.format("%d.%m.%Y %H:%M")
+        );
+        let item = format!(
+            "{}\n{}\t{}",
+            product.name,
+            quantity_str,
+            format::euro(total_ct)
+        );
+        let vat = format!(
+            "Enthaltene MwSt. {} %\t{}",
+            NumberFormat::Comma.shortest(self.settings.vat_percent),
+            format::euro(self.included_vat_ct(total_ct))
+        );
+        let payment = self
+            .payment_method
+            .map(|method| format!("Bezahlt: {}", method.name()));
+
+        let builder = VoucherBuilder::with_text_context(width, text_ctx)
+            .trim_empty_space(true)
+            // Business
+            .start_text_component(&info.business)
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 16.0, 4.0))
+            .font_size(40.0)
+            .alignment(Alignment::Center)
+            .bold(true)
+            .finalize_text_component()
+            .start_text_component(&address)
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 0.0, 24.0))
+            .font_size(body_font_size)
+            .alignment(Alignment::Center)
+            .finalize_text_component()
+            // Number and date
+            .start_text_component(&header)
+            .spacing(Spacing::horz_vert(MARGIN, 8.0))
+            .font_size(body_font_size)
+            .tab_stops(amount_stops.clone())
+            .finalize_text_component()
+            // Item
+            .start_text_component(&item)
+            .spacing(Spacing::horz_vert(MARGIN, 8.0))
+            .font_size(body_font_size)
+            .tab_stops(amount_stops.clone())
+            .finalize_text_component()
+            // Total
+            .start_text_component(&format!("Summe\t{}", format::euro(total_ct)))
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 16.0, 4.0))
+            .font_size(40.0)
+            .bold(true)
+            .tab_stops(amount_stops.clone())
+            .finalize_text_component()
+            // VAT
+            .start_text_component(&vat)
+            .spacing(Spacing::horz_vert(MARGIN, 4.0))
+            .font_size(body_font_size)
+            .tab_stops(amount_stops)
+            .finalize_text_component()
+            // Payment method
+            .start_text_component(payment.as_deref().unwrap_or_default())
+            .spacing(Spacing::horz_vert(MARGIN, 4.0))
+            .font_size(body_font_size)
+            .visible(payment.is_some())
+            .finalize_text_component()
+            // Trailer
+            .start_text_component("Vielen Dank für Ihren Einkauf!")
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 24.0, 16.0))
+            .font_size(body_font_size)
+            .alignment(Alignment::Center)
+            .italic(true)
+            .finalize_text_component();
+
+        let receipt = builder.build_with_metadata()?;
+
+        Ok(receipt)
+    }
+}
@@ -0,0 +1,139 @@
+use super::{Certification, Error, Origin, Overrides, RenderProfile};
+use crate::{
+    db::{ProductEntry, SettingsEntry},
+    format,
+    voucher::{barcode, Alignment, Builder as VoucherBuilder, Metadata, Spacing, TextContext},
+};
+
+use image::{DynamicImage, GrayImage};
+
+/// The width of the thinnest bar of the barcode in pixels
+const BARCODE_MODULE_WIDTH: u32 = 3;
+
+/// The width of the thinnest bar for the heavy render profile (it must still fit 62 mm labels)
+const HEAVY_BARCODE_MODULE_WIDTH: u32 = 4;
+
+/// The height of the barcode in pixels
+const BARCODE_HEIGHT: u32 = 100;
+
+/// The price tag of a product on the display counter.
+/// Unlike the voucher, it has neither a weight nor an expiration date.
+pub struct ShelfLabel<'a> {
+    pub product: &'a ProductEntry,
+    pub settings: &'a SettingsEntry,
+}
+
+impl<'a> ShelfLabel<'a> {
+    pub fn new(product: &'a ProductEntry, settings: &'a SettingsEntry) -> Self {
+        Self { product, settings }
+    }
+
+    /// Build the price tag with the given width.
+    /// The text context is handed back, so fonts and glyphs can be reused for the next label.
+    pub fn build(
+        &self,
+        width: u32,
+        text_ctx: TextContext,
+    ) -> Result<(GrayImage, TextContext, Metadata), Error> {
+        let product = self.product;
+        let body_font_size = 25.0;
+
+        // Labels on dark packaging need thicker bars to stay scannable.
+        let heavy =
+            Overrides::parse(&product.voucher_overrides)?.render_profile == RenderProfile::Heavy;
+
+        let module_width = if heavy {
+            HEAVY_BARCODE_MODULE_WIDTH
+        } else {
+            BARCODE_MODULE_WIDTH
+        };
+
+        // Format the price per unit.
+        let euro = (product.price_ct as f64) / 100.0;
+
+        let price_str = if product.is_kg_price {
+            format!("{} / kg", format::euro(product.price_ct))
+        } else {
+            format::euro(product.price_ct)
+        };
+
+        // The barcode is derived from the ID, so unsaved products have none.
+        // The digits are printed below the bars.
+        let barcode = product.in_store_code().and_then(|code| {
+            let digits = barcode::ean13_digits(&code)?;
+            let image = barcode::ean13(&code, module_width, BARCODE_HEIGHT)?;
+
+            Some((
+                DynamicImage::ImageLuma8(image),
+                digits
+                    .iter()
+                    .map(|digit| digit.to_string())
+                    .collect::<String>(),
+            ))
+        });
+
+        // Construct the label.
+        let mut builder = VoucherBuilder::with_text_context(width, text_ctx)
+            .trim_empty_space(true)
+            .heavy(heavy)
+            // Product
+            .start_text_component(&product.name)
+            .spacing(Spacing::horz_vert(16.0, 16.0))
+            .font_size(50.0)
+            .alignment(Alignment::Center)
+            .bold(true)
+            .finalize_text_component()
+            // Price
+            .start_text_component(&price_str)
+            .spacing(Spacing::horz_vert(16.0, 12.0))
+            .font_size(60.0)
+            .alignment(Alignment::Center)
+            .bold(true)
+            .finalize_text_component();
+
+        // Comparison price
+        if product.is_kg_price && self.settings.show_price_per_100g {
+            let comparison_str = format!("Grundpreis: {} € / 100 g", format::fixed(euro / 10.0, 2));
+
+            builder = builder
+                .start_text_component(&comparison_str)
+                .spacing(Spacing::lrtb(16.0, 16.0, 0.0, 12.0))
+                .font_size(body_font_size)
+                .alignment(Alignment::Center)
+                .finalize_text_component();
+        }
+
+        // Organic certification
+        if let Some(control_code) = &product.organic_control_code {
+            let certification = Certification {
+                control_code,
+                origin: product.agriculture_origin.as_deref(),
+            };
+
+            builder = certification.add_to(builder, body_font_size);
+        }
+
+        // Origin
+        if let Some(origin) = Origin::of(product) {
+            builder = origin.add_to(builder, body_font_size);
+        }
+
+        // Barcode
+        if let Some((image, digits)) = &barcode {
+            builder = builder
+                .start_image_component(image)
+                .spacing(Spacing::lrtb(16.0, 16.0, 16.0, 0.0))
+                .alignment(Alignment::Center)
+                .finalize_image_component()
+                .start_text_component(digits)
+                .spacing(Spacing::lrtb(16.0, 16.0, 4.0, 16.0))
+                .font_size(body_font_size)
+                .alignment(Alignment::Center)
+                .finalize_text_component();
+        }
+
+        let label = builder.build_with_metadata()?;
+
+        Ok(label)
+    }
+}
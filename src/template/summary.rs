@@ -0,0 +1,291 @@
+use super::Error;
+use crate::{
+    db::{InfoEntry, PaymentMethod, PaymentTotal, SaleEntry, ShiftEntry},
+    format,
+    voucher::{Alignment, Builder as VoucherBuilder, Metadata, Spacing, TabStop, TextContext},
+};
+
+use chrono::{DateTime, Local};
+use image::GrayImage;
+
+/// The horizontal margin of all texts in pixels
+const MARGIN: f32 = 16.0;
+
+/// The sales of one product on the slip
+#[derive(Debug, PartialEq, Eq)]
+struct ProductTotal {
+    name: String,
+    count: u64,
+    total_ct: u64,
+}
+
+/// A compact summary of some sales, e.g. as an interim cash-up slip during the day.
+/// It lists the sales per product and per payment method.
+pub struct SummarySlip<'a> {
+    pub sales: &'a [SaleEntry],
+    pub info: &'a InfoEntry,
+    pub date: DateTime<Local>,
+
+    /// The closed shift of the sales, whose cash reconciliation is added
+    pub shift: Option<&'a ShiftEntry>,
+}
+
+impl<'a> SummarySlip<'a> {
+    pub fn new(sales: &'a [SaleEntry], info: &'a InfoEntry) -> Self {
+        Self {
+            sales,
+            info,
+            date: Local::now(),
+            shift: None,
+        }
+    }
+
+    /// Print the given date instead of the clock.
+    pub fn date(mut self, date: DateTime<Local>) -> Self {
+        self.date = date;
+        self
+    }
+
+    /// Print the slip as the end of a shift, with the counted cash.
+    pub fn shift(mut self, shift: &'a ShiftEntry) -> Self {
+        self.shift = Some(shift);
+        self
+    }
+
+    /// The sales per product, sorted by name
+    fn product_totals(sales: &[SaleEntry]) -> Vec<ProductTotal> {
+        let mut totals: Vec<ProductTotal> = Vec::new();
+
+        for sale in sales {
+            match totals.iter_mut().find(|total| total.name == sale.name) {
+                Some(total) => {
+                    total.count += 1;
+                    total.total_ct += sale.total_ct;
+                }
+
+                None => totals.push(ProductTotal {
+                    name: sale.name.clone(),
+                    count: 1,
+                    total_ct: sale.total_ct,
+                }),
+            }
+        }
+
+        totals.sort_by(|a, b| a.name.cmp(&b.name));
+        totals
+    }
+
+    /// The sales per payment method in the order of the dialog (sales without a method come last)
+    fn payment_totals(sales: &[SaleEntry]) -> Vec<PaymentTotal> {
+        PaymentMethod::ALL
+            .iter()
+            .copied()
+            .map(Some)
+            .chain([None])
+            .filter_map(|payment_method| {
+                let (count, total_ct) = sales
+                    .iter()
+                    .filter(|sale| sale.payment_method == payment_method)
+                    .fold((0, 0), |(count, total_ct), sale| {
+                        (count + 1, total_ct + sale.total_ct)
+                    });
+
+                (count > 0).then_some(PaymentTotal {
+                    payment_method,
+                    count,
+                    total_ct,
+                })
+            })
+            .collect()
+    }
+
+    /// Build the slip with the given width.
+    /// The text context is handed back, so fonts and glyphs can be reused for the next voucher.
+    pub fn build(
+        &self,
+        width: u32,
+        text_ctx: TextContext,
+    ) -> Result<(GrayImage, TextContext, Metadata), Error> {
+        let body_font_size = 25.0;
+
+        // The table has a column for the count and one for the total at the right edge.
+        let text_width = (width as f32) - (2.0 * MARGIN);
+        let table_stops = vec![
+            TabStop::Right(text_width * 0.65),
+            TabStop::Right(text_width),
+        ];
+
+        // The sales are loaded newest first, but the period reads forward.
+        let period = match (
+            self.sales.iter().map(|sale| sale.date).min(),
+            self.sales.iter().map(|sale| sale.date).max(),
+        ) {
+            (Some(first), Some(last)) => format!(
+                "{} – {}",
+                first.with_timezone(&Local).format("%d.%m.%Y %H:%M"),
+                last.with_timezone(&Local).format("%d.%m.%Y %H:%M")
+            ),
+
+            _ => String::from("Keine Verkäufe"),
+        };
+
+        let header = format!(
+            "{}\n{}\nGedruckt am {}",
+            if self.shift.is_some() {
+                "Schichtabschluss"
+            } else {
+                "Zwischenbericht"
+            },
+            period,
+            self.date.format("%d.%m.%Y %H:%M")
+        );
+
+        let mut products = String::from("Artikel\tAnz.\tSumme");
+
+        for total in Self::product_totals(self.sales) {
+            products.push_str(&format!(
+                "\n{}\t{}\t{}",
+                total.name,
+                total.count,
+                format::euro(total.total_ct)
+            ));
+        }
+
+        let payments: Vec<String> = Self::payment_totals(self.sales)
+            .iter()
+            .map(|total| {
+                format!(
+                    "{}\t{}\t{}",
+                    total
+                        .payment_method
+                        .map_or("Ohne Angabe", |method| method.name()),
+                    total.count,
+                    format::euro(total.total_ct)
+                )
+            })
+            .collect();
+
+        let sum = format!(
+            "Summe\t{}\t{}",
+            self.sales.len(),
+            format::euro(self.sales.iter().map(|sale| sale.total_ct).sum())
+        );
+
+        // The cash drawer of a closed shift
+        let cash = match self.shift.and_then(|shift| Some((shift, shift.closing?))) {
+            Some((shift, closing)) => format!(
+                "Kassenabgleich\nAnfangsbestand\t\t{}\nBareinnahmen\t\t{}\nSoll\t\t{}\nGezählt\t\t{}\nDifferenz\t\t{}",
+                format::euro(shift.float_ct),
+                format::euro(closing.expected_ct - shift.float_ct),
+                format::euro(closing.expected_ct),
+                format::euro(closing.counted_ct),
+                format::euro_difference(closing.difference_ct())
+            ),
+
+            None => String::new(),
+        };
+
+        let builder = VoucherBuilder::with_text_context(width, text_ctx)
+            .trim_empty_space(true)
+            // Business
+            .start_text_component(&self.info.business)
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 16.0, 4.0))
+            .font_size(40.0)
+            .alignment(Alignment::Center)
+            .bold(true)
+            .finalize_text_component()
+            // Title and period
+            .start_text_component(&header)
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 0.0, 16.0))
+            .font_size(body_font_size)
+            .alignment(Alignment::Center)
+            .finalize_text_component()
+            // Products
+            .start_text_component(&products)
+            .spacing(Spacing::horz_vert(MARGIN, 8.0))
+            .font_size(body_font_size)
+            .tab_stops(table_stops.clone())
+            .finalize_text_component()
+            // Total
+            .start_text_component(&sum)
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 16.0, 8.0))
+            .font_size(body_font_size)
+            .bold(true)
+            .tab_stops(table_stops.clone())
+            .finalize_text_component()
+            // Payment methods
+            .start_text_component(&payments.join("\n"))
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 4.0, 16.0))
+            .font_size(body_font_size)
+            .tab_stops(table_stops.clone())
+            .visible(!payments.is_empty())
+            .finalize_text_component()
+            // Cash reconciliation
+            .start_text_component(&cash)
+            .spacing(Spacing::lrtb(MARGIN, MARGIN, 0.0, 16.0))
+            .font_size(body_font_size)
+            .tab_stops(table_stops)
+            .visible(!cash.is_empty())
+            .finalize_text_component();
+
+        let slip = builder.build_with_metadata()?;
+
+        Ok(slip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{date, ProductBuilder, SaleBuilder};
+
+    #[test]
+    fn sales_are_summed_per_product_and_payment_method() {
+        let bread = ProductBuilder::new("Brot").price_ct(400).build();
+        let cheese = ProductBuilder::new("Käse").price_ct(2000).build();
+
+        let sales = [
+            SaleBuilder::new(&cheese, date(1, 9))
+                .weight_kg(0.25)
+                .payment_method(PaymentMethod::Card)
+                .build(),
+            SaleBuilder::new(&bread, date(1, 10)).build(),
+            SaleBuilder::new(&cheese, date(1, 11))
+                .weight_kg(0.5)
+                .payment_method(PaymentMethod::Card)
+                .build(),
+        ];
+
+        assert_eq!(
+            SummarySlip::product_totals(&sales),
+            [
+                ProductTotal {
+                    name: String::from("Brot"),
+                    count: 1,
+                    total_ct: sales[1].total_ct,
+                },
+                ProductTotal {
+                    name: String::from("Käse"),
+                    count: 2,
+                    total_ct: sales[0].total_ct + sales[2].total_ct,
+                },
+            ]
+        );
+
+        assert_eq!(
+            SummarySlip::payment_totals(&sales),
+            [
+                PaymentTotal {
+                    payment_method: Some(PaymentMethod::Card),
+                    count: 2,
+                    total_ct: sales[0].total_ct + sales[2].total_ct,
+                },
+                PaymentTotal {
+                    payment_method: None,
+                    count: 1,
+                    total_ct: sales[1].total_ct,
+                },
+            ]
+        );
+    }
+}
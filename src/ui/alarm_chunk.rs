@@ -0,0 +1,62 @@
+use super::App;
+
+use std::io::{self, Write};
+
+use chrono::TimeDelta;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::Paragraph,
+    Frame,
+};
+
+impl App {
+    /// Check if the scales have failed for too long while they are actually needed.
+    pub(super) fn is_scale_alarm_active(&self) -> bool {
+        let alarm_secs = self.engine.db().settings().scale_alarm_secs;
+
+        let Some(weight_error_date) = self.weight_error_date else {
+            return false;
+        };
+
+        // Without kg-priced products, nobody cares about the scales.
+        if (alarm_secs == 0) || !self.engine.db().products().iter().any(|p| p.is_kg_price) {
+            return false;
+        }
+
+        let alarm_window =
+            TimeDelta::try_seconds(alarm_secs as _).expect("Scale alarm window out of bound");
+
+        (self.now - weight_error_date) >= alarm_window
+    }
+
+    /// Beep periodically while the scale alarm is active (if enabled).
+    pub(super) fn beep_scale_alarm(&mut self) {
+        if !self.engine.db().settings().scale_alarm_beep || (self.scale_alarm_beep_date > self.now)
+        {
+            return;
+        }
+
+        self.scale_alarm_beep_date = self.now + TimeDelta::try_seconds(5).unwrap();
+
+        // The terminal rings its bell on BEL.
+        let mut stdout = io::stdout();
+        _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+    }
+
+    pub(super) fn draw_alarm_chunk<B: Backend>(&mut self, frame: &mut Frame<B>, chunk: Rect) {
+        let paragraph = Paragraph::new(Spans::from(Span::styled(
+            "Die Waage antwortet nicht! Bitte Verbindung und Stromversorgung prüfen.",
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        )))
+        .style(Style::default().bg(Color::Red))
+        .alignment(Alignment::Center);
+
+        frame.render_widget(paragraph, chunk);
+    }
+}
@@ -0,0 +1,61 @@
+use tui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+
+/// Rewrites a drawn frame for limited terminals (serial consoles, minimal SSH clients):
+/// Borders, lines and arrows become ASCII, and the bright colors become their basic variants.
+/// It is rendered last, so the chunks don't need to know about it.
+pub(super) struct AsciiFilter;
+
+impl AsciiFilter {
+    fn symbol(c: char) -> char {
+        match c {
+            '─' | '━' | '═' | '╌' | '╍' | '┄' | '┅' => '-',
+            '│' | '┃' | '║' | '╎' | '╏' | '┆' | '┇' => '|',
+
+            // Corners and junctions
+            '\u{2500}'..='\u{257f}' => '+',
+
+            // Gauges and bars
+            '\u{2580}'..='\u{259f}' => '#',
+
+            '←' | '⇦' => '<',
+            '→' | '⇨' | '▶' => '>',
+            '↑' | '⇧' => '^',
+            '↓' | '⇩' => 'v',
+            '•' => '*',
+            '…' => '.',
+            c => c,
+        }
+    }
+
+    fn color(color: Color) -> Color {
+        match color {
+            Color::LightRed => Color::Red,
+            Color::LightGreen => Color::Green,
+            Color::LightYellow => Color::Yellow,
+            Color::LightBlue => Color::Blue,
+            Color::LightMagenta => Color::Magenta,
+            Color::LightCyan => Color::Cyan,
+            Color::White | Color::DarkGray => Color::Gray,
+            Color::Rgb(..) | Color::Indexed(_) => Color::Reset,
+            color => color,
+        }
+    }
+}
+
+impl Widget for AsciiFilter {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+
+                if !cell.symbol.is_ascii() {
+                    let symbol: String = cell.symbol.chars().map(Self::symbol).collect();
+                    cell.set_symbol(&symbol);
+                }
+
+                cell.fg = Self::color(cell.fg);
+                cell.bg = Self::color(cell.bg);
+            }
+        }
+    }
+}
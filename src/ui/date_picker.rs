@@ -0,0 +1,188 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use crossterm::event::KeyCode;
+
+use tui::{
+    backend::Backend,
+    layout::{Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// The parts of a date that can be changed with `Up` and `Down`
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum DatePart {
+    Day,
+    Month,
+    Year,
+}
+
+/// What the operator has done with the picker
+pub(super) enum DatePickerOutcome {
+    /// The picker stays open.
+    Pending,
+
+    /// The range (both days inclusive) has been confirmed with `Enter`.
+    Picked { from: NaiveDate, until: NaiveDate },
+
+    /// The range has been removed, so all dates match again.
+    Cleared,
+
+    /// The picker has been closed with `Esc`.
+    Cancelled,
+}
+
+/// Pick a range of days with the keyboard instead of typing dates:
+/// `Left` and `Right` select the day, month or year of both dates, `Up` and `Down` change it.
+/// Common ranges are available as shortcuts.
+#[derive(Clone)]
+pub(super) struct DateRangePicker {
+    from: NaiveDate,
+    until: NaiveDate,
+
+    /// The selected part (0 to 2 for the start, 3 to 5 for the end)
+    cursor: usize,
+}
+
+impl DateRangePicker {
+    const PARTS: [DatePart; 3] = [DatePart::Day, DatePart::Month, DatePart::Year];
+
+    /// Start with the given range or with today.
+    pub(super) fn new(from: Option<NaiveDate>, until: Option<NaiveDate>, today: NaiveDate) -> Self {
+        Self {
+            from: from.unwrap_or(today),
+            until: until.unwrap_or(today),
+            cursor: 0,
+        }
+    }
+
+    /// Move a date by one day, month or year (or back).
+    /// Months and years keep the day if possible (e.g. 31.01. becomes 29.02. in leap years).
+    fn step(date: NaiveDate, part: DatePart, forward: bool) -> NaiveDate {
+        let stepped = match (part, forward) {
+            (DatePart::Day, true) => date.checked_add_days(Days::new(1)),
+            (DatePart::Day, false) => date.checked_sub_days(Days::new(1)),
+            (DatePart::Month, true) => date.checked_add_months(Months::new(1)),
+            (DatePart::Month, false) => date.checked_sub_months(Months::new(1)),
+            (DatePart::Year, true) => date.checked_add_months(Months::new(12)),
+            (DatePart::Year, false) => date.checked_sub_months(Months::new(12)),
+        };
+
+        stepped.unwrap_or(date)
+    }
+
+    fn change_selected(&mut self, forward: bool) {
+        let part = Self::PARTS[self.cursor % 3];
+
+        if self.cursor < 3 {
+            self.from = Self::step(self.from, part, forward);
+        } else {
+            self.until = Self::step(self.until, part, forward);
+        }
+    }
+
+    /// Handle a key. The shortcuts count from `today` back to the start of the week or month.
+    pub(super) fn handle_key(
+        &mut self,
+        code: KeyCode,
+        today: NaiveDate,
+        week_start: Weekday,
+    ) -> DatePickerOutcome {
+        match code {
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(5),
+            KeyCode::Up => self.change_selected(true),
+            KeyCode::Down => self.change_selected(false),
+
+            // Shortcuts for the usual reports
+            KeyCode::Char('h') => (self.from, self.until) = (today, today),
+            KeyCode::Char('w') => {
+                (self.from, self.until) = (today.week(week_start).first_day(), today)
+            }
+            KeyCode::Char('m') => (self.from, self.until) = (today.with_day(1).unwrap(), today),
+            KeyCode::Char('j') => (self.from, self.until) = (today.with_ordinal(1).unwrap(), today),
+
+            KeyCode::Backspace | KeyCode::Delete => return DatePickerOutcome::Cleared,
+            KeyCode::Esc => return DatePickerOutcome::Cancelled,
+
+            // A reversed range is meant the other way round.
+            KeyCode::Enter => {
+                return DatePickerOutcome::Picked {
+                    from: self.from.min(self.until),
+                    until: self.from.max(self.until),
+                }
+            }
+
+            _ => (),
+        }
+
+        DatePickerOutcome::Pending
+    }
+
+    /// The spans of a date with the selected part highlighted
+    fn date_spans(&self, date: NaiveDate, first_cursor: usize) -> Vec<Span<'static>> {
+        let style = |cursor: usize| {
+            if cursor == self.cursor {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White).bg(Color::Black)
+            }
+        };
+
+        let dot = || Span::styled(".", Style::default().fg(Color::White).bg(Color::Black));
+
+        vec![
+            Span::styled(format!("{:02}", date.day()), style(first_cursor)),
+            dot(),
+            Span::styled(format!("{:02}", date.month()), style(first_cursor + 1)),
+            dot(),
+            Span::styled(format!("{:04}", date.year()), style(first_cursor + 2)),
+        ]
+    }
+
+    pub(super) fn draw<B: Backend>(&self, frame: &mut Frame<B>, chunk: Rect, title: &str) {
+        // Build and render the block.
+        let block = Block::default()
+            .title(String::from(title))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::LightBlue).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        let label_style = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let help_style = Style::default().fg(Color::Gray).bg(Color::Black);
+
+        let mut range_spans = vec![Span::styled("Von ", label_style)];
+        range_spans.extend(self.date_spans(self.from, 0));
+        range_spans.push(Span::styled(" bis ", label_style));
+        range_spans.extend(self.date_spans(self.until, 3));
+
+        let lines = vec![
+            Spans::from(range_spans),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                "←/→ wählen, ↑/↓ ändern, Enter übernehmen, Entf alle Tage, Esc abbrechen",
+                help_style,
+            )),
+            Spans::from(Span::styled(
+                "'h' heute, 'w' diese Woche, 'm' dieser Monat, 'j' dieses Jahr",
+                help_style,
+            )),
+        ];
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, inner_chunk);
+    }
+}
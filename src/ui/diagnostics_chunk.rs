@@ -0,0 +1,234 @@
+use super::{label_type_description, App};
+use crate::format;
+
+use std::ops::Range;
+
+use chrono::Local;
+
+use tui::{
+    backend::Backend,
+    layout::{Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// Format bytes as space-separated hex (e.g. "04 05").
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Format bytes as quoted ASCII with placeholders for unprintable ones.
+fn ascii(bytes: &[u8]) -> String {
+    let text = bytes
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || (byte == b' ') {
+                byte as char
+            } else {
+                '.'
+            }
+        })
+        .collect::<String>();
+
+    format!("\"{}\"", text)
+}
+
+/// A diagnostics line (e.g. "Letzter Rahmen: 20 2d ...")
+fn line(label: &str, value: String) -> Spans<'static> {
+    Spans::from(vec![
+        Span::styled(
+            format!("{}: ", label),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(value, Style::default().fg(Color::Gray).bg(Color::Black)),
+    ])
+}
+
+/// A heading between the diagnostics of the devices
+fn heading(title: &str) -> Spans<'static> {
+    Spans::from(Span::styled(
+        String::from(title),
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    ))
+}
+
+impl App {
+    pub(super) fn draw_diagnostics_chunk<B: Backend>(&mut self, frame: &mut Frame<B>, chunk: Rect) {
+        // Build and render the block.
+        let block = Block::default()
+            .title("Diagnose")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::LightBlue).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        // The popup is redrawn with every event, so the values are live.
+        let mut lines = vec![heading("Waage")];
+
+        let scales = self.engine.scales();
+        let diagnostics = scales.diagnostics();
+
+        lines.push(line(
+            "Ergebnis",
+            match scales.weight() {
+                Ok(weight_kg) => format!("{} kg", weight_kg),
+                Err(err) => err.to_string(),
+            },
+        ));
+
+        match scales.protocol() {
+            Some(protocol) => lines.push(line("Protokoll", protocol.to_string())),
+            None => lines.push(line("Protokoll", String::from("emuliert"))),
+        }
+
+        match &diagnostics.last_frame {
+            Some(last_frame) => {
+                lines.push(line("Letzter Rahmen", hex(last_frame)));
+                lines.push(line("Als Text", ascii(last_frame)));
+
+                // Show the fields as the protocol sees them.
+                if let Some(protocol) = scales.protocol() {
+                    let field = |range: Option<Range<usize>>| {
+                        range
+                            .and_then(|range| last_frame.get(range))
+                            .map_or_else(|| String::from("-"), ascii)
+                    };
+
+                    lines.push(line(
+                        "Felder",
+                        format!(
+                            "Vorzeichen {}, Ziffern {}, Einheit {}",
+                            field(protocol.sign_offset.map(|offset| offset..(offset + 1))),
+                            field(Some(protocol.digits.clone())),
+                            field(protocol.unit.clone()),
+                        ),
+                    ));
+                }
+            }
+
+            None => lines.push(line("Letzter Rahmen", String::from("keiner"))),
+        }
+
+        let quality = &diagnostics.quality;
+
+        lines.push(line(
+            "Qualität",
+            format!(
+                "{} % fehlerhaft, Antwortzeit Ø {} ms / max. {} ms (letzte {} Antworten)",
+                format::fixed(quality.failure_percent(), 1),
+                quality.mean_latency().as_millis(),
+                quality.max_latency().as_millis(),
+                quality.sample_count()
+            ),
+        ));
+
+        lines.push(line(
+            "Zähler",
+            format!(
+                "Port {} Mal geöffnet, {} Neustart(s) nach Absturz",
+                diagnostics.port_opens, diagnostics.restarts
+            ),
+        ));
+
+        // Printer
+        lines.push(Spans::from(""));
+        lines.push(heading("Drucker"));
+
+        match self.engine.printer() {
+            Ok(printer) => {
+                let (in_addr, out_addr) = printer.endpoints();
+
+                lines.push(line(
+                    "Gerät",
+                    format!(
+                        "{} (Seriennummer {})",
+                        printer.model(),
+                        printer.serial_number()
+                    ),
+                ));
+
+                lines.push(line(
+                    "USB-Endpunkte",
+                    format!("Eingang {:#04x}, Ausgang {:#04x}", in_addr, out_addr),
+                ));
+
+                lines.push(line(
+                    "Letzter Status",
+                    printer
+                        .last_status_bytes()
+                        .map_or_else(|| String::from("keiner"), |status| hex(&status)),
+                ));
+
+                if let Some(status) = printer.last_status() {
+                    let mut status_str = format!(
+                        "{:?}, Phase {:?}, {}",
+                        status.status_type(),
+                        status.phase(),
+                        status.label().map_or_else(
+                            || String::from("kein Etikett"),
+                            |label| { label_type_description(label.ty) }
+                        )
+                    );
+
+                    if let Some(notification) = status.notification() {
+                        status_str.push_str(&format!(", Hinweis {:?}", notification));
+                    }
+
+                    lines.push(line("Dekodiert", status_str));
+                }
+            }
+
+            Err(err) => lines.push(line("Gerät", err.to_string())),
+        }
+
+        let error_flags_str = match self.engine.printer_error_flags() {
+            Some(flags) => format!("{:?}", flags),
+            None => String::from("unbekannt"),
+        };
+
+        lines.push(line("Fehler-Flags", error_flags_str));
+
+        let (reconnects, reconnect_failures) = self.engine.printer_reconnects();
+
+        lines.push(line(
+            "Zähler",
+            format!(
+                "{} Verbindungsversuch(e), davon {} fehlgeschlagen",
+                reconnects, reconnect_failures
+            ),
+        ));
+
+        if let Some(roll) = &self.label_roll {
+            lines.push(line(
+                "Etikettenrolle",
+                format!(
+                    "{}: ca. {} von {} m verbraucht, {} Druck(e) seit {}",
+                    roll.label_type,
+                    format::fixed(roll.used_mm / 1000.0, 2),
+                    format::fixed(roll.length_mm / 1000.0, 2),
+                    roll.print_count,
+                    roll.inserted.with_timezone(&Local).format("%d.%m.%Y")
+                ),
+            ));
+        }
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, inner_chunk);
+    }
+}
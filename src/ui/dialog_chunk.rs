@@ -1,5 +1,10 @@
 use super::{Action, App};
-use crate::db::ProductEntry;
+use crate::{
+    db::{PaymentMethod, ProductEntry},
+    format,
+};
+
+use chrono::{DateTime, Local, Utc};
 
 use tui::{
     backend::Backend,
@@ -16,15 +21,39 @@ pub enum DialogAction {
     Cancel,
 }
 
+/// The confirmation dialog of an action
+#[derive(Clone)]
+pub(super) struct Dialog {
+    pub(super) action: Action,
+    pub(super) product: Box<ProductEntry>,
+    pub(super) weight_kg: Option<f64>,
+    pub(super) piece_count: Option<u64>,
+    pub(super) duplicate_of: Option<DateTime<Utc>>,
+
+    /// Only asked for sales if enabled in the settings
+    pub(super) payment_method: Option<PaymentMethod>,
+
+    /// Lines of the voucher would be truncated (checked before anything is printed)
+    pub(super) voucher_warning: Option<String>,
+}
+
 impl App {
     pub(super) fn draw_dialog_chunk<B: Backend>(
         &mut self,
         frame: &mut Frame<B>,
         chunk: Rect,
-        action: Action,
-        product: &ProductEntry,
-        weight_kg: Option<f64>,
+        dialog: &Dialog,
     ) {
+        let Dialog {
+            action,
+            ref product,
+            weight_kg,
+            piece_count,
+            duplicate_of,
+            payment_method,
+            ref voucher_warning,
+        } = *dialog;
+
         // Build and render the block.
         let block = Block::default()
             .title("Aktion bestätigen")
@@ -39,12 +68,24 @@ impl App {
 
         frame.render_widget(block, chunk);
 
+        // A negative weight can only get here if the operator has to confirm it.
+        let is_underweight = weight_kg.is_some_and(|weight_kg| weight_kg < 0.0);
+        let can_override_expiration = self.can_override_expiration(action, product);
+
         // Split the block into message and actions.
         let vert_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
                 [
-                    Constraint::Min(self.actions_count() as _),
+                    Constraint::Min(
+                        (self.actions_count()
+                            + (duplicate_of.is_some() as usize)
+                            + (payment_method.is_some() as usize)
+                            + (action.sale as usize)
+                            + (can_override_expiration as usize)
+                            + (voucher_warning.is_some() as usize)
+                            + (is_underweight as usize)) as _,
+                    ),
                     Constraint::Length(2),
                 ]
                 .as_ref(),
@@ -55,18 +96,23 @@ impl App {
         let actions_chunk = vert_chunks[1];
 
         // Build the paragraph for the message.
-        let sale_str = if product.is_kg_price {
+        let price_ct = product.total_price_ct(
+            weight_kg,
+            piece_count,
+            self.engine.db().settings().rounding_mode,
+        );
+        let euro_str = format::euro(price_ct);
+
+        let sale_str = if action.shelf_label {
+            format!("Preisschild für {}", product.name)
+        } else if product.is_kg_price {
             let weight_kg = weight_kg.expect("Product with kg price needs weight");
-            let weight_str = format!("{:.3} kg", weight_kg).replacen('.', ",", 1);
-            let euro_per_kg = (product.price_ct as f64) / 100.0;
-            let euro = weight_kg * euro_per_kg;
-            let euro_str = format!("{:.2} €", euro).replacen('.', ",", 1);
+            let weight_str = self.engine.db().settings().format_weight(weight_kg);
 
             format!("{} {} für {}", weight_str, product.name, euro_str)
+        } else if let Some(piece_count) = piece_count {
+            format!("{} Stück {} für {}", piece_count, product.name, euro_str)
         } else {
-            let euro = (product.price_ct as f64) / 100.0;
-            let euro_str = format!("{:.2} €", euro).replacen('.', ",", 1);
-
             format!("{} für {}", product.name, euro_str)
         };
 
@@ -80,11 +126,35 @@ impl App {
                 Style::default().fg(Color::Gray).bg(Color::Black),
             )),
             Spans::from(Span::styled(
-                format!("Bon drucken: {}", if action.print { "ja" } else { "nein" }),
+                if action.print {
+                    format!(
+                        "Bon drucken: ja, {} Stück ('+'/'-' zum Ändern)",
+                        action.copies
+                    )
+                } else {
+                    String::from("Bon drucken: nein")
+                },
+                Style::default().fg(Color::Gray).bg(Color::Black),
+            )),
+            Spans::from(Span::styled(
+                format!(
+                    "Preisschild drucken: {}",
+                    if action.shelf_label { "ja" } else { "nein" }
+                ),
                 Style::default().fg(Color::Gray).bg(Color::Black),
             )),
         ];
 
+        if action.sale {
+            actions.push(Spans::from(Span::styled(
+                format!(
+                    "Kundenbeleg drucken: {} ('k' zum Umschalten)",
+                    if action.receipt { "ja" } else { "nein" }
+                ),
+                Style::default().fg(Color::Gray).bg(Color::Black),
+            )));
+        }
+
         if self.dump_voucher {
             actions.push(Spans::from(Span::styled(
                 format!("Bon dumpen: {}", if action.dump { "ja" } else { "nein" }),
@@ -92,6 +162,71 @@ impl App {
             )));
         }
 
+        if can_override_expiration {
+            let expiration_offset_days = action.expiration_offset_days;
+            let expiration_date = self
+                .expiration_override(product, expiration_offset_days)
+                .map(|date| date.with_timezone(&Local))
+                .or_else(|| product.expiration_date(self.now.with_timezone(&Local)))
+                .expect("Product has an expiration date");
+
+            let offset_str = match expiration_offset_days {
+                0 => String::new(),
+                1 | -1 => format!(" ({:+} Tag)", expiration_offset_days),
+                days => format!(" ({:+} Tage)", days),
+            };
+
+            // A moved date stands out, it is printed on the voucher.
+            let color = if expiration_offset_days == 0 {
+                Color::Gray
+            } else {
+                Color::White
+            };
+
+            actions.push(Spans::from(Span::styled(
+                format!(
+                    "Mindestens haltbar bis: {}{} ('<'/'>' zum Ändern)",
+                    expiration_date.format("%d.%m.%Y"),
+                    offset_str
+                ),
+                Style::default().fg(color).bg(Color::Black),
+            )));
+        }
+
+        if let Some(payment_method) = payment_method {
+            actions.push(Spans::from(Span::styled(
+                format!(
+                    "Zahlungsart: {} (Links/Rechts zum Wechseln)",
+                    payment_method.name()
+                ),
+                Style::default().fg(Color::White).bg(Color::Black),
+            )));
+        }
+
+        if let Some(duplicate_of) = duplicate_of {
+            actions.push(Spans::from(Span::styled(
+                format!(
+                    "Achtung: Derselbe Vorgang wurde vor {} Sekunden bereits bestätigt!",
+                    (self.now - duplicate_of).num_seconds()
+                ),
+                Style::default().fg(Color::LightRed).bg(Color::Black),
+            )));
+        }
+
+        if let Some(voucher_warning) = voucher_warning {
+            actions.push(Spans::from(Span::styled(
+                format!("{} Trotzdem drucken?", voucher_warning),
+                Style::default().fg(Color::LightRed).bg(Color::Black),
+            )));
+        }
+
+        if is_underweight {
+            actions.push(Spans::from(Span::styled(
+                "Achtung: Das Gewicht ist negativ (z. B. bei einer Rücknahme)!",
+                Style::default().fg(Color::LightRed).bg(Color::Black),
+            )));
+        }
+
         let paragraph = Paragraph::new(actions)
             .wrap(Wrap { trim: true })
             .alignment(Alignment::Center);
@@ -0,0 +1,160 @@
+#[cfg(feature = "gpio")]
+use crate::gpio::{self, ButtonBindings};
+
+use crate::{
+    gpio::ButtonAction,
+    printer::StatusErrorFlags,
+    update::{check_for_update, Version},
+    weight::WeightResult,
+};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossterm::event::{self, Event as InputEvent};
+
+/// Everything that can happen to the app.
+/// Each subsystem feeds its events into the bus, the app loop handles them one by one.
+pub(super) enum Event {
+    /// The terminal has reported a key press, resize, ...
+    Input(InputEvent),
+
+    /// A physical button has been pressed (only with the "gpio" feature).
+    #[cfg_attr(not(feature = "gpio"), allow(dead_code))]
+    Button(ButtonAction),
+
+    /// The periodic tick has elapsed.
+    Tick,
+
+    /// The scales have reported a new weight (or a new error).
+    Weight(WeightResult),
+
+    /// The printer status has been polled (`None` if it is unknown).
+    PrinterStatus(Option<StatusErrorFlags>),
+
+    /// The database has been modified and must be reloaded.
+    DbChanged,
+
+    /// A newer release than the running one exists.
+    UpdateAvailable(Version),
+
+    /// A background worker has failed and will not send events anymore.
+    WorkerFailed(String),
+}
+
+pub(super) struct EventBus {
+    sender: Sender<Event>,
+    receiver: Receiver<Event>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self { sender, receiver }
+    }
+
+    /// Obtain a sender to feed events into the bus (e.g. from a background worker).
+    pub fn sender(&self) -> Sender<Event> {
+        self.sender.clone()
+    }
+
+    pub fn send(&self, event: Event) {
+        // We hold the receiver ourselves, so this cannot fail.
+        _ = self.sender.send(event);
+    }
+
+    /// Block until the next event arrives.
+    pub fn recv(&self) -> Event {
+        // We hold a sender ourselves, so the channel cannot disconnect.
+        self.receiver.recv().unwrap()
+    }
+
+    /// Fetch the next event if there is one.
+    pub fn try_recv(&self) -> Option<Event> {
+        match self.receiver.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => unreachable!(),
+        }
+    }
+
+    /// Spawn a detached background worker that feeds events into the bus.
+    /// Workers should return as soon as sending fails because the app has shut down.
+    pub fn spawn_worker<F>(&self, f: F)
+    where
+        F: FnOnce(Sender<Event>) + Send + 'static,
+    {
+        let sender = self.sender();
+        thread::spawn(move || f(sender));
+    }
+
+    /// Forward the terminal input.
+    pub fn spawn_input_worker(&self) {
+        self.spawn_worker(|sender| loop {
+            let event = match event::read() {
+                Ok(input) => Event::Input(input),
+
+                Err(err) => {
+                    _ = sender.send(Event::WorkerFailed(format!(
+                        "Fehler beim Lesen der Eingabe: {}",
+                        err
+                    )));
+
+                    return;
+                }
+            };
+
+            if sender.send(event).is_err() {
+                return;
+            }
+        });
+    }
+
+    /// Forward the presses of the physical buttons.
+    #[cfg(feature = "gpio")]
+    pub fn spawn_button_worker(&self, bindings: ButtonBindings, active_low: bool) {
+        self.spawn_worker(move |sender| {
+            let result = gpio::watch_buttons(&bindings, active_low, |action| {
+                sender.send(Event::Button(action)).is_ok()
+            });
+
+            // The keyboard still works, so this is no reason to stop the app.
+            if let Err(err) = result {
+                eprintln!("Failed to watch the GPIO buttons: {err}");
+            }
+        });
+    }
+
+    /// Look for a newer release now and then once a day.
+    /// Failed checks (e.g. without internet access) are simply repeated the next day.
+    pub fn spawn_update_worker(&self) {
+        self.spawn_worker(|sender| loop {
+            if let Ok(Some(version)) = check_for_update() {
+                if sender.send(Event::UpdateAvailable(version)).is_err() {
+                    return;
+                }
+            }
+
+            thread::sleep(Duration::from_secs(24 * 60 * 60));
+        });
+    }
+
+    /// Provide the app with a periodic tick.
+    /// The interval (in ms) is read before every tick, so the app can change it at runtime.
+    pub fn spawn_tick_worker(&self, tick_ms: Arc<AtomicU64>) {
+        self.spawn_worker(move |sender| loop {
+            // A tick rate of 0 would keep the CPU busy.
+            thread::sleep(Duration::from_millis(
+                tick_ms.load(Ordering::Relaxed).max(10),
+            ));
+
+            if sender.send(Event::Tick).is_err() {
+                return;
+            }
+        });
+    }
+}
@@ -0,0 +1,148 @@
+use super::App;
+use crate::printer::LabelType;
+
+use tui::{
+    backend::Backend,
+    layout::{Margin, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// The changes of a product that are protected by the admin PIN
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(super) enum ProductEdit {
+    Price,
+
+    /// Store a copy under a new name
+    Duplicate,
+}
+
+/// What the text typed into the input popup is used for
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(super) enum InputPurpose {
+    DailyMessage,
+
+    /// Unlock the change of the product with this ID
+    AdminPin {
+        product_id: i64,
+        edit: ProductEdit,
+    },
+
+    /// The new price of the product with this ID
+    ProductPrice {
+        product_id: i64,
+    },
+
+    /// The name of the copy of the product with this ID
+    ProductCopyName {
+        product_id: i64,
+    },
+
+    /// The length of a new roll of labels (or the number of die-cut labels on it)
+    LabelRoll {
+        label_type: LabelType,
+    },
+
+    /// The number of recent sales on the summary slip (empty for all of the period)
+    SalesSummary,
+
+    /// The cash in the drawer when a shift is opened
+    ShiftFloat,
+
+    /// The cash in the drawer when the shift is closed
+    ShiftCount,
+}
+
+impl InputPurpose {
+    fn title(&self) -> &'static str {
+        match self {
+            InputPurpose::DailyMessage => "Tagesnachricht (leer lassen zum Löschen)",
+            InputPurpose::AdminPin { .. } => "Admin-PIN",
+            InputPurpose::ProductPrice { .. } => "Neuer Preis in € (z. B. 12,99)",
+            InputPurpose::ProductCopyName { .. } => "Name der Kopie",
+            InputPurpose::LabelRoll { label_type } => match label_type {
+                LabelType::DieCut { .. } => "Anzahl der Etiketten auf der neuen Rolle",
+                _ => "Länge der neuen Rolle in m (z. B. 30,48)",
+            },
+            InputPurpose::SalesSummary => {
+                "Zwischenbericht über die letzten … Verkäufe (leer lassen für den ganzen Zeitraum)"
+            }
+            InputPurpose::ShiftFloat => "Anfangsbestand der Kasse in € (z. B. 150,00)",
+            InputPurpose::ShiftCount => "Gezählter Kassenbestand in € (z. B. 412,50)",
+        }
+    }
+
+    /// Secret inputs are not shown on the screen.
+    fn is_secret(&self) -> bool {
+        matches!(self, InputPurpose::AdminPin { .. })
+    }
+}
+
+/// Parse a price in € with up to two decimals (e.g. "12,99", "1.234,50" or "12.99 €") into cents.
+pub(super) fn parse_price_ct(text: &str) -> Option<u64> {
+    let text = text.trim_end_matches('€').trim();
+
+    // With a decimal comma, dots can only separate thousands.
+    let text = if text.contains(',') {
+        text.replace('.', "").replace(',', ".")
+    } else {
+        String::from(text)
+    };
+
+    let (euro_str, cent_str) = text.split_once('.').unwrap_or((&text, ""));
+
+    if euro_str.is_empty() || !euro_str.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    if (cent_str.len() > 2) || !cent_str.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let euro: u64 = euro_str.parse().ok()?;
+    let cent: u64 = format!("{:0<2}", cent_str).parse().ok()?;
+
+    euro.checked_mul(100)
+        .and_then(|price_ct| price_ct.checked_add(cent))
+}
+
+impl App {
+    pub(super) fn draw_input_chunk<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        chunk: Rect,
+        purpose: InputPurpose,
+        text: &str,
+    ) {
+        // Build and render the block.
+        let block = Block::default()
+            .title(purpose.title())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::LightBlue).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        // Build the paragraph for the text, followed by a cursor.
+        let text = if purpose.is_secret() {
+            "*".repeat(text.chars().count())
+        } else {
+            String::from(text)
+        };
+
+        let paragraph = Paragraph::new(Spans::from(vec![
+            Span::styled(text, Style::default().fg(Color::White).bg(Color::Black)),
+            Span::styled("_", Style::default().fg(Color::Green).bg(Color::Black)),
+        ]))
+        .wrap(Wrap { trim: false });
+
+        frame.render_widget(paragraph, inner_chunk);
+    }
+}
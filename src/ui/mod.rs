@@ -1,24 +1,45 @@
 use crate::{
-    db::{Database, ProductEntry, SaleEntry},
-    printer::{AttachError, LabelType, Model as PrinterModel, PrintError, Printer},
-    voucher::{
-        Alignment as VoucherAlignment, Builder as VoucherBuilder, Spacing as VoucherSpacing,
+    db::{
+        Attribution, Database, LabelRollEntry, PaymentMethod, PrintJobEntry, ProductEntry,
+        ProductError, RedrawStrategy, SaleEntry, SaleFilter, SaleJournal, SalesSummary,
+        SettingsEntry, ShiftEntry, UiCharset, UiTheme, ValidationError, MAX_COPIES,
+    },
+    format::{self, NumberFormat},
+    gpio::ButtonAction,
+    pos::{
+        ComponentState, Engine, Error as PosError, HealthEndpoint, InterruptedSale, Permissions,
+        Preflight, PreflightCheck, StorageLevel, Watchdog, WeightCheck,
+    },
+    printer::{Label, LabelType, PrintError, Printer, StatusErrorFlags},
+    template::{
+        self, CustomerReceipt, Error as TemplateError, Preset, ProductVoucher, ShelfLabel,
+        SummarySlip,
+    },
+    update::Version,
+    voucher::{Metadata as VoucherMetadata, TextContext as VoucherTextContext},
+    weight::{
+        Error as WeightError, FlowControl as ScaleFlowControl, LineConfig as ScaleLineConfig,
+        PieceCounter, Protocol as ScaleProtocol, Rs485, Scales, WeightResult,
     },
-    weight::{Scales, WeightResult},
 };
 
+use std::collections::VecDeque;
 use std::error::Error;
 use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-use chrono::{DateTime, TimeDelta, Utc};
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Utc};
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
-use image::{io::Reader as ImageReader, GrayImage, ImageFormat};
+use image::{GrayImage, ImageFormat};
 
 use tui::{
     backend::{Backend, CrosstermBackend},
@@ -27,24 +48,107 @@ use tui::{
     Frame, Terminal,
 };
 
+mod alarm_chunk;
+
+mod ascii;
+use ascii::AsciiFilter;
+
+mod date_picker;
+use date_picker::{DatePickerOutcome, DateRangePicker};
+
+mod diagnostics_chunk;
+
 mod dialog_chunk;
-use dialog_chunk::DialogAction;
+use dialog_chunk::{Dialog, DialogAction};
+
+mod event;
+use event::{Event as AppEvent, EventBus};
+
+mod input_chunk;
+use input_chunk::{parse_price_ct, InputPurpose, ProductEdit};
 
 mod message_chunk;
 use message_chunk::MessageType;
 
+mod monochrome;
+use monochrome::MonochromeFilter;
+
+mod palette_chunk;
+use palette_chunk::matching_commands;
+
+mod preflight_chunk;
+
+mod print_job_chunk;
+
 mod product_chunk;
 
+mod recovery_chunk;
+
+mod reprint_chunk;
+
 mod sale_chunk;
 
+mod sales_history_chunk;
+
+mod screensaver_chunk;
+
 mod status_chunk;
 
+/// The name of the summary slip in the print history
+const SUMMARY_SLIP_NAME: &str = "Zwischenbericht";
+
+/// Describe a label type in the way it is printed on the roll packaging.
+fn label_type_description(ty: LabelType) -> String {
+    match ty {
+        LabelType::Continuous { width } => format!("{} mm Endlos-Etikett", width),
+        LabelType::DieCut { width, length } => {
+            format!("{}x{} mm Einzeletikett", width, length)
+        }
+        // The 3.5 mm tape is reported as 4 mm.
+        LabelType::Tape { width: 4 } => String::from("3,5 mm Schriftband"),
+        LabelType::Tape { width } => format!("{} mm Schriftband", width),
+    }
+}
+
+/// Point the operator at text components whose lines have been cut off (if any).
+fn truncation_warning(metadata: &VoucherMetadata) -> Option<String> {
+    let components = metadata
+        .truncated_components()
+        .map(|component| {
+            format!(
+                "{} ({} Zeile(n))",
+                component.description, component.truncated_lines
+            )
+        })
+        .collect::<Vec<_>>();
+
+    (!components.is_empty()).then(|| {
+        format!(
+            "Achtung: Auf dem Bon wurden Zeilen gekürzt: {}",
+            components.join(", ")
+        )
+    })
+}
+
+/// Log the likely layout mistakes the linter has found on a voucher.
+fn log_layout_issues(metadata: &VoucherMetadata) {
+    for (component, issue) in metadata.issues() {
+        eprintln!("Layout issue in {}: {}", component.description, issue);
+    }
+}
+
+/// What receives the keys: A chunk of the main screen or the topmost popup
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum Focus {
     Product,
     Sale,
     Dialog,
     Message,
+    Input,
+    DatePicker,
+    Recovery,
+    Reprint,
+    Palette,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -59,40 +163,338 @@ enum Navigation {
 struct Action {
     pub sale: bool,
     pub print: bool,
+    pub shelf_label: bool,
     pub dump: bool,
+
+    /// Print a receipt for the customer after the sale (toggled in the dialog)
+    pub receipt: bool,
+
+    /// The number of vouchers to print (changed in the dialog)
+    pub copies: u64,
+
+    /// The days the best-before date is moved by (changed in the dialog, see `expiration_override_days`)
+    pub expiration_offset_days: i64,
+}
+
+impl Action {
+    /// The permission that is needed to perform the action (none for dumps)
+    fn permission(&self) -> Option<Permissions> {
+        if self.sale {
+            Some(Permissions::SELL)
+        } else if self.print || self.shelf_label {
+            Some(Permissions::PRINT_ONLY)
+        } else {
+            None
+        }
+    }
+}
+
+/// What is printed for a product
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Printout {
+    /// The voucher of a product, dated like its sale (if there is one).
+    /// The best-before date might have been set by the operator.
+    Voucher {
+        sale_date: Option<DateTime<Utc>>,
+        expiration_override: Option<DateTime<Utc>>,
+    },
+
+    /// The price tag for the display counter
+    ShelfLabel,
+
+    /// The receipt for the customer of a sale
+    Receipt {
+        number: u64,
+        payment_method: Option<PaymentMethod>,
+    },
+}
+
+impl Printout {
+    /// The date of the sale that the voucher belongs to
+    fn sale_date(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Printout::Voucher { sale_date, .. } => *sale_date,
+            _ => None,
+        }
+    }
+
+    /// The permission that is needed to print it (printouts of a sale are covered by selling)
+    fn permission(&self) -> Permissions {
+        match self {
+            Printout::Voucher {
+                sale_date: Some(_), ..
+            }
+            | Printout::Receipt { .. } => Permissions::SELL,
+            _ => Permissions::PRINT_ONLY,
+        }
+    }
+}
+
+/// How a printout is laid out for the label in the printer
+#[derive(Copy, Clone)]
+struct VoucherLayout {
+    /// The printable width in dots (without the margins)
+    width: u32,
+
+    /// The resolution of the printer (`None` if the voucher is not printed)
+    dpi: Option<u32>,
+
+    /// The preset of the label (with the margins taken off its length)
+    preset: Preset,
+}
+
+/// The last confirmed action, used to detect accidental repetitions
+struct ConfirmedAction {
+    date: DateTime<Utc>,
+    product_name: String,
+    weight_kg: Option<f64>,
+}
+
+/// A sale that has been performed in express mode and can still be undone
+struct ExpressSale {
+    sale: SaleEntry,
+    deadline: DateTime<Utc>,
+}
+
+/// The pre-packing of many portions of the same product.
+/// Every stable weight prints a voucher (and optionally performs a sale) without a dialog.
+struct Batch {
+    product: Box<ProductEntry>,
+    sale: bool,
+    count: u64,
+    total_ct: u64,
+
+    /// Has the last portion been removed from the scales?
+    armed: bool,
+
+    /// Truncated lines on the voucher have been pointed out (they affect every portion)
+    truncation_confirmed: bool,
+}
+
+/// A voucher to print (again) later, e.g. after a recoverable printer error
+#[derive(Clone)]
+struct PendingPrint {
+    product: Box<ProductEntry>,
+    weight_kg: Option<f64>,
+    piece_count: Option<u64>,
+    printout: Printout,
+
+    /// The copies are printed as one job (and retried together).
+    copies: u64,
+
+    /// The job has failed while the first of the copies was printing, so it might be on the label already.
+    /// The operator is asked before it is printed again.
+    partially_printed: bool,
 }
 
 #[derive(Clone)]
 enum Popup {
-    Dialog {
-        action: Action,
-        product: ProductEntry,
-        weight_kg: Option<f64>,
-    },
+    Dialog(Dialog),
 
     Message {
         ty: MessageType,
         text: String,
     },
+    Input {
+        purpose: InputPurpose,
+        text: String,
+    },
+    PrintJobs {
+        jobs: Vec<PrintJobEntry>,
+    },
+    SalesHistory {
+        filter: SaleFilter,
+        sales: Vec<SaleEntry>,
+        summary: SalesSummary,
+    },
+    Diagnostics,
+    Preflight {
+        preflight: Preflight,
+    },
+
+    /// Pick the dates of the sales history (which is below and shown again afterwards)
+    SalesDates {
+        picker: DateRangePicker,
+    },
+
+    /// Ask how to resolve a sale whose voucher or booking has been interrupted
+    Recovery {
+        interrupted: InterruptedSale,
+    },
+
+    /// Ask if a voucher that might have been printed partially is printed again
+    Reprint {
+        pending: PendingPrint,
+    },
+
+    /// Search all actions by name and perform one of them
+    Palette {
+        query: String,
+        selected: usize,
+    },
+}
+
+impl Popup {
+    /// How the keys are handled while the popup is on top
+    fn focus(&self) -> Focus {
+        match self {
+            Popup::Dialog(_) => Focus::Dialog,
+            Popup::Input { .. } => Focus::Input,
+            Popup::SalesDates { .. } => Focus::DatePicker,
+            Popup::Recovery { .. } => Focus::Recovery,
+            Popup::Reprint { .. } => Focus::Reprint,
+            Popup::Palette { .. } => Focus::Palette,
+            Popup::Message { .. }
+            | Popup::PrintJobs { .. }
+            | Popup::SalesHistory { .. }
+            | Popup::Diagnostics
+            | Popup::Preflight { .. } => Focus::Message,
+        }
+    }
+
+    /// Is this the same kind of popup (which is only open once)?
+    fn is_same_kind(&self, other: &Popup) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
 }
 
 pub struct App {
     now: DateTime<Utc>,
-    db: Database,
-    scales: Scales,
-    printer: Result<Printer, AttachError>,
+    events: EventBus,
+    engine: Engine,
+    watchdog: Watchdog,
+    weight: WeightResult,
+    held_weight: Option<f64>,
+
+    /// The raw response of the scales behind the held weight
+    held_frame: Option<Vec<u8>>,
+    piece_counter: Option<PieceCounter>,
+    batch: Option<Batch>,
+    weight_changed_date: DateTime<Utc>,
+
+    /// When a key or button has been pressed the last time
+    input_date: DateTime<Utc>,
+    weight_error_date: Option<DateTime<Utc>>,
+    scale_alarm_beep_date: DateTime<Utc>,
     reconnect_printer_date: DateTime<Utc>,
+    poll_printer_date: DateTime<Utc>,
+    check_storage_date: DateTime<Utc>,
+    check_db_date: DateTime<Utc>,
+    check_health_date: DateTime<Utc>,
+    health_endpoint: Option<HealthEndpoint>,
+
+    /// Diagnostics of the startup, printed once the terminal has been restored
+    startup_log: Vec<String>,
+    db_data_version: Option<u64>,
+    tick_ms: Arc<AtomicU64>,
+
+    /// When the settings have been reloaded and how many of them have changed (shown for a while)
+    settings_reload: Option<(DateTime<Utc>, usize)>,
+    voucher_text_ctx: Option<VoucherTextContext>,
+    last_confirmed_action: Option<ConfirmedAction>,
+    express_sale: Option<ExpressSale>,
+    pending_prints: VecDeque<PendingPrint>,
+
+    /// The last successful print, which can be repeated
+    last_print: Option<PendingPrint>,
+    interrupted_sales: VecDeque<InterruptedSale>,
+
+    /// The estimated usage of the inserted label roll (once it is known)
+    label_roll: Option<LabelRollEntry>,
+
+    /// The shift that is open at the cash drawer (if any)
+    shift: Option<ShiftEntry>,
     dump_voucher: bool,
-    focus: Focus,
-    popup: Option<Popup>,
+    read_only: bool,
+    voucher_language: Option<String>,
+    available_update: Option<Version>,
+
+    /// The focused chunk of the main screen (kept while popups are open)
+    main_focus: Focus,
+
+    /// The open popups from bottom to top.
+    /// Only the topmost one is focused, closing it returns to the one below.
+    popups: Vec<Popup>,
     product_list_state: ListState,
     action_list_state: ListState,
     dialog_list_state: ListState,
+    needs_redraw: bool,
+    drawn_date: DateTime<Utc>,
+    is_scale_alarm_drawn: bool,
+
+    /// The step of the screensaver on the screen (if it is shown)
+    screensaver_drawn_step: Option<i64>,
 }
 
 impl App {
     fn actions_count(&self) -> usize {
-        3 + if self.dump_voucher { 1 } else { 0 }
+        4 + if self.can_dump_voucher() { 1 } else { 0 }
+    }
+
+    /// Check if sales, prints and changes of the DB are disabled (via CLI or settings).
+    fn is_read_only(&self) -> bool {
+        self.read_only || self.engine.db().settings().read_only
+    }
+
+    /// Dumping is the only way to see a voucher in read-only mode.
+    fn can_dump_voucher(&self) -> bool {
+        self.dump_voucher || self.is_read_only()
+    }
+
+    /// The language of the next voucher (chosen by the operator or configured)
+    fn active_voucher_language(&self) -> &str {
+        self.voucher_language
+            .as_deref()
+            .unwrap_or(&self.engine.db().settings().voucher_language)
+    }
+
+    /// Switch to the next language that products have been translated to.
+    /// After the last one, the original texts are used again.
+    fn cycle_voucher_language(&mut self) {
+        let mut languages = self.engine.db().translation_languages();
+
+        if languages.is_empty() {
+            return;
+        }
+
+        languages.insert(0, String::new());
+
+        let next_idx = languages
+            .iter()
+            .position(|language| language == self.active_voucher_language())
+            .map_or(0, |idx| (idx + 1) % languages.len());
+
+        self.voucher_language = Some(languages.swap_remove(next_idx));
+    }
+
+    /// Tell the operator that an action is not possible in read-only mode.
+    fn reject_read_only(&mut self, what: &str) {
+        self.show_message(
+            MessageType::Error,
+            format!("{} ist im Nur-Lese-Modus nicht möglich.", what),
+        );
+    }
+
+    /// Tell the operator that their role does not permit an action.
+    fn reject_permission(&mut self, what: &str) {
+        self.show_message(
+            MessageType::Error,
+            format!(
+                "{} ist für die Rolle \"{}\" nicht freigegeben.",
+                what,
+                self.engine.role().name()
+            ),
+        );
+    }
+
+    /// Ask the engine if the operator may perform an action and explain it if not.
+    fn is_permitted(&mut self, permission: Permissions, what: &str) -> bool {
+        if self.engine.authorize(permission).is_err() {
+            self.reject_permission(what);
+            return false;
+        }
+
+        true
     }
 
     fn on_startup(&mut self) -> Result<(), Box<dyn Error>> {
@@ -105,485 +507,3014 @@ impl App {
         // Try to connect to the printer.
         self.reconnect_printer()?;
 
-        Ok(())
-    }
-
-    fn on_tick(&mut self) -> Result<(), Box<dyn Error>> {
-        // Check if we should reconnect the printer.
-        if self.reconnect_printer_date <= self.now {
-            self.reconnect_printer()?;
+        // Store the sales that could not be written to the DB during the last run.
+        // A read-only DB is not touched, the journal waits for the next regular run.
+        if !self.is_read_only() {
+            self.replay_journal();
         }
 
-        Ok(())
-    }
+        // Report all missing parts at once instead of failing the first sale.
+        self.run_preflight();
 
-    fn weight(&self) -> WeightResult {
-        self.scales.weight()
-    }
+        // Warn about a roll that is running out before the first print.
+        self.load_label_roll();
 
-    fn reconnect_printer(&mut self) -> Result<(), Box<dyn Error>> {
-        // Ensure that the old printer is dropped first!
-        self.printer = Err(AttachError::NoPrinter);
-
-        // Now try to reattach it.
-        let model_filter = self
-            .db
-            .info()
-            .printer_model
-            .as_deref()
-            .map(PrinterModel::try_from)
-            .transpose()?;
+        // A shift might have been left open by the last run.
+        match self.engine.db().current_shift() {
+            Ok(shift) => self.shift = shift,
+            Err(err) => self
+                .startup_log
+                .push(format!("Failed to load the shift: {err}")),
+        }
 
-        self.printer = Printer::attach(model_filter);
+        // Vouchers and sales of the last run might not match after a crash.
+        // They are resolved one by one as soon as no other popup is open.
+        if !self.is_read_only() {
+            self.find_interrupted_sales();
+        }
 
-        if self.printer.is_ok() {
-            self.reconnect_printer_date = self.now + TimeDelta::try_seconds(120).unwrap();
-        } else {
-            self.reconnect_printer_date = self.now + TimeDelta::try_seconds(10).unwrap();
+        // Tell the service manager that we are up.
+        if let Err(err) = self.watchdog.ready() {
+            self.startup_log
+                .push(format!("Failed to notify the service manager: {err}"));
         }
 
         Ok(())
     }
 
-    fn selected_product_idx(&self) -> Option<usize> {
-        self.product_list_state.selected()
-    }
+    fn run_preflight(&mut self) {
+        // The fonts are loaded for the check anyway, so the first voucher can reuse them.
+        let text_ctx = self.voucher_text_ctx.take().unwrap_or_default();
+        let preflight = self.engine.preflight(Path::new("logo.png"), &text_ctx);
 
-    fn selected_product(&self) -> Option<&ProductEntry> {
-        self.selected_product_idx()
-            .map(|idx| &self.db.products()[idx])
-    }
+        // Rasterize the common glyphs, so the first voucher of the day is printed quickly.
+        // Vouchers cannot be built without logo or fonts, so this waits for the next start.
+        let can_warm_up = [PreflightCheck::Logo, PreflightCheck::Fonts]
+            .into_iter()
+            .all(|check| preflight.state(check).is_some_and(ComponentState::is_ok));
 
-    fn reset_selected_product_idx(&mut self) {
-        let idx = if self.db.products().is_empty() {
-            None
+        self.voucher_text_ctx = if can_warm_up {
+            self.warm_up_vouchers(text_ctx)
         } else {
-            Some(0)
+            Some(text_ctx)
         };
 
-        self.product_list_state.select(idx);
-    }
+        if preflight.is_ok() {
+            return;
+        }
 
-    fn select_previous_product(&mut self) {
-        if let Some(product_idx) = self.selected_product_idx() {
-            if product_idx > 0 {
-                self.product_list_state.select(Some(product_idx - 1));
-            }
+        self.startup_log.push(format!("Preflight: {preflight}"));
+
+        // Errors of the journal replay are more urgent, the preflight is logged anyway.
+        if let Some(Popup::Message {
+            ty: MessageType::Error,
+            ..
+        }) = self.popup()
+        {
+            return;
         }
+
+        self.push_popup(Popup::Preflight { preflight });
     }
 
-    fn select_next_product(&mut self) {
-        if let Some(product_idx) = self.selected_product_idx() {
-            if product_idx < (self.db.products().len() - 1) {
-                self.product_list_state.select(Some(product_idx + 1));
+    /// Build a throwaway voucher with the text context (which is lost if that fails).
+    fn warm_up_vouchers(&mut self, text_ctx: VoucherTextContext) -> Option<VoucherTextContext> {
+        let db = self.engine.db();
+
+        // TODO: Use the width of the loaded label.
+        match template::warm_up(db.info(), db.settings(), 720, text_ctx) {
+            Ok(text_ctx) => Some(text_ctx),
+
+            Err(err) => {
+                self.startup_log
+                    .push(format!("Failed to warm up the voucher fonts: {err}"));
+                None
             }
         }
     }
 
-    fn selected_action(&self) -> Action {
-        match self.action_list_state.selected().unwrap() {
-            0 => Action {
-                sale: true,
-                print: true,
-                dump: false,
-            },
-            1 => Action {
-                sale: true,
-                print: false,
-                dump: false,
-            },
-            2 => Action {
-                sale: false,
-                print: true,
-                dump: false,
-            },
-            3 => Action {
-                sale: false,
-                print: false,
-                dump: true,
-            },
+    fn replay_journal(&mut self) {
+        match self.engine.replay_journal() {
+            Ok(0) => (),
 
-            _ => unreachable!(),
+            Ok(count) => self.show_message(
+                MessageType::Info,
+                format!(
+                    "{} zwischengespeicherte Verkäufe wurden in die Datenbank übernommen.",
+                    count
+                ),
+            ),
+
+            Err(err) => self.show_message(
+                MessageType::Error,
+                format!(
+                    "Fehler beim Übernehmen der zwischengespeicherten Verkäufe: {}",
+                    err
+                ),
+            ),
         }
     }
 
-    fn select_previous_action(&mut self) {
-        let idx = self.action_list_state.selected().unwrap();
-
-        if idx > 0 {
-            self.action_list_state.select(Some(idx - 1));
+    fn find_interrupted_sales(&mut self) {
+        match self.engine.interrupted_sales() {
+            Ok(interrupted_sales) => self.interrupted_sales = interrupted_sales.into(),
+            Err(err) => self
+                .startup_log
+                .push(format!("Failed to look for interrupted sales: {err}")),
         }
     }
 
-    fn select_next_action(&mut self) {
-        let idx = self.action_list_state.selected().unwrap();
+    /// Ask for the next interrupted sale unless another popup is open.
+    fn show_interrupted_sale(&mut self) {
+        if !self.popups.is_empty() {
+            return;
+        }
 
-        if idx < (self.actions_count() - 1) {
-            self.action_list_state.select(Some(idx + 1));
+        if let Some(interrupted) = self.interrupted_sales.pop_front() {
+            self.push_popup(Popup::Recovery { interrupted });
+            self.needs_redraw = true;
         }
     }
 
-    fn selected_dialog_action(&mut self) -> DialogAction {
-        match self.dialog_list_state.selected().unwrap() {
-            0 => DialogAction::Confirm,
-            1 => DialogAction::Cancel,
+    /// Resolve an interrupted sale: `n` reprints the voucher of an unprinted sale,
+    /// `s` voids it (or notes that the voucher of an unbooked sale has been thrown away).
+    /// `Esc` postpones it to the next start.
+    fn handle_recovery_key(&mut self, code: KeyCode) -> Result<(), Box<dyn Error>> {
+        let Some(Popup::Recovery { interrupted }) = self.popup() else {
+            panic!("Recovery is focused, but not present.");
+        };
 
-            _ => unreachable!(),
-        }
-    }
+        let interrupted = interrupted.clone();
 
-    fn select_previous_dialog_action(&mut self) {
-        let idx = self.dialog_list_state.selected().unwrap();
+        match (code, &interrupted) {
+            (KeyCode::Char('n'), InterruptedSale::Unprinted(sale)) => {
+                self.pop_popup();
 
-        if idx > 0 {
-            self.dialog_list_state.select(Some(idx - 1));
+                let Some(product) = self
+                    .engine
+                    .db()
+                    .products()
+                    .iter()
+                    .find(|product| product.name == sale.name)
+                    .cloned()
+                else {
+                    self.interrupted_sales.push_front(interrupted.clone());
+                    self.show_message(
+                        MessageType::Error,
+                        format!(
+                            "Das Produkt \"{}\" existiert nicht mehr. Der Verkauf kann nur storniert werden.",
+                            sale.name
+                        ),
+                    );
+
+                    return Ok(());
+                };
+
+                let printout = Printout::Voucher {
+                    sale_date: Some(sale.date),
+                    expiration_override: sale.expiration_override,
+                };
+                let pending_count = self.pending_prints.len();
+
+                if self.print_voucher(
+                    &product,
+                    sale.weight_kg,
+                    sale.piece_count,
+                    printout,
+                    1,
+                    true,
+                )? && (self.pending_prints.len() == pending_count)
+                {
+                    self.show_message(
+                        MessageType::Info,
+                        format!("Der Bon für \"{}\" wurde nachgedruckt.", sale.name),
+                    );
+                }
+            }
+
+            (KeyCode::Char('s'), InterruptedSale::Unprinted(sale)) => {
+                self.pop_popup();
+
+                if !self.is_permitted(Permissions::VOID, "Das Stornieren von Verkäufen") {
+                    self.interrupted_sales.push_front(interrupted.clone());
+                    return Ok(());
+                }
+
+                let text = if self.engine.void_sale(sale)? {
+                    format!(
+                        "Der Verkauf von \"{}\" über {} wurde storniert.",
+                        sale.name,
+                        format::euro(sale.total_ct)
+                    )
+                } else {
+                    format!("Der Verkauf von \"{}\" wurde nicht gefunden.", sale.name)
+                };
+
+                self.show_message(MessageType::Info, text);
+            }
+
+            (
+                KeyCode::Char('s'),
+                InterruptedSale::Unbooked {
+                    sale_date,
+                    product_name,
+                },
+            ) => {
+                self.pop_popup();
+                self.engine
+                    .discard_voucher(self.now, *sale_date, product_name.clone())?;
+            }
+
+            (KeyCode::Esc, _) => {
+                self.pop_popup();
+            }
+
+            _ => (),
         }
+
+        Ok(())
     }
 
-    fn select_next_dialog_action(&mut self) {
-        let idx = self.dialog_list_state.selected().unwrap();
+    /// Reload the DB if another program has changed it since the last check.
+    fn check_db_changes(&mut self) {
+        let data_version = match self.engine.db().data_version() {
+            Ok(data_version) => data_version,
 
-        if idx < (2 - 1) {
-            self.dialog_list_state.select(Some(idx + 1));
+            // The health check reports a broken DB.
+            Err(_) => return,
+        };
+
+        if self
+            .db_data_version
+            .replace(data_version)
+            .is_some_and(|last| last != data_version)
+        {
+            self.events.send(AppEvent::DbChanged);
         }
     }
 
-    fn show_message(&mut self, ty: MessageType, text: String) {
-        self.popup = Some(Popup::Message { ty, text });
-        self.focus = Focus::Message;
-    }
+    /// Reload everything from the DB and apply the settings that are used outside of the DB.
+    /// Changed settings are announced in the status bar.
+    fn reload_db(&mut self) -> Result<(), Box<dyn Error>> {
+        let old_settings = self.engine.db().settings().entries();
 
-    fn show_dialog(&mut self, action: Action, product: ProductEntry, weight_kg: Option<f64>) {
-        self.popup = Some(Popup::Dialog {
-            action,
-            product,
-            weight_kg,
-        });
+        self.engine.reload_db()?;
+        self.reset_selected_product_idx();
+        self.shift = self.engine.db().current_shift()?;
 
-        self.focus = Focus::Dialog;
+        let settings = self.engine.db().settings();
+        self.tick_ms.store(settings.ui_tick_ms, Ordering::Relaxed);
 
-        // The dialog always starts with a preselection of "Ok".
-        self.dialog_list_state.select(Some(0));
-    }
+        let changed_count = settings
+            .entries()
+            .iter()
+            .zip(&old_settings)
+            .filter(|(new, old)| new != old)
+            .count();
 
-    fn navigate(&mut self, navigation: Navigation) {
-        use Navigation::*;
+        if changed_count > 0 {
+            self.settings_reload = Some((self.now, changed_count));
+        }
 
-        match (self.focus, navigation) {
-            (Focus::Product, Up) => self.select_previous_product(),
-            (Focus::Product, Down) => self.select_next_product(),
-            (Focus::Product, Right) => self.focus = Focus::Sale,
-            (Focus::Sale, Up) => self.select_previous_action(),
-            (Focus::Sale, Down) => self.select_next_action(),
-            (Focus::Sale, Left) => self.focus = Focus::Product,
-            (Focus::Dialog, Up) => self.select_previous_dialog_action(),
+        Ok(())
+    }
+
+    fn on_tick(&mut self) -> Result<(), Box<dyn Error>> {
+        // Check if we should reconnect the printer.
+        if self.reconnect_printer_date <= self.now {
+            self.reconnect_printer()?;
+
+            // The printer might have come or gone.
+            self.needs_redraw = true;
+        }
+
+        // Check if we should poll the printer status.
+        if self.poll_printer_date <= self.now {
+            self.poll_printer_status();
+        }
+
+        // Apply changes of other programs (e.g. settings that have been edited with `sqlite3`).
+        if self.check_db_date <= self.now {
+            self.check_db_changes();
+            self.check_db_date = self.now + TimeDelta::try_seconds(2).unwrap();
+        }
+
+        // Hide the note about reloaded settings after a while.
+        if self
+            .settings_reload
+            .is_some_and(|(date, _)| (self.now - date) >= TimeDelta::try_seconds(10).unwrap())
+        {
+            self.settings_reload = None;
+            self.needs_redraw = true;
+        }
+
+        // Check the disk once a minute.
+        if self.check_storage_date <= self.now {
+            self.engine.check_storage();
+            self.check_storage_date = self.now + TimeDelta::try_seconds(60).unwrap();
+            self.needs_redraw = true;
+        }
+
+        // The status bar, the watchdog and the endpoint only read the last health check.
+        if self.check_health_date <= self.now {
+            if self.engine.refresh_health() {
+                self.needs_redraw = true;
+            }
+
+            if let Some(health_endpoint) = &self.health_endpoint {
+                health_endpoint.publish(self.engine.health());
+            }
+
+            self.check_health_date = self.now + TimeDelta::try_seconds(5).unwrap();
+        }
+
+        // Keep the service manager from restarting us as long as the DB is usable.
+        // Broken scales or printers cannot be fixed by a restart.
+        if !self.engine.health().db.is_failed() {
+            _ = self.watchdog.ping();
+        }
+
+        self.show_interrupted_sale();
+
+        // Remind the operator of broken scales.
+        if self.is_scale_alarm_active() {
+            self.beep_scale_alarm();
+        }
+
+        // The undo window of the last express sale has passed.
+        if self
+            .express_sale
+            .as_ref()
+            .is_some_and(|express_sale| express_sale.deadline <= self.now)
+        {
+            self.express_sale = None;
+            self.needs_redraw = true;
+        }
+
+        // Label the next portion in batch mode.
+        self.process_batch()?;
+
+        Ok(())
+    }
+
+    /// The live weight of the scales without the tare
+    fn live_weight(&self) -> WeightResult {
+        self.engine.net_weight(self.weight.clone())
+    }
+
+    fn weight(&self) -> WeightResult {
+        // A held weight wins over the live one.
+        let weight = match self.held_weight {
+            Some(weight_kg) => Ok(weight_kg),
+            None => self.live_weight(),
+        };
+
+        // Everything from the display to the stored sale uses the legally rounded weight.
+        let settings = self.engine.db().settings();
+        weight.map(|weight_kg| settings.round_weight(weight_kg))
+    }
+
+    /// The raw response of the scales behind `weight()` (for the weighing log)
+    fn weight_frame(&self) -> Option<Vec<u8>> {
+        if self.held_weight.is_some() {
+            self.held_frame.clone()
+        } else {
+            self.engine.scales().diagnostics().last_frame
+        }
+    }
+
+    /// Freeze the current weight, so the item can be removed from the scales.
+    /// If a weight is already held, it is released.
+    fn toggle_weight_hold(&mut self) {
+        if self.held_weight.take().is_some() {
+            return;
+        }
+
+        match self.live_weight() {
+            Ok(weight_kg) if weight_kg >= 0.0 => {
+                self.held_weight = Some(weight_kg);
+                self.held_frame = self.engine.scales().diagnostics().last_frame;
+            }
+
+            _ => self.show_message(
+                MessageType::Error,
+                String::from("Es liegt kein gültiges Gewicht zum Halten vor."),
+            ),
+        }
+    }
+
+    fn update_weight(&mut self, weight: WeightResult) {
+        // Remember since when the scales are failing.
+        // Deliberately released scales are no failure.
+        match (&weight, self.weight_error_date) {
+            (Ok(_), _) | (Err(WeightError::Paused), _) => self.weight_error_date = None,
+            (Err(_), None) => self.weight_error_date = Some(self.now),
+            (Err(_), Some(_)) => (),
+        }
+
+        // Remember since when the (rounded) weight is stable.
+        let previous_weight = self.weight().ok();
+        let previous_live_weight = self.live_weight().ok();
+        self.weight = weight;
+
+        if self.weight().ok() != previous_weight {
+            self.weight_changed_date = self.now;
+        }
+
+        // An item that is placed on the empty scales starts a new sale.
+        let threshold_kg = (self.engine.db().settings().wake_on_weight_g as f64) / 1000.0;
+
+        if (threshold_kg > 0.0)
+            && previous_live_weight.is_some_and(|weight_kg| weight_kg < threshold_kg)
+            && self
+                .live_weight()
+                .is_ok_and(|weight_kg| weight_kg >= threshold_kg)
+        {
+            self.wake_on_weight();
+        }
+    }
+
+    /// Get ready for the next sale: Close views and notes and focus the product list.
+    /// Dialogs, inputs and errors are left to the operator.
+    /// In batch mode, items are placed on the scales all the time, so nothing happens.
+    fn wake_on_weight(&mut self) {
+        if self.batch.is_some() {
+            return;
+        }
+
+        // Close popups from the top until one must be handled by the operator.
+        while let Some(popup) = self.popup() {
+            let is_dismissable = match popup {
+                Popup::Message { ty, .. } => *ty == MessageType::Info,
+                Popup::PrintJobs { .. } | Popup::SalesHistory { .. } | Popup::Diagnostics => true,
+                Popup::Dialog(_)
+                | Popup::Input { .. }
+                | Popup::Preflight { .. }
+                | Popup::SalesDates { .. }
+                | Popup::Recovery { .. }
+                | Popup::Reprint { .. }
+                | Popup::Palette { .. } => false,
+            };
+
+            if !is_dismissable {
+                return;
+            }
+
+            self.pop_popup();
+        }
+
+        self.main_focus = Focus::Product;
+    }
+
+    /// Take the weight on the scales as tare (e.g. of a bowl).
+    /// On empty scales, this clears the tare again.
+    fn tare(&mut self) {
+        match self.weight.clone() {
+            Ok(weight_kg) => self.engine.set_tare(weight_kg),
+
+            Err(err) => self.show_message(
+                MessageType::Error,
+                format!("Fehler beim Zugriff auf die Waage: {}", err),
+            ),
+        }
+    }
+
+    /// Print the last printout again (e.g. if it has been torn off badly).
+    /// No sale is performed, the print history notes the reprint like any other print.
+    fn reprint(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.is_read_only() {
+            self.reject_read_only("Das Nachdrucken");
+            return Ok(());
+        }
+
+        if !self.is_permitted(Permissions::PRINT_ONLY, "Das Nachdrucken") {
+            return Ok(());
+        }
+
+        let Some(last_print) = &self.last_print else {
+            self.show_message(
+                MessageType::Info,
+                String::from("Es wurde noch nichts gedruckt."),
+            );
+
+            return Ok(());
+        };
+
+        let product = (*last_print.product).clone();
+        let (weight_kg, piece_count, printout) = (
+            last_print.weight_kg,
+            last_print.piece_count,
+            last_print.printout,
+        );
+
+        self.print_voucher(&product, weight_kg, piece_count, printout, 1, true)?;
+
+        Ok(())
+    }
+
+    /// Perform the action of a physical button like the corresponding key.
+    fn handle_button(&mut self, action: ButtonAction) -> Result<(), Box<dyn Error>> {
+        match action {
+            ButtonAction::Confirm => {
+                let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+                self.handle_event(AppEvent::Input(Event::Key(enter)))?;
+            }
+
+            ButtonAction::Tare => self.tare(),
+            ButtonAction::Reprint => self.reprint()?,
+        }
+
+        Ok(())
+    }
+
+    /// Capture the average piece weight from the reference sample on the scales.
+    /// If a piece weight has already been captured, the counting mode is left.
+    fn toggle_piece_counter(&mut self) {
+        if self.piece_counter.take().is_some() {
+            return;
+        }
+
+        let reference_pieces = self.engine.db().settings().counting_reference_pieces;
+
+        match self
+            .weight()
+            .ok()
+            .and_then(|weight_kg| PieceCounter::from_reference(weight_kg, reference_pieces))
+        {
+            Some(piece_counter) => self.piece_counter = Some(piece_counter),
+
+            None => self.show_message(
+                MessageType::Error,
+                format!(
+                    "Für die Stückzählung bitte {} Referenzstücke auf die Waage legen.",
+                    reference_pieces
+                ),
+            ),
+        }
+    }
+
+    /// Start pre-packing the selected product with the selected action.
+    /// If a batch is already running, it is finished.
+    fn toggle_batch(&mut self) {
+        if let Some(batch) = self.batch.take() {
+            let total_str = format::euro(batch.total_ct);
+
+            self.show_message(
+                MessageType::Info,
+                format!(
+                    "Serienauszeichnung für \"{}\" beendet: {} Packung(en) für {}",
+                    batch.product.name, batch.count, total_str
+                ),
+            );
+
+            return;
+        }
+
+        if self.is_read_only() {
+            self.reject_read_only("Die Serienauszeichnung");
+            return;
+        }
+
+        let Some(product) = self.selected_product().cloned() else {
+            return;
+        };
+
+        // Fixed-price products need no scales, so there is nothing to detect.
+        if !product.is_kg_price {
+            self.show_message(
+                MessageType::Error,
+                String::from("Die Serienauszeichnung ist nur für Produkte mit Kilopreis möglich."),
+            );
+
+            return;
+        }
+
+        let action = self.selected_action();
+
+        if !action.print {
+            self.show_message(
+                MessageType::Error,
+                String::from("Für die Serienauszeichnung bitte \"Verbuchen und Bon drucken\" oder \"Nur Bon drucken\" auswählen."),
+            );
+
+            return;
+        }
+
+        if action
+            .permission()
+            .is_some_and(|permission| !self.is_permitted(permission, "Diese Serienauszeichnung"))
+        {
+            return;
+        }
+
+        // Every portion is weighed live.
+        self.held_weight = None;
+        self.piece_counter = None;
+
+        // A portion that already lies on the scales must be removed first.
+        self.batch = Some(Batch {
+            product: Box::new(product),
+            sale: action.sale,
+            count: 0,
+            total_ct: 0,
+            armed: false,
+            truncation_confirmed: false,
+        });
+    }
+
+    /// Print a voucher (and perform a sale) for the portion on the scales once its weight is stable.
+    /// Afterwards, the portion must be removed before the next one is labeled.
+    fn process_batch(&mut self) -> Result<(), Box<dyn Error>> {
+        // Open popups (e.g. printer errors) pause the batch.
+        if self.batch.is_none() || !self.popups.is_empty() {
+            return Ok(());
+        }
+
+        let Ok(weight_kg) = self.weight() else {
+            return Ok(());
+        };
+
+        let rounding_mode = self.engine.db().settings().rounding_mode;
+        let is_stable = self.is_weight_stable();
+
+        let batch = self.batch.as_mut().expect("Batch has been checked above");
+
+        if weight_kg <= 0.0 {
+            self.needs_redraw |= !batch.armed;
+            batch.armed = true;
+            return Ok(());
+        }
+
+        if !batch.armed || !is_stable {
+            return Ok(());
+        }
+
+        batch.armed = false;
+        self.needs_redraw = true;
+
+        let product = batch.product.clone();
+        let sale = batch.sale;
+        let truncation_confirmed = batch.truncation_confirmed;
+
+        let printout = Printout::Voucher {
+            sale_date: sale.then_some(self.now),
+            expiration_override: None,
+        };
+
+        // Truncated lines affect every portion, so they are pointed out before the first one is printed.
+        // The operator continues by placing the portion again.
+        if !truncation_confirmed {
+            let warning = self.preview_truncation(&product, Some(weight_kg), None, printout);
+
+            if let Some(batch) = &mut self.batch {
+                batch.truncation_confirmed = true;
+            }
+
+            if let Some(warning) = warning {
+                self.show_message(
+                    MessageType::Info,
+                    format!(
+                        "{} Zum Drucken die Portion erneut auflegen, 'b' beendet die Serie.",
+                        warning
+                    ),
+                );
+
+                return Ok(());
+            }
+        }
+
+        // A failed print is shown to the operator and the portion stays unlabeled.
+        if !self.print_voucher(&product, Some(weight_kg), None, printout, 1, true)? {
+            return Ok(());
+        }
+
+        let total_ct = product.total_price_ct(Some(weight_kg), None, rounding_mode);
+
+        if let Some(batch) = &mut self.batch {
+            batch.count += 1;
+            batch.total_ct += total_ct;
+        }
+
+        if sale {
+            self.perform_sale(&product, Some(weight_kg), None, false, None, None)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check if the weight has not changed for a while.
+    fn is_weight_stable(&self) -> bool {
+        let stable_window =
+            TimeDelta::try_milliseconds(self.engine.db().settings().batch_stable_ms as _)
+                .expect("Stability window out of bound");
+
+        (self.now - self.weight_changed_date) >= stable_window
+    }
+
+    fn reconnect_printer(&mut self) -> Result<(), Box<dyn Error>> {
+        self.engine.reconnect_printer()?;
+
+        if self.engine.printer().is_ok() {
+            self.reconnect_printer_date = self.now + TimeDelta::try_seconds(120).unwrap();
+        } else {
+            self.reconnect_printer_date = self.now + TimeDelta::try_seconds(10).unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn poll_printer_status(&mut self) {
+        // Ask the printer for its error flags to detect missing or ending media early.
+        // If that fails, we don't know anything about the printer state.
+        // The result goes through the event bus like the updates of all other subsystems.
+        let flags = self.engine.query_printer_error_flags();
+
+        self.events.send(AppEvent::PrinterStatus(flags));
+        self.poll_printer_date = self.now + TimeDelta::try_seconds(5).unwrap();
+    }
+
+    fn selected_product_idx(&self) -> Option<usize> {
+        self.product_list_state.selected()
+    }
+
+    fn selected_product(&self) -> Option<&ProductEntry> {
+        self.selected_product_idx()
+            .map(|idx| &self.engine.db().products()[idx])
+    }
+
+    fn reset_selected_product_idx(&mut self) {
+        let idx = if self.engine.db().products().is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+
+        self.product_list_state.select(idx);
+    }
+
+    fn select_previous_product(&mut self) {
+        if let Some(product_idx) = self.selected_product_idx() {
+            if product_idx > 0 {
+                self.product_list_state.select(Some(product_idx - 1));
+            }
+        }
+    }
+
+    fn select_next_product(&mut self) {
+        if let Some(product_idx) = self.selected_product_idx() {
+            if product_idx < (self.engine.db().products().len() - 1) {
+                self.product_list_state.select(Some(product_idx + 1));
+            }
+        }
+    }
+
+    fn selected_action(&self) -> Action {
+        match self.action_list_state.selected().unwrap() {
+            0 => Action {
+                sale: true,
+                print: true,
+                shelf_label: false,
+                dump: false,
+                receipt: false,
+                copies: 1,
+                expiration_offset_days: 0,
+            },
+            1 => Action {
+                sale: true,
+                print: false,
+                shelf_label: false,
+                dump: false,
+                receipt: false,
+                copies: 1,
+                expiration_offset_days: 0,
+            },
+            2 => Action {
+                sale: false,
+                print: true,
+                shelf_label: false,
+                dump: false,
+                receipt: false,
+                copies: 1,
+                expiration_offset_days: 0,
+            },
+            3 => Action {
+                sale: false,
+                print: false,
+                shelf_label: true,
+                dump: false,
+                receipt: false,
+                copies: 1,
+                expiration_offset_days: 0,
+            },
+            4 => Action {
+                sale: false,
+                print: false,
+                shelf_label: false,
+                dump: true,
+                receipt: false,
+                copies: 1,
+                expiration_offset_days: 0,
+            },
+
+            _ => unreachable!(),
+        }
+    }
+
+    fn select_previous_action(&mut self) {
+        let idx = self.action_list_state.selected().unwrap();
+
+        if idx > 0 {
+            self.action_list_state.select(Some(idx - 1));
+        }
+    }
+
+    fn select_next_action(&mut self) {
+        let idx = self.action_list_state.selected().unwrap();
+
+        if idx < (self.actions_count() - 1) {
+            self.action_list_state.select(Some(idx + 1));
+        }
+    }
+
+    fn selected_dialog_action(&mut self) -> DialogAction {
+        match self.dialog_list_state.selected().unwrap() {
+            0 => DialogAction::Confirm,
+            1 => DialogAction::Cancel,
+
+            _ => unreachable!(),
+        }
+    }
+
+    fn select_previous_dialog_action(&mut self) {
+        let idx = self.dialog_list_state.selected().unwrap();
+
+        if idx > 0 {
+            self.dialog_list_state.select(Some(idx - 1));
+        }
+    }
+
+    fn select_next_dialog_action(&mut self) {
+        let idx = self.dialog_list_state.selected().unwrap();
+
+        if idx < (2 - 1) {
+            self.dialog_list_state.select(Some(idx + 1));
+        }
+    }
+
+    /// The focus of the topmost popup or (without popups) of the main screen
+    fn focus(&self) -> Focus {
+        self.popup().map_or(self.main_focus, Popup::focus)
+    }
+
+    /// The topmost popup
+    fn popup(&self) -> Option<&Popup> {
+        self.popups.last()
+    }
+
+    fn popup_mut(&mut self) -> Option<&mut Popup> {
+        self.popups.last_mut()
+    }
+
+    /// Open a popup on top of the others.
+    /// A popup of the same kind is replaced, so e.g. a refreshed view doesn't pile up.
+    fn push_popup(&mut self, popup: Popup) {
+        self.popups.retain(|open| !open.is_same_kind(&popup));
+        self.popups.push(popup);
+    }
+
+    /// Close the topmost popup, the one below (or the main screen) gets the focus again.
+    fn pop_popup(&mut self) -> Option<Popup> {
+        self.popups.pop()
+    }
+
+    /// Back out one level: Close the topmost popup (a dialog is cancelled)
+    /// or move from the actions back to the product list.
+    fn go_back(&mut self) {
+        match self.pop_popup() {
+            // The held weight belongs to the dialog.
+            Some(Popup::Dialog(_)) => self.held_weight = None,
+            Some(_) => (),
+            None => self.main_focus = Focus::Product,
+        }
+    }
+
+    fn show_message(&mut self, ty: MessageType, text: String) {
+        self.push_popup(Popup::Message { ty, text });
+    }
+
+    /// Show the most recent print attempts.
+    /// Like messages, the popup is closed with `Enter` or `Esc`.
+    fn show_print_jobs(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut jobs = Vec::new();
+        self.engine.db().print_jobs(50, &mut jobs)?;
+
+        self.push_popup(Popup::PrintJobs { jobs });
+
+        Ok(())
+    }
+
+    /// Show the raw state of the scales and the printer for remote support.
+    /// Like messages, the popup is closed with `Enter` or `Esc`.
+    fn show_diagnostics(&mut self) {
+        self.push_popup(Popup::Diagnostics);
+    }
+
+    /// Show the most recent sales that match the filter.
+    fn show_sales_history(&mut self, filter: SaleFilter) -> Result<(), Box<dyn Error>> {
+        let mut sales = Vec::new();
+        self.engine.db().filtered_sales(&filter, 100, &mut sales)?;
+        let summary = self.engine.db().sales_summary(&filter)?;
+
+        self.push_popup(Popup::SalesHistory {
+            filter,
+            sales,
+            summary,
+        });
+
+        Ok(())
+    }
+
+    /// Change the filter of the sales history (if it is shown) and query the sales again.
+    fn update_sales_filter<F: FnOnce(&mut SaleFilter)>(
+        &mut self,
+        f: F,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(Popup::SalesHistory { filter, .. }) = self.popup() else {
+            return Ok(());
+        };
+
+        let mut filter = filter.clone();
+        f(&mut filter);
+
+        self.show_sales_history(filter)
+    }
+
+    /// Restrict the sales history to the selected product or show all products again.
+    fn toggle_sales_product_filter(&mut self) -> Result<(), Box<dyn Error>> {
+        let product_name = self.selected_product().map(|product| product.name.clone());
+
+        self.update_sales_filter(|filter| {
+            filter.product_name = match filter.product_name {
+                Some(_) => None,
+                None => product_name,
+            };
+        })
+    }
+
+    /// The current business day (which might still be yesterday after midnight)
+    fn today(&self) -> NaiveDate {
+        self.engine
+            .db()
+            .settings()
+            .business_day(self.now.with_timezone(&Local))
+    }
+
+    /// Open a picker for the date range on top of the sales history.
+    fn pick_sales_dates(&mut self) {
+        let Some(Popup::SalesHistory { filter, .. }) = self.popup() else {
+            return;
+        };
+
+        let picker = DateRangePicker::new(filter.from, filter.until, self.today());
+        self.push_popup(Popup::SalesDates { picker });
+    }
+
+    fn handle_date_picker_key(&mut self, code: KeyCode) -> Result<(), Box<dyn Error>> {
+        let (today, week_start) = (self.today(), self.engine.db().settings().week_start);
+
+        let Some(Popup::SalesDates { picker }) = self.popup_mut() else {
+            panic!("Date picker is focused, but not present.");
+        };
+
+        let dates = match picker.handle_key(code, today, week_start) {
+            DatePickerOutcome::Pending => return Ok(()),
+            DatePickerOutcome::Picked { from, until } => Some((Some(from), Some(until))),
+            DatePickerOutcome::Cleared => Some((None, None)),
+            DatePickerOutcome::Cancelled => None,
+        };
+
+        // Back to the history (which takes the focus again).
+        self.pop_popup();
+
+        match dates {
+            Some(dates) => self.update_sales_filter(|filter| (filter.from, filter.until) = dates),
+            None => Ok(()),
+        }
+    }
+
+    fn edit_daily_message(&mut self) {
+        if self.is_read_only() {
+            self.reject_read_only("Das Ändern der Tagesnachricht");
+            return;
+        }
+
+        self.show_input(
+            InputPurpose::DailyMessage,
+            self.engine.db().settings().daily_message.clone(),
+        );
+    }
+
+    /// Load the estimated usage of the inserted label roll (if the printer tells which it is).
+    fn load_label_roll(&mut self) {
+        let label_type = match self.engine.printer().map(Printer::current_label) {
+            Ok(Ok(Some(label))) => label.ty,
+            _ => return,
+        };
+
+        match self.engine.db().label_roll(label_type) {
+            Ok(roll) => self.label_roll = roll,
+            Err(err) => eprintln!("Failed to load the label roll: {err}"),
+        }
+    }
+
+    /// Record a new roll of the inserted label type.
+    /// The length of the previous roll (or the number of die-cut labels) is suggested.
+    fn change_label_roll(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.is_read_only() {
+            self.reject_read_only("Das Erfassen einer neuen Etikettenrolle");
+            return Ok(());
+        }
+
+        let label = match self.engine.printer().map(Printer::current_label) {
+            Ok(Ok(Some(label))) => label,
+
+            Ok(Ok(None)) => {
+                self.show_message(
+                    MessageType::Error,
+                    String::from("Es ist kein Etikett eingelegt."),
+                );
+
+                return Ok(());
+            }
+
+            Ok(Err(err)) => {
+                self.show_message(
+                    MessageType::Error,
+                    format!("Fehler bei der Label-Abfrage: {}", err),
+                );
+
+                return Ok(());
+            }
+
+            Err(err) => {
+                self.show_message(
+                    MessageType::Error,
+                    format!("Fehler beim Zugriff auf den Drucker: {}", err),
+                );
+
+                return Ok(());
+            }
+        };
+
+        let length_mm = match self.engine.db().label_roll(label.ty)? {
+            Some(roll) => roll.length_mm,
+            None => 1000.0 * self.engine.db().settings().label_roll_length_m,
+        };
+
+        let text = match label.ty.die_cut_pitch_mm() {
+            Some(pitch_mm) => ((length_mm / pitch_mm).round() as u64).to_string(),
+            None => NumberFormat::Comma.shortest(length_mm / 1000.0),
+        };
+
+        self.show_input(
+            InputPurpose::LabelRoll {
+                label_type: label.ty,
+            },
+            text,
+        );
+
+        Ok(())
+    }
+
+    fn submit_label_roll(
+        &mut self,
+        label_type: LabelType,
+        text: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(value) = text
+            .replace(',', ".")
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite() && (*value > 0.0))
+        else {
+            self.show_message(
+                MessageType::Error,
+                format!("\"{}\" ist keine gültige Angabe.", text),
+            );
+
+            return Ok(());
+        };
+
+        let length_mm = match label_type.die_cut_pitch_mm() {
+            Some(pitch_mm) => value.round() * pitch_mm,
+            None => 1000.0 * value,
+        };
+
+        let roll = LabelRollEntry::new(label_type, self.now, length_mm);
+        self.engine.db().add_label_roll(&roll)?;
+        self.label_roll = Some(roll);
+
+        self.show_message(
+            MessageType::Info,
+            format!(
+                "Die neue Rolle ({}) wurde erfasst.",
+                label_type_description(label_type)
+            ),
+        );
+
+        Ok(())
+    }
+
+    /// Print a summary slip of the sales in the period of the history (or of today).
+    /// Only the given number of most recent sales are included if the text is not empty.
+    fn submit_sales_summary(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        let filter = match self.popup() {
+            Some(Popup::SalesHistory { filter, .. }) => filter.clone(),
+
+            _ => SaleFilter {
+                from: Some(self.today()),
+                until: Some(self.today()),
+                ..Default::default()
+            },
+        };
+
+        let limit = if text.is_empty() {
+            self.engine.db().sales_summary(&filter)?.count as usize
+        } else {
+            match text.parse::<usize>() {
+                Ok(limit) if limit > 0 => limit,
+
+                _ => {
+                    self.show_message(
+                        MessageType::Error,
+                        format!("\"{}\" ist keine gültige Anzahl.", text),
+                    );
+
+                    return Ok(());
+                }
+            }
+        };
+
+        let mut sales = Vec::new();
+        self.engine
+            .db()
+            .filtered_sales(&filter, limit, &mut sales)?;
+
+        if sales.is_empty() {
+            self.show_message(
+                MessageType::Info,
+                String::from("Im gewählten Zeitraum gibt es keine Verkäufe."),
+            );
+
+            return Ok(());
+        }
+
+        self.print_summary_slip(&sales, None);
+
+        Ok(())
+    }
+
+    /// Open a shift with the float in the cash drawer or close the open one by counting the cash.
+    fn toggle_shift(&mut self) {
+        if self.is_read_only() {
+            self.reject_read_only("Das Öffnen und Schließen von Schichten");
+            return;
+        }
+
+        let purpose = if self.shift.is_some() {
+            InputPurpose::ShiftCount
+        } else {
+            InputPurpose::ShiftFloat
+        };
+
+        self.show_input(purpose, String::new());
+    }
+
+    fn submit_shift_float(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        let Some(float_ct) = parse_price_ct(text) else {
+            self.show_message(
+                MessageType::Error,
+                format!("\"{}\" ist kein gültiger Betrag.", text),
+            );
+
+            return Ok(());
+        };
+
+        let operator = self.engine.attribution().operator.clone();
+        let shift = ShiftEntry::new(self.now, float_ct, operator);
+        self.engine.db().open_shift(&shift)?;
+        self.shift = Some(shift);
+
+        self.show_message(
+            MessageType::Info,
+            format!(
+                "Die Schicht wurde mit einem Anfangsbestand von {} geöffnet.",
+                format::euro(float_ct)
+            ),
+        );
+
+        Ok(())
+    }
+
+    /// Close the shift with the counted cash and print its summary slip with the difference.
+    fn submit_shift_count(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        let Some(counted_ct) = parse_price_ct(text) else {
+            self.show_message(
+                MessageType::Error,
+                format!("\"{}\" ist kein gültiger Betrag.", text),
+            );
+
+            return Ok(());
+        };
+
+        // Another station might have closed the shift in the meantime.
+        self.shift = None;
+
+        let Some(shift) = self.engine.db().close_shift(self.now, counted_ct)? else {
+            return Ok(());
+        };
+
+        let closing = shift.closing.expect("The shift has just been closed");
+
+        self.show_message(
+            MessageType::Info,
+            format!(
+                "Die Schicht wurde abgeschlossen. Soll: {}, gezählt: {}, Differenz: {}",
+                format::euro(closing.expected_ct),
+                format::euro(closing.counted_ct),
+                format::euro_difference(closing.difference_ct())
+            ),
+        );
+
+        let mut sales = Vec::new();
+        self.engine.db().shift_sales(&shift, self.now, &mut sales)?;
+        self.print_summary_slip(&sales, Some(&shift));
+
+        Ok(())
+    }
+
+    /// Open the palette to search for an action (which is performed where it has been opened).
+    fn show_palette(&mut self) {
+        self.push_popup(Popup::Palette {
+            query: String::new(),
+            selected: 0,
+        });
+    }
+
+    /// Edit the search of the palette and pick one of the matching commands.
+    /// `Enter` closes the palette and returns the event of the picked command, `Esc` just closes it.
+    fn handle_palette_key(&mut self, code: KeyCode) -> Option<AppEvent> {
+        let Some(Popup::Palette { query, selected }) = self.popup_mut() else {
+            panic!("Palette is focused, but not present.");
+        };
+
+        let commands = matching_commands(query);
+
+        match code {
+            KeyCode::Char(c) => {
+                query.push(c);
+                *selected = 0;
+            }
+
+            KeyCode::Backspace => {
+                query.pop();
+                *selected = 0;
+            }
+
+            KeyCode::Up => *selected = selected.saturating_sub(1),
+            KeyCode::Down => *selected = (*selected + 1).min(commands.len().saturating_sub(1)),
+
+            KeyCode::Esc => {
+                self.pop_popup();
+            }
+
+            KeyCode::Enter => {
+                let command = commands.get(*selected)?;
+
+                self.pop_popup();
+
+                return Some(command.trigger.event());
+            }
+
+            _ => (),
+        }
+
+        None
+    }
+
+    fn show_input(&mut self, purpose: InputPurpose, text: String) {
+        self.push_popup(Popup::Input { purpose, text });
+    }
+
+    /// Edit the text of the input popup.
+    /// `Enter` submits the text, `Esc` discards it.
+    fn handle_input_key(&mut self, code: KeyCode) -> Result<(), Box<dyn Error>> {
+        let Some(Popup::Input { purpose, text }) = self.popup_mut() else {
+            panic!("Input is focused, but not present.");
+        };
+
+        match code {
+            KeyCode::Char(c) => text.push(c),
+
+            KeyCode::Backspace => {
+                text.pop();
+            }
+
+            KeyCode::Esc => {
+                // Back to where the input has been opened without applying the text.
+                self.pop_popup();
+            }
+
+            KeyCode::Enter => {
+                let (purpose, text) = (*purpose, text.trim().to_string());
+
+                // Back to where the input has been opened (might be overridden by message).
+                self.pop_popup();
+
+                self.submit_input(purpose, text)?;
+            }
+
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Change the selected product (e.g. its price).
+    /// If an admin PIN is configured, it must be entered first.
+    fn edit_product(&mut self, edit: ProductEdit) {
+        let Some(product_id) = self.selected_product().and_then(ProductEntry::id) else {
+            return;
+        };
+
+        if self.is_read_only() {
+            self.reject_read_only("Das Ändern von Produkten");
+            return;
+        }
+
+        if !self.is_permitted(Permissions::EDIT_PRODUCTS, "Das Ändern von Produkten") {
+            return;
+        }
+
+        // The batch has already labeled portions with the old price.
+        if (edit == ProductEdit::Price) && self.batch.is_some() {
+            self.show_message(
+                MessageType::Error,
+                String::from(
+                    "Während der Serienauszeichnung kann der Preis nicht geändert werden.",
+                ),
+            );
+
+            return;
+        }
+
+        if self.engine.db().settings().admin_pin.is_empty() {
+            self.show_product_input(product_id, edit);
+        } else {
+            self.show_input(InputPurpose::AdminPin { product_id, edit }, String::new());
+        }
+    }
+
+    fn show_product_input(&mut self, product_id: i64, edit: ProductEdit) {
+        let Some(product) = self
+            .product_idx_by_id(product_id)
+            .map(|idx| &self.engine.db().products()[idx])
+        else {
+            return;
+        };
+
+        match edit {
+            ProductEdit::Price => {
+                let price_str = NumberFormat::Comma.euro(product.price_ct);
+
+                self.show_input(InputPurpose::ProductPrice { product_id }, price_str);
+            }
+
+            ProductEdit::Duplicate => {
+                let name = format!("{} (Kopie)", product.name);
+                self.show_input(InputPurpose::ProductCopyName { product_id }, name);
+            }
+        }
+    }
+
+    /// The products might have been reloaded while an input was open, so they are referenced by ID.
+    fn product_idx_by_id(&self, product_id: i64) -> Option<usize> {
+        self.engine
+            .db()
+            .products()
+            .iter()
+            .position(|product| product.id() == Some(product_id))
+    }
+
+    fn show_missing_product(&mut self) {
+        self.show_message(
+            MessageType::Error,
+            String::from("Das Produkt existiert nicht mehr."),
+        );
+    }
+
+    fn submit_copy_name(&mut self, product_id: i64, name: String) -> Result<(), Box<dyn Error>> {
+        let Some(idx) = self.product_idx_by_id(product_id) else {
+            self.show_missing_product();
+            return Ok(());
+        };
+
+        // Without a name, the copy is recognizable by the suffix.
+        let name = if name.is_empty() {
+            format!("{} (Kopie)", self.engine.db().products()[idx].name)
+        } else {
+            name
+        };
+
+        let copy_id = match self.engine.duplicate_product(idx, name.clone()) {
+            Ok(copy_id) => copy_id,
+            Err(PosError::Product(ProductError::Invalid(errors))) => {
+                self.show_validation_errors(&errors);
+                return Ok(());
+            }
+
+            Err(PosError::NotPermitted { .. }) => {
+                self.reject_permission("Das Ändern von Produkten");
+                return Ok(());
+            }
+
+            Err(err) => return Err(err.into()),
+        };
+
+        // Select the copy, it is most likely edited next.
+        self.product_list_state
+            .select(self.product_idx_by_id(copy_id));
+
+        self.show_product_info(copy_id, format!("Das Produkt \"{}\" wurde angelegt.", name));
+
+        Ok(())
+    }
+
+    fn show_validation_errors(&mut self, errors: &[ValidationError]) {
+        let errors_str = errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.show_message(
+            MessageType::Error,
+            format!("Die Änderung wurde nicht gespeichert: {}", errors_str),
+        );
+    }
+
+    /// Confirm a product change and point out the problems the product might cause (if any).
+    fn show_product_info(&mut self, product_id: i64, mut text: String) {
+        let db = self.engine.db();
+
+        let warnings = self
+            .product_idx_by_id(product_id)
+            .map(|idx| db.products()[idx].warnings(db.settings()))
+            .unwrap_or_default();
+
+        for warning in warnings {
+            text = format!("{} Achtung: {}", text, warning);
+        }
+
+        self.show_message(MessageType::Info, text);
+    }
+
+    fn submit_price(&mut self, product_id: i64, text: &str) -> Result<(), Box<dyn Error>> {
+        let Some(idx) = self.product_idx_by_id(product_id) else {
+            self.show_missing_product();
+            return Ok(());
+        };
+
+        let Some(price_ct) = parse_price_ct(text) else {
+            self.show_message(
+                MessageType::Error,
+                format!("\"{}\" ist kein gültiger Preis.", text),
+            );
+
+            return Ok(());
+        };
+
+        let product = &self.engine.db().products()[idx];
+        let (name, old_price_ct) = (product.name.clone(), product.price_ct);
+
+        if price_ct == old_price_ct {
+            return Ok(());
+        }
+
+        match self.engine.update_product_price(idx, price_ct, self.now) {
+            Ok(()) => (),
+            Err(PosError::Product(ProductError::Invalid(errors))) => {
+                self.show_validation_errors(&errors);
+                return Ok(());
+            }
+
+            Err(PosError::NotPermitted { .. }) => {
+                self.reject_permission("Das Ändern von Produkten");
+                return Ok(());
+            }
+
+            Err(err) => return Err(err.into()),
+        }
+
+        self.show_product_info(
+            product_id,
+            format!(
+                "Der Preis von \"{}\" wurde von {} auf {} geändert.",
+                name,
+                format::euro(old_price_ct),
+                format::euro(price_ct)
+            ),
+        );
+
+        Ok(())
+    }
+
+    fn submit_input(&mut self, purpose: InputPurpose, text: String) -> Result<(), Box<dyn Error>> {
+        match purpose {
+            InputPurpose::AdminPin { product_id, edit } => {
+                if text == self.engine.db().settings().admin_pin {
+                    self.show_product_input(product_id, edit);
+                } else {
+                    self.show_message(MessageType::Error, String::from("Die PIN ist falsch."));
+                }
+            }
+
+            InputPurpose::ProductPrice { product_id } => self.submit_price(product_id, &text)?,
+            InputPurpose::LabelRoll { label_type } => self.submit_label_roll(label_type, &text)?,
+            InputPurpose::SalesSummary => self.submit_sales_summary(&text)?,
+            InputPurpose::ShiftFloat => self.submit_shift_float(&text)?,
+            InputPurpose::ShiftCount => self.submit_shift_count(&text)?,
+            InputPurpose::ProductCopyName { product_id } => {
+                self.submit_copy_name(product_id, text)?
+            }
+
+            InputPurpose::DailyMessage => {
+                let info = if text.is_empty() {
+                    "Die Tagesnachricht wurde gelöscht."
+                } else {
+                    "Die Tagesnachricht wird ab sofort auf jeden Bon gedruckt."
+                };
+
+                self.engine.set_daily_message(text)?;
+
+                self.show_message(MessageType::Info, String::from(info));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check if the same product with the same weight has just been confirmed.
+    /// This is most likely an accidental double key press.
+    fn duplicate_of(
+        &self,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+    ) -> Option<DateTime<Utc>> {
+        let guard_window =
+            TimeDelta::try_seconds(self.engine.db().settings().duplicate_guard_secs as _)
+                .expect("Duplicate guard window out of bound");
+
+        self.last_confirmed_action
+            .as_ref()
+            .filter(|last| {
+                (last.product_name == product.name)
+                    && (last.weight_kg == weight_kg)
+                    && ((self.now - last.date) < guard_window)
+            })
+            .map(|last| last.date)
+    }
+
+    fn show_dialog(
+        &mut self,
+        mut action: Action,
+        product: ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+    ) {
+        let duplicate_of = self.duplicate_of(&product, weight_kg);
+        let voucher_warning = self.action_truncation(action, &product, weight_kg, piece_count);
+        let settings = self.engine.db().settings();
+        let payment_method =
+            (action.sale && settings.ask_payment_method).then_some(PaymentMethod::Cash);
+
+        action.receipt = action.sale && settings.print_customer_receipt;
+        action.copies = product.default_copies;
+
+        self.push_popup(Popup::Dialog(Dialog {
+            action,
+            product: Box::new(product),
+            weight_kg,
+            piece_count,
+            duplicate_of,
+            payment_method,
+            voucher_warning: voucher_warning.clone(),
+        }));
+
+        // The dialog usually starts with a preselection of "Ok".
+        // For duplicates and truncated lines, the operator must explicitly select it.
+        let preselection = if duplicate_of.is_some() || voucher_warning.is_some() {
+            1
+        } else {
+            0
+        };
+        self.dialog_list_state.select(Some(preselection));
+    }
+
+    fn navigate(&mut self, navigation: Navigation) {
+        use Navigation::*;
+
+        match (self.focus(), navigation) {
+            (Focus::Product, Up) => self.select_previous_product(),
+            (Focus::Product, Down) => self.select_next_product(),
+            (Focus::Product, Right) => self.main_focus = Focus::Sale,
+            (Focus::Sale, Up) => self.select_previous_action(),
+            (Focus::Sale, Down) => self.select_next_action(),
+            (Focus::Sale, Left) => self.main_focus = Focus::Product,
+            (Focus::Dialog, Up) => self.select_previous_dialog_action(),
             (Focus::Dialog, Down) => self.select_next_dialog_action(),
+            (Focus::Dialog, Left) => self.change_payment_method(PaymentMethod::previous),
+            (Focus::Dialog, Right) => self.change_payment_method(PaymentMethod::next),
+            _ => (),
+        }
+    }
+
+    /// Switch the receipt for the customer on or off in the dialog of a sale.
+    fn toggle_customer_receipt(&mut self) {
+        if let Some(Popup::Dialog(Dialog { action, .. })) = self.popup_mut() {
+            action.receipt = action.sale && !action.receipt;
+        }
+    }
+
+    /// Print more or fewer vouchers in the dialog (at least one).
+    fn change_copies(&mut self, more: bool) {
+        if let Some(Popup::Dialog(Dialog { action, .. })) = self.popup_mut() {
+            action.copies = if more {
+                (action.copies + 1).min(MAX_COPIES)
+            } else {
+                action.copies.saturating_sub(1).max(1)
+            };
+        }
+    }
+
+    /// Can the operator move the best-before date of the voucher in the dialog?
+    fn can_override_expiration(&self, action: Action, product: &ProductEntry) -> bool {
+        action.print
+            && product.expiration_days.is_some()
+            && (self.engine.db().settings().expiration_override_days > 0)
+    }
+
+    /// Move the best-before date in the dialog by a day (within the configured bounds).
+    fn change_expiration_offset(&mut self, later: bool) {
+        let max_days = self.engine.db().settings().expiration_override_days as i64;
+
+        let Some(Popup::Dialog(Dialog {
+            action, product, ..
+        })) = self.popup()
+        else {
+            return;
+        };
+
+        if !self.can_override_expiration(*action, product) {
+            return;
+        }
+
+        if let Some(Popup::Dialog(Dialog { action, .. })) = self.popup_mut() {
+            let days = action.expiration_offset_days + if later { 1 } else { -1 };
+            action.expiration_offset_days = days.clamp(-max_days, max_days);
+        }
+    }
+
+    /// The best-before date that replaces the calculated one (`None` without an offset)
+    fn expiration_override(
+        &self,
+        product: &ProductEntry,
+        expiration_offset_days: i64,
+    ) -> Option<DateTime<Utc>> {
+        if expiration_offset_days == 0 {
+            return None;
+        }
+
+        let offset =
+            TimeDelta::try_days(expiration_offset_days).expect("Expiration offset out of bound");
+        let date = product.expiration_date(self.now.with_timezone(&Local))? + offset;
+
+        Some(date.with_timezone(&Utc))
+    }
+
+    /// Select another payment method in the dialog (if it asks for one).
+    fn change_payment_method(&mut self, f: fn(&PaymentMethod) -> PaymentMethod) {
+        if let Some(Popup::Dialog(Dialog {
+            payment_method: Some(payment_method),
+            ..
+        })) = self.popup_mut()
+        {
+            *payment_method = f(payment_method);
+        }
+    }
+
+    fn perform_action(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.focus() {
+            Focus::Sale => {
+                // If there is no product or weight, we exit early.
+                // Because we must cache the product in the confirmation dialog, it must be cloned.
+                let Some(product) = self.selected_product().cloned() else {
+                    return Ok(());
+                };
+
+                // Shelf labels need no weight, so the dialog can be shown right away.
+                let action = self.selected_action();
+
+                if !action.dump && self.is_read_only() {
+                    self.reject_read_only("Verbuchen oder Drucken");
+                    return Ok(());
+                }
+
+                if action
+                    .permission()
+                    .is_some_and(|permission| !self.is_permitted(permission, "Diese Aktion"))
+                {
+                    return Ok(());
+                }
+
+                // Refuse sales before the voucher is printed, the engine would refuse them anyway.
+                if action.sale && (self.engine.storage_level() == StorageLevel::Critical) {
+                    self.show_disk_full();
+                    return Ok(());
+                }
+
+                if action.shelf_label {
+                    self.show_dialog(action, product, None, None);
+                    return Ok(());
+                }
+
+                // Under-weights must always be confirmed in the dialog.
+                let mut needs_confirmation = false;
+
+                let weight_kg = if product.is_kg_price {
+                    // We need a weight, this is no fixed-price product.
+                    let weight_kg = match self.weight() {
+                        Ok(weight) => weight,
+
+                        Err(err) => {
+                            // Show an error message.
+                            self.show_message(
+                                MessageType::Error,
+                                format!("Fehler beim Zugriff auf die Waage: {}", err),
+                            );
+
+                            return Ok(());
+                        }
+                    };
+
+                    // The engine decides what happens with negative weights.
+                    match self.engine.check_weight(weight_kg) {
+                        WeightCheck::Accepted => (),
+                        WeightCheck::NeedsConfirmation => needs_confirmation = true,
+
+                        WeightCheck::Tared => {
+                            self.show_message(
+                                MessageType::Info,
+                                String::from("Untergewicht auf der Waage: Die Waage wurde neu tariert. Bitte erneut wiegen."),
+                            );
+
+                            return Ok(());
+                        }
+
+                        WeightCheck::Rejected => {
+                            self.show_message(
+                                MessageType::Error,
+                                String::from("Untergewicht (< 0.0 kg) auf der Waage"),
+                            );
+
+                            return Ok(());
+                        }
+                    }
+
+                    Some(weight_kg)
+                } else {
+                    None
+                };
+
+                // In counting mode, fixed-price products are sold per counted piece.
+                let (weight_kg, piece_count) = match self.piece_counter {
+                    Some(piece_counter) if !product.is_kg_price => {
+                        let weight_kg = match self.weight() {
+                            Ok(weight) => weight,
+
+                            Err(err) => {
+                                // Show an error message.
+                                self.show_message(
+                                    MessageType::Error,
+                                    format!("Fehler beim Zugriff auf die Waage: {}", err),
+                                );
+
+                                return Ok(());
+                            }
+                        };
+
+                        let piece_count = piece_counter.count(weight_kg);
+
+                        if piece_count == 0 {
+                            self.show_message(
+                                MessageType::Error,
+                                String::from("Es liegen keine Stücke auf der Waage."),
+                            );
+
+                            return Ok(());
+                        }
+
+                        (Some(weight_kg), Some(piece_count))
+                    }
+
+                    _ => (weight_kg, None),
+                };
+
+                // In express mode, a stable weight is processed right away.
+                // Duplicates and truncated lines still need the dialog.
+                if self.engine.db().settings().express_mode
+                    && !needs_confirmation
+                    && (weight_kg.is_none() || self.is_weight_stable())
+                    && self.duplicate_of(&product, weight_kg).is_none()
+                    && self
+                        .action_truncation(action, &product, weight_kg, piece_count)
+                        .is_none()
+                {
+                    return self.perform_express(action, product, weight_kg, piece_count);
+                }
+
+                // Show a confirmation dialog.
+                self.show_dialog(action, product, weight_kg, piece_count);
+
+                Ok(())
+            }
+
+            Focus::Dialog => {
+                let Some(Popup::Dialog(Dialog {
+                    action,
+                    product,
+                    weight_kg,
+                    piece_count,
+                    duplicate_of,
+                    payment_method,
+                    ..
+                })) = self.pop_popup()
+                else {
+                    panic!("Dialog is focused, but not present.");
+                };
+
+                // Back to the sale chunk (might be overridden by message).
+                self.main_focus = Focus::Sale;
+
+                // The held weight belongs to this dialog.
+                self.held_weight = None;
+
+                match self.selected_dialog_action() {
+                    DialogAction::Confirm => {
+                        let pending_count = self.pending_prints.len();
+
+                        if !self.execute_action(
+                            action,
+                            &product,
+                            weight_kg,
+                            piece_count,
+                            duplicate_of.is_some(),
+                            payment_method,
+                        )? {
+                            return Ok(());
+                        }
+
+                        // Show a success message unless the voucher waits for the printer.
+                        if self.pending_prints.len() == pending_count {
+                            self.show_message(
+                                MessageType::Info,
+                                String::from("Vorgang erfolgreich abgeschlossen"),
+                            );
+                        }
+                    }
+
+                    DialogAction::Cancel => (),
+                }
+
+                Ok(())
+            }
+
+            Focus::Message => {
+                // Back to the popup below (or the main screen).
+                self.pop_popup();
+
+                Ok(())
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    fn build_voucher(
+        &mut self,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+        printout: Printout,
+        layout: VoucherLayout,
+    ) -> Result<(GrayImage, VoucherMetadata), TemplateError> {
+        let VoucherLayout { width, dpi, preset } = layout;
+
+        // The text context is kept across vouchers, so fonts are only loaded once.
+        let text_ctx = self.voucher_text_ctx.take().unwrap_or_default();
+
+        let (voucher, text_ctx, metadata) = match printout {
+            Printout::Voucher {
+                sale_date,
+                expiration_override,
+            } => ProductVoucher::new(
+                product,
+                weight_kg,
+                self.engine.db().info(),
+                self.engine.db().settings(),
+            )
+            .piece_count(piece_count)
+            .language(self.active_voucher_language())
+            .date(sale_date.unwrap_or(self.now).with_timezone(&Local))
+            .expiration_override(expiration_override.map(|date| date.with_timezone(&Local)))
+            .dpi(dpi)
+            .preset(preset)
+            .build(width, text_ctx)?,
+
+            Printout::ShelfLabel => {
+                ShelfLabel::new(product, self.engine.db().settings()).build(width, text_ctx)?
+            }
+
+            Printout::Receipt {
+                number,
+                payment_method,
+            } => CustomerReceipt::new(
+                product,
+                weight_kg,
+                self.engine.db().info(),
+                self.engine.db().settings(),
+                number,
+            )
+            .piece_count(piece_count)
+            .payment_method(payment_method)
+            .date(self.now.with_timezone(&Local))
+            .build(width, text_ctx)?,
+        };
+
+        self.voucher_text_ctx = Some(text_ctx);
+        log_layout_issues(&metadata);
+
+        Ok((voucher, metadata))
+    }
+
+    /// Store a print attempt in the history.
+    /// Failing to do so must not interrupt the sale, so the error is only logged.
+    fn record_print_job(&self, job: PrintJobEntry) {
+        if let Err(err) = self.engine.db().add_print_job(&job) {
+            eprintln!("Failed to record print job: {err}");
+        }
+    }
+
+    /// Record every printed copy of a voucher in the print history.
+    fn record_printed_copies(&self, product: &ProductEntry, printout: Printout, copies: u64) {
+        for _ in 0..copies {
+            self.record_print_job(
+                PrintJobEntry::printed(self.now, product.name.clone())
+                    .for_sale(printout.sale_date()),
+            );
+        }
+    }
+
+    /// Estimate what is left on the label roll after a print.
+    /// Like the print history, this must not interrupt the sale.
+    fn record_label_usage(&mut self, label_type: LabelType, used_mm: f64) {
+        match self
+            .engine
+            .db()
+            .consume_label(label_type, self.now, used_mm)
+        {
+            Ok(roll) => self.label_roll = Some(roll),
+            Err(err) => eprintln!("Failed to record the label usage: {err}"),
+        }
+    }
+
+    /// Record a failed print attempt and show the error to the operator.
+    fn reject_print(&mut self, product: &ProductEntry, printout: Printout, text: String) -> bool {
+        self.record_print_job(
+            PrintJobEntry::failed(self.now, product.name.clone(), text.clone())
+                .for_sale(printout.sale_date()),
+        );
+
+        self.show_message(MessageType::Error, text);
+
+        false
+    }
+
+    /// Build the voucher for the label in the printer (with the configured margins), but don't print it.
+    /// On failure, the reason is returned for the operator.
+    fn layout_voucher(
+        &mut self,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+        printout: Printout,
+    ) -> Result<(GrayImage, VoucherMetadata, Label), String> {
+        let printer = self
+            .engine
+            .printer()
+            .map_err(|err| format!("Fehler beim Zugriff auf den Drucker: {}", err))?;
+
+        // Ask the printer for its current label.
+        let label = match printer.current_label() {
+            Ok(Some(label)) => label,
+
+            Ok(None) => {
+                return Err(String::from(
+                    "Fehler bei der Label-Abfrage: Es ist kein Label eingelegt.",
+                ))
+            }
+
+            Err(err) => return Err(format!("Fehler bei der Label-Abfrage: {}", err)),
+        };
+
+        // The layout depends on the label. Shelf labels can only be printed on continuous labels.
+        let preset = match Preset::for_label(&label) {
+            Some(preset)
+                if matches!(printout, Printout::Voucher { .. }) || preset.length.is_none() =>
+            {
+                preset
+            }
+
+            _ => {
+                // List the supported labels.
+                let mut text = format!(
+                    "Fehler bei der Label-Abfrage: Es werden nur {}, {} und {} (nur Bons) unterstützt.",
+                    Preset::CONTINUOUS_62.name,
+                    Preset::CONTINUOUS_102.name,
+                    Preset::DIE_CUT_62X100.name
+                );
+
+                let width = label.printable_dots_width;
+
+                if let Some(suggested_label) =
+                    self.suggest_label(product, weight_kg, piece_count, printout, width)
+                {
+                    text.push_str(&format!(
+                        " Bitte {} einlegen.",
+                        label_type_description(suggested_label)
+                    ));
+                }
+
+                return Err(text);
+            }
+        };
+
+        // The configured margins of the label are left blank, so the voucher shrinks by them.
+        let dpi = printer.capabilities().dpi;
+        let margins = self.engine.db().settings().label_margins.of(label.ty);
+
+        let Some(width) = label
+            .printable_dots_width
+            .checked_sub(margins.horz_dots(dpi))
+            .filter(|&width| width > 0)
+        else {
+            let text = format!(
+                "Die eingestellten Ränder sind breiter als das {}.",
+                label_type_description(label.ty)
+            );
+            return Err(text);
+        };
+
+        let preset = Preset {
+            length: preset
+                .length
+                .map(|length| length.saturating_sub(margins.vert_dots(dpi))),
+            ..preset
+        };
+
+        // Build the voucher.
+        // Use the width propagated by the label and the resolution of the printer.
+        let layout = VoucherLayout {
+            width,
+            dpi: Some(dpi),
+            preset,
+        };
+
+        let (voucher, metadata) =
+            match self.build_voucher(product, weight_kg, piece_count, printout, layout) {
+                Ok(voucher) => voucher,
+
+                Err(err) => return Err(format!("Fehler beim Erstellen des Bons: {}", err)),
+            };
+
+        Ok((margins.pad(&voucher, dpi), metadata, label))
+    }
+
+    /// The label type that fits the printout when it is laid out for a continuous label of `width`.
+    /// Only labels that the printer supports are suggested, so an unsupported die-cut label is not
+    /// simply replaced by a continuous roll of the same width (which might not exist).
+    fn suggest_label(
+        &mut self,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+        printout: Printout,
+        width: u32,
+    ) -> Option<LabelType> {
+        let dpi = self.engine.printer().ok()?.capabilities().dpi;
+
+        let layout = VoucherLayout {
+            width,
+            dpi: Some(dpi),
+            preset: Preset::CONTINUOUS_62,
+        };
+
+        let (voucher, _) = self
+            .build_voucher(product, weight_kg, piece_count, printout, layout)
+            .ok()?;
+
+        self.engine.printer().ok()?.nearest_label(&voucher)
+    }
+
+    /// The warning about truncated lines on the voucher the action would print (if any).
+    /// If the voucher cannot be built, there is nothing to warn about (printing fails anyway).
+    fn preview_truncation(
+        &mut self,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+        printout: Printout,
+    ) -> Option<String> {
+        let (_, metadata, _) = self
+            .layout_voucher(product, weight_kg, piece_count, printout)
+            .ok()?;
+
+        truncation_warning(&metadata)
+    }
+
+    /// The warning about truncated lines on the voucher of an action (if it prints one).
+    fn action_truncation(
+        &mut self,
+        action: Action,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+    ) -> Option<String> {
+        let printout = Printout::Voucher {
+            sale_date: action.sale.then_some(self.now),
+            expiration_override: None,
+        };
+
+        action
+            .print
+            .then(|| self.preview_truncation(product, weight_kg, piece_count, printout))
+            .flatten()
+    }
+
+    fn print_voucher(
+        &mut self,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+        printout: Printout,
+        copies: u64,
+        should_retry: bool,
+    ) -> Result<bool, Box<dyn Error>> {
+        // Check if a printer is present.
+        match self.engine.printer() {
+            Ok(_) => (),
+
+            Err(err) => {
+                // If there is no printer, try to reconnect it once.
+                if should_retry {
+                    self.reconnect_printer()?;
+                    return self.print_voucher(
+                        product,
+                        weight_kg,
+                        piece_count,
+                        printout,
+                        copies,
+                        false,
+                    );
+                }
+
+                // Show an error message.
+                let text = format!("Fehler beim Zugriff auf den Drucker: {}", err);
+                return Ok(self.reject_print(product, printout, text));
+            }
+        }
+
+        // Build the voucher for the current label.
+        let (voucher, label) = match self.layout_voucher(product, weight_kg, piece_count, printout)
+        {
+            Ok((voucher, _, label)) => (voucher, label),
+            Err(text) => return Ok(self.reject_print(product, printout, text)),
+        };
+
+        // Try to print it.
+        let printer = self
+            .engine
+            .printer()
+            .expect("Printer has been checked above");
+
+        // The copies are streamed as one job, which is much faster than a job per copy.
+        let used_mm = printer.consumed_mm(&label, &voucher);
+        let pages = vec![&voucher; copies as usize];
+
+        if let Err(err) = self.engine.print_pages(&pages, printout.permission()) {
+            // Errors of the engine (e.g. a missing permission) cannot be fixed at the printer.
+            let err = match err {
+                PosError::Print(err) => err,
+
+                err => {
+                    let text = format!("Fehler beim Drucken: {}", err);
+                    return Ok(self.reject_print(product, printout, text));
+                }
+            };
+
+            // If the operator can fix the printer (e.g. by closing the cover), keep the copies that are missing.
+            // They are printed automatically as soon as the status poll reports no more errors.
+            // If the printer has failed in the middle of a voucher, the operator is asked first.
+            if err.is_recoverable() {
+                let printed = self.engine.completed_pages() as u64;
+                self.record_printed_copies(product, printout, printed);
+                self.record_label_usage(label.ty, used_mm * printed as f64);
+
+                let partially_printed = matches!(err, PrintError::PrintingFailed(_));
+                let job = if partially_printed {
+                    PrintJobEntry::interrupted(self.now, product.name.clone(), err.to_string())
+                } else {
+                    PrintJobEntry::failed(self.now, product.name.clone(), err.to_string())
+                };
+
+                self.record_print_job(job.for_sale(printout.sale_date()));
+
+                self.pending_prints.push_back(PendingPrint {
+                    product: Box::new(product.clone()),
+                    weight_kg,
+                    piece_count,
+                    printout,
+                    copies: copies - printed,
+                    partially_printed,
+                });
+
+                let mut text = if printed > 0 {
+                    format!(
+                        "Der Druck wurde nach {} von {} Bons unterbrochen ({}).",
+                        printed, copies, err
+                    )
+                } else {
+                    format!("Der Druck wurde unterbrochen ({}).", err)
+                };
+
+                text.push_str(if partially_printed {
+                    " Der angefangene Bon ist eventuell unvollständig. Sobald der Fehler am Drucker behoben ist, wird nachgefragt, ob er nachgedruckt werden soll."
+                } else if printed > 0 {
+                    " Die übrigen Bons werden automatisch gedruckt, sobald der Fehler am Drucker behoben ist."
+                } else {
+                    " Der Bon wird automatisch gedruckt, sobald der Fehler am Drucker behoben ist."
+                });
+
+                self.show_message(MessageType::Info, text);
+
+                return Ok(true);
+            }
+
+            // Try a reconnect once on USB errors.
+            // The failed attempt is recorded nevertheless.
+            if matches!(err, PrintError::USBError(_)) && should_retry {
+                self.record_print_job(
+                    PrintJobEntry::failed(self.now, product.name.clone(), err.to_string())
+                        .for_sale(printout.sale_date()),
+                );
+
+                self.reconnect_printer()?;
+                return self.print_voucher(
+                    product,
+                    weight_kg,
+                    piece_count,
+                    printout,
+                    copies,
+                    false,
+                );
+            }
+
+            // If the label does not fit, tell the operator which one to insert.
+            if let PrintError::WrongImageDimensions {
+                suggested_label: Some(suggested_label),
+                ..
+            } = err
+            {
+                let text = format!(
+                    "Das eingelegte Etikett passt nicht zum Bon. Bitte {} einlegen.",
+                    label_type_description(suggested_label)
+                );
+                return Ok(self.reject_print(product, printout, text));
+            }
+
+            // Show an error message.
+            let text = format!("Fehler beim Drucken: {}", err);
+            return Ok(self.reject_print(product, printout, text));
+        }
+
+        self.record_printed_copies(product, printout, copies);
+        self.record_label_usage(label.ty, used_mm * copies as f64);
+        self.last_print = Some(PendingPrint {
+            product: Box::new(product.clone()),
+            weight_kg,
+            piece_count,
+            printout,
+            copies,
+            partially_printed: false,
+        });
+
+        Ok(true)
+    }
+
+    /// Print the summary of some sales on a continuous label.
+    /// Unlike vouchers, an interrupted slip is not kept, it can simply be printed again.
+    fn print_summary_slip(&mut self, sales: &[SaleEntry], shift: Option<&ShiftEntry>) {
+        let label = match self.engine.printer().map(Printer::current_label) {
+            Ok(Ok(Some(label))) => label,
+            Ok(Ok(None)) => {
+                let text =
+                    String::from("Fehler bei der Label-Abfrage: Es ist kein Label eingelegt.");
+                return self.reject_summary_slip(text);
+            }
+            Ok(Err(err)) => {
+                let text = format!("Fehler bei der Label-Abfrage: {}", err);
+                return self.reject_summary_slip(text);
+            }
+            Err(err) => {
+                let text = format!("Fehler beim Zugriff auf den Drucker: {}", err);
+                return self.reject_summary_slip(text);
+            }
+        };
+
+        // The length of the slip depends on the number of products.
+        if Preset::for_label(&label).is_none_or(|preset| preset.length.is_some()) {
+            let text = format!(
+                "Der Zwischenbericht kann nur auf {} oder {} gedruckt werden.",
+                Preset::CONTINUOUS_62.name,
+                Preset::CONTINUOUS_102.name
+            );
+            return self.reject_summary_slip(text);
+        }
+
+        let printer = self
+            .engine
+            .printer()
+            .expect("Printer has been checked above");
+        let dpi = printer.capabilities().dpi;
+        let margins = self.engine.db().settings().label_margins.of(label.ty);
+
+        let Some(width) = label
+            .printable_dots_width
+            .checked_sub(margins.horz_dots(dpi))
+            .filter(|&width| width > 0)
+        else {
+            let text = format!(
+                "Die eingestellten Ränder sind breiter als das {}.",
+                label_type_description(label.ty)
+            );
+            return self.reject_summary_slip(text);
+        };
+
+        let text_ctx = self.voucher_text_ctx.take().unwrap_or_default();
+
+        let mut slip =
+            SummarySlip::new(sales, self.engine.db().info()).date(self.now.with_timezone(&Local));
+
+        if let Some(shift) = shift {
+            slip = slip.shift(shift);
+        }
+
+        let slip = slip.build(width, text_ctx);
+
+        let slip = match slip {
+            Ok((slip, text_ctx, metadata)) => {
+                self.voucher_text_ctx = Some(text_ctx);
+                log_layout_issues(&metadata);
+
+                margins.pad(&slip, dpi)
+            }
+
+            Err(err) => {
+                let text = format!("Fehler beim Erstellen des Zwischenberichts: {}", err);
+                return self.reject_summary_slip(text);
+            }
+        };
+
+        let printer = self
+            .engine
+            .printer()
+            .expect("Printer has been checked above");
+        let used_mm = printer.consumed_mm(&label, &slip);
+
+        // The summary belongs to the sales, so everyone who may sell may print it.
+        if let Err(err) = self.engine.print_pages(&[&slip], Permissions::SELL) {
+            let text = format!("Fehler beim Drucken: {}", err);
+            return self.reject_summary_slip(text);
+        }
+
+        self.record_print_job(PrintJobEntry::printed(
+            self.now,
+            String::from(SUMMARY_SLIP_NAME),
+        ));
+        self.record_label_usage(label.ty, used_mm);
+    }
+
+    /// Record a failed summary slip and show the error to the operator.
+    fn reject_summary_slip(&mut self, text: String) {
+        self.record_print_job(PrintJobEntry::failed(
+            self.now,
+            String::from(SUMMARY_SLIP_NAME),
+            text.clone(),
+        ));
+
+        self.show_message(MessageType::Error, text);
+    }
+
+    /// Print the oldest interrupted voucher once the printer reports no more errors.
+    /// A voucher that might have been printed partially is only printed again if the operator agrees.
+    /// Open popups are not interrupted, the next status poll will try again.
+    fn retry_pending_print(&mut self) -> Result<(), Box<dyn Error>> {
+        // A sleeping printer is woken up by the print itself.
+        let flags = self
+            .engine
+            .printer_error_flags()
+            .map(|flags| flags.difference(StatusErrorFlags::TURNED_OFF));
+
+        if (flags != Some(StatusErrorFlags::empty())) || !self.popups.is_empty() {
+            return Ok(());
+        }
+
+        let Some(pending) = self.pending_prints.pop_front() else {
+            return Ok(());
+        };
+
+        if pending.partially_printed {
+            self.push_popup(Popup::Reprint { pending });
+            return Ok(());
+        }
+
+        self.print_pending(pending)
+    }
+
+    /// Resolve a voucher that might have been printed partially:
+    /// `n` prints the missing copies again, `Esc` drops them (e.g. because the voucher is complete after all).
+    fn handle_reprint_key(&mut self, code: KeyCode) -> Result<(), Box<dyn Error>> {
+        let Some(Popup::Reprint { pending }) = self.popup() else {
+            panic!("Reprint is focused, but not present.");
+        };
+
+        let pending = pending.clone();
+
+        match code {
+            KeyCode::Char('n') => {
+                self.pop_popup();
+                self.print_pending(pending)?;
+            }
+
+            KeyCode::Esc => {
+                self.pop_popup();
+            }
+
             _ => (),
         }
-    }
 
-    fn perform_action(&mut self) -> Result<(), Box<dyn Error>> {
-        match self.focus {
-            Focus::Sale => {
-                // If there is no product or weight, we exit early.
-                // Because we must cache the product in the confirmation dialog, it must be cloned.
-                let Some(product) = self.selected_product().cloned() else {
-                    return Ok(());
-                };
+        Ok(())
+    }
+
+    /// Print an interrupted voucher again.
+    fn print_pending(&mut self, pending: PendingPrint) -> Result<(), Box<dyn Error>> {
+        // A recoverable error puts the voucher back into the queue, so this is no success.
+        let queue_len = self.pending_prints.len();
+
+        if self.print_voucher(
+            &pending.product,
+            pending.weight_kg,
+            pending.piece_count,
+            pending.printout,
+            pending.copies,
+            true,
+        )? && (self.pending_prints.len() == queue_len)
+        {
+            self.show_message(
+                MessageType::Info,
+                format!(
+                    "Der unterbrochene Bon für \"{}\" wurde nachgedruckt.",
+                    pending.product.name
+                ),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Print and book a confirmed action.
+    /// Returns `false` if it has been aborted (the reason has been shown to the operator).
+    fn execute_action(
+        &mut self,
+        action: Action,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+        duplicate_override: bool,
+        payment_method: Option<PaymentMethod>,
+    ) -> Result<bool, Box<dyn Error>> {
+        // Remember the action to detect a repetition.
+        self.last_confirmed_action = Some(ConfirmedAction {
+            date: self.now,
+            product_name: product.name.clone(),
+            weight_kg,
+        });
+
+        // Should we print a voucher?
+        // An interrupted print is queued, so the sale is performed nevertheless.
+        let expiration_override = self.expiration_override(product, action.expiration_offset_days);
+        let printout = Printout::Voucher {
+            sale_date: action.sale.then_some(self.now),
+            expiration_override,
+        };
+
+        if action.print
+            && !self.print_voucher(
+                product,
+                weight_kg,
+                piece_count,
+                printout,
+                action.copies,
+                true,
+            )?
+        {
+            return Ok(false);
+        }
+
+        // Should we print a shelf label?
+        if action.shelf_label
+            && !self.print_voucher(product, None, None, Printout::ShelfLabel, 1, true)?
+        {
+            return Ok(false);
+        }
+
+        // Should we add a sale?
+        if action.sale
+            && !self.perform_sale(
+                product,
+                weight_kg,
+                piece_count,
+                duplicate_override,
+                payment_method,
+                expiration_override,
+            )?
+        {
+            return Ok(false);
+        }
+
+        // Should we print a receipt for the customer?
+        // It is numbered once, so an interrupted print keeps its number.
+        if action.receipt {
+            let total_ct = product.total_price_ct(
+                weight_kg,
+                piece_count,
+                self.engine.db().settings().rounding_mode,
+            );
+            let number = self.engine.db().add_receipt(self.now, total_ct)?;
+            let printout = Printout::Receipt {
+                number,
+                payment_method,
+            };
+
+            if !self.print_voucher(product, weight_kg, piece_count, printout, 1, true)? {
+                return Ok(false);
+            }
+        }
+
+        // Should we dump a voucher?
+        if action.dump {
+            self.dump_voucher(product, weight_kg, piece_count);
+        }
+
+        Ok(true)
+    }
+
+    /// Perform the action without the confirmation dialog.
+    /// Sales can be undone for a while, so there is no success message in the way of the next one.
+    fn perform_express(
+        &mut self,
+        action: Action,
+        product: ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+    ) -> Result<(), Box<dyn Error>> {
+        // The held weight belongs to this sale.
+        self.held_weight = None;
+        self.express_sale = None;
+        self.needs_redraw = true;
+
+        // Without a dialog, the copies of the product cannot be changed.
+        let action = Action {
+            copies: product.default_copies,
+            ..action
+        };
+
+        if !self.execute_action(action, &product, weight_kg, piece_count, false, None)? {
+            return Ok(());
+        }
+
+        if action.sale {
+            let undo_window =
+                TimeDelta::try_seconds(self.engine.db().settings().express_undo_secs as _)
+                    .expect("Undo window out of bound");
+
+            self.express_sale = Some(ExpressSale {
+                sale: self.new_sale(&product, weight_kg, piece_count),
+                deadline: self.now + undo_window,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Take back the last express sale while its undo window is open.
+    /// The voucher has already been printed, so the operator is asked to throw it away.
+    fn undo_express_sale(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.express_sale.is_some()
+            && !self.is_permitted(Permissions::VOID, "Das Rückgängigmachen von Verkäufen")
+        {
+            return Ok(());
+        }
+
+        let Some(express_sale) = self.express_sale.take() else {
+            self.show_message(
+                MessageType::Error,
+                String::from("Es gibt keinen Expressverkauf, der rückgängig gemacht werden kann."),
+            );
 
-                let weight_kg = if product.is_kg_price {
-                    // We need a weight, this is no fixed-price product.
-                    let weight_kg = match self.weight() {
-                        Ok(weight) => weight,
+            return Ok(());
+        };
 
-                        Err(err) => {
-                            // Show an error message.
-                            self.show_message(
-                                MessageType::Error,
-                                format!("Fehler beim Zugriff auf die Waage: {}", err),
-                            );
+        let sale = express_sale.sale;
 
-                            return Ok(());
-                        }
-                    };
+        if !self.engine.void_sale(&sale)? {
+            self.show_message(
+                MessageType::Error,
+                format!("Der Verkauf von \"{}\" wurde nicht gefunden.", sale.name),
+            );
 
-                    // If the weight is negative, we also show an error.
-                    if weight_kg < 0.0 {
-                        self.show_message(
-                            MessageType::Error,
-                            String::from("Untergewicht (< 0.0 kg) auf der Waage"),
-                        );
+            return Ok(());
+        }
 
-                        return Ok(());
-                    }
+        // The voucher belongs to no sale anymore, which is no interruption.
+        if let Err(err) = self
+            .engine
+            .discard_voucher(self.now, sale.date, sale.name.clone())
+        {
+            eprintln!("Failed to record the discarded voucher: {err}");
+        }
 
-                    Some(weight_kg)
-                } else {
-                    None
-                };
+        // The sale may be repeated right away.
+        self.last_confirmed_action = None;
 
-                // Show a confirmation dialog.
-                self.show_dialog(self.selected_action(), product, weight_kg);
+        self.show_message(
+            MessageType::Info,
+            format!(
+                "Der Verkauf von \"{}\" über {} wurde rückgängig gemacht. Bitte den Bon entsorgen.",
+                sale.name,
+                format::euro(sale.total_ct)
+            ),
+        );
 
-                Ok(())
-            }
+        Ok(())
+    }
 
-            Focus::Dialog => {
-                let Some(Popup::Dialog {
-                    action,
-                    product,
-                    weight_kg,
-                }) = self.popup.take()
-                else {
-                    panic!("Dialog is focused, but not present.");
-                };
+    fn perform_sale(
+        &mut self,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+        duplicate_override: bool,
+        payment_method: Option<PaymentMethod>,
+        expiration_override: Option<DateTime<Utc>>,
+    ) -> Result<bool, Box<dyn Error>> {
+        let mut sale = self.new_sale(product, weight_kg, piece_count);
+        sale.duplicate_override = duplicate_override;
+        sale.payment_method = payment_method;
+        sale.expiration_override = expiration_override;
+
+        // Negative weights never skip the dialog, so the operator has confirmed them.
+        let is_underweight = weight_kg.is_some_and(|weight_kg| weight_kg < 0.0);
+        let frame = self.weight_frame();
+
+        // If the DB fails, the engine keeps the sale in the journal.
+        let result = if is_underweight {
+            self.engine.store_confirmed_sale(&sale, frame)
+        } else {
+            self.engine.store_sale(&sale, frame)
+        };
 
-                // Back to the sale chunk (might be overridden by message).
-                self.focus = Focus::Sale;
+        match result {
+            Ok(()) => Ok(true),
 
-                match self.selected_dialog_action() {
-                    DialogAction::Confirm => {
-                        // Should we print a voucher?
-                        if action.print && !self.print_voucher(&product, weight_kg, true)? {
-                            return Ok(());
-                        }
+            Err(PosError::SaleJournaled(db_err)) => {
+                self.show_message(
+                    MessageType::Error,
+                    format!(
+                        "Fehler beim Speichern in der Datenbank: {}. Der Verkauf wurde zwischengespeichert und wird beim nächsten Start übernommen.",
+                        db_err
+                    ),
+                );
 
-                        // Should we add a sale?
-                        if action.sale && !self.perform_sale(&product, weight_kg)? {
-                            return Ok(());
-                        }
+                Ok(false)
+            }
 
-                        // Should we dump a voucher?
-                        if action.dump {
-                            self.dump_voucher(&product, weight_kg);
-                        }
+            Err(PosError::SaleLost(db_err, journal_err)) => {
+                self.show_message(
+                    MessageType::Error,
+                    format!(
+                        "Der Verkauf konnte nicht gespeichert werden: {} ({})",
+                        db_err, journal_err
+                    ),
+                );
 
-                        // Show a success message.
-                        self.show_message(
-                            MessageType::Info,
-                            String::from("Vorgang erfolgreich abgeschlossen"),
-                        );
-                    }
+                Ok(false)
+            }
 
-                    DialogAction::Cancel => {
-                        // Back to the sale chunk.
-                        self.focus = Focus::Sale;
-                    }
-                }
+            Err(PosError::DiskFull { .. }) => {
+                self.show_disk_full();
+                Ok(false)
+            }
 
-                Ok(())
+            Err(PosError::NotPermitted { .. }) => {
+                self.reject_permission("Das Verbuchen von Verkäufen");
+                Ok(false)
             }
 
-            Focus::Message => {
-                // Back to the sale chunk.
-                self.popup = None;
-                self.focus = Focus::Sale;
+            Err(PosError::NegativeWeight) => {
+                self.show_message(
+                    MessageType::Error,
+                    String::from("Untergewicht (< 0.0 kg) kann nicht verbucht werden."),
+                );
 
-                Ok(())
+                Ok(false)
             }
 
-            _ => Ok(()),
+            Err(err) => Err(err.into()),
         }
     }
 
-    fn build_voucher(
+    fn show_disk_full(&mut self) {
+        let free_mb = self.engine.storage().map_or(0, |storage| storage.free_mb());
+
+        self.show_message(
+            MessageType::Error,
+            format!(
+                "Der Speicherplatz ist fast erschöpft ({} MB frei). Verkäufe sind gesperrt, bis Platz geschaffen wurde.",
+                free_mb
+            ),
+        );
+    }
+
+    /// The sale of the product at this moment
+    fn new_sale(
         &self,
         product: &ProductEntry,
         weight_kg: Option<f64>,
-        width: u32,
-    ) -> GrayImage {
-        // Calculate the price.
-        let (weight_str, price_ct) = if product.is_kg_price {
-            let weight_kg = weight_kg.expect("Product with kg price needs weight");
-            let weight_str = format!("{:.3} kg", weight_kg).replacen('.', ",", 1);
-            let price_ct = weight_kg * (product.price_ct as f64);
-
-            (weight_str, price_ct)
-        } else {
-            (String::from("-"), product.price_ct as f64)
-        };
+        piece_count: Option<u64>,
+    ) -> SaleEntry {
+        let total_ct = product.total_price_ct(
+            weight_kg,
+            piece_count,
+            self.engine.db().settings().rounding_mode,
+        );
+
+        let mut sale = SaleEntry::new(
+            self.now,
+            product.name.clone(),
+            weight_kg,
+            product.price_ct,
+            total_ct,
+        )
+        .attributed_to(self.engine.attribution());
+        sale.piece_count = piece_count;
 
-        let price_str = format!("{:.2} €", price_ct / 100.0).replacen('.', ",", 1);
+        sale
+    }
 
-        // Load the logo.
-        let logo = ImageReader::open("logo.png")
-            .expect("Failed to load logo")
-            .decode()
-            .expect("Failed to decode logo");
+    fn dump_voucher(
+        &mut self,
+        product: &ProductEntry,
+        weight_kg: Option<f64>,
+        piece_count: Option<u64>,
+    ) {
+        // TODO: Allow to configure the width.
+        let layout = VoucherLayout {
+            width: 720,
+            dpi: None,
+            preset: Preset::default(),
+        };
 
-        // Format the product parameters.
-        let storage_temp = product.storage_temp_formatted();
-        let mhd = product.expiration_date_formatted();
+        let voucher = match self.build_voucher(
+            product,
+            weight_kg,
+            piece_count,
+            Printout::Voucher {
+                sale_date: None,
+                expiration_override: None,
+            },
+            layout,
+        ) {
+            Ok((voucher, _)) => voucher,
 
-        let storage = match (storage_temp, mhd) {
-            (None, None) => String::from(""),
-            (Some(temp), None) => format!("Lagerungstemperatur: {}", temp),
-            (None, Some(mhd)) => format!("Ungeöffnet mindestens haltbar bis: {}", mhd),
-            (Some(temp), Some(mhd)) => {
-                format!("Ungeöffnet bei {} mindestens haltbar bis: {}", temp, mhd)
+            Err(err) => {
+                eprintln!("Failed to build voucher: {err}");
+                return;
             }
         };
 
-        // Build the trailer.
-        let info = self.db.info();
+        if let Err(err) = voucher.save_with_format("voucher.png", ImageFormat::Png) {
+            eprintln!("Failed to dump voucher: {err}");
+        }
+    }
+
+    /// Check if the UI must be redrawn after the pending events have been handled.
+    fn should_redraw(&self) -> bool {
+        // The screensaver only changes every few seconds.
+        if self.is_screensaver_active() {
+            return self.screensaver_drawn_step != Some(self.screensaver_step());
+        }
 
-        let trailer = format!(
-            "{} · {} · {}, {}, · {} · {}",
-            info.business, info.owners, info.street, info.locality, info.phone, info.mail
-        );
+        if self.engine.db().settings().ui_redraw == RedrawStrategy::Always {
+            return true;
+        }
 
-        // Finally, construct the voucher.
-        VoucherBuilder::new(width)
-            // Logo
-            .start_image_component(&logo)
-            .spacing(VoucherSpacing::horz_vert(20.0, 20.0))
-            .finalize_image_component()
-            // Product
-            .start_text_component(&product.name)
-            .spacing(VoucherSpacing::horz_vert(16.0, 16.0))
-            .font_size(50.0)
-            .alignment(VoucherAlignment::Center)
-            .bold(true)
-            .finalize_text_component()
-            // Weight
-            .start_text_component(&format!("Gewicht: {}", weight_str))
-            .spacing(VoucherSpacing::horz_vert(16.0, 12.0))
-            .font_size(25.0)
-            .finalize_text_component()
-            // Price
-            .start_text_component(&format!("Preis: {}", price_str))
-            .spacing(VoucherSpacing::horz_vert(16.0, 24.0))
-            .font_size(40.0)
-            .bold(true)
-            .finalize_text_component()
-            // Ingredients
-            .start_text_component(&format!("Zutaten: {}", product.ingredients))
-            .spacing(VoucherSpacing::horz_vert(16.0, 12.0))
-            .font_size(25.0)
-            .finalize_text_component()
-            // Additionals
-            .start_text_component(&product.additional_info)
-            .spacing(VoucherSpacing::horz_vert(16.0, 12.0))
-            .font_size(25.0)
-            .finalize_text_component()
-            // Storage
-            .start_text_component(&storage)
-            .spacing(VoucherSpacing::horz_vert(16.0, 12.0))
-            .font_size(25.0)
-            .finalize_text_component()
-            // Trailer
-            .start_text_component(&trailer)
-            .spacing(VoucherSpacing::lrtb(8.0, 8.0, 48.0, 8.0))
-            .font_size(21.0)
-            .alignment(VoucherAlignment::Center)
-            .italic(true)
-            .finalize_text_component()
-            .build()
+        // Popups show live values (e.g. diagnostics) or ages (e.g. duplicate sales).
+        // The undo window of an express sale counts down.
+        // The expiration dates change at midnight, so redraw once a minute anyway.
+        self.needs_redraw
+            || !self.popups.is_empty()
+            || self.express_sale.is_some()
+            || (self.is_scale_alarm_active() != self.is_scale_alarm_drawn)
+            || ((self.now - self.drawn_date) >= TimeDelta::try_minutes(1).unwrap())
     }
 
-    fn print_voucher(
-        &mut self,
-        product: &ProductEntry,
-        weight_kg: Option<f64>,
-        should_retry: bool,
-    ) -> Result<bool, Box<dyn Error>> {
-        // Check if a printer is present.
-        let printer = match &self.printer {
-            Ok(printer) => printer,
+    fn handle_event(&mut self, event: AppEvent) -> Result<bool, Box<dyn Error>> {
+        // Ticks only cause a redraw if they change something.
+        // Printer status polls are handled below.
+        match &event {
+            AppEvent::Tick => (),
+            AppEvent::PrinterStatus(flags) => {
+                self.needs_redraw |= *flags != self.engine.printer_error_flags();
+            }
 
-            Err(err) => {
-                // If there is no printer, try to reconnect it once.
-                if should_retry {
-                    self.reconnect_printer()?;
-                    return self.print_voucher(product, weight_kg, false);
-                }
+            _ => self.needs_redraw = true,
+        }
 
-                // Show an error message.
-                self.show_message(
-                    MessageType::Error,
-                    format!("Fehler beim Zugriff auf den Drucker: {}", err),
-                );
+        // Keys and buttons only wake the screensaver, so nothing happens by accident.
+        if matches!(event, AppEvent::Input(Event::Key(_)) | AppEvent::Button(_)) {
+            let was_screensaver_active = self.is_screensaver_active();
+            self.input_date = self.now;
 
-                return Ok(false);
+            if was_screensaver_active {
+                return Ok(true);
             }
-        };
+        }
 
-        // Ask the printer for its current label.
-        let label = match printer.current_label() {
-            Ok(Some(label)) => label,
+        match event {
+            // While typing, all keys belong to the input.
+            AppEvent::Input(Event::Key(key)) if self.focus() == Focus::Input => {
+                self.handle_input_key(key.code)?
+            }
 
-            Ok(None) => {
-                // Show an error message.
-                self.show_message(
-                    MessageType::Error,
-                    String::from("Fehler bei der Label-Abfrage: Es ist kein Label eingelegt."),
-                );
+            AppEvent::Input(Event::Key(key)) if self.focus() == Focus::DatePicker => {
+                self.handle_date_picker_key(key.code)?
+            }
 
-                return Ok(false);
+            AppEvent::Input(Event::Key(key)) if self.focus() == Focus::Recovery => {
+                self.handle_recovery_key(key.code)?
             }
 
-            Err(err) => {
-                // Show an error message.
-                self.show_message(
-                    MessageType::Error,
-                    format!("Fehler bei der Label-Abfrage: {}", err),
-                );
+            AppEvent::Input(Event::Key(key)) if self.focus() == Focus::Reprint => {
+                self.handle_reprint_key(key.code)?
+            }
 
-                return Ok(false);
+            // The command is performed like its key where the palette has been opened.
+            AppEvent::Input(Event::Key(key)) if self.focus() == Focus::Palette => {
+                if let Some(event) = self.handle_palette_key(key.code) {
+                    return self.handle_event(event);
+                }
             }
-        };
 
-        // At the moment, we only support continuous labels.
-        if !matches!(label.ty, LabelType::Continuous { .. }) {
-            // Show an error message.
-            self.show_message(
-                MessageType::Error,
-                String::from("Fehler bei der Label-Abfrage: Es werden derzeit nur laufende Labels unterstützt."),
-            );
+            // Popups only know their own keys, so e.g. 'q' doesn't quit in the middle of a sale.
+            AppEvent::Input(Event::Key(key)) if self.focus() == Focus::Dialog => {
+                self.handle_dialog_key(key.code)?
+            }
 
-            return Ok(false);
-        }
+            AppEvent::Input(Event::Key(key)) if self.focus() == Focus::Message => {
+                self.handle_message_key(key.code)?
+            }
 
-        // Build the voucher.
-        // Use the width propagated by the label.
-        let voucher = self.build_voucher(product, weight_kg, label.printable_dots_width);
+            AppEvent::Input(Event::Key(key))
+                if (key.code == KeyCode::Char('p'))
+                    && key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.show_palette()
+            }
 
-        // Try to print it.
-        if let Err(err) = printer.print(&voucher) {
-            // Try a reconnect once on USB errors.
-            if matches!(err, PrintError::USBError(_)) {
-                self.reconnect_printer()?;
-                return self.print_voucher(product, weight_kg, false);
+            // Without a popup, the letters are global commands.
+            AppEvent::Input(Event::Key(key)) => match key.code {
+                KeyCode::Char('q') => return Ok(false),
+                KeyCode::Char('r') => self.events.send(AppEvent::DbChanged),
+                KeyCode::Char('h') => self.toggle_weight_hold(),
+                KeyCode::Char('z') => self.toggle_piece_counter(),
+                KeyCode::Char('b') => self.toggle_batch(),
+                KeyCode::Char('t') => self.engine.reset_tare(),
+                KeyCode::Char('m') => self.edit_daily_message(),
+                KeyCode::Char('l') => self.show_print_jobs()?,
+                KeyCode::Char('i') => self.show_diagnostics(),
+                KeyCode::Char('u') => self.cycle_voucher_language(),
+                KeyCode::Char('x') => self.undo_express_sale()?,
+                KeyCode::Char('w') => self.change_label_roll()?,
+                KeyCode::Char('n') => self.reprint()?,
+                KeyCode::Char('s') => self.show_input(InputPurpose::SalesSummary, String::new()),
+                KeyCode::Char('o') => self.toggle_shift(),
+                KeyCode::Char('e') => self.edit_product(ProductEdit::Price),
+                KeyCode::Char('c') => self.edit_product(ProductEdit::Duplicate),
+                KeyCode::Char('v') => self.show_sales_history(SaleFilter::default())?,
+                KeyCode::Up => self.navigate(Navigation::Up),
+                KeyCode::Down => self.navigate(Navigation::Down),
+                KeyCode::Left => self.navigate(Navigation::Left),
+                KeyCode::Right => self.navigate(Navigation::Right),
+                KeyCode::Enter => self.perform_action()?,
+                KeyCode::Esc => self.go_back(),
+
+                _ => {}
+            },
+
+            AppEvent::Input(_) => (),
+            AppEvent::Button(action) => self.handle_button(action)?,
+            AppEvent::Tick => self.on_tick()?,
+            AppEvent::Weight(weight) => self.update_weight(weight),
+            AppEvent::PrinterStatus(flags) => {
+                self.engine.set_printer_error_flags(flags);
+
+                // Retrying might print and show messages.
+                self.needs_redraw |= !self.pending_prints.is_empty();
+                self.retry_pending_print()?;
             }
 
-            // Show an error message.
-            self.show_message(MessageType::Error, format!("Fehler beim Drucken: {}", err));
+            AppEvent::DbChanged => self.reload_db()?,
 
-            return Ok(false);
+            AppEvent::UpdateAvailable(version) => self.available_update = Some(version),
+
+            AppEvent::WorkerFailed(err) => return Err(err.into()),
         }
 
         Ok(true)
     }
 
-    fn perform_sale(
-        &self,
-        product: &ProductEntry,
-        weight_kg: Option<f64>,
-    ) -> Result<bool, Box<dyn Error>> {
-        let sale = SaleEntry::new(self.now, product.name.clone(), weight_kg, product.price_ct);
-        self.db.add_sale(&sale)?;
+    /// Handle the keys of the confirmation dialog.
+    fn handle_dialog_key(&mut self, code: KeyCode) -> Result<(), Box<dyn Error>> {
+        match code {
+            KeyCode::Char('h') => self.toggle_weight_hold(),
+            KeyCode::Char('k') => self.toggle_customer_receipt(),
+            KeyCode::Char('+') => self.change_copies(true),
+            KeyCode::Char('-') => self.change_copies(false),
+            KeyCode::Char('<') => self.change_expiration_offset(false),
+            KeyCode::Char('>') => self.change_expiration_offset(true),
+            KeyCode::Up => self.navigate(Navigation::Up),
+            KeyCode::Down => self.navigate(Navigation::Down),
+            KeyCode::Left => self.navigate(Navigation::Left),
+            KeyCode::Right => self.navigate(Navigation::Right),
+            KeyCode::Enter => self.perform_action()?,
+            KeyCode::Esc => self.go_back(),
 
-        Ok(true)
+            _ => (),
+        }
+
+        Ok(())
     }
 
-    fn dump_voucher(&self, product: &ProductEntry, weight_kg: Option<f64>) {
-        // TODO: Allow to configure the width.
-        if let Err(err) = self
-            .build_voucher(product, weight_kg, 720)
-            .save_with_format("voucher.png", ImageFormat::Png)
-        {
-            eprintln!("Failed to dump voucher: {err}");
+    /// Handle the keys of messages and views (which are closed with `Enter` or `Esc`).
+    fn handle_message_key(&mut self, code: KeyCode) -> Result<(), Box<dyn Error>> {
+        match code {
+            // The filters of the sales history
+            KeyCode::Char('p') => self.toggle_sales_product_filter()?,
+            KeyCode::Char('f') => self.pick_sales_dates(),
+            KeyCode::Char('d') => self.update_sales_filter(|filter| {
+                filter.duplicate_override = !filter.duplicate_override
+            })?,
+            KeyCode::Char('s') if matches!(self.popup(), Some(Popup::SalesHistory { .. })) => {
+                self.show_input(InputPurpose::SalesSummary, String::new())
+            }
+            KeyCode::Enter => self.perform_action()?,
+            KeyCode::Esc => self.go_back(),
+
+            _ => (),
         }
+
+        Ok(())
     }
 
     fn run_in_terminal<B: Backend>(
@@ -593,59 +3524,89 @@ impl App {
         // Perform the startup logic.
         self.on_startup()?;
 
-        // Track the time to provide the application with a tick.
-        let tick_rate = TimeDelta::try_milliseconds(250).unwrap();
-        let mut last_tick = self.now;
+        // Start the workers that feed the event bus.
+        self.events.spawn_input_worker();
+        self.tick_ms
+            .store(self.engine.db().settings().ui_tick_ms, Ordering::Relaxed);
+        self.events.spawn_tick_worker(self.tick_ms.clone());
+
+        #[cfg(feature = "gpio")]
+        if !self.engine.db().settings().gpio_buttons.is_empty() {
+            let settings = self.engine.db().settings();
+
+            self.events.spawn_button_worker(
+                settings.gpio_buttons.clone(),
+                settings.gpio_buttons_active_low,
+            );
+        }
+
+        if self.engine.db().settings().update_check {
+            self.events.spawn_update_worker();
+        }
+
+        let weight_sender = self.events.sender();
+
+        self.engine.scales().set_listener(move |weight| {
+            _ = weight_sender.send(AppEvent::Weight(weight.clone()));
+        });
 
         loop {
-            // Set the current timestamp.
-            self.now = Utc::now();
-            let time_since_last_tick = self.now - last_tick;
-
-            // Draw the UI.
-            terminal.draw(|frame| self.draw_ui(frame))?;
-
-            // Poll the terminal for events.
-            // Make sure that we don't miss the next tick.
-            let timeout = (tick_rate - time_since_last_tick).max(TimeDelta::zero());
-
-            if event::poll(timeout.to_std().unwrap())? {
-                // Handle key events.
-                if let Event::Key(key) = event::read()? {
-                    match key.code {
-                        KeyCode::Char('q') => return Ok(()),
-                        KeyCode::Char('r') => {
-                            self.db.reload_info()?;
-                            self.db.reload_products()?;
-                            self.reset_selected_product_idx();
-                        }
-                        KeyCode::Up => self.navigate(Navigation::Up),
-                        KeyCode::Down => self.navigate(Navigation::Down),
-                        KeyCode::Left => self.navigate(Navigation::Left),
-                        KeyCode::Right => self.navigate(Navigation::Right),
-                        KeyCode::Enter => self.perform_action()?,
+            // Draw the UI (unless nothing has changed).
+            if self.should_redraw() {
+                terminal.draw(|frame| self.draw_ui(frame))?;
 
-                        _ => {}
-                    }
-                }
+                self.needs_redraw = false;
+                self.drawn_date = self.now;
             }
 
-            if time_since_last_tick >= tick_rate {
-                self.on_tick()?;
-                last_tick = self.now;
+            // Block until something happens.
+            // Then handle everything that is pending before we redraw.
+            let mut event = Some(self.events.recv());
+
+            while let Some(next_event) = event {
+                self.now = Utc::now();
+
+                if !self.handle_event(next_event)? {
+                    return Ok(());
+                }
+
+                event = self.events.try_recv();
             }
         }
     }
 
     fn draw_ui<B: Backend>(&mut self, frame: &mut Frame<B>) {
-        // Split the window into body and status line.
+        // The screensaver hides everything else.
+        if self.is_screensaver_active() {
+            self.screensaver_drawn_step = Some(self.screensaver_step());
+            self.draw_screensaver_chunk(frame, frame.size());
+            self.draw_filters(frame);
+
+            return;
+        }
+
+        self.screensaver_drawn_step = None;
+
+        // Split the window into alarm banner, body and status lines.
+        let status = self.build_status();
+        let is_scale_alarm_active = self.is_scale_alarm_active();
+        self.is_scale_alarm_drawn = is_scale_alarm_active;
+
         let vert_chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(3), Constraint::Length(4)].as_ref())
+            .constraints(
+                [
+                    Constraint::Length(is_scale_alarm_active as u16),
+                    Constraint::Min(3),
+                    Constraint::Length(2 + (status.len() as u16)),
+                ]
+                .as_ref(),
+            )
             .split(frame.size());
 
-        let body_chunk = vert_chunks[0];
-        let status_chunk = vert_chunks[1];
+        let alarm_chunk = vert_chunks[0];
+        let body_chunk = vert_chunks[1];
+        let status_chunk = vert_chunks[2];
 
         // Split the body into product and selection.
         let horz_chunks = Layout::default()
@@ -657,19 +3618,53 @@ impl App {
         let sale_chunk = horz_chunks[1];
 
         // Draw the chunks.
+        if is_scale_alarm_active {
+            self.draw_alarm_chunk(frame, alarm_chunk);
+        }
+
         self.draw_product_chunk(frame, product_chunk);
         self.draw_sale_chunk(frame, sale_chunk);
-        self.draw_status_chunk(frame, status_chunk);
+        self.draw_status_chunk(frame, status_chunk, status);
 
-        // Is there a popup?
+        // Are there popups?
+        // They are drawn from bottom to top, so nested ones appear above their parents.
         // Borrow checker shenanigans ...
-        let popup = self.popup.take();
+        let popups = std::mem::take(&mut self.popups);
 
-        if let Some(popup) = &popup {
+        for popup in &popups {
             // Crop a centered rectangle to render the popup into.
             let (percent_x, percent_y, min_y) = match popup {
-                Popup::Dialog { .. } => (70, 15, 1 + (self.actions_count() as u16) + 2 + 1),
+                Popup::Dialog(Dialog {
+                    action,
+                    product,
+                    weight_kg,
+                    duplicate_of,
+                    payment_method,
+                    voucher_warning,
+                    ..
+                }) => (
+                    70,
+                    15,
+                    1 + (self.actions_count() as u16)
+                        + (action.sale as u16)
+                        + (self.can_override_expiration(*action, product) as u16)
+                        + (duplicate_of.is_some() as u16)
+                        + (payment_method.is_some() as u16)
+                        + (voucher_warning.is_some() as u16)
+                        + (weight_kg.is_some_and(|weight_kg| weight_kg < 0.0) as u16)
+                        + 2
+                        + 1,
+                ),
                 Popup::Message { .. } => (70, 10, 3),
+                Popup::Input { .. } => (70, 10, 3),
+                Popup::PrintJobs { .. } => (80, 60, 10),
+                Popup::SalesHistory { .. } => (80, 60, 10),
+                Popup::Diagnostics => (80, 60, 19),
+                Popup::Preflight { preflight } => (70, 30, (preflight.results.len() as u16) + 4),
+                Popup::SalesDates { .. } => (70, 20, 6),
+                Popup::Recovery { .. } => (70, 20, 7),
+                Popup::Reprint { .. } => (70, 20, 8),
+                Popup::Palette { .. } => (60, 50, 8),
             };
 
             let popup_chunk = Layout::default()
@@ -701,44 +3696,159 @@ impl App {
 
             // Render the popup.
             match popup {
-                Popup::Dialog {
-                    action,
-                    product,
-                    weight_kg,
-                } => self.draw_dialog_chunk(frame, popup_chunk, *action, product, *weight_kg),
+                Popup::Dialog(dialog) => self.draw_dialog_chunk(frame, popup_chunk, dialog),
 
                 Popup::Message { ty, text } => {
                     self.draw_message_chunk(frame, popup_chunk, *ty, text)
                 }
+
+                Popup::Input { purpose, text } => {
+                    self.draw_input_chunk(frame, popup_chunk, *purpose, text)
+                }
+
+                Popup::PrintJobs { jobs } => self.draw_print_job_chunk(frame, popup_chunk, jobs),
+                Popup::SalesHistory {
+                    filter,
+                    sales,
+                    summary,
+                } => self.draw_sales_history_chunk(frame, popup_chunk, filter, sales, summary),
+                Popup::Diagnostics => self.draw_diagnostics_chunk(frame, popup_chunk),
+                Popup::Preflight { preflight } => {
+                    self.draw_preflight_chunk(frame, popup_chunk, preflight)
+                }
+                Popup::SalesDates { picker } => {
+                    picker.draw(frame, popup_chunk, "Zeitraum der Verkäufe")
+                }
+                Popup::Recovery { interrupted } => {
+                    self.draw_recovery_chunk(frame, popup_chunk, interrupted)
+                }
+                Popup::Reprint { pending } => self.draw_reprint_chunk(frame, popup_chunk, pending),
+                Popup::Palette { query, selected } => {
+                    self.draw_palette_chunk(frame, popup_chunk, query, *selected)
+                }
             }
         }
 
-        self.popup = popup;
+        self.popups = popups;
+        self.draw_filters(frame);
+    }
+
+    /// Adapt the drawn frame to the terminal (which must happen last).
+    fn draw_filters<B: Backend>(&self, frame: &mut Frame<B>) {
+        if self.engine.db().settings().ui_charset == UiCharset::Ascii {
+            frame.render_widget(AsciiFilter, frame.size());
+        }
+
+        if self.engine.db().settings().ui_theme == UiTheme::Monochrome {
+            frame.render_widget(MonochromeFilter, frame.size());
+        }
+    }
+
+    /// Parse the framing and the serial line of the scales from the settings.
+    /// Invalid settings fall back to the defaults (the terminal is still ours, so they can be reported).
+    fn scale_config(settings: &SettingsEntry) -> (ScaleProtocol, ScaleLineConfig) {
+        let protocol = settings.scale_protocol.parse().unwrap_or_else(|err| {
+            eprintln!("Invalid setting scale_protocol, using the default: {err}");
+            ScaleProtocol::default()
+        });
+
+        let flow_control = settings.scale_flow_control.parse().unwrap_or_else(|()| {
+            eprintln!(
+                "Invalid setting scale_flow_control, using none: {}",
+                settings.scale_flow_control
+            );
+            ScaleFlowControl::None
+        });
+
+        let line_config = ScaleLineConfig {
+            flow_control,
+            rs485: settings.scale_rs485.then(|| Rs485 {
+                delay_before_send: Duration::from_micros(settings.scale_rs485_delay_before_us),
+                delay_after_send: Duration::from_micros(settings.scale_rs485_delay_after_us),
+            }),
+        };
+
+        (protocol, line_config)
     }
 
-    pub fn run(emulated_scales: bool, dump_voucher: bool) -> Result<(), Box<dyn Error>> {
+    pub fn run(
+        emulated_scales: bool,
+        dump_voucher: bool,
+        read_only: bool,
+    ) -> Result<(), Box<dyn Error>> {
         // Instantiate the app.
         let now = Utc::now();
         let db = Database::open_or_create("db.sqlite")?;
+        let journal = SaleJournal::new(db.journal_path());
 
         let scales = if emulated_scales {
             Scales::emulated()
         } else {
-            Scales::on_serial_port(&db.info().serial_port)
+            let (protocol, line_config) = Self::scale_config(db.settings());
+            Scales::on_serial_port(&db.info().serial_port, protocol, line_config)
+        };
+
+        let weight = scales.weight();
+
+        // The terminal is still ours, so a busy address can be reported right here.
+        let health_endpoint = match db.settings().health_endpoint.as_str() {
+            "" => None,
+
+            addr => match HealthEndpoint::bind(addr) {
+                Ok(health_endpoint) => Some(health_endpoint),
+
+                Err(err) => {
+                    eprintln!("Failed to serve the health on {addr}: {err}");
+                    None
+                }
+            },
         };
 
         let mut app = App {
             now,
-            db,
-            scales,
-            printer: Err(AttachError::NoPrinter),
+            events: EventBus::new(),
+            engine: Engine::new(db, journal, scales, Attribution::current()),
+            watchdog: Watchdog::from_env(),
+            weight_error_date: weight.is_err().then_some(now),
+            weight,
+            held_weight: None,
+            held_frame: None,
+            piece_counter: None,
+            batch: None,
+            weight_changed_date: now,
+            input_date: now,
+            scale_alarm_beep_date: now,
             reconnect_printer_date: now,
+            poll_printer_date: now,
+            check_storage_date: now,
+            check_db_date: now,
+            check_health_date: now,
+            health_endpoint,
+            startup_log: Vec::new(),
+            db_data_version: None,
+            tick_ms: Arc::new(AtomicU64::new(0)),
+            settings_reload: None,
+            voucher_text_ctx: None,
+            last_confirmed_action: None,
+            express_sale: None,
+            pending_prints: VecDeque::new(),
+            last_print: None,
+            interrupted_sales: VecDeque::new(),
+            label_roll: None,
+            shift: None,
             dump_voucher,
-            focus: Focus::Product,
-            popup: None,
+            read_only,
+            voucher_language: None,
+            available_update: None,
+            main_focus: Focus::Product,
+            popups: Vec::new(),
             product_list_state: Default::default(),
             action_list_state: Default::default(),
             dialog_list_state: Default::default(),
+            needs_redraw: true,
+            drawn_date: now,
+            is_scale_alarm_drawn: false,
+            screensaver_drawn_step: None,
         };
 
         // Configure the terminal.
@@ -765,6 +3875,11 @@ impl App {
 
         terminal.show_cursor()?;
 
+        // Printed earlier, they would have been drawn over by the TUI.
+        for line in &app.startup_log {
+            eprintln!("{line}");
+        }
+
         result
     }
 }
@@ -0,0 +1,50 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier},
+    widgets::Widget,
+};
+
+/// Rewrites a drawn frame to pure black on white (for e-ink displays and color-blind operators):
+/// Errors become bold and underlined, highlights and warnings bold, and colored backgrounds
+/// (like the alarm banner) are reversed. Dimmed text loses its emphasis.
+/// It is rendered last, so the chunks don't need to know about it.
+pub(super) struct MonochromeFilter;
+
+impl MonochromeFilter {
+    /// The emphasis that replaces a foreground color
+    fn modifier(color: Color) -> Modifier {
+        match color {
+            Color::Red | Color::LightRed => Modifier::BOLD | Modifier::UNDERLINED,
+
+            Color::Reset | Color::Black | Color::Gray | Color::DarkGray => Modifier::empty(),
+
+            // Focus, success and warnings
+            _ => Modifier::BOLD,
+        }
+    }
+
+    fn is_plain_background(color: Color) -> bool {
+        matches!(color, Color::Reset | Color::Black)
+    }
+}
+
+impl Widget for MonochromeFilter {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+
+                // Text on a colored background (e.g. the alarm) is emphasized by the reversal alone.
+                if Self::is_plain_background(cell.bg) {
+                    cell.modifier.insert(Self::modifier(cell.fg));
+                } else {
+                    cell.modifier.insert(Modifier::BOLD | Modifier::REVERSED);
+                }
+
+                cell.fg = Color::Black;
+                cell.bg = Color::White;
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use super::{event::Event as AppEvent, App};
+use crate::gpio::ButtonAction;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+/// How a command of the palette is triggered
+#[derive(Copy, Clone)]
+pub(super) enum Trigger {
+    /// Like pressing the key where the palette has been opened
+    Key(KeyCode),
+
+    /// Like pressing the physical button (for actions without a key)
+    Button(ButtonAction),
+}
+
+impl Trigger {
+    pub(super) fn event(self) -> AppEvent {
+        match self {
+            Trigger::Key(code) => {
+                AppEvent::Input(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+            }
+
+            Trigger::Button(action) => AppEvent::Button(action),
+        }
+    }
+
+    /// The key to remember for next time (if there is one)
+    fn hint(self) -> Option<String> {
+        match self {
+            Trigger::Key(KeyCode::Enter) => Some(String::from("Enter")),
+            Trigger::Key(KeyCode::Char(c)) => Some(format!("'{}'", c)),
+            _ => None,
+        }
+    }
+}
+
+/// An action that can be searched for in the palette
+pub(super) struct Command {
+    pub(super) name: &'static str,
+    pub(super) trigger: Trigger,
+}
+
+const fn key(name: &'static str, c: char) -> Command {
+    Command {
+        name,
+        trigger: Trigger::Key(KeyCode::Char(c)),
+    }
+}
+
+/// All actions of the palette (in the order they are listed without a search)
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "Ausgewähltes Produkt verkaufen",
+        trigger: Trigger::Key(KeyCode::Enter),
+    },
+    key("Letzten Bon erneut drucken", 'n'),
+    Command {
+        name: "Tara übernehmen",
+        trigger: Trigger::Button(ButtonAction::Tare),
+    },
+    key("Tara zurücksetzen", 't'),
+    key("Gewicht halten oder lösen", 'h'),
+    key("Stückzählung starten oder beenden", 'z'),
+    key("Serienauszeichnung starten oder beenden", 'b'),
+    key("Expressverkauf rückgängig machen", 'x'),
+    key("Verkaufshistorie anzeigen", 'v'),
+    key("Zwischenbericht drucken", 's'),
+    key("Schicht öffnen oder abschließen", 'o'),
+    key("Druckprotokoll anzeigen", 'l'),
+    key("Etikettenrolle gewechselt", 'w'),
+    key("Preis des Produkts ändern", 'e'),
+    key("Produkt kopieren", 'c'),
+    key("Tagesnachricht bearbeiten", 'm'),
+    key("Bonsprache wechseln", 'u'),
+    key("Diagnose anzeigen", 'i'),
+    key("Datenbank neu laden", 'r'),
+    key("Programm beenden", 'q'),
+];
+
+/// Score how well the query matches the name (`None` if it doesn't at all).
+/// All characters of the query must appear in order, but not necessarily next to each other.
+/// Consecutive characters and the starts of words score higher (e.g. "tz" for "Tara zurücksetzen").
+fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+    let mut name_chars = name.chars().flat_map(char::to_lowercase).enumerate();
+    let mut score = 0;
+    let mut last_idx = None;
+    let mut prev_char = ' ';
+
+    for query_char in query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+    {
+        let idx = loop {
+            let (idx, name_char) = name_chars.next()?;
+            let is_word_start = !prev_char.is_alphanumeric();
+            prev_char = name_char;
+
+            if name_char == query_char {
+                score += 1;
+
+                if is_word_start {
+                    score += 2;
+                }
+
+                break idx;
+            }
+        };
+
+        if last_idx.is_some_and(|last_idx| idx == last_idx + 1) {
+            score += 2;
+        }
+
+        last_idx = Some(idx);
+    }
+
+    Some(score)
+}
+
+/// The commands that match the query, the best matches first
+pub(super) fn matching_commands(query: &str) -> Vec<&'static Command> {
+    let mut matches: Vec<_> = COMMANDS
+        .iter()
+        .filter_map(|command| Some((fuzzy_score(query, command.name)?, command)))
+        .collect();
+
+    // The sort is stable, so equal scores keep the order of the list.
+    matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+    matches.into_iter().map(|(_, command)| command).collect()
+}
+
+impl App {
+    pub(super) fn draw_palette_chunk<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        chunk: Rect,
+        query: &str,
+        selected: usize,
+    ) {
+        // Build and render the block.
+        let block = Block::default()
+            .title("Befehl suchen (Enter zum Ausführen, Esc zum Schließen)")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::LightBlue).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        let vert_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Min(0)].as_ref())
+            .split(inner_chunk);
+
+        // The query is followed by a cursor.
+        let paragraph = Paragraph::new(Spans::from(vec![
+            Span::styled(query, Style::default().fg(Color::White).bg(Color::Black)),
+            Span::styled("_", Style::default().fg(Color::Green).bg(Color::Black)),
+        ]));
+
+        frame.render_widget(paragraph, vert_chunks[0]);
+
+        // List the matching commands with their keys.
+        let commands = matching_commands(query);
+
+        let items: Vec<_> = if commands.is_empty() {
+            vec![ListItem::new("Kein passender Befehl")
+                .style(Style::default().fg(Color::DarkGray).bg(Color::Black))]
+        } else {
+            commands
+                .iter()
+                .map(|command| {
+                    let mut spans = vec![Span::raw(command.name)];
+
+                    if let Some(hint) = command.trigger.hint() {
+                        spans.push(Span::styled(
+                            format!(" ({})", hint),
+                            Style::default().fg(Color::DarkGray).bg(Color::Black),
+                        ));
+                    }
+
+                    ListItem::new(Spans::from(spans))
+                        .style(Style::default().fg(Color::Gray).bg(Color::Black))
+                })
+                .collect()
+        };
+
+        let list = List::new(items)
+            .highlight_style(Style::default().fg(Color::Green).bg(Color::Black))
+            .highlight_symbol("⇨ ");
+
+        let mut list_state = ListState::default();
+        list_state.select((!commands.is_empty()).then_some(selected));
+
+        frame.render_stateful_widget(list, vert_chunks[1], &mut list_state);
+    }
+}
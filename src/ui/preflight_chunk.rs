@@ -0,0 +1,70 @@
+use super::App;
+use crate::pos::{ComponentState, Preflight};
+
+use tui::{
+    backend::Backend,
+    layout::{Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+impl App {
+    pub(super) fn draw_preflight_chunk<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        chunk: Rect,
+        preflight: &Preflight,
+    ) {
+        // Build and render the block.
+        let block = Block::default()
+            .title("Startprüfung")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::LightBlue).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        // One line per check, like "Waage: OK"
+        let mut lines: Vec<Spans> = preflight
+            .results
+            .iter()
+            .map(|(check, state)| {
+                let (status, color) = match state {
+                    ComponentState::Ok => (String::from("OK"), Color::Green),
+                    ComponentState::Degraded(reason) => {
+                        (format!("Prüfen ({})", reason), Color::Yellow)
+                    }
+                    ComponentState::Failed(reason) => {
+                        (format!("Fehler ({})", reason), Color::LightRed)
+                    }
+                };
+
+                Spans::from(vec![
+                    Span::styled(
+                        format!("{}: ", check.name()),
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(status, Style::default().fg(color).bg(Color::Black)),
+                ])
+            })
+            .collect();
+
+        lines.push(Spans::from(""));
+        lines.push(Spans::from(Span::styled(
+            "Mit Enter geht es trotzdem weiter.",
+            Style::default().fg(Color::Gray).bg(Color::Black),
+        )));
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, inner_chunk);
+    }
+}
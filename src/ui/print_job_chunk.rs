@@ -0,0 +1,82 @@
+use super::App;
+use crate::db::{PrintJobEntry, PrintOutcome};
+
+use chrono::Local;
+
+use tui::{
+    backend::Backend,
+    layout::{Margin, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+impl App {
+    pub(super) fn draw_print_job_chunk<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        chunk: Rect,
+        jobs: &[PrintJobEntry],
+    ) {
+        // Build and render the block.
+        let block = Block::default()
+            .title("Druckprotokoll")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::LightBlue).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        // Build one line per job, the newest first.
+        let lines = if jobs.is_empty() {
+            vec![Spans::from(Span::styled(
+                "Es wurden noch keine Bons gedruckt.",
+                Style::default().fg(Color::DarkGray).bg(Color::Black),
+            ))]
+        } else {
+            jobs.iter()
+                .map(|job| {
+                    let (outcome, color) = match job.outcome {
+                        PrintOutcome::Printed => ("gedruckt", Color::Green),
+                        PrintOutcome::Failed => ("fehlgeschlagen", Color::LightRed),
+                        PrintOutcome::Interrupted => ("evtl. teilweise gedruckt", Color::LightRed),
+                        PrintOutcome::Discarded => ("entsorgt", Color::Yellow),
+                    };
+
+                    let mut spans = vec![
+                        Span::styled(
+                            job.date
+                                .with_timezone(&Local)
+                                .format("%d.%m.%Y %H:%M:%S ")
+                                .to_string(),
+                            Style::default().fg(Color::Gray).bg(Color::Black),
+                        ),
+                        Span::styled(
+                            format!("{}: ", job.product_name),
+                            Style::default().fg(Color::Gray).bg(Color::Black),
+                        ),
+                        Span::styled(outcome, Style::default().fg(color).bg(Color::Black)),
+                    ];
+
+                    if let Some(error) = &job.error {
+                        spans.push(Span::styled(
+                            format!(" ({})", error),
+                            Style::default().fg(Color::DarkGray).bg(Color::Black),
+                        ));
+                    }
+
+                    Spans::from(spans)
+                })
+                .collect()
+        };
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, inner_chunk);
+    }
+}
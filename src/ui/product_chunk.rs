@@ -1,4 +1,8 @@
 use super::{App, Focus};
+use crate::{
+    db::{ProductColumn, ProductEntry},
+    format,
+};
 
 use tui::{
     backend::Backend,
@@ -8,6 +12,17 @@ use tui::{
     Frame,
 };
 
+/// The text of a product in an extra column of the list
+fn cell(product: &ProductEntry, column: ProductColumn) -> String {
+    match column {
+        ProductColumn::Price if product.is_kg_price => {
+            format!("{} / kg", format::euro(product.price_ct))
+        }
+        ProductColumn::Price => format::euro(product.price_ct),
+        ProductColumn::Code => product.in_store_code().unwrap_or_default(),
+    }
+}
+
 impl App {
     pub(super) fn draw_product_chunk<B: Backend>(&mut self, frame: &mut Frame<B>, chunk: Rect) {
         // Build and render the block.
@@ -17,7 +32,7 @@ impl App {
             .border_type(BorderType::Rounded)
             .style(
                 Style::default()
-                    .fg(if self.focus == Focus::Product {
+                    .fg(if self.focus() == Focus::Product {
                         Color::LightBlue
                     } else {
                         Color::DarkGray
@@ -44,14 +59,62 @@ impl App {
             return;
         };
 
+        // The extra columns are as wide as their widest cell.
+        let settings = self.engine.db().settings();
+        let products = self.engine.db().products();
+
+        let mut columns: Vec<_> = settings
+            .product_columns
+            .as_slice()
+            .iter()
+            .map(|&column| {
+                let cells: Vec<_> = products
+                    .iter()
+                    .map(|product| cell(product, column))
+                    .collect();
+                let width = cells.iter().map(|cell| cell.chars().count()).max();
+
+                (column, cells, width.unwrap_or(0))
+            })
+            .collect();
+
+        // Columns are hidden from the right until the names have enough room.
+        // The highlight symbol takes two characters, every column is preceded by two spaces.
+        let list_width = (inner_chunk.width as usize).saturating_sub(2);
+
+        let name_width = loop {
+            let columns_width: usize = columns.iter().map(|(_, _, width)| width + 2).sum();
+            let name_width = list_width.saturating_sub(columns_width);
+
+            if (name_width >= settings.product_name_min_width as usize) || columns.is_empty() {
+                break name_width;
+            }
+
+            columns.pop();
+        };
+
         // Build list items for the products.
-        let items: Vec<_> = self
-            .db
-            .products()
+        let items: Vec<_> = products
             .iter()
-            .map(|product| {
-                ListItem::new(product.name.as_str())
-                    .style(Style::default().fg(Color::DarkGray).bg(Color::Black))
+            .enumerate()
+            .map(|(idx, product)| {
+                let mut line = format::truncate(&product.name, name_width);
+
+                if !columns.is_empty() {
+                    line = format!("{:<width$}", line, width = name_width);
+                }
+
+                // Prices are aligned at the right, codes at the left.
+                for (column, cells, width) in &columns {
+                    let cell = match column {
+                        ProductColumn::Price => format!("  {:>width$}", cells[idx]),
+                        ProductColumn::Code => format!("  {:<width$}", cells[idx]),
+                    };
+
+                    line.push_str(&cell);
+                }
+
+                ListItem::new(line).style(Style::default().fg(Color::DarkGray).bg(Color::Black))
             })
             .collect();
 
@@ -59,7 +122,7 @@ impl App {
         let list = List::new(items)
             .highlight_style(
                 Style::default()
-                    .fg(if self.focus == Focus::Product {
+                    .fg(if self.focus() == Focus::Product {
                         Color::Green
                     } else {
                         Color::White
@@ -0,0 +1,78 @@
+use super::App;
+use crate::{format, pos::InterruptedSale};
+
+use chrono::Local;
+
+use tui::{
+    backend::Backend,
+    layout::{Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+impl App {
+    pub(super) fn draw_recovery_chunk<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        chunk: Rect,
+        interrupted: &InterruptedSale,
+    ) {
+        // Build and render the block.
+        let block = Block::default()
+            .title("Unterbrochener Verkauf")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        let (date, problem, choices) = match interrupted {
+            InterruptedSale::Unbooked { sale_date, .. } => (
+                *sale_date,
+                String::from("Der Bon wurde gedruckt, aber der Verkauf wurde nicht gebucht."),
+                "'s' Bon wurde entsorgt, Esc beim nächsten Start erneut fragen",
+            ),
+
+            InterruptedSale::Unprinted(sale) => (
+                sale.date,
+                format!(
+                    "Der Verkauf über {} wurde gebucht, aber der Bon wurde nie gedruckt.",
+                    format::euro(sale.total_ct)
+                ),
+                "'n' Bon nachdrucken, 's' Verkauf stornieren, Esc beim nächsten Start erneut fragen",
+            ),
+        };
+
+        let lines = vec![
+            Spans::from(Span::styled(
+                format!(
+                    "{} ({})",
+                    interrupted.product_name(),
+                    date.with_timezone(&Local).format("%d.%m.%Y %H:%M:%S")
+                ),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Spans::from(Span::styled(
+                problem,
+                Style::default().fg(Color::Yellow).bg(Color::Black),
+            )),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                choices,
+                Style::default().fg(Color::Gray).bg(Color::Black),
+            )),
+        ];
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, inner_chunk);
+    }
+}
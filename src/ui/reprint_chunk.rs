@@ -0,0 +1,58 @@
+use super::{App, PendingPrint};
+
+use tui::{
+    backend::Backend,
+    layout::{Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+impl App {
+    pub(super) fn draw_reprint_chunk<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        chunk: Rect,
+        pending: &PendingPrint,
+    ) {
+        // Build and render the block.
+        let block = Block::default()
+            .title("Unterbrochener Druck")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        let lines = vec![
+            Spans::from(Span::styled(
+                pending.product.name.clone(),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Spans::from(Span::styled(
+                "Der Fehler am Drucker ist behoben, aber der angefangene Bon ist eventuell unvollständig.",
+                Style::default().fg(Color::Yellow).bg(Color::Black),
+            )),
+            Spans::from(Span::styled(
+                format!("Es fehlen noch {} Bon(s).", pending.copies),
+                Style::default().fg(Color::Yellow).bg(Color::Black),
+            )),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                "'n' Bon(s) nachdrucken, Esc nicht nachdrucken",
+                Style::default().fg(Color::Gray).bg(Color::Black),
+            )),
+        ];
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, inner_chunk);
+    }
+}
@@ -1,5 +1,7 @@
 use super::{App, Focus};
+use crate::format;
 
+use chrono::Local;
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
@@ -18,7 +20,7 @@ impl App {
             .border_type(BorderType::Rounded)
             .style(
                 Style::default()
-                    .fg(if self.focus == Focus::Sale {
+                    .fg(if self.focus() == Focus::Sale {
                         Color::LightBlue
                     } else {
                         Color::DarkGray
@@ -62,10 +64,10 @@ impl App {
 
         // Build the paragraph for the details.
         let euro: f64 = (product.price_ct as f64) / 100.0;
-        let euro_str = format!("{:.2} €", euro).replacen('.', ",", 1);
+        let euro_str = format::euro(product.price_ct);
         let storage_temp = product.storage_temp_formatted();
-        let mhd = product.expiration_date_formatted();
-        let mut details = Vec::with_capacity(7);
+        let mhd = product.expiration_date_formatted(self.now.with_timezone(&Local));
+        let mut details = Vec::with_capacity(8);
 
         details.push(Spans::from(vec![
             Span::styled(
@@ -97,6 +99,23 @@ impl App {
             ),
         ]));
 
+        if product.is_kg_price && self.engine.db().settings().show_price_per_100g {
+            let euro_per_100g_str = format!("{} € / 100 g", format::fixed(euro / 10.0, 2));
+
+            details.push(Spans::from(vec![
+                Span::styled(
+                    "Grundpreis: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    euro_per_100g_str,
+                    Style::default().fg(Color::DarkGray).bg(Color::Black),
+                ),
+            ]));
+        }
+
         details.push(Spans::from(vec![
             Span::styled(
                 "Zutaten: ",
@@ -149,6 +168,26 @@ impl App {
             ),
         ]));
 
+        if let Some(control_code) = &product.organic_control_code {
+            let certification = match &product.agriculture_origin {
+                Some(origin) => format!("{} ({})", control_code, origin),
+                None => control_code.clone(),
+            };
+
+            details.push(Spans::from(vec![
+                Span::styled(
+                    "Öko-Kontrollstelle: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    certification,
+                    Style::default().fg(Color::DarkGray).bg(Color::Black),
+                ),
+            ]));
+        }
+
         details.push(Spans::from(Span::styled(
             "─".repeat(details_chunk.width as _),
             Style::default().fg(Color::DarkGray).bg(Color::Black),
@@ -157,7 +196,12 @@ impl App {
         if product.is_kg_price {
             if let Ok(weight_kg) = self.weight() {
                 if weight_kg >= 0.0 {
-                    let euro_str = format!("{:.2} €", weight_kg * euro).replacen('.', ",", 1);
+                    let price_ct = product.total_price_ct(
+                        Some(weight_kg),
+                        None,
+                        self.engine.db().settings().rounding_mode,
+                    );
+                    let euro_str = format::euro(price_ct);
 
                     details.push(Spans::from(vec![
                         Span::styled(
@@ -182,9 +226,10 @@ impl App {
             ListItem::new("Verbuchen und Bon drucken").style(item_style),
             ListItem::new("Nur verbuchen").style(item_style),
             ListItem::new("Nur Bon drucken").style(item_style),
+            ListItem::new("Preisschild drucken").style(item_style),
         ];
 
-        if self.dump_voucher {
+        if self.can_dump_voucher() {
             items.push(ListItem::new("Bon dumpen").style(item_style));
         }
 
@@ -192,7 +237,7 @@ impl App {
         let list = List::new(items)
             .highlight_style(
                 Style::default()
-                    .fg(if self.focus == Focus::Sale {
+                    .fg(if self.focus() == Focus::Sale {
                         Color::Green
                     } else {
                         Color::White
@@ -0,0 +1,227 @@
+use super::App;
+use crate::{
+    db::{SaleEntry, SaleFilter, SalesSummary},
+    format,
+};
+
+use chrono::{Local, NaiveDate};
+
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+impl App {
+    pub(super) fn draw_sales_history_chunk<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        chunk: Rect,
+        filter: &SaleFilter,
+        sales: &[SaleEntry],
+        summary: &SalesSummary,
+    ) {
+        // Build and render the block.
+        let block = Block::default()
+            .title("Verkäufe")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::LightBlue).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        // The sum is broken out by payment method once sales note one.
+        let has_payment_methods = summary
+            .payment_totals
+            .iter()
+            .any(|total| total.payment_method.is_some());
+
+        // Split the block into the filter, the sales and the sum.
+        let vert_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(3),
+                    Constraint::Min(1),
+                    Constraint::Length(1 + (has_payment_methods as u16)),
+                ]
+                .as_ref(),
+            )
+            .split(inner_chunk);
+
+        let filter_chunk = vert_chunks[0];
+        let sales_chunk = vert_chunks[1];
+        let sum_chunk = vert_chunks[2];
+
+        // Describe the filter and how to change it.
+        let product_str = filter.product_name.as_deref().unwrap_or("alle");
+        let duplicate_str = if filter.duplicate_override {
+            "nur trotz Warnung bestätigte"
+        } else {
+            "alle"
+        };
+
+        let date_str = |date: Option<NaiveDate>, open_str: &str| {
+            date.map_or_else(
+                || String::from(open_str),
+                |date| date.format("%d.%m.%Y").to_string(),
+            )
+        };
+
+        let dates_str = match (filter.from, filter.until) {
+            (None, None) => String::from("alle Tage"),
+            (from, until) => format!(
+                "{} bis {}",
+                date_str(from, "Anfang"),
+                date_str(until, "heute")
+            ),
+        };
+
+        let filter_lines = vec![
+            Spans::from(vec![
+                Span::styled(
+                    "Produkt: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{} ('p' zum Umschalten), ", product_str),
+                    Style::default().fg(Color::Gray).bg(Color::Black),
+                ),
+                Span::styled(
+                    "Doppelbuchungen: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{} ('d' zum Umschalten)", duplicate_str),
+                    Style::default().fg(Color::Gray).bg(Color::Black),
+                ),
+            ]),
+            Spans::from(vec![
+                Span::styled(
+                    "Zeitraum: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "{} ('f' zum Ändern, 's' druckt einen Zwischenbericht)",
+                        dates_str
+                    ),
+                    Style::default().fg(Color::Gray).bg(Color::Black),
+                ),
+            ]),
+            Spans::from(Span::styled(
+                "─".repeat(filter_chunk.width as _),
+                Style::default().fg(Color::DarkGray).bg(Color::Black),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(filter_lines), filter_chunk);
+
+        // Build one line per sale, the newest first.
+        let lines = if sales.is_empty() {
+            vec![Spans::from(Span::styled(
+                "Es gibt keine passenden Verkäufe.",
+                Style::default().fg(Color::DarkGray).bg(Color::Black),
+            ))]
+        } else {
+            let settings = self.engine.db().settings();
+
+            sales
+                .iter()
+                .map(|sale| {
+                    let quantity_str = match (sale.piece_count, sale.weight_kg) {
+                        (Some(piece_count), _) => format!("{} Stück", piece_count),
+                        (None, Some(weight_kg)) => settings.format_weight(weight_kg),
+                        (None, None) => String::from("-"),
+                    };
+
+                    let total_str = format::euro(sale.total_ct);
+
+                    Spans::from(vec![
+                        Span::styled(
+                            sale.date
+                                .with_timezone(&Local)
+                                .format("%d.%m.%Y %H:%M:%S ")
+                                .to_string(),
+                            Style::default().fg(Color::Gray).bg(Color::Black),
+                        ),
+                        Span::styled(
+                            format!("{}: {} für ", sale.name, quantity_str),
+                            Style::default().fg(Color::Gray).bg(Color::Black),
+                        ),
+                        Span::styled(
+                            total_str,
+                            Style::default().fg(Color::Green).bg(Color::Black),
+                        ),
+                    ])
+                })
+                .collect()
+        };
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, sales_chunk);
+
+        // The sum covers all matching sales, not only the visible ones.
+        let sum_str = format::euro(summary.total_ct);
+
+        let mut sum_lines = vec![Spans::from(vec![
+            Span::styled(
+                "Summe: ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{} ({} Verkäufe)", sum_str, summary.count),
+                Style::default().fg(Color::White).bg(Color::Black),
+            ),
+        ])];
+
+        if has_payment_methods {
+            let totals_str = summary
+                .payment_totals
+                .iter()
+                .map(|total| {
+                    format!(
+                        "{} {} ({})",
+                        total
+                            .payment_method
+                            .map_or("ohne Angabe", |method| method.name()),
+                        format::euro(total.total_ct),
+                        total.count
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            sum_lines.push(Spans::from(vec![
+                Span::styled(
+                    "Zahlungsarten: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    totals_str,
+                    Style::default().fg(Color::White).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        frame.render_widget(Paragraph::new(sum_lines), sum_chunk);
+    }
+}
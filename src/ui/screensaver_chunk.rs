@@ -0,0 +1,115 @@
+use super::App;
+use crate::format;
+
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+/// The screensaver moves (and shows the next price) this often.
+const SCREENSAVER_STEP_SECS: i64 = 10;
+
+impl App {
+    /// Check if nothing has happened for a while and nothing needs the attention of the operator.
+    pub(super) fn is_screensaver_active(&self) -> bool {
+        let screensaver_secs = self.engine.db().settings().screensaver_secs;
+
+        // Batches and alarms are watched from a distance.
+        if (screensaver_secs == 0) || self.batch.is_some() || self.is_scale_alarm_active() {
+            return false;
+        }
+
+        let idle_window =
+            TimeDelta::try_seconds(screensaver_secs as _).expect("Screensaver window out of bound");
+
+        (self.now - self.activity_date()) >= idle_window
+    }
+
+    /// When a key has been pressed or the weight has changed the last time
+    fn activity_date(&self) -> DateTime<Utc> {
+        self.input_date.max(self.weight_changed_date)
+    }
+
+    /// Counts up every few seconds while the screensaver is active.
+    /// It is only redrawn when this changes.
+    pub(super) fn screensaver_step(&self) -> i64 {
+        (self.now - self.activity_date()).num_seconds() / SCREENSAVER_STEP_SECS
+    }
+
+    pub(super) fn draw_screensaver_chunk<B: Backend>(&mut self, frame: &mut Frame<B>, chunk: Rect) {
+        let step = self.screensaver_step();
+        let now = self.now.with_timezone(&Local);
+
+        let mut lines = vec![
+            Spans::from(Span::styled(
+                self.engine.db().info().business.clone(),
+                Style::default()
+                    .fg(Color::Gray)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                now.format("%H:%M").to_string(),
+                Style::default().fg(Color::Gray),
+            )),
+            Spans::from(Span::styled(
+                now.format("%d.%m.%Y").to_string(),
+                Style::default().fg(Color::DarkGray),
+            )),
+        ];
+
+        // One product after another
+        let products = self.engine.db().products();
+
+        if self.engine.db().settings().screensaver_prices && !products.is_empty() {
+            let product = &products[(step as usize) % products.len()];
+
+            let price_str = if product.is_kg_price {
+                format!("{} / kg", format::euro(product.price_ct))
+            } else {
+                format::euro(product.price_ct)
+            };
+
+            lines.push(Spans::from(""));
+            lines.push(Spans::from(Span::styled(
+                format!("{}: {}", product.name, price_str),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+
+        // The text wanders around the screen against burn-in.
+        let width = lines
+            .iter()
+            .map(Spans::width)
+            .max()
+            .unwrap_or(0)
+            .min(chunk.width as usize) as u16;
+        let height = (lines.len() as u16).min(chunk.height);
+
+        let free_x = (chunk.width - width) as i64;
+        let free_y = (chunk.height - height) as i64;
+
+        let text_chunk = Rect::new(
+            chunk.x + ((step * 7) % (free_x + 1)) as u16,
+            chunk.y + ((step * 3) % (free_y + 1)) as u16,
+            width,
+            height,
+        );
+
+        frame.render_widget(
+            Block::default().style(Style::default().bg(Color::Black)),
+            chunk,
+        );
+
+        let paragraph = Paragraph::new(lines)
+            .style(Style::default().bg(Color::Black))
+            .alignment(Alignment::Center);
+
+        frame.render_widget(paragraph, text_chunk);
+    }
+}
@@ -1,4 +1,14 @@
 use super::App;
+use crate::{
+    db::UnderweightPolicy,
+    format,
+    pos::{ComponentState, StorageLevel},
+    printer::{AttachError, StatusErrorFlags},
+    update::Version,
+    weight::QualityIssue,
+};
+
+use chrono::Local;
 
 use tui::{
     backend::Backend,
@@ -10,43 +20,60 @@ use tui::{
 };
 
 impl App {
-    pub(super) fn draw_status_chunk<B: Backend>(&mut self, frame: &mut Frame<B>, chunk: Rect) {
-        // Build and render the block.
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .style(Style::default().fg(Color::DarkGray).bg(Color::Black));
-
-        let inner_chunk = block.inner(chunk).inner(&Margin {
-            horizontal: 1,
-            vertical: 0,
-        });
-
-        frame.render_widget(block, chunk);
-
-        // Build the status line.
-        let mut status = Vec::with_capacity(2);
+    /// Build the lines of the status chunk.
+    /// The number of lines determines the height of the chunk.
+    pub(super) fn build_status(&self) -> Vec<Spans<'static>> {
+        let mut status = Vec::with_capacity(3);
 
         // Scales
         match self.weight() {
             Ok(weight_kg) => {
-                let weight_str = if weight_kg >= 0.0 {
-                    format!("{:.3} kg", weight_kg).replacen('.', ",", 1)
+                let settings = self.engine.db().settings();
+
+                // Negative weights are only shown if they can be confirmed.
+                let mut weight_str = if (weight_kg >= 0.0)
+                    || (settings.underweight_policy == UnderweightPolicy::Confirm)
+                {
+                    settings.format_weight(weight_kg)
                 } else {
                     String::from("-----")
                 };
 
-                status.push(Spans::from(vec![
+                // An automatic tare is pointed out, so the operator can reset it.
+                let tare_kg = self.engine.tare_kg();
+
+                if tare_kg != 0.0 {
+                    weight_str = format!(
+                        "{} (Tara {}, 't' zum Zurücksetzen)",
+                        weight_str,
+                        settings.format_weight(tare_kg)
+                    );
+                }
+
+                // A held weight is highlighted, so it is not mistaken for the live one.
+                let weight_span = if self.held_weight.is_some() {
                     Span::styled(
-                        "Waage: ",
+                        format!("{} (gehalten, 'h' zum Lösen)", weight_str),
                         Style::default()
-                            .fg(Color::Yellow)
+                            .fg(Color::Black)
+                            .bg(Color::Yellow)
                             .add_modifier(Modifier::BOLD),
-                    ),
+                    )
+                } else {
                     Span::styled(
                         weight_str,
                         Style::default().fg(Color::Green).bg(Color::Black),
+                    )
+                };
+
+                status.push(Spans::from(vec![
+                    Span::styled(
+                        "Waage: ",
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
                     ),
+                    weight_span,
                 ]))
             }
 
@@ -64,35 +91,436 @@ impl App {
             ])),
         }
 
+        // Data quality of the scales (only if it degrades)
+        if let Some(issue) = self.engine.scale_quality_issue() {
+            let issue_str = match issue {
+                QualityIssue::Failures { percent } => {
+                    format!("{} % fehlerhafte Antworten", format::fixed(percent, 1))
+                }
+                QualityIssue::Latency { mean } => {
+                    format!("Antwortzeit Ø {} ms", mean.as_millis())
+                }
+            };
+
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Verbindung zur Waage: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{} (Adapter prüfen, 'i' für Diagnose)", issue_str),
+                    Style::default().fg(Color::Yellow).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Verification of the scales (only if it is due soon)
+        let settings = self.engine.db().settings();
+        let today = self.now.with_timezone(&Local).date_naive();
+
+        if let Some(due) = settings
+            .scale_verification_due()
+            .filter(|_| settings.is_scale_verification_due(today))
+        {
+            let (verification_str, color) = if due < today {
+                (
+                    format!("überfällig seit {}", due.format("%d.%m.%Y")),
+                    Color::LightRed,
+                )
+            } else {
+                (
+                    format!("fällig am {}", due.format("%d.%m.%Y")),
+                    Color::Yellow,
+                )
+            };
+
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Eichung: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    verification_str,
+                    Style::default().fg(color).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Counting mode
+        if let Some(piece_counter) = self.piece_counter {
+            let piece_count = self
+                .weight()
+                .map_or(0, |weight_kg| piece_counter.count(weight_kg));
+
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Stückzählung: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "{} Stück (Ø {} g, 'z' zum Beenden)",
+                        piece_count,
+                        format::fixed(piece_counter.piece_weight_kg() * 1000.0, 1)
+                    ),
+                    Style::default().fg(Color::Green).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Batch mode
+        if let Some(batch) = &self.batch {
+            let total_str = format::euro(batch.total_ct);
+
+            let next_str = if batch.armed {
+                "nächste Packung auflegen"
+            } else {
+                "Waage leeren"
+            };
+
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Serie: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "{}, {} Packung(en) für {}, {} ('b' zum Beenden)",
+                        batch.product.name, batch.count, total_str, next_str
+                    ),
+                    Style::default().fg(Color::Green).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Express mode
+        if self.engine.db().settings().express_mode {
+            let express_str = match &self.express_sale {
+                Some(express_sale) => format!(
+                    "\"{}\" verbucht ('x' zum Rückgängigmachen, noch {} s)",
+                    express_sale.sale.name,
+                    (express_sale.deadline - self.now).num_seconds().max(0) + 1
+                ),
+                None => String::from("Stabile Gewichte werden ohne Rückfrage verbucht"),
+            };
+
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Expressmodus: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    express_str,
+                    Style::default().fg(Color::Green).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Voucher language (only if there are translations to choose from)
+        if !self.engine.db().translation_languages().is_empty() {
+            let language = self.active_voucher_language();
+
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Bon-Sprache: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "{} ('u' zum Wechseln)",
+                        if language.is_empty() {
+                            "Original"
+                        } else {
+                            language
+                        }
+                    ),
+                    Style::default().fg(Color::Green).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Open shift
+        if let Some(shift) = &self.shift {
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Schicht: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "seit {}, Anfangsbestand {} ('o' zum Abschließen)",
+                        shift.opened.with_timezone(&Local).format("%H:%M"),
+                        format::euro(shift.float_ct)
+                    ),
+                    Style::default().fg(Color::Green).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Reloaded settings (for a few seconds)
+        if let Some((_, changed_count)) = self.settings_reload {
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Einstellungen: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("neu geladen, {} geändert", changed_count),
+                    Style::default().fg(Color::Green).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Read-only mode
+        if self.is_read_only() {
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Nur-Lese-Modus: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    "Verkäufe, Drucke und Produktänderungen sind deaktiviert",
+                    Style::default().fg(Color::LightRed).bg(Color::Black),
+                ),
+            ]));
+        }
+
         // Printer
-        match self.printer {
-            Ok(_) => status.push(Spans::from(vec![
+        match self.engine.printer() {
+            Ok(printer) => {
+                // Warn about missing or ending media before the next print fails.
+                let flags = self
+                    .engine
+                    .printer_error_flags()
+                    .unwrap_or(StatusErrorFlags::empty());
+
+                let (printer_str, color) = if flags.contains(StatusErrorFlags::NO_MEDIA) {
+                    ("verbunden, kein Etikett eingelegt", Color::LightRed)
+                } else if flags.contains(StatusErrorFlags::END_OF_MEDIA) {
+                    ("verbunden, Etikettenrolle zu Ende", Color::Yellow)
+                } else if flags.contains(StatusErrorFlags::TURNED_OFF) {
+                    (
+                        "verbunden, im Ruhezustand (wird beim Drucken geweckt)",
+                        Color::Green,
+                    )
+                } else {
+                    ("verbunden", Color::Green)
+                };
+
+                status.push(Spans::from(vec![
+                    Span::styled(
+                        "Drucker: ",
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(printer_str, Style::default().fg(color).bg(Color::Black)),
+                    Span::raw(format!(" ({}", printer.model())),
+                    Span::styled(
+                        match self.engine.printer_rank() {
+                            0 => String::new(),
+                            rank => format!(", Ausweichdrucker Nr. {}", rank + 1),
+                        },
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::raw(")"),
+                ]))
+            }
+
+            Err(err) => {
+                // Missing permissions can be fixed by the operator, so we explain how.
+                let printer_str = match err {
+                    AttachError::PermissionDenied { .. } => format!(
+                        "keine Berechtigung für den USB-Zugriff. Bitte udev-Regel einrichten und den Benutzer zur Gruppe \"lp\" hinzufügen ({})",
+                        err
+                    ),
+
+                    err => format!("{}", err),
+                };
+
+                status.push(Spans::from(vec![
+                    Span::styled(
+                        "Drucker: ",
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        printer_str,
+                        Style::default().fg(Color::LightRed).bg(Color::Black),
+                    ),
+                ]))
+            }
+        }
+
+        // Label stock (only if the roll is running out)
+        if let Some(roll) = self.label_roll.as_ref().filter(|roll| {
+            roll.remaining_mm() < 1000.0 * self.engine.db().settings().label_stock_warning_m
+        }) {
+            let remaining_str = match roll.remaining_labels() {
+                Some(labels) => format!("{} Etiketten", labels),
+                None => format!("{} m", format::fixed(roll.remaining_mm() / 1000.0, 1)),
+            };
+
+            status.push(Spans::from(vec![
                 Span::styled(
-                    "Drucker: ",
+                    "Etiketten: ",
                     Style::default()
                         .fg(Color::Yellow)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    "verbunden",
+                    format!(
+                        "Rolle fast leer, noch ca. {} ('w' nach dem Wechsel)",
+                        remaining_str
+                    ),
+                    Style::default().fg(Color::Yellow).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Interrupted prints (only shown if there are some)
+        if !self.pending_prints.is_empty() {
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Nachdruck: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "{} Bon(s) warten, bis der Fehler am Drucker behoben ist",
+                        self.pending_prints.len()
+                    ),
+                    Style::default().fg(Color::Yellow).bg(Color::Black),
+                ),
+            ]));
+        }
+
+        // Storage (only shown if the disk is getting full or the DB large)
+        if let Some(storage) = self.engine.storage() {
+            let storage_str = format!(
+                "{} MB frei, Datenbank {} MB",
+                storage.free_mb(),
+                storage.db_mb()
+            );
+
+            let storage_span = match self.engine.storage_level() {
+                StorageLevel::Ok => None,
+                StorageLevel::Low => Some(Span::styled(
+                    storage_str,
+                    Style::default().fg(Color::Yellow).bg(Color::Black),
+                )),
+                StorageLevel::Critical => Some(Span::styled(
+                    format!("{} (Verkäufe gesperrt)", storage_str),
+                    Style::default().fg(Color::LightRed).bg(Color::Black),
+                )),
+            };
+
+            if let Some(storage_span) = storage_span {
+                status.push(Spans::from(vec![
+                    Span::styled(
+                        "Speicher: ",
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    storage_span,
+                ]));
+            }
+        }
+
+        // Update (only if a newer release exists)
+        if let Some(version) = &self.available_update {
+            status.push(Spans::from(vec![
+                Span::styled(
+                    "Update: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "Version {} ist verfügbar (installiert: {})",
+                        version,
+                        Version::current()
+                    ),
                     Style::default().fg(Color::Green).bg(Color::Black),
                 ),
-            ])),
+            ]));
+        }
 
-            Err(err) => status.push(Spans::from(vec![
+        // Database (only shown if something is wrong)
+        let health = self.engine.health();
+
+        let db_str = match &health.db {
+            ComponentState::Ok => None,
+
+            ComponentState::Degraded(_) => Some(format!(
+                "{} Verkäufe nicht gespeichert (zwischengespeichert im Journal)",
+                health.queue_depth
+            )),
+
+            ComponentState::Failed(err) => Some(format!("nicht erreichbar ({})", err)),
+        };
+
+        if let Some(db_str) = db_str {
+            status.push(Spans::from(vec![
                 Span::styled(
-                    "Drucker: ",
+                    "Datenbank: ",
                     Style::default()
                         .fg(Color::Yellow)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    format!("{}", err),
+                    db_str,
                     Style::default().fg(Color::LightRed).bg(Color::Black),
                 ),
-            ])),
+            ]));
         }
 
+        status
+    }
+
+    pub(super) fn draw_status_chunk<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        chunk: Rect,
+        status: Vec<Spans>,
+    ) {
+        // Build and render the block.
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::DarkGray).bg(Color::Black));
+
+        let inner_chunk = block.inner(chunk).inner(&Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+
+        frame.render_widget(block, chunk);
+
+        // Render the status lines.
         let paragraph = Paragraph::new(status).wrap(Wrap { trim: true });
         frame.render_widget(paragraph, inner_chunk);
     }
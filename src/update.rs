@@ -0,0 +1,213 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::io::{Error as IOError, ErrorKind as IOErrorKind};
+use std::process::Command;
+use std::str::FromStr;
+
+use serde_json::{Error as JsonError, Value as JsonValue};
+
+/// The latest release of this repository, as JSON
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/JayTee42/weight-wb/releases/latest";
+
+/// Stalls are often offline, so we don't wait long for an answer.
+const TIMEOUT_SECS: u32 = 10;
+
+#[derive(Debug)]
+pub enum Error {
+    CurlMissing,
+    Curl(IOError),
+    Request(String),
+    Json(JsonError),
+    InvalidResponse,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+
+        match self {
+            CurlMissing => write!(
+                f,
+                "curl is not installed, but it is needed to fetch the latest release."
+            ),
+            Curl(err) => write!(f, "curl could not be started: {}", err),
+            Request(err) => write!(f, "The latest release could not be fetched: {}", err),
+            Json(err) => write!(f, "The response is no valid JSON: {}", err),
+            InvalidResponse => write!(f, "The response contains no valid release version."),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<IOError> for Error {
+    fn from(value: IOError) -> Self {
+        if value.kind() == IOErrorKind::NotFound {
+            Error::CurlMissing
+        } else {
+            Error::Curl(value)
+        }
+    }
+}
+
+impl From<JsonError> for Error {
+    fn from(value: JsonError) -> Self {
+        Error::Json(value)
+    }
+}
+
+/// A version like "1.2.3". Release tags may start with a "v".
+/// Missing parts count as zero, so "1.0" and "1.0.0" are the same version.
+#[derive(Debug, Clone)]
+pub struct Version(Vec<u64>);
+
+impl Version {
+    /// The version of the running binary
+    pub fn current() -> Self {
+        env!("CARGO_PKG_VERSION")
+            .parse()
+            .expect("Package version must be valid")
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        let part = |version: &Self, idx| version.0.get(idx).copied().unwrap_or(0);
+
+        (0..len)
+            .map(|idx| part(self, idx).cmp(&part(other, idx)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version {}
+
+impl FromStr for Version {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix('v').unwrap_or(s);
+
+        // Pre-release suffixes (e.g. "1.2.3-rc1") are ignored.
+        let s = s.split(['-', '+']).next().unwrap_or_default();
+
+        s.split('.')
+            .map(|part| part.parse().map_err(|_| ()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Version)
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(u64::to_string).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+/// Read the version from the tag name in the JSON of a release.
+fn release_version(json: &[u8]) -> Result<Version, Error> {
+    let release: JsonValue = serde_json::from_slice(json)?;
+
+    release["tag_name"]
+        .as_str()
+        .and_then(|tag_name| tag_name.parse().ok())
+        .ok_or(Error::InvalidResponse)
+}
+
+/// Ask GitHub for the version of the latest release.
+/// This uses curl, so we need no HTTP and TLS stack of our own.
+pub fn latest_release() -> Result<Version, Error> {
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--max-time",
+            &TIMEOUT_SECS.to_string(),
+            "--header",
+            "Accept: application/vnd.github+json",
+            "--user-agent",
+            "weight-wb",
+            LATEST_RELEASE_URL,
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::Request(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    release_version(&output.stdout)
+}
+
+/// Check if there is a newer release than the running binary.
+/// Nothing is installed, the newer version is only returned.
+pub fn check_for_update() -> Result<Option<Version>, Error> {
+    let latest = latest_release()?;
+
+    Ok((latest > Version::current()).then_some(latest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_compared_numerically() {
+        let version = |s: &str| s.parse::<Version>().unwrap();
+
+        assert_eq!(version("v1.2.3"), version("1.2.3"));
+        assert_eq!(version("1.2.3-rc1").to_string(), "1.2.3");
+        assert!(version("0.10.0") > version("0.9.12"));
+        assert!(version("1.0") < version("1.0.1"));
+        assert!(version("2") > version("1.99.99"));
+        assert!("latest".parse::<Version>().is_err());
+        assert!("1..2".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn trailing_zeros_are_the_same_version() {
+        let version = |s: &str| s.parse::<Version>().unwrap();
+
+        assert_eq!(version("1.0"), version("1.0.0"));
+        assert_eq!(version("v1"), version("1.0.0.0"));
+        assert_eq!(version("1.0.0").cmp(&version("1.0")), Ordering::Equal);
+        assert!(version("1.0.0") <= version("1.0"));
+        assert!(version("1.0.0.1") > version("1.0"));
+
+        // The original notation is kept.
+        assert_eq!(version("1.0").to_string(), "1.0");
+    }
+
+    #[test]
+    fn release_version_is_parsed_from_json() {
+        let json =
+            br#"{"name": "Spring \"tag_name\": \"v9\"", "url": "...", "tag_name": "v0.2.0"}"#;
+        assert_eq!(release_version(json).unwrap(), "0.2.0".parse().unwrap());
+
+        let json = br#"{"message": "Not Found"}"#;
+        assert!(matches!(release_version(json), Err(Error::InvalidResponse)));
+
+        let json = br#"{"tag_name": 2}"#;
+        assert!(matches!(release_version(json), Err(Error::InvalidResponse)));
+
+        assert!(matches!(release_version(b"<html>"), Err(Error::Json(_))));
+    }
+}
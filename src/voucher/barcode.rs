@@ -0,0 +1,239 @@
+use image::{GrayImage, Luma};
+
+/// The L (odd parity) patterns of the digits 0 to 9
+const L_PATTERNS: [u8; 10] = [
+    0b0001101, 0b0011001, 0b0010011, 0b0111101, 0b0100011, 0b0110001, 0b0101111, 0b0111011,
+    0b0110111, 0b0001011,
+];
+
+/// The parities of the left half (1 = G, even parity), selected by the first digit
+const PARITIES: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011, 0b011001, 0b011100, 0b010101, 0b010110,
+    0b011010,
+];
+
+/// The white modules before and after the bars that scanners need
+const QUIET_ZONE_MODULES: u32 = 9;
+
+/// Calculate the check digit of the first 12 digits of an EAN-13.
+pub fn ean13_check_digit(digits: &[u8; 12]) -> u8 {
+    let sum = digits
+        .iter()
+        .enumerate()
+        .map(|(idx, &digit)| (digit as u32) * if idx % 2 == 0 { 1 } else { 3 })
+        .sum::<u32>();
+
+    ((10 - (sum % 10)) % 10) as u8
+}
+
+/// Complete an EAN-13 code.
+/// `code` consists of 12 digits (the check digit is appended) or 13 digits (the check digit is validated).
+/// Returns `None` if the code is invalid.
+pub fn ean13_digits(code: &str) -> Option<[u8; 13]> {
+    let digits = code
+        .chars()
+        .map(|c| c.to_digit(10).map(|digit| digit as u8))
+        .collect::<Option<Vec<_>>>()?;
+
+    let data: [u8; 12] = digits.get(..12)?.try_into().ok()?;
+    let check_digit = ean13_check_digit(&data);
+
+    match digits.len() {
+        12 => (),
+        13 if digits[12] == check_digit => (),
+        _ => return None,
+    }
+
+    let mut complete = [check_digit; 13];
+    complete[..12].copy_from_slice(&data);
+
+    Some(complete)
+}
+
+/// Render an EAN-13 barcode (including its quiet zones) of a code that `ean13_digits()` accepts.
+/// Each module (= the thinnest bar) is `module_width` pixels wide.
+/// Returns `None` if the code is invalid.
+pub fn ean13(code: &str, module_width: u32, height: u32) -> Option<GrayImage> {
+    let digits = ean13_digits(code)?;
+
+    // Collect the modules from left to right (true = black).
+    let mut modules = Vec::with_capacity(95);
+
+    let mut push_pattern = |pattern: u8, count: u32| {
+        for bit in (0..count).rev() {
+            modules.push((pattern >> bit) & 1 == 1);
+        }
+    };
+
+    // Start guard
+    push_pattern(0b101, 3);
+
+    // Left half: The first digit is encoded in the parities.
+    let parities = PARITIES[digits[0] as usize];
+
+    for (idx, &digit) in digits[1..7].iter().enumerate() {
+        let l_pattern = L_PATTERNS[digit as usize];
+
+        // G patterns are the mirrored complements of L patterns.
+        let pattern = if (parities >> (5 - idx)) & 1 == 1 {
+            (!l_pattern & 0b1111111).reverse_bits() >> 1
+        } else {
+            l_pattern
+        };
+
+        push_pattern(pattern, 7);
+    }
+
+    // Center guard
+    push_pattern(0b01010, 5);
+
+    // Right half: R patterns are the complements of L patterns.
+    for &digit in &digits[7..13] {
+        push_pattern(!L_PATTERNS[digit as usize] & 0b1111111, 7);
+    }
+
+    // End guard
+    push_pattern(0b101, 3);
+
+    // Draw the modules.
+    let width = ((modules.len() as u32) + (2 * QUIET_ZONE_MODULES)) * module_width;
+
+    Some(GrayImage::from_fn(width, height, |x, _| {
+        let module = (x / module_width).checked_sub(QUIET_ZONE_MODULES);
+
+        match module.and_then(|module| modules.get(module as usize)) {
+            Some(true) => Luma([0x00]),
+            _ => Luma([0xff]),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::db::test_support::{memory_db, ProductBuilder};
+
+    /// The R patterns of the digits 0 to 9 (G patterns are their mirror images)
+    const R_PATTERNS: [&str; 10] = [
+        "1110010", "1100110", "1101100", "1000010", "1011100", "1001110", "1010000", "1000100",
+        "1001000", "1110100",
+    ];
+
+    /// The left-half parities selected by the first digit
+    const PARITY_NAMES: [&str; 10] = [
+        "LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG", "LGGLLG", "LGGGLL", "LGLGLG", "LGLGGL",
+        "LGGLGL",
+    ];
+
+    /// Read the modules of a barcode with a module width of 1 (checking the quiet zones).
+    fn modules(image: &GrayImage) -> String {
+        let row = (0..image.width())
+            .map(|x| match image.get_pixel(x, 0).0[0] {
+                0x00 => '1',
+                _ => '0',
+            })
+            .collect::<String>();
+
+        let quiet_zone = "0".repeat(QUIET_ZONE_MODULES as usize);
+        let modules = row
+            .strip_prefix(&quiet_zone)
+            .and_then(|row| row.strip_suffix(&quiet_zone))
+            .expect("Quiet zones are white");
+
+        modules.to_string()
+    }
+
+    /// Encode a digit with the given pattern set from the tables above.
+    fn pattern(set: char, digit: u8) -> String {
+        let r_pattern = R_PATTERNS[digit as usize];
+
+        match set {
+            'L' => r_pattern
+                .chars()
+                .map(|c| if c == '1' { '0' } else { '1' })
+                .collect(),
+            'G' => r_pattern.chars().rev().collect(),
+            _ => r_pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn check_digits_of_known_codes() {
+        assert_eq!(ean13_check_digit(&[4, 0, 0, 6, 3, 8, 1, 3, 3, 3, 9, 3]), 1);
+        assert_eq!(ean13_check_digit(&[5, 9, 0, 1, 2, 3, 4, 1, 2, 3, 4, 5]), 7);
+        assert_eq!(ean13_check_digit(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+
+        assert_eq!(
+            ean13_digits("590123412345"),
+            Some([5, 9, 0, 1, 2, 3, 4, 1, 2, 3, 4, 5, 7])
+        );
+        assert_eq!(
+            ean13_digits("5901234123457"),
+            Some([5, 9, 0, 1, 2, 3, 4, 1, 2, 3, 4, 5, 7])
+        );
+        assert_eq!(ean13_digits("5901234123458"), None);
+        assert_eq!(ean13_digits("59012341234"), None);
+        assert_eq!(ean13_digits("59012341234578"), None);
+        assert_eq!(ean13_digits("59012341234a"), None);
+        assert_eq!(ean13_digits(""), None);
+    }
+
+    #[test]
+    fn barcodes_have_guards_and_95_modules() {
+        let image = ean13("4006381333931", 3, 20).unwrap();
+        assert_eq!(image.dimensions(), ((95 + 2 * QUIET_ZONE_MODULES) * 3, 20));
+
+        // Every row is the same.
+        assert!((0..image.width()).all(|x| image.get_pixel(x, 0) == image.get_pixel(x, 19)));
+
+        let modules = modules(&ean13("4006381333931", 1, 1).unwrap());
+        assert_eq!(modules.len(), 95);
+        assert_eq!(&modules[..3], "101");
+        assert_eq!(&modules[45..50], "01010");
+        assert_eq!(&modules[92..], "101");
+    }
+
+    #[test]
+    fn the_first_digit_selects_the_parities() {
+        for (first_digit, parities) in PARITY_NAMES.iter().enumerate() {
+            let code = format!("{first_digit}12345678901");
+            let digits = ean13_digits(&code).unwrap();
+            let modules = modules(&ean13(&code, 1, 1).unwrap());
+
+            let mut expected = String::from("101");
+
+            for (idx, set) in parities.chars().enumerate() {
+                expected += &pattern(set, digits[idx + 1]);
+            }
+
+            expected += "01010";
+
+            for &digit in &digits[7..] {
+                expected += &pattern('R', digit);
+            }
+
+            expected += "101";
+
+            assert_eq!(modules, expected, "First digit {first_digit}");
+        }
+    }
+
+    #[test]
+    fn in_store_codes_are_valid() {
+        let mut db = memory_db();
+        ProductBuilder::new("Rinderhack").add_to(&mut db);
+
+        let code = db.products()[0].in_store_code().unwrap();
+        assert_eq!(code, "200000000001");
+        assert_eq!(
+            ean13_digits(&code),
+            Some([2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 5])
+        );
+
+        // The prefix 20 selects LLGGLG.
+        let modules = modules(&ean13(&code, 1, 1).unwrap());
+        assert_eq!(&modules[3..10], pattern('L', 0));
+        assert_eq!(&modules[17..24], pattern('G', 0));
+    }
+}
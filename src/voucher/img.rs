@@ -1,29 +1,99 @@
-use super::{Alignment, Builder as VoucherBuilder, Component as VoucherComponent, Spacing};
+use super::{
+    blend_luma, Alignment, Builder as VoucherBuilder, Component as VoucherComponent, Spacing,
+};
 
-use image::{imageops::FilterType, DynamicImage, GrayImage};
+use image::{
+    imageops::{self, FilterType},
+    DynamicImage, GrayAlphaImage, GrayImage,
+};
+
+/// The filter to upscale images with
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UpscaleFilter {
+    /// Repeat pixels by an integer factor, so pixel logos stay crisp
+    Nearest,
+
+    /// Interpolate smoothly, which suits photos
+    CatmullRom,
+}
+
+/// The part of an image that is kept when it is cropped to fill a region
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Gravity {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Gravity {
+    /// Distribute the excess width and height and return the offset of the kept part.
+    fn offset(&self, excess_width: u32, excess_height: u32) -> (u32, u32) {
+        use Gravity::*;
+
+        let x = match self {
+            TopLeft | Left | BottomLeft => 0,
+            Top | Center | Bottom => excess_width / 2,
+            TopRight | Right | BottomRight => excess_width,
+        };
+
+        let y = match self {
+            TopLeft | Top | TopRight => 0,
+            Left | Center | Right => excess_height / 2,
+            BottomLeft | Bottom | BottomRight => excess_height,
+        };
+
+        (x, y)
+    }
+}
+
+/// Fill a region of fixed size with the image, cropping whatever does not fit
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Fill {
+    width: u32,
+    height: u32,
+    gravity: Gravity,
+}
 
 pub struct Builder {
     /// The underlying voucher builder
     voucher: VoucherBuilder,
 
     /// The image that shall be rendered
-    image: GrayImage,
+    image: GrayAlphaImage,
 
     /// The spacing to apply to this component
     spacing: Spacing,
 
     /// The alignment to apply to this component
     alignment: Alignment,
+
+    /// Do we render the component at all?
+    visible: bool,
+
+    /// Do we upscale images that are narrower than the component (and how)?
+    upscale: Option<UpscaleFilter>,
+
+    /// The region the image is scaled and cropped to (if any)
+    fill: Option<Fill>,
 }
 
 impl Builder {
     fn new(voucher: VoucherBuilder, image: &DynamicImage) -> Self {
-        // Convert the image to grayscale.
+        // Convert the image to grayscale, but keep its transparency.
         Self {
             voucher,
-            image: image.to_luma8(),
+            image: image.to_luma_alpha8(),
             spacing: Default::default(),
             alignment: Alignment::Center,
+            visible: true,
+            upscale: None,
+            fill: None,
         }
     }
 
@@ -37,7 +107,48 @@ impl Builder {
         self
     }
 
+    /// Upscale images that are narrower than the component to its width.
+    /// By default, images are only downscaled.
+    pub fn upscale(mut self, upscale: Option<UpscaleFilter>) -> Self {
+        self.upscale = upscale;
+        self
+    }
+
+    /// Cut a rectangle out of the image (clamped to the image bounds).
+    pub fn crop(mut self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        let x = x.min(self.image.width());
+        let y = y.min(self.image.height());
+        let width = width.min(self.image.width() - x);
+        let height = height.min(self.image.height() - y);
+
+        self.image = imageops::crop_imm(&self.image, x, y, width, height).to_image();
+        self
+    }
+
+    /// Scale the image until it covers `width` x `height` pixels and crop the rest.
+    /// The gravity decides which part of the image is kept (e.g. the top of a photo for a banner).
+    /// If the region is wider than the component, it is downscaled like any other image.
+    pub fn fill(mut self, width: u32, height: u32, gravity: Gravity) -> Self {
+        self.fill = Some(Fill {
+            width,
+            height,
+            gravity,
+        });
+
+        self
+    }
+
+    /// Hidden components take no space at all (including their spacing).
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
     pub fn finalize_image_component(mut self) -> VoucherBuilder {
+        if !self.visible {
+            return self.voucher;
+        }
+
         // Calculate the available line width.
         // If it is degenerated, we return early.
         let width_pix = ((self.voucher.width as f32) - self.spacing.horz()).floor() as u32;
@@ -46,12 +157,67 @@ impl Builder {
             return self.voucher;
         }
 
+        // Scale and crop the image to the fill region.
+        if let Some(fill) = self.fill {
+            if (fill.width == 0)
+                || (fill.height == 0)
+                || (self.image.width() == 0)
+                || (self.image.height() == 0)
+            {
+                return self.voucher;
+            }
+
+            let (width, height) = self.image.dimensions();
+
+            // Scale by the larger factor, so the image covers the region in both directions.
+            let scale = f32::max(
+                (fill.width as f32) / (width as f32),
+                (fill.height as f32) / (height as f32),
+            );
+
+            let scaled_width = ((width as f32) * scale).ceil().max(fill.width as f32) as u32;
+            let scaled_height = ((height as f32) * scale).ceil().max(fill.height as f32) as u32;
+
+            let scaled = DynamicImage::from(self.image)
+                .resize_exact(scaled_width, scaled_height, FilterType::CatmullRom)
+                .to_luma_alpha8();
+
+            let (x, y) = fill
+                .gravity
+                .offset(scaled_width - fill.width, scaled_height - fill.height);
+
+            self.image = imageops::crop_imm(&scaled, x, y, fill.width, fill.height).to_image();
+        }
+
         // Downscale the image to the given width if it is above it.
         // This method keeps its aspect ratio.
         if self.image.width() > width_pix {
             self.image = DynamicImage::from(self.image)
                 .resize(width_pix, u32::MAX, FilterType::CatmullRom)
-                .to_luma8();
+                .to_luma_alpha8();
+        }
+
+        // Upscale the image to the given width if it is below it (and we are asked to).
+        match self.upscale {
+            Some(UpscaleFilter::Nearest) => {
+                let factor = width_pix / self.image.width().max(1);
+
+                if factor > 1 {
+                    let (width, height) = self.image.dimensions();
+
+                    self.image = DynamicImage::from(self.image)
+                        .resize_exact(factor * width, factor * height, FilterType::Nearest)
+                        .to_luma_alpha8();
+                }
+            }
+
+            Some(UpscaleFilter::CatmullRom) if self.image.width() < width_pix => {
+                self.image = DynamicImage::from(self.image)
+                    .resize(width_pix, u32::MAX, FilterType::CatmullRom)
+                    .to_luma_alpha8();
+            }
+
+            _ => (),
         }
 
         // Determine the X offset of the image.
@@ -71,6 +237,7 @@ impl Builder {
             offset_x_pix,
             offset_y_pix: self.spacing.top.round() as u32,
             vert_spacing_pix: self.spacing.vert().round() as u32,
+            spacing_clamped: self.spacing.clamped,
         };
 
         self.voucher
@@ -92,8 +259,8 @@ impl VoucherBuilder {
 }
 
 pub struct Component {
-    /// The converted and resized image
-    image: GrayImage,
+    /// The converted and resized image (with alpha)
+    image: GrayAlphaImage,
 
     /// The X pixel offset to render the image to (aka `spacing.left` + potential alignment)
     offset_x_pix: u32,
@@ -103,6 +270,9 @@ pub struct Component {
 
     /// The vertical spacing in pixels
     vert_spacing_pix: u32,
+
+    /// Has a negative spacing been raised to zero?
+    spacing_clamped: bool,
 }
 
 impl Component {
@@ -110,18 +280,22 @@ impl Component {
         self.vert_spacing_pix + self.image.height()
     }
 
+    pub fn spacing_clamped(&self) -> bool {
+        self.spacing_clamped
+    }
+
     pub(super) fn render(&self, image: &mut GrayImage, offset_y_pix: u32) {
         // Combine our vertical component offset and spacing.
         let total_offset_y = offset_y_pix + self.offset_y_pix;
 
-        // Walk the pixels.
+        // Walk the pixels and blend them over the voucher like the text renderer does.
+        // Transparent parts of the image keep the white background.
         for y in 0..self.image.height() {
             for x in 0..self.image.width() {
-                let pix = *self.image.get_pixel(x, y);
-                let x_pix = self.offset_x_pix + x;
-                let y_pix = total_offset_y + y;
+                let [luma_a, alpha_a] = self.image.get_pixel(x, y).0;
+                let pix = image.get_pixel_mut(self.offset_x_pix + x, total_offset_y + y);
 
-                image.put_pixel(x_pix, y_pix, pix);
+                pix.0[0] = blend_luma(pix.0[0], luma_a, alpha_a);
             }
         }
     }
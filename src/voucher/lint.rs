@@ -0,0 +1,74 @@
+use super::Component;
+
+use std::fmt::Display;
+
+/// The smallest font size in pixels that is reliably legible on 300 dpi print heads (about 1.4 mm)
+pub const DEFAULT_MIN_FONT_SIZE: f32 = 16.0;
+
+/// A likely mistake in the layout of a voucher.
+/// Nothing stops the voucher from being printed, but it should be looked at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// Glyphs reach beyond the top or bottom of the component and have been cut off.
+    ClippedText,
+
+    /// A spacing has been negative (and was raised to zero), so the component would have overlapped its neighbours.
+    NegativeSpacing,
+
+    /// The font size in pixels (after shrinking the voucher) is below the minimum.
+    SmallFont { font_size: f32, min_font_size: f32 },
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Issue::*;
+
+        match self {
+            ClippedText => write!(f, "Glyphs have been cut off at the top or bottom."),
+            NegativeSpacing => write!(
+                f,
+                "A negative spacing has been raised to zero (the component would have overlapped its neighbours)."
+            ),
+            SmallFont {
+                font_size,
+                min_font_size,
+            } => write!(
+                f,
+                "The font size of {:.1} pixels is below the printable minimum of {:.1} pixels.",
+                font_size, min_font_size
+            ),
+        }
+    }
+}
+
+/// Check a rendered component for likely mistakes.
+/// `clipped` tells if glyphs have been cut off during rendering, `scale` is the factor the voucher has been shrunk by.
+pub(super) fn lint_component(
+    component: &Component,
+    clipped: bool,
+    scale: f32,
+    min_font_size: f32,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    if clipped {
+        issues.push(Issue::ClippedText);
+    }
+
+    if component.spacing_clamped() {
+        issues.push(Issue::NegativeSpacing);
+    }
+
+    if let Component::Text(text_component) = component {
+        let font_size = text_component.font_size() * scale;
+
+        if font_size < min_font_size {
+            issues.push(Issue::SmallFont {
+                font_size,
+                min_font_size,
+            });
+        }
+    }
+
+    issues
+}
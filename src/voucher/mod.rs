@@ -1,4 +1,81 @@
-use image::GrayImage;
+use std::{fmt::Display, ops::Range};
+
+use image::{imageops::FilterType, DynamicImage, GrayImage};
+
+#[derive(Debug)]
+pub enum Error {
+    TooLong {
+        height: u32,
+        max_height: u32,
+        components: Vec<String>,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+
+        match self {
+            TooLong {
+                height,
+                max_height,
+                components,
+            } => write!(
+                f,
+                "The voucher is too long ({} instead of at most {} pixels). These components exceed the limit: {}",
+                height,
+                max_height,
+                components.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Facts about a built voucher, e.g. to warn about truncated lines before printing
+#[derive(Debug, Default)]
+pub struct Metadata {
+    /// The height of the final image in pixels
+    pub height: u32,
+
+    /// The components from top to bottom
+    pub components: Vec<ComponentMetadata>,
+
+    /// The font families of all text components (including fallbacks)
+    pub fonts: Vec<String>,
+}
+
+impl Metadata {
+    /// The text components that did not fit into their lines
+    pub fn truncated_components(&self) -> impl Iterator<Item = &ComponentMetadata> {
+        self.components
+            .iter()
+            .filter(|component| component.truncated_lines > 0)
+    }
+
+    /// The likely layout mistakes with the components they have been found in
+    pub fn issues(&self) -> impl Iterator<Item = (&ComponentMetadata, &LayoutIssue)> {
+        self.components
+            .iter()
+            .flat_map(|component| component.issues.iter().map(move |issue| (component, issue)))
+    }
+}
+
+#[derive(Debug)]
+pub struct ComponentMetadata {
+    /// A short description to point the user at the component
+    pub description: String,
+
+    /// The rows of the component in pixels (before trimming or shrinking the voucher)
+    pub rows: Range<u32>,
+
+    /// The number of lines that have been cut off at the right (for text components)
+    pub truncated_lines: usize,
+
+    /// The findings of the layout linter (see `lint::lint_component()`)
+    pub issues: Vec<LayoutIssue>,
+}
 
 #[derive(Copy, Clone)]
 pub struct Spacing {
@@ -6,20 +83,23 @@ pub struct Spacing {
     right: f32,
     top: f32,
     bottom: f32,
+
+    /// Has a negative spacing been raised to zero?
+    clamped: bool,
 }
 
 impl Spacing {
+    /// Negative spacings (e.g. from miscalculated offsets) would overlap the neighbouring components.
+    /// They are raised to zero and reported by the layout linter.
     pub fn lrtb(left: f32, right: f32, top: f32, bottom: f32) -> Self {
-        assert!(
-            (left >= 0.0) && (right >= 0.0) && (top >= 0.0) && (bottom >= 0.0),
-            "Spacing must be non-negative"
-        );
+        let clamped = (left < 0.0) || (right < 0.0) || (top < 0.0) || (bottom < 0.0);
 
         Self {
-            left,
-            right,
-            top,
-            bottom,
+            left: left.max(0.0),
+            right: right.max(0.0),
+            top: top.max(0.0),
+            bottom: bottom.max(0.0),
+            clamped,
         }
     }
 
@@ -47,6 +127,7 @@ impl Default for Spacing {
             right: 0.0,
             top: 0.0,
             bottom: 0.0,
+            clamped: false,
         }
     }
 }
@@ -72,6 +153,26 @@ impl Component {
             Image(image_component) => image_component.height(),
         }
     }
+
+    /// A short description to point the user at the component
+    fn description(&self) -> String {
+        use Component::*;
+
+        match self {
+            Text(text_component) => format!("\"{}\"", text_component.description()),
+            Image(image_component) => format!("image ({} pixels high)", image_component.height()),
+        }
+    }
+
+    /// Has a negative spacing of the component been raised to zero?
+    fn spacing_clamped(&self) -> bool {
+        use Component::*;
+
+        match self {
+            Text(text_component) => text_component.spacing_clamped(),
+            Image(image_component) => image_component.spacing_clamped(),
+        }
+    }
 }
 
 pub struct Builder {
@@ -83,18 +184,112 @@ pub struct Builder {
 
     /// A shared context for the text layout data
     text_ctx: TextContext,
+
+    /// Do we drop blank components and the white rows at the end?
+    trim_empty_space: bool,
+
+    /// The maximum height of the voucher in pixels (if any)
+    max_height: Option<u32>,
+
+    /// The minimum height of the voucher in pixels (if any)
+    min_height: Option<u32>,
+
+    /// Do we shrink vouchers that exceed the maximum height instead of failing?
+    shrink_to_fit: bool,
+
+    /// Do we avoid everything that might render differently on other machines?
+    deterministic: bool,
+
+    /// Do we render all texts bold and darken anti-aliased edges?
+    heavy: bool,
+
+    /// The smallest font size in pixels that is still legible on the print (see `min_font_size()`)
+    min_font_size: f32,
 }
 
 impl Builder {
     pub fn new(width: u32) -> Self {
+        Self::with_text_context(width, TextContext::new())
+    }
+
+    /// Create a builder that reuses the fonts and rasterized glyphs of earlier vouchers.
+    pub fn with_text_context(width: u32, mut text_ctx: TextContext) -> Self {
+        // The layout lines belong to the previous voucher.
+        text_ctx.lines.clear();
+
         Self {
             width,
             components: Vec::new(),
-            text_ctx: TextContext::new(),
+            text_ctx,
+            trim_empty_space: false,
+            max_height: None,
+            min_height: None,
+            shrink_to_fit: false,
+            deterministic: false,
+            heavy: false,
+            min_font_size: DEFAULT_MIN_FONT_SIZE,
         }
     }
 
-    pub fn build(mut self) -> GrayImage {
+    /// Skip text components without visible content and cut off the white rows at the end.
+    /// This shortens continuous labels and saves media.
+    pub fn trim_empty_space(mut self, trim_empty_space: bool) -> Self {
+        self.trim_empty_space = trim_empty_space;
+        self
+    }
+
+    /// Limit the height of the voucher, e.g. to avoid wasting media on a pasted essay.
+    pub fn max_height(mut self, max_height: Option<u32>) -> Self {
+        self.max_height = max_height;
+        self
+    }
+
+    /// Pad shorter vouchers with white rows, e.g. to fill a die-cut label.
+    /// Combined with the same maximum height, this makes the voucher exactly that high.
+    pub fn min_height(mut self, min_height: Option<u32>) -> Self {
+        self.min_height = min_height;
+        self
+    }
+
+    /// Scale vouchers that exceed the maximum height down instead of failing.
+    pub fn shrink_to_fit(mut self, shrink_to_fit: bool) -> Self {
+        self.shrink_to_fit = shrink_to_fit;
+        self
+    }
+
+    /// Render the same inputs to the same pixels on every machine, e.g. for archives and snapshot tests.
+    /// At the moment, this places glyphs on whole pixels. Combine it with `TextContext::with_fonts()`
+    /// to pin the fonts as well.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Render all texts bold and print anti-aliased edges black, so the strokes get thicker.
+    /// This keeps vouchers legible on dark or frozen packaging that takes the heat badly.
+    pub fn heavy(mut self, heavy: bool) -> Self {
+        self.heavy = heavy;
+        self
+    }
+
+    /// Report texts below this font size in pixels (after shrinking the voucher) as layout issues.
+    pub fn min_font_size(mut self, min_font_size: f32) -> Self {
+        self.min_font_size = min_font_size;
+        self
+    }
+
+    pub fn build(self) -> Result<GrayImage, Error> {
+        Ok(self.build_with_text_context()?.0)
+    }
+
+    /// Like `build()`, but also hand back the text context for the next voucher.
+    pub fn build_with_text_context(self) -> Result<(GrayImage, TextContext), Error> {
+        let (image, text_ctx, _) = self.build_with_metadata()?;
+        Ok((image, text_ctx))
+    }
+
+    /// Like `build_with_text_context()`, but also describe what ended up on the voucher.
+    pub fn build_with_metadata(mut self) -> Result<(GrayImage, TextContext, Metadata), Error> {
         // Accumulate the total height.
         let height = self.components.iter().map(Component::height).sum::<u32>();
 
@@ -103,39 +298,309 @@ impl Builder {
         image.fill(0xff);
 
         // Render the components.
+        // The linter needs to know which of them have cut off glyphs.
         let mut offset_y_px = 0;
+        let mut clipped_components = Vec::with_capacity(self.components.len());
 
         for component in &self.components {
             use Component::*;
 
-            match component {
+            let clipped = match component {
                 Text(comp) => comp.render(&mut image, offset_y_px, &mut self.text_ctx),
-                Image(comp) => comp.render(&mut image, offset_y_px),
+                Image(comp) => {
+                    comp.render(&mut image, offset_y_px);
+                    false
+                }
+            };
+
+            clipped_components.push(clipped);
+            offset_y_px += component.height();
+        }
+
+        if self.heavy {
+            darken(&mut image);
+        }
+
+        if self.trim_empty_space {
+            image = trim_trailing_rows(image);
+        }
+
+        // Enforce the maximum height.
+        // Shrinking scales the font sizes down as well.
+        let mut scale = 1.0;
+
+        if let Some(max_height) = self.max_height.filter(|&h| image.height() > h) {
+            if !self.shrink_to_fit {
+                return Err(Error::TooLong {
+                    height: image.height(),
+                    max_height,
+                    components: self.overflowing_components(max_height),
+                });
             }
 
+            scale = (max_height as f32) / (image.height() as f32);
+            image = shrink_to_height(image, max_height);
+        }
+
+        // Enforce the minimum height.
+        if let Some(min_height) = self.min_height.filter(|&h| image.height() < h) {
+            image = pad_to_height(image, min_height);
+        }
+
+        let metadata = self.metadata(image.height(), scale, &clipped_components);
+
+        Ok((image, self.text_ctx, metadata))
+    }
+
+    /// Collect the metadata of the components and lint their layout.
+    fn metadata(&self, height: u32, scale: f32, clipped_components: &[bool]) -> Metadata {
+        let mut metadata = Metadata {
+            height,
+            ..Default::default()
+        };
+
+        let mut offset_y_px = 0;
+
+        for (component, &clipped) in self.components.iter().zip(clipped_components) {
+            use Component::*;
+
+            let truncated_lines = match component {
+                Text(text_component) => {
+                    for font in text_component.fonts() {
+                        if !metadata.fonts.contains(font) {
+                            metadata.fonts.push(font.clone());
+                        }
+                    }
+
+                    text_component.truncated_lines()
+                }
+                Image(_) => 0,
+            };
+
+            metadata.components.push(ComponentMetadata {
+                description: component.description(),
+                rows: offset_y_px..(offset_y_px + component.height()),
+                truncated_lines,
+                issues: lint::lint_component(component, clipped, scale, self.min_font_size),
+            });
+
+            offset_y_px += component.height();
+        }
+
+        metadata
+    }
+
+    /// Describe the components that end below the maximum height.
+    fn overflowing_components(&self, max_height: u32) -> Vec<String> {
+        let mut offset_y_px = 0;
+        let mut components = Vec::new();
+
+        for component in &self.components {
             offset_y_px += component.height();
+
+            if offset_y_px > max_height {
+                components.push(component.description());
+            }
         }
 
-        image
+        components
+    }
+}
+
+/// Blend A over B:
+/// - `alpha_a` is the glyph coverage or the image alpha.
+/// - `luma_a` is the color of the glyph or the image.
+/// - `alpha_b` is always 0xff (as our background is opaque).
+/// - `luma_b` is the existing pixel in the voucher.
+///
+/// Now, the blend equation simplifies to alpha_a * luma_a + (1 - alpha_a) * luma_b.
+/// In integer arithmetic, adding 127 before the division rounds to the nearest value.
+fn blend_luma(luma_b: u8, luma_a: u8, alpha_a: u8) -> u8 {
+    (((alpha_a as u32) * (luma_a as u32) + ((0xff - alpha_a) as u32) * (luma_b as u32) + 127) / 255)
+        as u8
+}
+
+/// Cut off the rows at the end of the image that are completely white.
+fn trim_trailing_rows(image: GrayImage) -> GrayImage {
+    let width = image.width();
+
+    if width == 0 {
+        return image;
     }
+
+    let height = image
+        .as_raw()
+        .chunks_exact(width as usize)
+        .rposition(|row| row.iter().any(|&luma| luma != 0xff))
+        .map_or(0, |last_row| (last_row as u32) + 1);
+
+    let mut raw = image.into_raw();
+    raw.truncate((width as usize) * (height as usize));
+
+    GrayImage::from_raw(width, height, raw).expect("Buffer has been truncated to fit")
+}
+
+/// Scale the image down to the given height, keeping its aspect ratio.
+/// The result is centered horizontally, so the width does not change.
+fn shrink_to_height(image: GrayImage, height: u32) -> GrayImage {
+    let width = image.width();
+    let shrunk_width = ((width as u64) * (height as u64) / (image.height() as u64)).max(1) as u32;
+
+    let shrunk = DynamicImage::from(image)
+        .resize_exact(shrunk_width, height, FilterType::CatmullRom)
+        .to_luma8();
+
+    let mut image = GrayImage::new(width, height);
+    image.fill(0xff);
+
+    image::imageops::overlay(&mut image, &shrunk, ((width - shrunk_width) / 2) as i64, 0);
+
+    image
 }
 
+/// Darken all gray levels (gamma 2), so light edges cross the print threshold as well.
+fn darken(image: &mut GrayImage) {
+    for pixel in image.pixels_mut() {
+        let luma = pixel.0[0] as u32;
+        pixel.0[0] = ((luma * luma) / 0xff) as u8;
+    }
+}
+
+/// Append white rows to the image until it has the given height.
+fn pad_to_height(image: GrayImage, height: u32) -> GrayImage {
+    let width = image.width();
+
+    let mut raw = image.into_raw();
+    raw.resize((width as usize) * (height as usize), 0xff);
+
+    GrayImage::from_raw(width, height, raw).expect("Buffer has been resized to fit")
+}
+
+/// Render barcodes that can be added as image components
+pub mod barcode;
+
 /// Add image components to a voucher
 pub mod img;
 
-pub use img::Builder as ImageComponentBuilder;
+/// Flag likely mistakes in the layout of built vouchers
+pub mod lint;
+
+pub use lint::{Issue as LayoutIssue, DEFAULT_MIN_FONT_SIZE};
+
 use img::Component as ImageComponent;
+pub use img::{Builder as ImageComponentBuilder, Gravity, UpscaleFilter};
 
 /// Add text components to a voucher
 pub mod text;
 
-pub use text::Builder as TextComponentBuilder;
-use text::{Component as TextComponent, Context as TextContext};
+use text::Component as TextComponent;
+pub use text::{
+    parse_script_markup, Builder as TextComponentBuilder, Context as TextContext, Outline, Script,
+    TabStop,
+};
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use image::{io::Reader as ImageReader, ImageFormat};
+    use image::{ImageFormat, ImageReader};
+
+    #[test]
+    fn heavy_rendering_prints_more_dots() {
+        let printed_dots = |heavy| {
+            Builder::new(400)
+                .heavy(heavy)
+                .start_text_component("Rinderhack")
+                .spacing(Spacing::horz_vert(16.0, 16.0))
+                .font_size(30.0)
+                .finalize_text_component()
+                .build()
+                .unwrap()
+                .pixels()
+                .filter(|pixel| pixel.0[0] < 0x80)
+                .count()
+        };
+
+        assert!(printed_dots(true) > printed_dots(false));
+    }
+
+    #[test]
+    fn fixed_height() {
+        let text = "Rinderhack";
+
+        let build = |height| {
+            Builder::new(400)
+                .max_height(Some(height))
+                .min_height(Some(height))
+                .shrink_to_fit(true)
+                .start_text_component(text)
+                .spacing(Spacing::horz_vert(16.0, 16.0))
+                .font_size(50.0)
+                .finalize_text_component()
+                .build()
+                .expect("Voucher is shrunk to fit")
+        };
+
+        // Short vouchers are padded with white rows, long ones are shrunk.
+        let padded = build(1000);
+        assert_eq!((padded.width(), padded.height()), (400, 1000));
+        assert!(padded
+            .rows()
+            .next_back()
+            .unwrap()
+            .all(|luma| luma.0[0] == 0xff));
+
+        let shrunk = build(20);
+        assert_eq!((shrunk.width(), shrunk.height()), (400, 20));
+    }
+
+    #[test]
+    fn layout_issues_are_reported() {
+        let (_, _, metadata) = Builder::new(400)
+            .start_text_component("Rinderhack")
+            .spacing(Spacing::horz_vert(16.0, 16.0))
+            .font_size(50.0)
+            .finalize_text_component()
+            // A margin that has been calculated wrongly
+            .start_text_component("Gewicht: 20 kg")
+            .spacing(Spacing::lrtb(16.0, 16.0, -8.0, 12.0))
+            .font_size(25.0)
+            .finalize_text_component()
+            .start_text_component("Kleingedrucktes")
+            .spacing(Spacing::horz_vert(16.0, 12.0))
+            .font_size(10.0)
+            .finalize_text_component()
+            .build_with_metadata()
+            .unwrap();
+
+        let issues: Vec<_> = metadata.issues().collect();
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].0.description, "\"Gewicht: 20 kg\"");
+        assert_eq!(*issues[0].1, LayoutIssue::NegativeSpacing);
+        assert_eq!(
+            *issues[1].1,
+            LayoutIssue::SmallFont {
+                font_size: 10.0,
+                min_font_size: DEFAULT_MIN_FONT_SIZE
+            }
+        );
+
+        // Shrinking the voucher shrinks the fonts as well.
+        let (_, _, metadata) = Builder::new(400)
+            .max_height(Some(20))
+            .shrink_to_fit(true)
+            .start_text_component("Rinderhack")
+            .spacing(Spacing::horz_vert(16.0, 16.0))
+            .font_size(50.0)
+            .finalize_text_component()
+            .build_with_metadata()
+            .unwrap();
+
+        assert!(matches!(
+            metadata.issues().next(),
+            Some((_, LayoutIssue::SmallFont { .. }))
+        ));
+    }
 
     #[test]
     fn realistic_voucher() {
@@ -190,7 +655,112 @@ mod tests {
             .italic(true)
             .finalize_text_component()
             .build()
+            .expect("Voucher has no maximum height")
             .save_with_format("test.png", ImageFormat::Png)
             .expect("Failed to save test image");
     }
+
+    #[test]
+    fn script_markup() {
+        let (text, runs) = parse_script_markup("3 m^{2}, H_{2}O, a^b, x_{y");
+
+        assert_eq!(text, "3 m2, H2O, a^b, x_{y");
+        assert_eq!(
+            runs,
+            vec![(3..4, Script::Superscript), (7..8, Script::Subscript)]
+        );
+    }
+
+    /// Lay out one line per value behind a label and a tab.
+    /// Returns the start and the end of each value and the position of its decimal comma.
+    fn tab_columns(tab_stops: &[TabStop], values: &[&str]) -> Vec<(f32, f32, Option<f32>)> {
+        let text = values
+            .iter()
+            .map(|value| format!("Gewicht:\t{}", value))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let (_, text_ctx) = Builder::new(400)
+            .start_text_component(&text)
+            .font_size(20.0)
+            .tab_stops(tab_stops.to_vec())
+            .finalize_text_component()
+            .build_with_text_context()
+            .unwrap();
+
+        assert_eq!(text_ctx.lines.len(), values.len());
+
+        text_ctx
+            .lines
+            .iter()
+            .zip(values)
+            .map(|(line, value)| {
+                // The glyphs are relative to the paragraph, the value starts behind the tab.
+                let value_start = "Gewicht:\t".len();
+                let glyphs = line
+                    .glyphs
+                    .iter()
+                    .filter(|glyph| glyph.start >= value_start)
+                    .collect::<Vec<_>>();
+
+                let first = glyphs.first().unwrap();
+                let last = glyphs.last().unwrap();
+                let comma = glyphs.iter().find(|glyph| {
+                    &value[(glyph.start - value_start)..(glyph.end - value_start)] == ","
+                });
+
+                (first.x, last.x + last.w, comma.map(|glyph| glyph.x))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tab_stops() {
+        let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+        let values = ["0,347 kg", "12,5 kg", "3 kg"];
+
+        let left = tab_columns(&[TabStop::Left(150.0)], &values);
+        assert!(left.iter().all(|&(start, _, _)| close(start, 150.0)));
+
+        let right = tab_columns(&[TabStop::Right(300.0)], &values);
+        assert!(right.iter().all(|&(_, end, _)| close(end, 300.0)));
+
+        // Values without a separator end at the stop.
+        let decimal = tab_columns(&[TabStop::Decimal(200.0, ',')], &values);
+        assert!(close(decimal[0].2.unwrap(), 200.0));
+        assert!(close(decimal[1].2.unwrap(), 200.0));
+        assert!(close(decimal[2].1, 200.0));
+
+        // More integer digits start further left.
+        assert!(decimal[1].0 < decimal[0].0);
+
+        // The value follows the label if there is no stop or the stop is taken.
+        let label_end = tab_columns(&[], &values)[0].0;
+        assert!(label_end > 50.0);
+
+        let taken = tab_columns(&[TabStop::Left(10.0)], &values);
+        assert!(taken.iter().all(|&(start, _, _)| close(start, label_end)));
+    }
+
+    #[test]
+    fn ean13_barcode() {
+        assert_eq!(
+            barcode::ean13_check_digit(&[4, 0, 0, 6, 3, 8, 1, 3, 3, 3, 9, 3]),
+            1
+        );
+
+        // 95 modules plus two quiet zones of 9 modules
+        let image = barcode::ean13("400638133393", 2, 10).expect("Check digit is appended");
+        assert_eq!(image.dimensions(), ((95 + 18) * 2, 10));
+
+        // Start guard (101) after the quiet zone
+        let row = (18..24)
+            .map(|x| image.get_pixel(x, 0).0[0])
+            .collect::<Vec<_>>();
+        assert_eq!(row, vec![0x00, 0x00, 0xff, 0xff, 0x00, 0x00]);
+
+        assert!(barcode::ean13("4006381333931", 2, 10).is_some());
+        assert!(barcode::ean13("4006381333932", 2, 10).is_none());
+        assert!(barcode::ean13("40063813339", 2, 10).is_none());
+    }
 }
@@ -1,10 +1,12 @@
-use super::{Alignment, Builder as VoucherBuilder, Component as VoucherComponent, Spacing};
+use super::{
+    blend_luma, Alignment, Builder as VoucherBuilder, Component as VoucherComponent, Spacing,
+};
 
 use std::ops::Range;
 
 use cosmic_text::{
-    Align, Attrs, AttrsList, BidiParagraphs, Family, FontSystem, LayoutLine, PhysicalGlyph,
-    ShapeBuffer, ShapeLine, Shaping, Style, SwashCache as RasterCache,
+    fontdb, Align, Attrs, AttrsList, BidiParagraphs, Family, FontSystem, LayoutLine, PhysicalGlyph,
+    ShapeBuffer, ShapeLine, Shaping, Style, SubpixelBin, SwashCache as RasterCache,
     SwashContent as GlyphImageContent, Weight, Wrap,
 };
 use image::GrayImage;
@@ -12,14 +14,201 @@ use image::GrayImage;
 /// Line height = LINE_HEIGHT_FACTOR * font size
 const LINE_HEIGHT_FACTOR: f32 = 1.3;
 
-pub(super) struct Context {
+/// The vertical position of a run of text
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Script {
+    Normal,
+    Superscript,
+    Subscript,
+}
+
+impl Script {
+    /// The runs are tagged via the glyph metadata, so they survive shaping and layouting.
+    fn from_metadata(metadata: usize) -> Self {
+        match metadata {
+            1 => Script::Superscript,
+            2 => Script::Subscript,
+            _ => Script::Normal,
+        }
+    }
+
+    fn metadata(&self) -> usize {
+        match self {
+            Script::Normal => 0,
+            Script::Superscript => 1,
+            Script::Subscript => 2,
+        }
+    }
+
+    /// The scale of the font size and the upward shift of the baseline (relative to the font size)
+    fn scale_and_shift(&self) -> (f32, f32) {
+        match self {
+            Script::Normal => (1.0, 0.0),
+            Script::Superscript => (0.6, 0.35),
+            Script::Subscript => (0.6, -0.15),
+        }
+    }
+}
+
+/// Extract super- and subscript runs from a text with the markup `^{...}` and `_{...}`.
+/// Returns the text without the markup and the byte ranges of the runs in it.
+/// Unterminated markup is kept as it is.
+pub fn parse_script_markup(markup: &str) -> (String, Vec<(Range<usize>, Script)>) {
+    let mut text = String::with_capacity(markup.len());
+    let mut runs = Vec::new();
+    let mut rest = markup;
+
+    while let Some(idx) = rest.find(['^', '_']) {
+        let script = if rest[idx..].starts_with('^') {
+            Script::Superscript
+        } else {
+            Script::Subscript
+        };
+
+        // Copy everything up to the marker.
+        text.push_str(&rest[..idx]);
+
+        // Look for the braces.
+        let after_marker = &rest[(idx + 1)..];
+
+        let Some(run_len) = after_marker.strip_prefix('{').and_then(|run| run.find('}')) else {
+            // This is no markup, so we keep the marker.
+            text.push_str(&rest[idx..(idx + 1)]);
+            rest = after_marker;
+
+            continue;
+        };
+
+        let run = &after_marker[1..(1 + run_len)];
+
+        runs.push((text.len()..(text.len() + run.len()), script));
+        text.push_str(run);
+
+        rest = &after_marker[(run_len + 2)..];
+    }
+
+    text.push_str(rest);
+
+    (text, runs)
+}
+
+/// Shrink and shift the glyphs of super- and subscript runs.
+/// The following glyphs move closer to fill the gap.
+fn shrink_script_runs(line: &mut LayoutLine, font_size: f32) {
+    let mut shrunk_width = 0.0;
+
+    for glyph in line.glyphs.iter_mut() {
+        glyph.x -= shrunk_width;
+
+        let (scale, shift) = Script::from_metadata(glyph.metadata).scale_and_shift();
+
+        if scale != 1.0 {
+            shrunk_width += glyph.w * (1.0 - scale);
+
+            glyph.font_size *= scale;
+            glyph.w *= scale;
+            glyph.y -= shift * font_size;
+        }
+    }
+
+    line.w -= shrunk_width;
+}
+
+/// Where the text after a tab is placed (in pixels from the left edge of the text)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TabStop {
+    /// The text starts at the position.
+    Left(f32),
+
+    /// The text ends at the position.
+    Right(f32),
+
+    /// The decimal separator sits at the position (or the text ends there if it has none).
+    Decimal(f32, char),
+}
+
+/// Move the glyphs between the tabs of a line to their tab stops.
+/// The tabs themselves are dropped. Text after tabs without a stop simply follows the previous text.
+fn apply_tab_stops(line: &mut LayoutLine, text: &str, tab_stops: &[TabStop]) {
+    let glyphs = std::mem::take(&mut line.glyphs);
+    let mut segment_idx: usize = 0;
+    let mut segment = Vec::new();
+    let mut pen_x = 0.0;
+
+    // The chain adds a virtual tab at the end to flush the last segment.
+    for glyph in glyphs.into_iter().map(Some).chain([None]) {
+        if let Some(glyph) = glyph.filter(|g| &text[g.start..g.end] != "\t") {
+            segment.push(glyph);
+            continue;
+        }
+
+        // Measure the segment.
+        if let (Some(first), Some(last)) = (segment.first(), segment.last()) {
+            let start_x = first.x;
+            let width = last.x + last.w - start_x;
+
+            // Segment 0 is in front of the first tab.
+            let target_x = match segment_idx
+                .checked_sub(1)
+                .and_then(|idx| tab_stops.get(idx).copied())
+            {
+                None => pen_x,
+                Some(TabStop::Left(pos)) => pos,
+                Some(TabStop::Right(pos)) => pos - width,
+
+                Some(TabStop::Decimal(pos, separator)) => {
+                    let separator_x = segment
+                        .iter()
+                        .find(|g| text[g.start..g.end].starts_with(separator))
+                        .map_or(width, |g| g.x - start_x);
+
+                    pos - separator_x
+                }
+            };
+
+            // Never overlap the previous segment.
+            let target_x = f32::max(target_x, pen_x);
+
+            for glyph in segment.iter_mut() {
+                glyph.x += target_x - start_x;
+            }
+
+            pen_x = target_x + width;
+            line.glyphs.append(&mut segment);
+        }
+
+        segment_idx += 1;
+    }
+
+    line.w = pen_x;
+}
+
+/// A stroke around the glyphs of a text component
+#[derive(Copy, Clone)]
+pub struct Outline {
+    /// The width of the stroke in pixels
+    pub width: u32,
+
+    /// Draw white glyphs with a black stroke instead of black glyphs with a white stroke
+    pub inverted: bool,
+}
+
+/// Fonts, layout buffers and rasterized glyphs shared by the text components.
+/// Loading the fonts is expensive, so the context should be reused across vouchers.
+pub struct Context {
     font_system: FontSystem,
     scratch_buffer: ShapeBuffer,
-    lines: Vec<LayoutLine>,
+    pub(super) lines: Vec<LayoutLine>,
     glyphs: Vec<PhysicalGlyph>,
     raster_cache: RasterCache,
 }
 
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Context {
     pub fn new() -> Self {
         Self {
@@ -30,6 +219,44 @@ impl Context {
             raster_cache: RasterCache::new(),
         }
     }
+
+    /// Create a context that only knows the given fonts (e.g. TTF files) instead of the system fonts.
+    /// The generic families (like sans-serif) resolve to the first font, so the glyphs
+    /// do not depend on the fonts that happen to be installed on the machine.
+    pub fn with_fonts(fonts: Vec<Vec<u8>>) -> Self {
+        let mut db = fontdb::Database::new();
+
+        for font in fonts {
+            db.load_font_data(font);
+        }
+
+        let first_family = db
+            .faces()
+            .next()
+            .and_then(|face| face.families.first())
+            .map(|(family, _)| family.clone());
+
+        if let Some(family) = first_family {
+            db.set_serif_family(family.clone());
+            db.set_sans_serif_family(family.clone());
+            db.set_cursive_family(family.clone());
+            db.set_fantasy_family(family.clone());
+            db.set_monospace_family(family);
+        }
+
+        Self {
+            font_system: FontSystem::new_with_locale_and_db(String::from("en-US"), db),
+            scratch_buffer: ShapeBuffer::default(),
+            lines: Vec::new(),
+            glyphs: Vec::new(),
+            raster_cache: RasterCache::new(),
+        }
+    }
+
+    /// The number of font faces that can be used (none means that no text can be rendered)
+    pub fn font_count(&self) -> usize {
+        self.font_system.db().len()
+    }
 }
 
 pub struct Builder<'t, 'f> {
@@ -56,6 +283,18 @@ pub struct Builder<'t, 'f> {
 
     /// Do we render italic text?
     italic: bool,
+
+    /// Do we render the component at all?
+    visible: bool,
+
+    /// The stroke around the glyphs (if any)
+    outline: Option<Outline>,
+
+    /// The super- and subscript runs (byte ranges in the text)
+    scripts: Vec<(Range<usize>, Script)>,
+
+    /// The positions the text after each tab is aligned to
+    tab_stops: Vec<TabStop>,
 }
 
 impl<'t, 'f> Builder<'t, 'f> {
@@ -69,6 +308,10 @@ impl<'t, 'f> Builder<'t, 'f> {
             font_size: 12.0,
             bold: false,
             italic: false,
+            visible: true,
+            outline: None,
+            scripts: Vec::new(),
+            tab_stops: Vec::new(),
         }
     }
 
@@ -109,7 +352,38 @@ impl<'t, 'f> Builder<'t, 'f> {
         self
     }
 
+    /// Draw a stroke around the glyphs, e.g. to keep the text legible on top of an image.
+    pub fn outline(mut self, outline: Option<Outline>) -> Self {
+        self.outline = outline;
+        self
+    }
+
+    /// Render the given byte ranges of the text as super- or subscript.
+    /// See `parse_script_markup()` to obtain them from markup.
+    pub fn scripts(mut self, scripts: Vec<(Range<usize>, Script)>) -> Self {
+        self.scripts = scripts;
+        self
+    }
+
+    /// Align the text after the n-th tab of a line to the n-th tab stop.
+    /// This is meant for simple columns like "Gewicht:\t1,234 kg" in left-aligned text.
+    pub fn tab_stops(mut self, tab_stops: Vec<TabStop>) -> Self {
+        self.tab_stops = tab_stops;
+        self
+    }
+
+    /// Hidden components take no space at all (including their spacing).
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
     pub fn finalize_text_component(mut self) -> VoucherBuilder {
+        // Blank text would only contribute its spacing.
+        if !self.visible || (self.voucher.trim_empty_space && self.text.trim().is_empty()) {
+            return self.voucher;
+        }
+
         // Obtain the context.
         let ctx = &mut self.voucher.text_ctx;
 
@@ -123,10 +397,10 @@ impl<'t, 'f> Builder<'t, 'f> {
         }
 
         // Build the attributes.
-        let attrs_list = {
+        let attrs = {
             let family = self.font_family.map_or(Family::SansSerif, Family::Name);
 
-            let weight = if self.bold {
+            let weight = if self.bold || self.voucher.heavy {
                 Weight::BOLD
             } else {
                 Weight::NORMAL
@@ -138,14 +412,27 @@ impl<'t, 'f> Builder<'t, 'f> {
                 Style::Normal
             };
 
-            let attrs = Attrs::new().family(family).weight(weight).style(style);
-            AttrsList::new(attrs)
+            Attrs::new().family(family).weight(weight).style(style)
         };
 
         // Break the text into bidi paragraphs.
         let old_lines_count = ctx.lines.len();
 
         for text_line in BidiParagraphs::new(self.text) {
+            // Tag the script runs within the paragraph.
+            let mut attrs_list = AttrsList::new(attrs);
+            let line_start = (text_line.as_ptr() as usize) - (self.text.as_ptr() as usize);
+            let line_end = line_start + text_line.len();
+
+            for (range, script) in &self.scripts {
+                let start = range.start.clamp(line_start, line_end) - line_start;
+                let end = range.end.clamp(line_start, line_end) - line_start;
+
+                if start < end {
+                    attrs_list.add_span(start..end, attrs.metadata(script.metadata()));
+                }
+            }
+
             // Shape the line.
             let shape_line = ShapeLine::new_in_buffer(
                 &mut ctx.scratch_buffer,
@@ -156,6 +443,8 @@ impl<'t, 'f> Builder<'t, 'f> {
             );
 
             // Perform layouting.
+            let paragraph_lines_count = ctx.lines.len();
+
             shape_line.layout_to_buffer(
                 &mut ctx.scratch_buffer,
                 self.font_size,
@@ -165,6 +454,20 @@ impl<'t, 'f> Builder<'t, 'f> {
                 &mut ctx.lines,
                 None,
             );
+
+            // Layouting has reserved the full advance for script runs and knows nothing about tabs.
+            // So we adjust the glyphs of the new lines ourselves (in this order).
+            let has_tabs = text_line.contains('\t');
+
+            for line in ctx.lines[paragraph_lines_count..].iter_mut() {
+                if !self.scripts.is_empty() {
+                    shrink_script_runs(line, self.font_size);
+                }
+
+                if has_tabs {
+                    apply_tab_stops(line, text_line, &self.tab_stops);
+                }
+            }
         }
 
         // Count the layout lines we have just added.
@@ -176,12 +479,18 @@ impl<'t, 'f> Builder<'t, 'f> {
         }
 
         // Walk the lines to check their widths.
+        let mut truncated_lines = 0;
+
         for line in ctx.lines[lines_range.clone()].iter_mut() {
             // The line *can* exceed our maximum width at this point:
             // - Word wrapping might have failed (e.g. no spaces).
             // - A single glyph might be wide enough to overshoot.
             // In that case, we simply truncate the line until it fits.
             // TODO: It would be nice to ellipsize :)
+            if line.w > line_width {
+                truncated_lines += 1;
+            }
+
             while line.w > line_width {
                 // If we fail here, the line is exceeded.
                 let Some(last_glyph) = line.glyphs.pop() else {
@@ -193,6 +502,24 @@ impl<'t, 'f> Builder<'t, 'f> {
             }
         }
 
+        // Remember the font families that have actually been picked (including fallbacks).
+        let mut fonts = Vec::<String>::new();
+
+        for glyph in ctx.lines[lines_range.clone()]
+            .iter()
+            .flat_map(|line| &line.glyphs)
+        {
+            let Some(face) = ctx.font_system.db().face(glyph.font_id) else {
+                continue;
+            };
+
+            if let Some((family, _)) = face.families.first() {
+                if !fonts.contains(family) {
+                    fonts.push(family.clone());
+                }
+            }
+        }
+
         // Calculate the total height of the component in pixels.
         let height_pix =
             (self.spacing.vert() + ((lines_range.len() as f32) * line_height)).ceil() as u32;
@@ -206,7 +533,14 @@ impl<'t, 'f> Builder<'t, 'f> {
             offset_y: self.spacing.top,
             line_width,
             line_height,
+            font_size: self.font_size,
+            spacing_clamped: self.spacing.clamped,
             alignment: self.alignment,
+            outline: self.outline,
+            description: describe_text(self.text),
+            truncated_lines,
+            fonts,
+            whole_pixels: self.voucher.deterministic,
         };
 
         self.voucher
@@ -227,6 +561,67 @@ impl VoucherBuilder {
     }
 }
 
+/// Shorten a text to its first few characters.
+fn describe_text(text: &str) -> String {
+    const MAX_CHARS: usize = 30;
+
+    // Tabs and line breaks would disturb the UI.
+    let mut description: String = text
+        .chars()
+        .take(MAX_CHARS)
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .collect();
+
+    if text.chars().nth(MAX_CHARS).is_some() {
+        description.push('…');
+    }
+
+    description
+}
+
+/// Blend a row of glyph coverage values into a row of the image.
+/// `combine` receives the existing pixel and the glyph coverage and returns the new pixel.
+fn blend_row<I: Iterator<Item = u8>, F: Fn(u8, u8) -> u8>(
+    image_row: &mut [u8],
+    glyph_alphas: I,
+    combine: &F,
+) {
+    for (luma_b, alpha_a) in image_row.iter_mut().zip(glyph_alphas) {
+        *luma_b = combine(*luma_b, alpha_a);
+    }
+}
+
+/// Grow a coverage mask by `radius` pixels in every direction (square structuring element).
+/// Rows and columns are handled in separate passes, so the cost does not grow with the area.
+fn dilate(mask: &[u8], width: usize, height: usize, radius: usize) -> Vec<u8> {
+    let mut horz = vec![0x00; mask.len()];
+
+    for y in 0..height {
+        let row = &mask[(y * width)..((y + 1) * width)];
+
+        for x in 0..width {
+            let window = &row[x.saturating_sub(radius)..(x + radius + 1).min(width)];
+            horz[y * width + x] = window.iter().copied().max().unwrap_or(0x00);
+        }
+    }
+
+    let mut dilated = vec![0x00; mask.len()];
+
+    for y in 0..height {
+        let rows = y.saturating_sub(radius)..(y + radius + 1).min(height);
+
+        for x in 0..width {
+            dilated[y * width + x] = rows
+                .clone()
+                .map(|y| horz[y * width + x])
+                .max()
+                .unwrap_or(0x00);
+        }
+    }
+
+    dilated
+}
+
 pub struct Component {
     /// The total height of the component in pixels
     height_pix: u32,
@@ -246,8 +641,29 @@ pub struct Component {
     /// The height of a line (aka `LINE_HEIGHT_FACTOR * font_size`)
     line_height: f32,
 
+    /// The font size in pixels
+    font_size: f32,
+
+    /// Has a negative spacing been raised to zero?
+    spacing_clamped: bool,
+
     /// The alignment
     alignment: Alignment,
+
+    /// The stroke around the glyphs (if any)
+    outline: Option<Outline>,
+
+    /// The beginning of the text to describe the component in errors
+    description: String,
+
+    /// The number of lines that did not fit and have been cut off
+    truncated_lines: usize,
+
+    /// The font families of the rendered glyphs
+    fonts: Vec<String>,
+
+    /// Do we place the glyphs on whole pixels (instead of subpixel positions)?
+    whole_pixels: bool,
 }
 
 impl Component {
@@ -255,7 +671,98 @@ impl Component {
         self.height_pix
     }
 
-    pub(super) fn render(&self, image: &mut GrayImage, offset_y_pix: u32, ctx: &mut Context) {
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn truncated_lines(&self) -> usize {
+        self.truncated_lines
+    }
+
+    pub fn fonts(&self) -> &[String] {
+        &self.fonts
+    }
+
+    pub fn font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    pub fn spacing_clamped(&self) -> bool {
+        self.spacing_clamped
+    }
+
+    /// Render the lines into the image.
+    /// Returns whether glyphs reached beyond the top or bottom of the component and have been cut off.
+    pub(super) fn render(
+        &self,
+        image: &mut GrayImage,
+        offset_y_pix: u32,
+        ctx: &mut Context,
+    ) -> bool {
+        let image_width_pix = image.width() as usize;
+
+        // Without an outline, we blend the black glyphs directly into the image.
+        let Some(outline) = self.outline else {
+            let image_data: &mut [u8] = image;
+
+            return self.draw_glyphs(
+                image_data,
+                image_width_pix,
+                0,
+                offset_y_pix,
+                ctx,
+                |luma, alpha| blend_luma(luma, 0x00, alpha),
+            );
+        };
+
+        // Otherwise, we collect the glyph coverage of the component in a mask first.
+        // The stroke is the grown mask and goes below the glyphs.
+        let height_pix = self.height_pix as usize;
+        let mut mask = vec![0x00; image_width_pix * height_pix];
+
+        let clipped = self.draw_glyphs(
+            &mut mask,
+            image_width_pix,
+            offset_y_pix,
+            offset_y_pix,
+            ctx,
+            u8::max,
+        );
+
+        let stroke = dilate(&mask, image_width_pix, height_pix, outline.width as usize);
+
+        let (glyph_luma, stroke_luma) = if outline.inverted {
+            (0xff, 0x00)
+        } else {
+            (0x00, 0xff)
+        };
+
+        let image_offset_pix = (offset_y_pix as usize) * image_width_pix;
+        let image_data: &mut [u8] = image;
+
+        for (idx, luma) in image_data[image_offset_pix..(image_offset_pix + mask.len())]
+            .iter_mut()
+            .enumerate()
+        {
+            *luma = blend_luma(*luma, stroke_luma, stroke[idx]);
+            *luma = blend_luma(*luma, glyph_luma, mask[idx]);
+        }
+
+        clipped
+    }
+
+    /// Rasterize the glyphs and combine their coverage with the target buffer.
+    /// The target holds rows of `target_width_pix` pixels, starting at the image row `target_top_pix`.
+    /// Returns whether glyphs have been clipped at the top or bottom of the component.
+    fn draw_glyphs<F: Fn(u8, u8) -> u8>(
+        &self,
+        target: &mut [u8],
+        target_width_pix: usize,
+        target_top_pix: u32,
+        offset_y_pix: u32,
+        ctx: &mut Context,
+        combine: F,
+    ) -> bool {
         use Alignment::*;
         use GlyphImageContent::*;
 
@@ -272,25 +779,11 @@ impl Component {
 
         // This rect defines the valid component area we can draw into.
         let comp_left_pix = 0;
-        let comp_right_pix = comp_left_pix + (image.width() as i32);
+        let comp_right_pix = comp_left_pix + (target_width_pix as i32);
         let comp_top_pix = offset_y_pix as i32;
         let comp_bottom_pix = comp_top_pix + (self.height_pix as i32);
 
-        // This closure sets pixels in the image.
-        let mut set_pixel = |x_pix, y_pix, glyph_a| {
-            // Perform manual alpha blending. We blend A over B.
-            // - `alpha_a` is color.a().
-            // - `luma_a` is always 0xff (as our base color is black).
-            // - `alpha_b` is always 0xff (as our background is opaque).
-            // - `luma_b` is the existing pixel in the image.
-            // Now, the blend equation simplifies to (1 - alpha_a) * luma_b.
-            let pix = image.get_pixel_mut(x_pix, y_pix);
-            let luma_b = (pix[0] as f32) / 255.0;
-            let alpha_a = (glyph_a as f32) / 255.0;
-            let new_luma = (1.0 - alpha_a) * luma_b;
-
-            pix[0] = (new_luma * 255.0).round() as u8;
-        };
+        let mut clipped = false;
 
         // Walk the lines.
         for (idx, line) in ctx.lines[self.lines_range.clone()].iter().enumerate() {
@@ -310,6 +803,15 @@ impl Component {
                     .map(|g| g.physical((glyph_origin_x, glyph_origin_y), 1.0)),
             );
 
+            // Subpixel positions depend on tiny float differences in the layout.
+            // Snapping them leaves fewer rasterizations that can vary between machines.
+            if self.whole_pixels {
+                for glyph in &mut ctx.glyphs {
+                    glyph.cache_key.x_bin = SubpixelBin::Zero;
+                    glyph.cache_key.y_bin = SubpixelBin::Zero;
+                }
+            }
+
             // Walk the glyphs.
             for glyph in &ctx.glyphs {
                 // Get the glyph image.
@@ -338,6 +840,10 @@ impl Component {
                 let top_pix = glyph_image_top_pix.max(comp_top_pix);
                 let bottom_pix = glyph_image_bottom_pix.min(comp_bottom_pix);
 
+                if (top_pix > glyph_image_top_pix) || (bottom_pix < glyph_image_bottom_pix) {
+                    clipped = true;
+                }
+
                 // If the image is empty, we can bail out.
                 if (left_pix >= right_pix) || (top_pix >= bottom_pix) {
                     continue;
@@ -348,39 +854,46 @@ impl Component {
                     * glyph_image_width_pix)
                     + ((left_pix - glyph_image_left_pix) as usize);
 
-                // Draw the image.
-                match glyph_image.content {
-                    Mask => {
-                        for y_pix in top_pix..bottom_pix {
-                            let glyph_row = &glyph_image.data[glyph_row_offset_pix..];
+                // Draw the image row by row.
+                let row_width_pix = (right_pix - left_pix) as usize;
 
-                            for (x_pix, &glyph_a) in (left_pix..right_pix).zip(glyph_row) {
-                                set_pixel(x_pix as u32, y_pix as u32, glyph_a);
-                            }
+                for y_pix in top_pix..bottom_pix {
+                    let target_row_offset_pix = ((y_pix as usize) - (target_top_pix as usize))
+                        * target_width_pix
+                        + (left_pix as usize);
 
-                            glyph_row_offset_pix += glyph_image_width_pix;
-                        }
-                    }
+                    let target_row =
+                        &mut target[target_row_offset_pix..(target_row_offset_pix + row_width_pix)];
+
+                    match glyph_image.content {
+                        Mask => {
+                            let glyph_row = &glyph_image.data
+                                [glyph_row_offset_pix..(glyph_row_offset_pix + row_width_pix)];
 
-                    Color => {
-                        for y_pix in top_pix..bottom_pix {
-                            let glyph_row = &glyph_image.data[(glyph_row_offset_pix * 4)..];
+                            blend_row(target_row, glyph_row.iter().copied(), &combine);
+                        }
 
-                            for (x_pix, glyph_a) in (left_pix..right_pix)
-                                .zip(glyph_row.chunks_exact(4).map(|pix| pix[3]))
-                            {
-                                set_pixel(x_pix as u32, y_pix as u32, glyph_a);
-                            }
+                        Color => {
+                            let glyph_row = &glyph_image.data[(glyph_row_offset_pix * 4)
+                                ..((glyph_row_offset_pix + row_width_pix) * 4)];
 
-                            glyph_row_offset_pix += glyph_image_width_pix;
+                            blend_row(
+                                target_row,
+                                glyph_row.chunks_exact(4).map(|pix| pix[3]),
+                                &combine,
+                            );
                         }
+
+                        // Since we ordered `GlyphFormat::Alpha` via the renderer,
+                        // we should never encounter anything else (e.g. subpixel antialiasing) here.
+                        _ => unreachable!("Invalid glyph image content (expected mask or color)"),
                     }
 
-                    // Since we ordered `GlyphFormat::Alpha` via the renderer,
-                    // we should never encounter anything else (e.g. subpixel antialiasing) here.
-                    _ => unreachable!("Invalid glyph image content (expected mask or color)"),
+                    glyph_row_offset_pix += glyph_image_width_pix;
                 }
             }
         }
+
+        clipped
     }
 }
@@ -0,0 +1,32 @@
+/// Convert weights into piece counts based on the average weight of a reference sample.
+#[derive(Debug, Copy, Clone)]
+pub struct PieceCounter {
+    piece_weight_kg: f64,
+}
+
+impl PieceCounter {
+    /// Derive the average piece weight from a reference sample of `pieces` pieces.
+    /// Returns `None` if the sample is empty.
+    pub fn from_reference(weight_kg: f64, pieces: u64) -> Option<Self> {
+        if (pieces == 0) || (weight_kg <= 0.0) {
+            return None;
+        }
+
+        Some(Self {
+            piece_weight_kg: weight_kg / (pieces as f64),
+        })
+    }
+
+    pub fn piece_weight_kg(&self) -> f64 {
+        self.piece_weight_kg
+    }
+
+    /// Count the pieces on the scales (rounded to the nearest piece).
+    pub fn count(&self, weight_kg: f64) -> u64 {
+        if weight_kg <= 0.0 {
+            return 0;
+        }
+
+        (weight_kg / self.piece_weight_kg).round() as u64
+    }
+}
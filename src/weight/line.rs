@@ -0,0 +1,96 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serialport::FlowControl as PortFlowControl;
+
+/// The flow control of the serial port
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlowControl {
+    None,
+
+    /// XON / XOFF
+    Software,
+
+    /// RTS / CTS
+    Hardware,
+}
+
+impl Display for FlowControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use FlowControl::*;
+
+        match self {
+            None => write!(f, "none"),
+            Software => write!(f, "xonxoff"),
+            Hardware => write!(f, "rtscts"),
+        }
+    }
+}
+
+impl FromStr for FlowControl {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use FlowControl::*;
+
+        match s {
+            "none" => Ok(None),
+            "xonxoff" => Ok(Software),
+            "rtscts" => Ok(Hardware),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<FlowControl> for PortFlowControl {
+    fn from(value: FlowControl) -> Self {
+        match value {
+            FlowControl::None => PortFlowControl::None,
+            FlowControl::Software => PortFlowControl::Software,
+            FlowControl::Hardware => PortFlowControl::Hardware,
+        }
+    }
+}
+
+/// The driver-enable timing of a half-duplex RS-485 converter.
+/// The driver is enabled via RTS while a request is sent and disabled before the response arrives.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rs485 {
+    /// The time between enabling the driver and sending the first byte
+    pub delay_before_send: Duration,
+
+    /// The time between sending the last byte and disabling the driver
+    pub delay_after_send: Duration,
+}
+
+/// The characteristics of the serial line to the scales
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LineConfig {
+    pub flow_control: FlowControl,
+    pub rs485: Option<Rs485>,
+}
+
+impl Default for LineConfig {
+    fn default() -> Self {
+        Self {
+            flow_control: FlowControl::None,
+            rs485: None,
+        }
+    }
+}
+
+impl LineConfig {
+    /// The flow control that is configured on the port.
+    /// RS-485 needs RTS for the driver, so RTS / CTS is not possible.
+    pub fn port_flow_control(&self) -> PortFlowControl {
+        match (self.flow_control, self.rs485) {
+            (FlowControl::Hardware, Some(_)) => {
+                eprintln!("RTS / CTS flow control is not possible with RS-485, disabling it");
+                PortFlowControl::None
+            }
+
+            (flow_control, _) => flow_control.into(),
+        }
+    }
+}
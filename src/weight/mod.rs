@@ -1,18 +1,42 @@
+use std::any::Any;
 use std::fmt::Display;
 use std::io::Error as IOError;
+use std::panic::{self, AssertUnwindSafe};
 use std::str;
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use serialport::{DataBits, Error as SerialPortError, FlowControl, Parity, SerialPort, StopBits};
+use serialport::{DataBits, Error as SerialPortError, Parity, SerialPort, StopBits};
+
+/// Count pieces by their average weight
+mod counting;
+pub use counting::PieceCounter;
+
+/// Detect and convert the unit of the reported weights
+mod unit;
+pub use unit::Unit;
+
+/// Describe the requests and responses of the scales
+mod protocol;
+pub use protocol::Protocol;
+
+/// Configure the serial line (flow control, RS-485)
+mod line;
+pub use line::{FlowControl, LineConfig, Rs485};
+
+/// Judge the data quality of the scales
+mod quality;
+pub use quality::{Quality, QualityIssue};
 
 #[derive(Debug, Clone)]
 pub enum Error {
     NotOpenedYet,
+    Paused,
     SerialPort(SerialPortError),
     IO(String),
     FailedToParse,
+    Runloop(String),
 }
 
 impl Display for Error {
@@ -21,9 +45,15 @@ impl Display for Error {
 
         match self {
             NotOpenedYet => write!(f, "The serial port has not been opened yet."),
+            Paused => write!(f, "The serial port has been released."),
             SerialPort(err) => write!(f, "{}", err),
             IO(err) => write!(f, "{}", err),
             FailedToParse => write!(f, "Failed to parse response."),
+            Runloop(err) => write!(
+                f,
+                "The scale runloop has crashed ({}) and is restarted.",
+                err
+            ),
         }
     }
 }
@@ -53,22 +83,39 @@ impl Display for AwakeError {
 
 impl std::error::Error for AwakeError {}
 
-/// A guard to notify the runloop that it should exit
-struct Guard(Mutex<bool>, Condvar);
+/// What the runloop has been asked to do
+#[derive(Debug, Copy, Clone, Default)]
+struct GuardState {
+    should_exit: bool,
+    is_paused: bool,
+}
+
+impl GuardState {
+    fn should_wake(&self) -> bool {
+        self.should_exit || self.is_paused
+    }
+}
+
+/// Lock a mutex, even if the runloop has panicked while holding it.
+/// All shared state stays consistent between single assignments.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A guard to notify the runloop that it should exit or release the port
+struct Guard(Mutex<GuardState>, Condvar);
 
 impl Guard {
-    /// Block on the guard until it is cancelled or `timeout_duration` runs out.
-    /// In case of cancel, `Err(AwakeError)` is returned.
+    /// Block on the guard until it is cancelled / paused or `timeout_duration` runs out.
+    /// In case of cancel / pause, `Err(AwakeError)` is returned.
     fn wait(&self, timeout_duration: Duration) -> Result<(), AwakeError> {
         // Block up to `timeout_duration` on the cvar.
         let (lock, wait_result) = self
             .1
-            .wait_timeout_while(
-                self.0.lock().unwrap(),
-                timeout_duration,
-                |&mut should_exit| !should_exit,
-            )
-            .unwrap();
+            .wait_timeout_while(lock(&self.0), timeout_duration, |state| {
+                !state.should_wake()
+            })
+            .unwrap_or_else(PoisonError::into_inner);
 
         drop(lock);
 
@@ -83,22 +130,46 @@ impl Guard {
 
     /// Like `wait()`, but just check the guard without blocking.
     fn check(&self) -> Result<(), AwakeError> {
-        if *self.0.lock().unwrap() {
+        if lock(&self.0).should_wake() {
+            Err(AwakeError)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Block on the guard as long as it is paused.
+    /// In case of cancel, `Err(AwakeError)` is returned.
+    fn wait_while_paused(&self) -> Result<(), AwakeError> {
+        let state = *self
+            .1
+            .wait_while(lock(&self.0), |state| state.is_paused && !state.should_exit)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if state.should_exit {
             Err(AwakeError)
         } else {
             Ok(())
         }
     }
 
+    fn is_cancelled(&self) -> bool {
+        lock(&self.0).should_exit
+    }
+
     fn cancel(&self) {
-        *self.0.lock().unwrap() = true;
-        self.1.notify_one();
+        lock(&self.0).should_exit = true;
+        self.1.notify_all();
+    }
+
+    fn set_paused(&self, is_paused: bool) {
+        lock(&self.0).is_paused = is_paused;
+        self.1.notify_all();
     }
 }
 
 impl Default for Guard {
     fn default() -> Self {
-        Self(Mutex::new(false), Condvar::new())
+        Self(Mutex::new(GuardState::default()), Condvar::new())
     }
 }
 
@@ -108,30 +179,100 @@ const IO_TIMEOUT: Duration = Duration::from_millis(1000);
 /// The timeout to wait until a new port access is issued.
 const PORT_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// The timeout to wait until a crashed runloop is restarted.
+const RESTART_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// The result of a weight poll
 pub type WeightResult = Result<f64, Error>;
 
+/// A callback that is invoked by the runloop whenever the weight result changes
+type Listener = Box<dyn Fn(&WeightResult) + Send>;
+
+/// What the runloop has seen so far, to support the operator in case of trouble
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    /// The raw last weight response
+    pub last_frame: Option<Vec<u8>>,
+
+    /// How often the serial port has been opened
+    pub port_opens: u64,
+
+    /// How often the runloop has been restarted after a crash
+    pub restarts: u64,
+
+    /// The failure rate and response times of the last responses
+    pub quality: Quality,
+}
+
+/// The latest weight result, shared between the runloop and the `Scales` object
+struct SharedWeight {
+    result: Mutex<WeightResult>,
+    listener: Mutex<Option<Listener>>,
+    diagnostics: Mutex<Diagnostics>,
+}
+
+impl SharedWeight {
+    fn new() -> Self {
+        Self {
+            result: Mutex::new(Err(Error::NotOpenedYet)),
+            listener: Mutex::new(None),
+            diagnostics: Mutex::new(Diagnostics::default()),
+        }
+    }
+
+    fn get(&self) -> WeightResult {
+        lock(&self.result).clone()
+    }
+
+    /// Store a new result and notify the listener if it differs from the old one.
+    fn set(&self, result: WeightResult) {
+        let mut current = lock(&self.result);
+
+        let changed = match (&*current, &result) {
+            (Ok(old_weight), Ok(new_weight)) => old_weight != new_weight,
+            (Err(old_err), Err(new_err)) => old_err.to_string() != new_err.to_string(),
+            _ => true,
+        };
+
+        *current = result;
+
+        if changed {
+            if let Some(listener) = &*lock(&self.listener) {
+                listener(&current);
+            }
+        }
+    }
+}
+
 pub struct Scales {
     runloop_handle: Option<thread::JoinHandle<Result<(), AwakeError>>>,
     guard: Arc<Guard>,
-    weight: Arc<Mutex<WeightResult>>,
+    weight: Arc<SharedWeight>,
+    protocol: Option<Protocol>,
 }
 
 impl Scales {
-    pub fn on_serial_port(port_path: &str) -> Self {
+    pub fn on_serial_port(port_path: &str, protocol: Protocol, line: LineConfig) -> Self {
         let guard = Arc::new(Guard::default());
         let guard2 = Arc::clone(&guard);
 
-        let weight = Arc::new(Mutex::new(Err(Error::NotOpenedYet)));
+        let weight = Arc::new(SharedWeight::new());
         let weight2 = Arc::clone(&weight);
 
         let port_path = String::from(port_path);
-        let runloop_handle = thread::spawn(move || Self::runloop(port_path, &guard2, &weight2));
+        let protocol2 = protocol.clone();
+
+        let runloop_handle = thread::spawn(move || {
+            Self::supervise(&guard2, &weight2, |guard, weight| {
+                Self::runloop(&port_path, &protocol2, &line, guard, weight)
+            })
+        });
 
         Self {
             runloop_handle: Some(runloop_handle),
             guard,
             weight,
+            protocol: Some(protocol),
         }
     }
 
@@ -139,59 +280,180 @@ impl Scales {
         let guard = Arc::new(Guard::default());
         let guard2 = Arc::clone(&guard);
 
-        let weight = Arc::new(Mutex::new(Err(Error::NotOpenedYet)));
+        let weight = Arc::new(SharedWeight::new());
+        let weight2 = Arc::clone(&weight);
+
+        let runloop_handle =
+            thread::spawn(move || Self::supervise(&guard2, &weight2, Self::runloop_emulated));
+
+        Self {
+            runloop_handle: Some(runloop_handle),
+            guard,
+            weight,
+            protocol: None,
+        }
+    }
+
+    /// Scales that always report the same weight (e.g. for tests).
+    /// The weight is available right away, so there is no need to wait for the runloop.
+    pub fn fixed(weight_kg: f64) -> Self {
+        let guard = Arc::new(Guard::default());
+        let guard2 = Arc::clone(&guard);
+
+        let weight = Arc::new(SharedWeight::new());
         let weight2 = Arc::clone(&weight);
 
-        let runloop_handle = thread::spawn(move || Self::runloop_emulated(&guard2, &weight2));
+        weight.set(Ok(weight_kg));
+
+        let runloop_handle = thread::spawn(move || {
+            Self::supervise(&guard2, &weight2, |guard, weight| {
+                Self::runloop_fixed(weight_kg, guard, weight)
+            })
+        });
 
         Self {
             runloop_handle: Some(runloop_handle),
             guard,
             weight,
+            protocol: None,
         }
     }
 
     pub fn weight(&self) -> WeightResult {
-        self.weight.lock().unwrap().clone()
+        self.weight.get()
     }
 
-    fn runloop(
-        port_path: String,
+    /// The framing of the responses (`None` for emulated scales)
+    pub fn protocol(&self) -> Option<&Protocol> {
+        self.protocol.as_ref()
+    }
+
+    pub fn diagnostics(&self) -> Diagnostics {
+        lock(&self.weight.diagnostics).clone()
+    }
+
+    /// Register a callback that is invoked from the runloop thread whenever the weight changes.
+    /// It is invoked once right away with the current weight (which might not change for a while).
+    /// This replaces the previous listener.
+    pub fn set_listener<F: Fn(&WeightResult) + Send + 'static>(&self, listener: F) {
+        // Keep the result locked, so no change can slip in between.
+        let current = lock(&self.weight.result);
+        listener(&current);
+
+        *lock(&self.weight.listener) = Some(Box::new(listener));
+    }
+
+    /// Release the serial port (e.g. for a calibration tool) until `resume()` is called.
+    /// The runloop notices this after the current IO (at most `IO_TIMEOUT`).
+    /// From then on, the weight is `Err(Error::Paused)`.
+    pub fn pause(&self) {
+        self.guard.set_paused(true);
+    }
+
+    /// Reopen the serial port after `pause()`.
+    pub fn resume(&self) {
+        self.guard.set_paused(false);
+    }
+
+    /// Run the runloop and restart it if it panics.
+    /// Until the restarted runloop delivers a new result, the panic is reported as `Error::Runloop`.
+    fn supervise<F: Fn(&Guard, &SharedWeight) -> Result<(), AwakeError>>(
         guard: &Guard,
-        weight: &Mutex<WeightResult>,
+        weight: &SharedWeight,
+        runloop: F,
     ) -> Result<(), AwakeError> {
         loop {
+            let payload = match panic::catch_unwind(AssertUnwindSafe(|| runloop(guard, weight))) {
+                Ok(result) => return result,
+                Err(payload) => payload,
+            };
+
+            weight.set(Err(Error::Runloop(panic_message(payload.as_ref()))));
+            lock(&weight.diagnostics).restarts += 1;
+
+            // Don't restart a runloop that keeps crashing in a busy loop.
+            // A pause is handled by the restarted runloop.
+            if guard.wait(RESTART_TIMEOUT).is_err() && guard.is_cancelled() {
+                return Err(AwakeError);
+            }
+        }
+    }
+
+    /// Run `f` until it is awoken.
+    /// If that happens because of a pause, `f` is restarted after `resume()`.
+    fn run_pausable<F: FnMut() -> Result<(), AwakeError>>(
+        guard: &Guard,
+        weight: &SharedWeight,
+        mut f: F,
+    ) -> Result<(), AwakeError> {
+        loop {
+            guard.wait_while_paused()?;
+
+            // `f` only returns on wake, so this is either a cancel or a pause.
+            let result = f();
+
+            if guard.is_cancelled() {
+                return result;
+            }
+
+            // The port has been dropped by now.
+            weight.set(Err(Error::Paused));
+        }
+    }
+
+    fn runloop(
+        port_path: &str,
+        protocol: &Protocol,
+        line: &LineConfig,
+        guard: &Guard,
+        weight: &SharedWeight,
+    ) -> Result<(), AwakeError> {
+        Self::run_pausable(guard, weight, || loop {
             // Try to open the port.
-            let port = Self::open_port(&port_path, guard, weight)?;
+            let port = Self::open_port(port_path, line, guard, weight)?;
 
             // Yay, we have an open port.
             // Try to perform IO with it.
-            Self::perform_io(port, guard, weight)?;
+            Self::perform_io(port, protocol, line, guard, weight)?;
 
             // When we leave `perform_io()` without an `AwakeError`, the port has been lost.
             // Therefore, we simply restart the loop.
-        }
+        })
     }
 
     fn open_port(
         port_path: &str,
+        line: &LineConfig,
         guard: &Guard,
-        weight: &Mutex<WeightResult>,
+        weight: &SharedWeight,
     ) -> Result<Box<dyn SerialPort>, AwakeError> {
         loop {
             // Specify the characteristics of the port.
             let port_builder = serialport::new(port_path, 9600)
                 .data_bits(DataBits::Eight)
                 .stop_bits(StopBits::One)
-                .flow_control(FlowControl::None)
+                .flow_control(line.port_flow_control())
                 .parity(Parity::None)
                 .timeout(IO_TIMEOUT);
 
             // Try to open it. If that succeeds, we return instantly.
             // Errors are recorded in the weight mutex.
-            match port_builder.open() {
-                Ok(port) => return Ok(port),
-                Err(err) => *weight.lock().unwrap() = Err(err.into()),
+            // With RS-485, the driver stays disabled until we send something.
+            let result = port_builder.open().and_then(|mut port| {
+                if line.rs485.is_some() {
+                    port.write_request_to_send(false)?;
+                }
+
+                Ok(port)
+            });
+
+            match result {
+                Ok(port) => {
+                    lock(&weight.diagnostics).port_opens += 1;
+                    return Ok(port);
+                }
+
+                Err(err) => weight.set(Err(err.into())),
             }
 
             // Wait the given timeout on the guard.
@@ -202,93 +464,144 @@ impl Scales {
         }
     }
 
+    /// Send a request to the scales.
+    /// With RS-485, the driver is enabled while sending and disabled afterwards.
+    fn send(port: &mut dyn SerialPort, line: &LineConfig, request: &[u8]) -> Result<(), Error> {
+        let Some(rs485) = line.rs485 else {
+            port.write_all(request)?;
+            return Ok(());
+        };
+
+        port.write_request_to_send(true)?;
+        thread::sleep(rs485.delay_before_send);
+
+        // Wait until all bytes have left the UART before the driver is disabled.
+        // Otherwise, the end of the request is cut off.
+        let result = port.write_all(request).and_then(|_| port.flush());
+
+        thread::sleep(rs485.delay_after_send);
+        port.write_request_to_send(false)?;
+
+        Ok(result?)
+    }
+
     fn perform_io(
         mut port: Box<dyn SerialPort>,
+        protocol: &Protocol,
+        line: &LineConfig,
         guard: &Guard,
-        weight: &Mutex<WeightResult>,
+        weight: &SharedWeight,
     ) -> Result<(), AwakeError> {
+        // The unit field of the last response, so a mismatch is only reported once.
+        let mut last_unit_field = None;
+
         loop {
-            // Send the info request.
-            if let Err(err) = port.write_all(&[0x04, 0x05]) {
-                *weight.lock().unwrap() = Err(err.into());
-                return Ok(());
-            }
+            // Send the info request (if the scales need one).
+            if !protocol.info_request.is_empty() {
+                if let Err(err) = Self::send(port.as_mut(), line, &protocol.info_request) {
+                    weight.set(Err(err));
+                    return Ok(());
+                }
 
-            guard.check()?;
+                guard.check()?;
 
-            // Read the result.
-            let mut info_response = [0x00u8; 1];
+                // Read the result.
+                let mut info_response = vec![0x00u8; protocol.info_response_len];
 
-            if let Err(err) = port.read_exact(&mut info_response) {
-                *weight.lock().unwrap() = Err(err.into());
-                return Ok(());
-            }
+                if let Err(err) = port.read_exact(&mut info_response) {
+                    weight.set(Err(err.into()));
+                    return Ok(());
+                }
 
-            guard.check()?;
+                guard.check()?;
+            }
 
             // Send the weight request.
-            if let Err(err) = port.write_all(&[0x13]) {
-                *weight.lock().unwrap() = Err(err.into());
+            let request_date = Instant::now();
+
+            if let Err(err) = Self::send(port.as_mut(), line, &protocol.weight_request) {
+                weight.set(Err(err));
                 return Ok(());
             }
 
             guard.check()?;
 
             // Read the result.
-            let mut weight_response = [0x00u8; 45];
+            let mut weight_response = vec![0x00u8; protocol.weight_response_len];
 
             if let Err(err) = port.read_exact(&mut weight_response) {
-                *weight.lock().unwrap() = Err(err.into());
+                // A missing response counts as failure, too.
+                lock(&weight.diagnostics)
+                    .quality
+                    .record(false, request_date.elapsed());
+
+                weight.set(Err(err.into()));
                 return Ok(());
             }
 
+            let latency = request_date.elapsed();
+            lock(&weight.diagnostics).last_frame = Some(weight_response.clone());
+
             guard.check()?;
 
-            // Extract the sign.
-            let sign = match weight_response[14] {
-                0x20 => 1.0,
-                0x2d => -1.0,
+            // Extract the sign, the digits and the unit.
+            let parsed = protocol.parse_weight(&weight_response);
+            lock(&weight.diagnostics)
+                .quality
+                .record(parsed.is_ok(), latency);
+
+            let (value, unit_field) = match parsed {
+                Ok(parsed) => parsed,
 
-                _ => {
-                    *weight.lock().unwrap() = Err(Error::FailedToParse);
+                Err(err) => {
+                    weight.set(Err(err));
                     return Ok(());
                 }
             };
 
-            // Extract the digits.
-            let weight_bytes = &weight_response[15..21];
+            // Without a unit field, we assume the canonical one.
+            let unit_changed = last_unit_field.as_deref() != Some(unit_field);
 
-            let weight_str = match str::from_utf8(weight_bytes) {
-                Ok(weight_str) => weight_str,
+            let unit = match Unit::from_bytes(unit_field) {
+                Ok(unit) => unit.unwrap_or(Unit::CANONICAL),
 
-                Err(_) => {
-                    *weight.lock().unwrap() = Err(Error::FailedToParse);
-                    return Ok(());
+                Err(field) => {
+                    if unit_changed {
+                        eprintln!(
+                            "Unknown unit \"{}\" reported by the scales, assuming {}",
+                            field,
+                            Unit::CANONICAL
+                        );
+                    }
+
+                    Unit::CANONICAL
                 }
             };
 
-            // Parse the string slice.
-            match weight_str.trim().parse::<f64>() {
-                Ok(weight_kg) => *weight.lock().unwrap() = Ok(sign * weight_kg),
-
-                Err(_) => {
-                    *weight.lock().unwrap() = Err(Error::FailedToParse);
-                    return Ok(());
-                }
+            if unit_changed && (unit != Unit::CANONICAL) {
+                eprintln!(
+                    "The scales report weights in {} instead of {}, converting them",
+                    unit,
+                    Unit::CANONICAL
+                );
             }
 
+            last_unit_field = Some(unit_field.to_vec());
+
+            weight.set(Ok(unit.convert(value, Unit::CANONICAL)));
+
             // Just to prevent a busy loop ... probably unnecessary
             // because the serial port induces blocking ...
             guard.wait(Duration::from_millis(1))?;
         }
     }
 
-    fn runloop_emulated(guard: &Guard, weight: &Mutex<WeightResult>) -> Result<(), AwakeError> {
+    fn runloop_emulated(guard: &Guard, weight: &SharedWeight) -> Result<(), AwakeError> {
         let mut fake_weight = 42.0;
 
-        loop {
+        Self::run_pausable(guard, weight, || loop {
             // Fake a value.
-            *weight.lock().unwrap() = Ok(fake_weight);
+            weight.set(Ok(fake_weight));
 
             // Wait a second on the guard.
             guard.wait(Duration::from_secs(1))?;
@@ -299,14 +612,60 @@ impl Scales {
             } else {
                 fake_weight += 0.1;
             }
-        }
+        })
+    }
+
+    fn runloop_fixed(
+        weight_kg: f64,
+        guard: &Guard,
+        weight: &SharedWeight,
+    ) -> Result<(), AwakeError> {
+        Self::run_pausable(guard, weight, || loop {
+            // Report the weight again after a pause.
+            weight.set(Ok(weight_kg));
+            guard.wait(Duration::from_secs(1))?;
+        })
     }
 }
 
 impl Drop for Scales {
     fn drop(&mut self) {
         // Cancel the guard and wait for the runloop to come down.
+        // Panics are caught by the supervisor, so this is just a safety net.
         self.guard.cancel();
-        _ = self.runloop_handle.take().unwrap().join().unwrap();
+
+        if self.runloop_handle.take().unwrap().join().is_err() {
+            eprintln!("The scale runloop has panicked.");
+        }
+    }
+}
+
+/// Extract the message of a panic (if it has one).
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        String::from(*message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown panic")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    #[test]
+    fn listeners_receive_the_current_weight() {
+        let scales = Scales::fixed(1.5);
+
+        // The weight is steady, so there is no change to report.
+        let (sender, receiver) = mpsc::channel();
+        scales.set_listener(move |weight| _ = sender.send(weight.clone()));
+
+        let weight = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(weight.unwrap(), 1.5);
     }
 }
@@ -0,0 +1,306 @@
+use super::{Error, Unit};
+
+use std::fmt::Display;
+use std::ops::Range;
+use std::str::{self, FromStr};
+
+/// The request / response framing of the scales.
+/// It is written as `key=value` pairs separated by `;`, e.g.
+/// `info=0405/1;weight=13/45;sign=14;digits=15..21;unit=21..24`.
+/// Requests are hex bytes followed by the length of their response.
+/// Missing keys keep their defaults, empty values disable optional fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Protocol {
+    /// The request that is sent before every weight request (empty for none)
+    pub info_request: Vec<u8>,
+
+    /// The length of the response to the info request (ignored)
+    pub info_response_len: usize,
+
+    /// The request for the weight
+    pub weight_request: Vec<u8>,
+
+    /// The length of the response to the weight request
+    pub weight_response_len: usize,
+
+    /// The offset of the sign (' ' or '-') in the weight response, if the scales report one
+    pub sign_offset: Option<usize>,
+
+    /// The bytes of the weight response that hold the digits
+    pub digits: Range<usize>,
+
+    /// The bytes of the weight response that hold the unit, if the scales report one
+    pub unit: Option<Range<usize>>,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Self {
+            info_request: vec![0x04, 0x05],
+            info_response_len: 1,
+            weight_request: vec![0x13],
+            weight_response_len: 45,
+            sign_offset: Some(14),
+            digits: 15..21,
+            unit: Some(21..24),
+        }
+    }
+}
+
+impl Protocol {
+    /// Extract the signed weight (in the reported unit) and the unit field from a weight response.
+    pub fn parse_weight<'a>(&self, response: &'a [u8]) -> Result<(f64, &'a [u8]), Error> {
+        // Extract the sign.
+        let sign = match self.sign_offset.map(|offset| response.get(offset)) {
+            None | Some(Some(0x20)) => 1.0,
+            Some(Some(0x2d)) => -1.0,
+
+            _ => return Err(Error::FailedToParse),
+        };
+
+        // Extract the digits.
+        let weight_bytes = response
+            .get(self.digits.clone())
+            .ok_or(Error::FailedToParse)?;
+
+        let value = str::from_utf8(weight_bytes)
+            .ok()
+            .and_then(|weight_str| weight_str.trim().parse::<f64>().ok())
+            .ok_or(Error::FailedToParse)?;
+
+        // Extract the unit (if the scales report one).
+        let unit_field = match &self.unit {
+            Some(unit) => response.get(unit.clone()).ok_or(Error::FailedToParse)?,
+            None => &[],
+        };
+
+        Ok((sign * value, unit_field))
+    }
+
+    /// Build the weight response the scales would send for the given weight (the counterpart of `parse_weight()`).
+    /// The digits are right-aligned with as many decimals as fit, unused bytes are blanks.
+    pub fn format_weight(&self, weight: f64, unit: Unit) -> Vec<u8> {
+        let mut response = vec![0x20u8; self.weight_response_len];
+
+        // Without a sign field, the sign is part of the digits.
+        let value = match self.sign_offset {
+            Some(offset) => {
+                if weight < 0.0 {
+                    response[offset] = 0x2d;
+                }
+
+                weight.abs()
+            }
+
+            None => weight,
+        };
+
+        let width = self.digits.len();
+
+        let digits_str = (0..=3)
+            .rev()
+            .map(|precision| format!("{:>width$.precision$}", value))
+            .find(|digits_str| digits_str.len() <= width)
+            .unwrap_or_else(|| "9".repeat(width));
+
+        response[self.digits.clone()].copy_from_slice(digits_str.as_bytes());
+
+        if let Some(unit_range) = &self.unit {
+            let unit_str = format!("{:>width$}", unit.to_string(), width = unit_range.len());
+            let unit_bytes = &unit_str.as_bytes()[(unit_str.len() - unit_range.len())..];
+
+            response[unit_range.clone()].copy_from_slice(unit_bytes);
+        }
+
+        response
+    }
+
+    /// Check that all fields lie within the weight response.
+    fn validate(&self) -> Result<(), String> {
+        let fits = |range: &Range<usize>| {
+            (range.start < range.end) && (range.end <= self.weight_response_len)
+        };
+
+        if self.weight_request.is_empty() {
+            return Err(String::from("The weight request is empty."));
+        }
+
+        if self.info_request.is_empty() && (self.info_response_len > 0) {
+            return Err(String::from("There is an info response without a request."));
+        }
+
+        if self
+            .sign_offset
+            .is_some_and(|offset| offset >= self.weight_response_len)
+        {
+            return Err(String::from(
+                "The sign lies outside of the weight response.",
+            ));
+        }
+
+        if !fits(&self.digits) {
+            return Err(String::from(
+                "The digits lie outside of the weight response.",
+            ));
+        }
+
+        if self.unit.as_ref().is_some_and(|unit| !fits(unit)) {
+            return Err(String::from(
+                "The unit lies outside of the weight response.",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a request like `0405/1` into its bytes and the length of the response.
+fn parse_request(value: &str) -> Result<(Vec<u8>, usize), String> {
+    let (hex, len) = value
+        .split_once('/')
+        .ok_or_else(|| format!("Missing response length in \"{}\".", value))?;
+
+    let hex = hex.trim();
+
+    if hex.len() % 2 != 0 {
+        return Err(format!("Odd number of hex digits in \"{}\".", hex));
+    }
+
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|idx| {
+            hex.get(idx..(idx + 2))
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| format!("Invalid hex bytes \"{}\".", hex))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let len = parse_number(len)?;
+
+    Ok((bytes, len))
+}
+
+/// Parse a range like `15..21`.
+fn parse_range(value: &str) -> Result<Range<usize>, String> {
+    let (start, end) = value
+        .split_once("..")
+        .ok_or_else(|| format!("Invalid range \"{}\".", value))?;
+
+    Ok(parse_number(start)?..parse_number(end)?)
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid number \"{}\".", value.trim()))
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut protocol = Protocol::default();
+
+        for pair in s.split(';').filter(|pair| !pair.trim().is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Missing value in \"{}\".", pair))?;
+
+            let value = value.trim();
+
+            match key.trim() {
+                "info" if value.is_empty() => {
+                    protocol.info_request = Vec::new();
+                    protocol.info_response_len = 0;
+                }
+
+                "info" => {
+                    (protocol.info_request, protocol.info_response_len) = parse_request(value)?
+                }
+
+                "weight" => {
+                    (protocol.weight_request, protocol.weight_response_len) = parse_request(value)?
+                }
+
+                "sign" if value.is_empty() => protocol.sign_offset = None,
+                "sign" => protocol.sign_offset = Some(parse_number(value)?),
+                "digits" => protocol.digits = parse_range(value)?,
+                "unit" if value.is_empty() => protocol.unit = None,
+                "unit" => protocol.unit = Some(parse_range(value)?),
+
+                key => return Err(format!("Unknown key \"{}\".", key)),
+            }
+        }
+
+        protocol.validate()?;
+
+        Ok(protocol)
+    }
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        };
+
+        if self.info_request.is_empty() {
+            write!(f, "info=;")?;
+        } else {
+            write!(
+                f,
+                "info={}/{};",
+                hex(&self.info_request),
+                self.info_response_len
+            )?;
+        }
+
+        write!(
+            f,
+            "weight={}/{};",
+            hex(&self.weight_request),
+            self.weight_response_len
+        )?;
+
+        match self.sign_offset {
+            Some(offset) => write!(f, "sign={};", offset)?,
+            None => write!(f, "sign=;")?,
+        }
+
+        write!(f, "digits={}..{};", self.digits.start, self.digits.end)?;
+
+        match &self.unit {
+            Some(unit) => write!(f, "unit={}..{}", unit.start, unit.end),
+            None => write!(f, "unit="),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatted_weights_are_parsed_back() {
+        let protocol = Protocol::default();
+
+        let response = protocol.format_weight(-1.25, Unit::Gram);
+        assert_eq!(response.len(), protocol.weight_response_len);
+        assert_eq!(&response[14..24], b"- 1.250  g");
+        assert_eq!(
+            protocol.parse_weight(&response).unwrap(),
+            (-1.25, &b"  g"[..])
+        );
+
+        // Without a sign field, the sign takes a digit.
+        let protocol: Protocol = "info=;sign=;digits=0..5;unit=;weight=13/5".parse().unwrap();
+        let response = protocol.format_weight(-12.5, Unit::Kilogram);
+
+        assert_eq!(response, b"-12.5");
+        assert_eq!(protocol.parse_weight(&response).unwrap(), (-12.5, &b""[..]));
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// The number of responses the statistics are computed over
+const WINDOW: usize = 100;
+
+/// Fewer responses don't say much (e.g. right after opening the port)
+const MIN_SAMPLES: usize = 20;
+
+#[derive(Debug, Copy, Clone)]
+struct Sample {
+    /// Has a valid weight been parsed from the response?
+    valid: bool,
+
+    /// The time from sending the request to receiving the response (or giving up)
+    latency: Duration,
+}
+
+/// The reason why the data of the scales are not trustworthy
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum QualityIssue {
+    /// Too many responses have been missing or garbled.
+    Failures { percent: f64 },
+
+    /// The scales answer slowly on average.
+    Latency { mean: Duration },
+}
+
+impl Display for QualityIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QualityIssue::Failures { percent } => write!(f, "{:.1}% failed responses", percent),
+            QualityIssue::Latency { mean } => {
+                write!(f, "mean response time of {} ms", mean.as_millis())
+            }
+        }
+    }
+}
+
+/// Statistics over the last responses of the scales.
+/// Degrading values are often an early sign of a failing USB-serial adapter.
+#[derive(Debug, Clone, Default)]
+pub struct Quality {
+    samples: VecDeque<Sample>,
+}
+
+impl Quality {
+    pub(super) fn record(&mut self, valid: bool, latency: Duration) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(Sample { valid, latency });
+    }
+
+    /// The number of responses the statistics cover
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// The share of missing or garbled responses in %
+    pub fn failure_percent(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+
+        let failures = self.samples.iter().filter(|sample| !sample.valid).count();
+        100.0 * (failures as f64) / (self.samples.len() as f64)
+    }
+
+    /// The mean time between request and response
+    pub fn mean_latency(&self) -> Duration {
+        let sum: Duration = self.samples.iter().map(|sample| sample.latency).sum();
+        sum / (self.samples.len().max(1) as u32)
+    }
+
+    /// The longest time between request and response
+    pub fn max_latency(&self) -> Duration {
+        self.samples
+            .iter()
+            .map(|sample| sample.latency)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Check the statistics against the limits (once there are enough responses).
+    pub fn issue(
+        &self,
+        max_failure_percent: f64,
+        max_mean_latency: Duration,
+    ) -> Option<QualityIssue> {
+        if self.samples.len() < MIN_SAMPLES {
+            return None;
+        }
+
+        let percent = self.failure_percent();
+
+        if percent > max_failure_percent {
+            return Some(QualityIssue::Failures { percent });
+        }
+
+        let mean = self.mean_latency();
+
+        if mean > max_mean_latency {
+            return Some(QualityIssue::Latency { mean });
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_and_slow_responses_are_issues() {
+        let fast = Duration::from_millis(40);
+        let limit = Duration::from_millis(500);
+        let mut quality = Quality::default();
+
+        // Too few responses to judge
+        for _ in 0..10 {
+            quality.record(false, fast);
+        }
+
+        assert_eq!(quality.issue(5.0, limit), None);
+
+        // The failures leave the window over time.
+        for _ in 0..190 {
+            quality.record(true, fast);
+        }
+
+        assert_eq!(quality.sample_count(), 100);
+        assert_eq!(quality.issue(5.0, limit), None);
+
+        for _ in 0..6 {
+            quality.record(false, fast);
+        }
+
+        assert_eq!(
+            quality.issue(5.0, limit),
+            Some(QualityIssue::Failures { percent: 6.0 })
+        );
+
+        for _ in 0..100 {
+            quality.record(true, Duration::from_millis(600));
+        }
+
+        assert_eq!(
+            quality.issue(5.0, limit),
+            Some(QualityIssue::Latency {
+                mean: Duration::from_millis(600)
+            })
+        );
+        assert_eq!(quality.max_latency(), Duration::from_millis(600));
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt::Display;
+use std::str;
+
+/// The unit of a weight reported by the scales
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Unit {
+    Kilogram,
+    Gram,
+    Pound,
+    Ounce,
+}
+
+impl Unit {
+    /// The unit all weights are converted to (prices are per kg)
+    pub const CANONICAL: Unit = Unit::Kilogram;
+
+    /// Parse the unit field of a weight response (e.g. b" kg").
+    /// Returns `None` if the field is blank, `Err` with the field if it is unknown.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Option<Self>, String> {
+        use Unit::*;
+
+        let field = str::from_utf8(bytes)
+            .map_err(|_| String::from_utf8_lossy(bytes).into_owned())?
+            .trim_matches(|c: char| c.is_ascii_whitespace() || (c == '\0'));
+
+        match field.to_ascii_lowercase().as_str() {
+            "" => Ok(None),
+            "kg" => Ok(Some(Kilogram)),
+            "g" => Ok(Some(Gram)),
+            "lb" | "lbs" => Ok(Some(Pound)),
+            "oz" => Ok(Some(Ounce)),
+            _ => Err(String::from(field)),
+        }
+    }
+
+    /// The weight of one unit in kg
+    fn kg(&self) -> f64 {
+        use Unit::*;
+
+        match self {
+            Kilogram => 1.0,
+            Gram => 0.001,
+            Pound => 0.453_592_37,
+            Ounce => 0.028_349_523_125,
+        }
+    }
+
+    /// Convert a weight in this unit to the given unit.
+    pub fn convert(&self, weight: f64, to: Unit) -> f64 {
+        weight * self.kg() / to.kg()
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Unit::*;
+
+        match self {
+            Kilogram => write!(f, "kg"),
+            Gram => write!(f, "g"),
+            Pound => write!(f, "lb"),
+            Ounce => write!(f, "oz"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-12,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn unit_fields_are_parsed() {
+        assert_eq!(Unit::from_bytes(b"kg"), Ok(Some(Unit::Kilogram)));
+        assert_eq!(Unit::from_bytes(b" KG "), Ok(Some(Unit::Kilogram)));
+        assert_eq!(Unit::from_bytes(b"g\0\0"), Ok(Some(Unit::Gram)));
+        assert_eq!(Unit::from_bytes(b"lb"), Ok(Some(Unit::Pound)));
+        assert_eq!(Unit::from_bytes(b"lbs"), Ok(Some(Unit::Pound)));
+        assert_eq!(Unit::from_bytes(b" oz"), Ok(Some(Unit::Ounce)));
+
+        // Blank fields leave the unit open.
+        assert_eq!(Unit::from_bytes(b""), Ok(None));
+        assert_eq!(Unit::from_bytes(b"  \0"), Ok(None));
+
+        assert_eq!(Unit::from_bytes(b" t "), Err(String::from("t")));
+        assert_eq!(Unit::from_bytes(b"k\xffg"), Err(String::from("k\u{fffd}g")));
+    }
+
+    #[test]
+    fn units_are_displayed_as_parsed() {
+        for unit in [Unit::Kilogram, Unit::Gram, Unit::Pound, Unit::Ounce] {
+            assert_eq!(
+                Unit::from_bytes(unit.to_string().as_bytes()),
+                Ok(Some(unit))
+            );
+        }
+    }
+
+    #[test]
+    fn weights_are_converted() {
+        assert_eq!(Unit::Kilogram.convert(1.5, Unit::CANONICAL), 1.5);
+        assert_close(Unit::Gram.convert(250.0, Unit::Kilogram), 0.25);
+        assert_close(Unit::Kilogram.convert(0.25, Unit::Gram), 250.0);
+        assert_close(Unit::Pound.convert(1.0, Unit::Kilogram), 0.453_592_37);
+        assert_close(Unit::Ounce.convert(1.0, Unit::Gram), 28.349_523_125);
+        assert_close(Unit::Pound.convert(1.0, Unit::Ounce), 16.0);
+        assert_close(Unit::Ounce.convert(-8.0, Unit::Pound), -0.5);
+    }
+}
@@ -0,0 +1,264 @@
+use std::env;
+use std::process;
+
+use chrono::{TimeZone, Utc};
+use image::{GrayImage, Luma};
+
+use weight_wb::db::{
+    Attribution, Database, ProductEntry, SaleEntry, SaleJournal, UnderweightPolicy,
+};
+use weight_wb::pos::{Engine, Error as PosError, Permissions, Role, WeightCheck};
+use weight_wb::printer::{LabelType, Model};
+use weight_wb::template::ProductVoucher;
+use weight_wb::voucher::TextContext;
+use weight_wb::weight::Scales;
+
+/// A 62 mm continuous label has 696 printable dots on the QL-700.
+const LABEL_WIDTH: u32 = 696;
+
+/// The QL-700 expects 90 bytes per raster line.
+const LINE_WIDTH: usize = 90;
+
+/// Weigh a product, print its voucher and store the sale, just like the UI does.
+#[test]
+fn sale_with_emulated_hardware() {
+    // Set up an empty DB with a single product.
+    let mut db = Database::open_or_create(":memory:").expect("Failed to create in-memory DB");
+
+    db.add_product(ProductEntry::new(
+        String::from("Rinderhack"),
+        1490,
+        true,
+        String::from("Rind"),
+        String::new(),
+        Some(7.0),
+        Some(3),
+    ))
+    .expect("Failed to add product");
+
+    // The journal is only written if the DB fails, so it must stay empty.
+    let journal_path = env::temp_dir().join(format!("weight-wb-sale-{}.journal", process::id()));
+    let journal = SaleJournal::new(&journal_path);
+
+    // Attach the emulated hardware.
+    let mut engine = Engine::new(db, journal, Scales::fixed(0.5), Attribution::default());
+    engine.emulate_printer(Model::BrotherQL700, LabelType::Continuous { width: 62 });
+    engine
+        .reconnect_printer()
+        .expect("Failed to reconnect printer");
+
+    // Weigh.
+    let weight_kg = engine
+        .net_weight(engine.scales().weight())
+        .expect("Fixed scales must report a weight");
+
+    assert_eq!(weight_kg, 0.5);
+    assert_eq!(engine.check_weight(weight_kg), WeightCheck::Accepted);
+
+    // Build the voucher for the label that is loaded.
+    let label = engine
+        .printer_label()
+        .expect("Failed to query label")
+        .expect("Emulated printer must have a label");
+
+    assert_eq!(label.printable_dots_width, LABEL_WIDTH);
+
+    let product = &engine.db().products()[0];
+
+    let (voucher, _, _) = ProductVoucher::new(
+        product,
+        Some(weight_kg),
+        engine.db().info(),
+        engine.db().settings(),
+    )
+    .dpi(Some(Model::BrotherQL700.family().dpi()))
+    .build(label.printable_dots_width, TextContext::new())
+    .expect("Failed to build voucher");
+
+    assert_eq!(voucher.width(), LABEL_WIDTH);
+
+    // Print it and check the raster lines that arrived at the printer.
+    engine
+        .print_pages(&[&voucher], Permissions::SELL)
+        .expect("Failed to print voucher");
+
+    let pages = engine.emulated_pages().expect("Printer must be emulated");
+
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].lines.len(), voucher.height() as usize);
+    assert!(pages[0].lines.iter().all(|line| line.len() == LINE_WIDTH));
+    assert!(pages[0].lines.iter().flatten().any(|&byte| byte != 0));
+
+    // Store the sale.
+    let date = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+    let total_ct =
+        product.total_price_ct(Some(weight_kg), None, engine.db().settings().rounding_mode);
+
+    assert_eq!(total_ct, 745);
+
+    let sale = SaleEntry::new(date, product.name.clone(), Some(weight_kg), 1490, total_ct);
+    let frame = engine.scales().diagnostics().last_frame;
+    engine
+        .store_sale(&sale, frame)
+        .expect("Failed to store sale");
+
+    let mut sales = Vec::new();
+    engine.db().sales(&mut sales).expect("Failed to load sales");
+
+    assert_eq!(sales.len(), 1);
+    assert_eq!(sales[0].date, date);
+    assert_eq!(sales[0].name, "Rinderhack");
+    assert_eq!(sales[0].weight_kg, Some(0.5));
+    assert_eq!(sales[0].total_ct, 745);
+    assert_eq!(engine.unsaved_sales(), 0);
+    assert!(!journal_path.exists());
+
+    // The weight can be traced back (fixed scales have no raw frame).
+    let mut weighings = Vec::new();
+    engine
+        .db()
+        .weighings(10, &mut weighings)
+        .expect("Failed to load weighings");
+
+    assert_eq!(weighings.len(), 1);
+    assert_eq!(weighings[0].sale_date, date);
+    assert_eq!(weighings[0].weight_kg, 0.5);
+    assert_eq!(weighings[0].frame, None);
+}
+
+/// Negative weights are only booked after the operator has confirmed them.
+#[test]
+fn confirmed_negative_weights_are_sold() {
+    let mut db = Database::open_or_create(":memory:").expect("Failed to create in-memory DB");
+    db.update_settings(|settings| settings.underweight_policy = UnderweightPolicy::Confirm)
+        .expect("Failed to update settings");
+
+    let journal_path = env::temp_dir().join(format!("weight-wb-return-{}.journal", process::id()));
+    let mut engine = Engine::new(
+        db,
+        SaleJournal::new(&journal_path),
+        Scales::fixed(-0.2),
+        Attribution::default(),
+    );
+
+    assert_eq!(engine.check_weight(-0.2), WeightCheck::NeedsConfirmation);
+
+    let date = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+    let sale = SaleEntry::new(date, String::from("Rinderhack"), Some(-0.2), 1490, 0);
+
+    // Without the confirmation, the sale is refused.
+    assert!(matches!(
+        engine.store_sale(&sale, None),
+        Err(PosError::NegativeWeight)
+    ));
+
+    engine
+        .store_confirmed_sale(&sale, None)
+        .expect("Failed to store confirmed sale");
+
+    let mut sales = Vec::new();
+    engine.db().sales(&mut sales).expect("Failed to load sales");
+
+    assert_eq!(sales.len(), 1);
+    assert_eq!(sales[0].weight_kg, Some(-0.2));
+    assert_eq!(sales[0].total_ct, 0);
+    assert!(!journal_path.exists());
+
+    // Other policies never book negative weights, even if the UI claims a confirmation.
+    let db = Database::open_or_create(":memory:").expect("Failed to create in-memory DB");
+    let mut engine = Engine::new(
+        db,
+        SaleJournal::new(&journal_path),
+        Scales::fixed(-0.2),
+        Attribution::default(),
+    );
+
+    assert_eq!(engine.check_weight(-0.2), WeightCheck::Rejected);
+    assert!(matches!(
+        engine.store_confirmed_sale(&sale, None),
+        Err(PosError::NegativeWeight)
+    ));
+}
+
+/// Trainees may sell, but the engine refuses everything else, whatever the UI offers.
+#[test]
+fn trainees_are_refused() {
+    let mut db = Database::open_or_create(":memory:").expect("Failed to create in-memory DB");
+    db.add_product(ProductEntry::new(
+        String::from("Rinderhack"),
+        1490,
+        true,
+        String::from("Rind"),
+        String::new(),
+        Some(7.0),
+        Some(3),
+    ))
+    .expect("Failed to add product");
+    db.update_settings(|settings| settings.trainee_operators = String::from("lena"))
+        .expect("Failed to update settings");
+
+    let journal_path = env::temp_dir().join(format!("weight-wb-trainee-{}.journal", process::id()));
+    let attribution = Attribution {
+        operator: Some(String::from("lena")),
+        station: None,
+    };
+    let mut engine = Engine::new(
+        db,
+        SaleJournal::new(&journal_path),
+        Scales::fixed(0.5),
+        attribution,
+    );
+    engine.emulate_printer(Model::BrotherQL700, LabelType::Continuous { width: 62 });
+    engine
+        .reconnect_printer()
+        .expect("Failed to reconnect printer");
+
+    assert_eq!(engine.role(), Role::Trainee);
+
+    // Printing without a sale is refused before anything reaches the printer.
+    let voucher = GrayImage::from_pixel(LABEL_WIDTH, 100, Luma([255]));
+
+    assert!(matches!(
+        engine.print_pages(&[&voucher], Permissions::PRINT_ONLY),
+        Err(PosError::NotPermitted {
+            role: Role::Trainee,
+            permission: Permissions::PRINT_ONLY
+        })
+    ));
+    assert_eq!(engine.emulated_pages().map(|pages| pages.len()), Some(0));
+
+    // The voucher of a sale is printed and the sale is stored.
+    let date = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+    let sale = SaleEntry::new(date, String::from("Rinderhack"), Some(0.5), 1490, 745);
+
+    engine
+        .print_pages(&[&voucher], Permissions::SELL)
+        .expect("Failed to print voucher");
+    engine
+        .store_sale(&sale, None)
+        .expect("Failed to store sale");
+
+    assert_eq!(engine.emulated_pages().map(|pages| pages.len()), Some(1));
+
+    // Neither the sale nor the product can be changed.
+    assert!(matches!(
+        engine.void_sale(&sale),
+        Err(PosError::NotPermitted { .. })
+    ));
+    assert!(matches!(
+        engine.update_product_price(0, 990, date),
+        Err(PosError::NotPermitted { .. })
+    ));
+    assert!(matches!(
+        engine.duplicate_product(0, String::from("Rinderhack (Kopie)")),
+        Err(PosError::NotPermitted { .. })
+    ));
+
+    let mut sales = Vec::new();
+    engine.db().sales(&mut sales).expect("Failed to load sales");
+
+    assert_eq!(sales.len(), 1);
+    assert_eq!(engine.db().products().len(), 1);
+    assert_eq!(engine.db().products()[0].price_ct, 1490);
+    assert!(!journal_path.exists());
+}